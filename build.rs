@@ -10,7 +10,10 @@ static CONFIGS: &[(&str, usize)] = &[
     ("IFACE_MAX_MULTICAST_GROUP_COUNT", 4),
     ("IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT", 4),
     ("IFACE_NEIGHBOR_CACHE_COUNT", 4),
+    ("IFACE_NEIGHBOR_PENDING_COUNT", 1),
     ("IFACE_MAX_ROUTE_COUNT", 2),
+    ("IFACE_MAX_PROXY_ADDR_COUNT", 2),
+    ("IFACE_MAX_EVENT_COUNT", 4),
     ("FRAGMENTATION_BUFFER_SIZE", 1500),
     ("ASSEMBLER_MAX_SEGMENT_COUNT", 4),
     ("REASSEMBLY_BUFFER_SIZE", 1500),
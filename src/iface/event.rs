@@ -0,0 +1,110 @@
+use heapless::Deque;
+
+use crate::config::IFACE_MAX_EVENT_COUNT;
+use crate::wire::{HardwareAddress, IpAddress, IpCidr};
+
+/// Something that happened on an interface.
+///
+/// Events are only recorded after [Interface::set_events_enabled] has been called, and are
+/// retrieved with [Interface::next_event].
+///
+/// [Interface::set_events_enabled]: crate::iface::Interface::set_events_enabled
+/// [Interface::next_event]: crate::iface::Interface::next_event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Event {
+    /// An address was assigned to the interface.
+    AddressAdded(IpCidr),
+    /// An address was removed from the interface.
+    AddressRemoved(IpCidr),
+    /// Another host on the link claims one of the addresses assigned to the interface.
+    ///
+    /// This is reported when an ARP packet or a Neighbor Advertisement from another host
+    /// uses one of our addresses, and when another host runs Duplicate Address Detection
+    /// for one of our addresses. The hardware address of the other host is included when
+    /// it is known.
+    AddressConflict {
+        addr: IpAddress,
+        hardware_addr: Option<HardwareAddress>,
+    },
+    /// A neighbor was added to the neighbor cache, or its hardware address changed.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    NeighborAdded {
+        addr: IpAddress,
+        hardware_addr: HardwareAddress,
+    },
+    /// A neighbor was evicted from the neighbor cache to make room for another one.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    NeighborEvicted {
+        addr: IpAddress,
+        hardware_addr: HardwareAddress,
+    },
+    /// The routing table was borrowed mutably, and may have changed, or a route was
+    /// installed because of a redirect.
+    RoutesChanged,
+    /// A packet was dropped.
+    PacketDropped(DropReason),
+}
+
+/// The reason a packet was dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum DropReason {
+    /// A response to an incoming packet could not be sent because there is no route to
+    /// its destination.
+    NoRoute,
+    /// A response to an incoming packet could not be sent because the hardware address
+    /// of its destination is not known yet.
+    NeighborPending,
+    /// A packet could not be sent because its destination did not answer neighbor
+    /// discovery, see [Interface::set_neighbor_solicit_retries].
+    ///
+    /// [Interface::set_neighbor_solicit_retries]: crate::iface::Interface::set_neighbor_solicit_retries
+    NeighborUnreachable,
+    /// A packet was larger than the MTU, and was not fragmented because the socket that
+    /// sent it was set to not fragment packets.
+    TooLarge,
+}
+
+/// A bounded queue of events that discards the oldest event when full.
+pub(crate) struct EventQueue {
+    enabled: bool,
+    events: Deque<Event, IFACE_MAX_EVENT_COUNT>,
+}
+
+impl EventQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            enabled: false,
+            events: Deque::new(),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.events.clear();
+        }
+    }
+
+    pub(crate) fn push(&mut self, event: Event) {
+        if !self.enabled {
+            return;
+        }
+        if self.events.is_full() {
+            net_debug!("iface: event queue full, discarding oldest event");
+            self.events.pop_front();
+        }
+        let _ = self.events.push_back(event);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
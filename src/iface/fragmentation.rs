@@ -228,10 +228,26 @@ impl<K: Eq + Copy> PacketAssemblerSet<K> {
         Ok(slot)
     }
 
+    /// Return the earliest instant at which one of the [`PacketAssembler`]s expires.
+    pub(crate) fn expires_at(&self) -> Option<Instant> {
+        self.assemblers
+            .iter()
+            .filter(|frag| !frag.is_free())
+            .map(|frag| frag.expires_at)
+            .min()
+    }
+
+    /// Remove all [`PacketAssembler`]s, discarding the fragments they hold.
+    pub(crate) fn clear(&mut self) {
+        for frag in &mut self.assemblers {
+            frag.reset();
+        }
+    }
+
     /// Remove all [`PacketAssembler`]s that are expired.
     pub fn remove_expired(&mut self, timestamp: Instant) {
         for frag in &mut self.assemblers {
-            if !frag.is_free() && frag.expires_at < timestamp {
+            if !frag.is_free() && frag.expires_at <= timestamp {
                 frag.reset();
             }
         }
@@ -468,6 +484,19 @@ mod tests {
         assert!(set.get(&Key { id: 4 }, Instant::ZERO).is_err());
     }
 
+    #[test]
+    fn packet_assembler_set_expires_at() {
+        let mut set = PacketAssemblerSet::new();
+        assert_eq!(set.expires_at(), None);
+
+        set.get(&Key { id: 0 }, Instant::from_secs(20)).unwrap();
+        set.get(&Key { id: 1 }, Instant::from_secs(10)).unwrap();
+        assert_eq!(set.expires_at(), Some(Instant::from_secs(10)));
+
+        set.remove_expired(Instant::from_secs(10));
+        assert_eq!(set.expires_at(), Some(Instant::from_secs(20)));
+    }
+
     #[test]
     fn packet_assembler_set_assembling_many() {
         let mut set = PacketAssemblerSet::new();
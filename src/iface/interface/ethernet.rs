@@ -1,6 +1,6 @@
 use super::*;
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
     pub(super) fn process_ethernet<'frame>(
         &mut self,
         sockets: &mut SocketSet,
@@ -1,6 +1,6 @@
 use super::*;
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
     /// Return the next IEEE802.15.4 sequence number.
     #[cfg(feature = "medium-ieee802154")]
     pub(super) fn next_ieee802154_seq_number(&mut self) -> u8 {
@@ -9,6 +9,13 @@ impl InterfaceInner {
         no
     }
 
+    /// Return the link-layer source address for outgoing frames: the short address
+    /// if one is assigned, otherwise the extended address.
+    pub(super) fn ieee802154_src_addr(&self) -> Ieee802154Address {
+        self.short_addr
+            .unwrap_or_else(|| self.hardware_addr.ieee802154_or_panic())
+    }
+
     pub(super) fn process_ieee802154<'output, 'payload: 'output>(
         &mut self,
         sockets: &mut SocketSet,
@@ -54,7 +61,7 @@ impl InterfaceInner {
         packet: Packet,
         frag: &mut Fragmenter,
     ) {
-        let ll_src_a = self.hardware_addr.ieee802154_or_panic();
+        let ll_src_a = self.ieee802154_src_addr();
 
         // Create the IEEE802.15.4 header.
         let ieee_repr = Ieee802154Repr {
@@ -25,7 +25,7 @@ impl core::fmt::Display for MulticastError {
 #[cfg(feature = "std")]
 impl std::error::Error for MulticastError {}
 
-impl Interface {
+impl Interface<'_> {
     /// Add an address to a list of subscribed multicast IP addresses.
     ///
     /// Returns `Ok(announce_sent)` if the address was added successfully, where `announce_sent`
@@ -201,7 +201,16 @@ impl Interface {
     }
 }
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
+    /// Return the instant at which a pending IGMP report should be sent, if any.
+    pub(super) fn igmp_poll_at(&self) -> Option<Instant> {
+        match self.igmp_report_state {
+            IgmpReportState::Inactive => None,
+            IgmpReportState::ToGeneralQuery { timeout, .. }
+            | IgmpReportState::ToSpecificQuery { timeout, .. } => Some(timeout),
+        }
+    }
+
     /// Host duties of the **IGMPv2** protocol.
     ///
     /// Sets up `igmp_report_state` for responding to IGMP general/specific membership queries.
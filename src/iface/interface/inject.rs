@@ -0,0 +1,86 @@
+use alloc::vec::Vec;
+
+use super::*;
+
+impl Interface<'_> {
+    /// Feed a single raw frame into the interface and return the frames it emits.
+    ///
+    /// This runs one [poll](#method.poll) cycle as if `frame` had just been received by
+    /// the device: the frame is processed, the sockets are given a chance to react, and
+    /// every frame transmitted in response is returned in order. The frame must be
+    /// formatted for the medium of the interface.
+    ///
+    /// This is intended for tests that exercise the interface at the wire level
+    /// without having to implement a [Device].
+    pub fn process_frame(
+        &mut self,
+        timestamp: Instant,
+        sockets: &mut SocketSet<'_>,
+        frame: &[u8],
+    ) -> Vec<Vec<u8>> {
+        let mut device = InjectDevice {
+            caps: self.inner.caps.clone(),
+            rx: Some(frame.to_vec()),
+            tx: Vec::new(),
+        };
+        self.poll(timestamp, &mut device, sockets);
+        device.tx
+    }
+}
+
+/// A device that receives a single frame and records every frame it transmits.
+struct InjectDevice {
+    caps: DeviceCapabilities,
+    rx: Option<Vec<u8>>,
+    tx: Vec<Vec<u8>>,
+}
+
+impl Device for InjectDevice {
+    type RxToken<'a> = InjectRxToken;
+    type TxToken<'a> = InjectTxToken<'a>;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        self.caps.clone()
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        self.rx.take().map(move |buffer| {
+            let rx = InjectRxToken { buffer };
+            let tx = InjectTxToken { tx: &mut self.tx };
+            (rx, tx)
+        })
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(InjectTxToken { tx: &mut self.tx })
+    }
+}
+
+struct InjectRxToken {
+    buffer: Vec<u8>,
+}
+
+impl RxToken for InjectRxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.buffer)
+    }
+}
+
+struct InjectTxToken<'a> {
+    tx: &'a mut Vec<Vec<u8>>,
+}
+
+impl TxToken for InjectTxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buffer = alloc::vec![0; len];
+        let result = f(&mut buffer);
+        self.tx.push(buffer);
+        result
+    }
+}
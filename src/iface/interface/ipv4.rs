@@ -1,6 +1,11 @@
 use super::*;
 
-impl Interface {
+/// The UDP port DHCP clients receive replies on, even without the DHCP socket.
+const DHCPV4_CLIENT_PORT: u16 = 68;
+/// The UDP port DHCP servers receive requests on.
+const DHCPV4_SERVER_PORT: u16 = 67;
+
+impl Interface<'_> {
     /// Process fragments that still need to be sent for IPv4 packets.
     ///
     /// This function returns a boolean value indicating whether any packets were
@@ -32,7 +37,7 @@ impl Interface {
     }
 }
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
     /// Get the next IPv4 fragment identifier.
     #[cfg(feature = "proto-ipv4-fragmentation")]
     pub(super) fn next_ipv4_frag_ident(&mut self) -> u16 {
@@ -87,6 +92,33 @@ impl InterfaceInner {
         })
     }
 
+    /// Return whether a packet from the unspecified address is accepted, see
+    /// [UnspecifiedSourcePolicy].
+    fn accepts_unspecified_source_v4(&self, ipv4_repr: &Ipv4Repr, ip_payload: &[u8]) -> bool {
+        match self.unspecified_source_policy {
+            // Hosts that don't have an address yet send DHCP requests, and may be sent DHCP
+            // replies, from the unspecified address. They may also report their multicast
+            // group memberships from it (RFC 3376 § 4.2.13).
+            UnspecifiedSourcePolicy::Restricted => match ipv4_repr.next_header {
+                IpProtocol::Udp => UdpPacket::new_checked(ip_payload).map_or(false, |udp_packet| {
+                    matches!(
+                        udp_packet.dst_port(),
+                        DHCPV4_CLIENT_PORT | DHCPV4_SERVER_PORT
+                    )
+                }),
+                #[cfg(feature = "proto-igmp")]
+                IpProtocol::Igmp => IgmpPacket::new_checked(ip_payload)
+                    .and_then(|igmp_packet| IgmpRepr::parse(&igmp_packet))
+                    .map_or(false, |igmp_repr| {
+                        matches!(igmp_repr, IgmpRepr::MembershipReport { .. })
+                    }),
+                _ => false,
+            },
+            UnspecifiedSourcePolicy::Accept => true,
+            UnspecifiedSourcePolicy::Drop => false,
+        }
+    }
+
     pub(super) fn process_ipv4<'a>(
         &mut self,
         sockets: &mut SocketSet,
@@ -142,6 +174,13 @@ impl InterfaceInner {
         #[cfg(not(feature = "proto-ipv4-fragmentation"))]
         let ip_payload = ipv4_packet.payload();
 
+        if ipv4_repr.src_addr.is_unspecified()
+            && !self.accepts_unspecified_source_v4(&ipv4_repr, ip_payload)
+        {
+            net_debug!("unspecified source address");
+            return None;
+        }
+
         let ip_repr = IpRepr::Ipv4(ipv4_repr);
 
         #[cfg(feature = "socket-raw")]
@@ -202,7 +241,11 @@ impl InterfaceInner {
             #[cfg(feature = "proto-igmp")]
             IpProtocol::Igmp => self.process_igmp(ipv4_repr, ip_payload),
 
-            #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
+            #[cfg(any(
+                feature = "socket-udp",
+                feature = "socket-dns",
+                feature = "socket-tftp"
+            ))]
             IpProtocol::Udp => {
                 self.process_udp(sockets, meta, handled_by_raw_socket, ip_repr, ip_payload)
             }
@@ -243,8 +286,26 @@ impl InterfaceInner {
                 target_protocol_addr,
                 ..
             } => {
-                // Only process ARP packets for us.
-                if !self.has_ip_addr(target_protocol_addr) && !self.any_ip {
+                // Another host using one of our addresses (RFC 5227 § 2.4).
+                if self.has_ip_addr(source_protocol_addr)
+                    && HardwareAddress::Ethernet(source_hardware_addr) != self.hardware_addr
+                {
+                    net_debug!(
+                        "arp: {} claims our address {}",
+                        source_hardware_addr,
+                        source_protocol_addr
+                    );
+                    self.events.push(Event::AddressConflict {
+                        addr: source_protocol_addr.into(),
+                        hardware_addr: Some(source_hardware_addr.into()),
+                    });
+                }
+
+                // Only process ARP packets for us, or for addresses we proxy.
+                if !self.has_ip_addr(target_protocol_addr)
+                    && !self.any_ip
+                    && !self.is_proxied(target_protocol_addr)
+                {
                     return None;
                 }
 
@@ -269,13 +330,19 @@ impl InterfaceInner {
                 // We fill from requests too because if someone is requesting our address they
                 // are probably going to talk to us, so we avoid having to request their address
                 // when we later reply to them.
-                self.neighbor_cache.fill(
+                self.fill_neighbor(
                     source_protocol_addr.into(),
                     source_hardware_addr.into(),
                     timestamp,
                 );
 
-                if operation == ArpOperation::Request {
+                // Never answer a gratuitous ARP on behalf of the host announcing itself.
+                // Otherwise, answer with whichever address was asked for, which does not
+                // have to be the first address of the interface.
+                if operation == ArpOperation::Request
+                    && !(source_protocol_addr == target_protocol_addr
+                        && self.is_proxied(target_protocol_addr))
+                {
                     let src_hardware_addr = self.hardware_addr.ethernet_or_panic();
 
                     Some(EthernetPacket::Arp(ArpRepr::EthernetIpv4 {
@@ -318,6 +385,12 @@ impl InterfaceInner {
         match icmp_repr {
             // Respond to echo requests.
             #[cfg(feature = "proto-ipv4")]
+            Icmpv4Repr::EchoRequest { .. }
+                if !self.echo_broadcast && !self.is_unicast_v4(ip_repr.dst_addr) =>
+            {
+                net_debug!("ignoring echo request to {}", ip_repr.dst_addr);
+                None
+            }
             Icmpv4Repr::EchoRequest {
                 ident,
                 seq_no,
@@ -334,6 +407,13 @@ impl InterfaceInner {
             // Ignore any echo replies.
             Icmpv4Repr::EchoReply { .. } => None,
 
+            Icmpv4Repr::Redirect {
+                gateway, header, ..
+            } => {
+                self.process_icmpv4_redirect(ip_repr.src_addr, gateway, header.dst_addr);
+                None
+            }
+
             // Don't report an error if a packet with unknown type
             // has been handled by an ICMP socket
             #[cfg(feature = "socket-icmp")]
@@ -344,6 +424,37 @@ impl InterfaceInner {
         }
     }
 
+    /// Follow a redirect, which is treated as a redirect for the host regardless of its code
+    /// (RFC 1122 § 3.2.2.2).
+    fn process_icmpv4_redirect(
+        &mut self,
+        src_addr: Ipv4Address,
+        gateway: Ipv4Address,
+        dst_addr: Ipv4Address,
+    ) {
+        if !self.accept_redirects {
+            net_debug!("icmp: ignoring redirect for {}", dst_addr);
+            return;
+        }
+
+        // Only the gateway currently used for the destination may redirect it, and only
+        // to another gateway on a directly connected network.
+        if !self.is_unicast_v4(dst_addr)
+            || !self.is_unicast_v4(gateway)
+            || !self.in_same_network(&gateway.into())
+            || self.has_ip_addr(gateway)
+            || self.route(&dst_addr.into(), self.now) != Some(src_addr.into())
+        {
+            net_debug!("icmp: ignoring invalid redirect for {}", dst_addr);
+            return;
+        }
+
+        net_debug!("icmp: redirecting {} via {}", dst_addr, gateway);
+        self.routes
+            .redirect(dst_addr.into(), gateway.into(), self.now);
+        self.events.push(Event::RoutesChanged);
+    }
+
     pub(super) fn icmpv4_reply<'frame, 'icmp: 'frame>(
         &self,
         ipv4_repr: Ipv4Repr,
@@ -395,11 +506,13 @@ impl InterfaceInner {
     pub(super) fn dispatch_ipv4_frag<Tx: TxToken>(&mut self, tx_token: Tx, frag: &mut Fragmenter) {
         let caps = self.caps.clone();
 
-        let mtu_max = self.ip_mtu();
-        let ip_len = (frag.packet_len - frag.sent_bytes + frag.ipv4.repr.buffer_len()).min(mtu_max);
-        let payload_len = ip_len - frag.ipv4.repr.buffer_len();
+        // The payload of every fragment but the last one must be a multiple of 8 octets.
+        let remaining = frag.packet_len - frag.sent_bytes;
+        let max_payload_len = (self.ip_mtu() - frag.ipv4.repr.buffer_len()) & !7;
+        let payload_len = remaining.min(max_payload_len);
+        let ip_len = frag.ipv4.repr.buffer_len() + payload_len;
 
-        let more_frags = (frag.packet_len - frag.sent_bytes) != payload_len;
+        let more_frags = remaining != payload_len;
         frag.ipv4.repr.payload_len = payload_len;
         frag.sent_bytes += payload_len;
 
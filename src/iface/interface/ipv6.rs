@@ -1,5 +1,14 @@
 use super::*;
 
+/// The number of ICMPv6 error messages that can be sent in a burst.
+pub(super) const ICMPV6_ERROR_BURST: u8 = 10;
+
+/// The time after which one more ICMPv6 error message can be sent.
+const ICMPV6_ERROR_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The offset of the Next Header field in the IPv6 header.
+const IPV6_NEXT_HEADER_OFFSET: usize = 6;
+
 /// Enum used for the process_hopbyhop function. In some cases, when discarding a packet, an ICMMP
 /// parameter problem message needs to be transmitted to the source of the address. In other cases,
 /// the processing of the IP packet can continue.
@@ -18,7 +27,7 @@ impl Default for HopByHopResponse<'_> {
     }
 }
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
     /// Return the IPv6 address that is a candidate source address for the given destination
     /// address, based on RFC 6724.
     #[allow(unused)]
@@ -137,6 +146,29 @@ impl InterfaceInner {
         })
     }
 
+    /// Check whether the given address is the solicited-node multicast address of one of
+    /// the addresses the interface proxies neighbor discovery for.
+    pub(super) fn has_proxied_solicited_node(&self, addr: Ipv6Address) -> bool {
+        self.proxy_addrs.iter().any(|cidr| match *cidr {
+            IpCidr::Ipv6(cidr) => {
+                // Solicited-node addresses only retain the lower order 24 bits, so only
+                // the part of the prefix that falls within those bits can be compared.
+                let low_bits = |addr: &Ipv6Address| {
+                    let bytes = addr.as_bytes();
+                    u32::from_be_bytes([0, bytes[13], bytes[14], bytes[15]])
+                };
+                let mask = match cidr.prefix_len().saturating_sub(104) {
+                    0 => 0,
+                    bits => (0x00ff_ffff << (24 - bits)) & 0x00ff_ffff,
+                };
+                addr.as_bytes()[..13] == cidr.address().solicited_node().as_bytes()[..13]
+                    && (low_bits(&addr) ^ low_bits(&cidr.address())) & mask == 0
+            }
+            #[allow(unreachable_patterns)]
+            _ => false,
+        })
+    }
+
     /// Get the first IPv6 address if present.
     pub fn ipv6_addr(&self) -> Option<Ipv6Address> {
         self.ip_addrs.iter().find_map(|addr| match *addr {
@@ -149,12 +181,31 @@ impl InterfaceInner {
     pub(super) fn process_ipv6<'frame>(
         &mut self,
         sockets: &mut SocketSet,
-        meta: PacketMeta,
+        #[allow(unused_mut)] mut meta: PacketMeta,
         ipv6_packet: &Ipv6Packet<&'frame [u8]>,
     ) -> Option<Packet<'frame>> {
         let ipv6_repr = check!(Ipv6Repr::parse(ipv6_packet));
+        #[cfg(feature = "packetmeta-flow-label")]
+        {
+            meta.flow_label = ipv6_packet.flow_label();
+        }
 
-        if !ipv6_repr.src_addr.is_unicast() {
+        if ipv6_repr.src_addr.is_unspecified() {
+            // Neighbor Solicitations sent during Duplicate Address Detection are the only
+            // packets that are legitimately sent from the unspecified address
+            // (RFC 4861 § 7.1.1).
+            let dad_solicit = ipv6_repr.next_header == IpProtocol::Icmpv6
+                && ipv6_packet.payload().first() == Some(&Icmpv6Message::NeighborSolicit.into());
+            let accepted = match self.unspecified_source_policy {
+                UnspecifiedSourcePolicy::Restricted => dad_solicit,
+                UnspecifiedSourcePolicy::Accept => true,
+                UnspecifiedSourcePolicy::Drop => false,
+            };
+            if !accepted {
+                net_debug!("unspecified source address");
+                return None;
+            }
+        } else if !ipv6_repr.src_addr.is_unicast() {
             // Discard packets with non-unicast source addresses.
             net_debug!("non-unicast source address");
             return None;
@@ -177,17 +228,12 @@ impl InterfaceInner {
             return None;
         }
 
-        #[cfg(feature = "socket-raw")]
-        let handled_by_raw_socket = self.raw_socket_filter(sockets, &ipv6_repr.into(), ip_payload);
-        #[cfg(not(feature = "socket-raw"))]
-        let handled_by_raw_socket = false;
-
         self.process_nxt_hdr(
             sockets,
             meta,
             ipv6_repr,
+            ipv6_packet.payload(),
             next_header,
-            handled_by_raw_socket,
             ip_payload,
         )
     }
@@ -197,49 +243,47 @@ impl InterfaceInner {
         ipv6_repr: Ipv6Repr,
         ip_payload: &'frame [u8],
     ) -> HopByHopResponse<'frame> {
-        let param_problem = || {
-            let payload_len =
-                icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
-            self.icmpv6_reply(
-                ipv6_repr,
-                Icmpv6Repr::ParamProblem {
-                    reason: Icmpv6ParamProblem::UnrecognizedOption,
-                    pointer: ipv6_repr.buffer_len() as u32,
-                    header: ipv6_repr,
-                    data: &ip_payload[0..payload_len],
-                },
-            )
-        };
-
         let ext_hdr = check!(Ipv6ExtHeader::new_checked(ip_payload));
         let ext_repr = check!(Ipv6ExtHeaderRepr::parse(&ext_hdr));
         let hbh_hdr = check!(Ipv6HopByHopHeader::new_checked(ext_repr.data));
-        let hbh_repr = check!(Ipv6HopByHopRepr::parse(&hbh_hdr));
 
-        for opt_repr in &hbh_repr.options {
+        // The options are iterated over rather than collected in an `Ipv6HopByHopRepr`,
+        // which only holds a limited number of them, so that none is ignored.
+        //
+        // The offset of the current option in the packet, which the Parameter Problem
+        // message points at.
+        let mut opt_offset = ipv6_repr.buffer_len() + ext_repr.header_len();
+        for opt_repr in Ipv6OptionsIterator::new(hbh_hdr.options()) {
+            let opt_repr = check!(opt_repr);
             match opt_repr {
                 Ipv6OptionRepr::Pad1 | Ipv6OptionRepr::PadN(_) => (),
                 #[cfg(feature = "proto-rpl")]
                 Ipv6OptionRepr::Rpl(_) => {}
 
-                Ipv6OptionRepr::Unknown { type_, .. } => {
-                    match Ipv6OptionFailureType::from(*type_) {
-                        Ipv6OptionFailureType::Skip => (),
-                        Ipv6OptionFailureType::Discard => {
-                            return HopByHopResponse::Discard(None);
-                        }
-                        Ipv6OptionFailureType::DiscardSendAll => {
-                            return HopByHopResponse::Discard(param_problem());
-                        }
-                        Ipv6OptionFailureType::DiscardSendUnicast
-                            if !ipv6_repr.dst_addr.is_multicast() =>
-                        {
-                            return HopByHopResponse::Discard(param_problem());
-                        }
-                        _ => unreachable!(),
+                Ipv6OptionRepr::Unknown { type_, .. } => match Ipv6OptionFailureType::from(type_) {
+                    Ipv6OptionFailureType::Skip => (),
+                    Ipv6OptionFailureType::Discard => {
+                        return HopByHopResponse::Discard(None);
                     }
-                }
+                    Ipv6OptionFailureType::DiscardSendUnicast
+                        if ipv6_repr.dst_addr.is_multicast() =>
+                    {
+                        return HopByHopResponse::Discard(None);
+                    }
+                    Ipv6OptionFailureType::DiscardSendAll
+                    | Ipv6OptionFailureType::DiscardSendUnicast => {
+                        return HopByHopResponse::Discard(self.icmpv6_param_problem(
+                            ipv6_repr,
+                            ip_payload,
+                            Icmpv6ParamProblem::UnrecognizedOption,
+                            opt_offset,
+                        ));
+                    }
+                    Ipv6OptionFailureType::Unknown(_) => unreachable!(),
+                },
             }
+
+            opt_offset += opt_repr.buffer_len();
         }
 
         HopByHopResponse::Continue((
@@ -255,14 +299,23 @@ impl InterfaceInner {
         sockets: &mut SocketSet,
         meta: PacketMeta,
         ipv6_repr: Ipv6Repr,
+        ipv6_payload: &'frame [u8],
         nxt_hdr: IpProtocol,
-        handled_by_raw_socket: bool,
         ip_payload: &'frame [u8],
     ) -> Option<Packet<'frame>> {
+        #[cfg(feature = "socket-raw")]
+        let handled_by_raw_socket = self.raw_socket_filter(sockets, &ipv6_repr.into(), ip_payload);
+        #[cfg(not(feature = "socket-raw"))]
+        let handled_by_raw_socket = false;
+
         match nxt_hdr {
             IpProtocol::Icmpv6 => self.process_icmpv6(sockets, ipv6_repr, ip_payload),
 
-            #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
+            #[cfg(any(
+                feature = "socket-udp",
+                feature = "socket-dns",
+                feature = "socket-tftp"
+            ))]
             IpProtocol::Udp => self.process_udp(
                 sockets,
                 meta,
@@ -278,21 +331,45 @@ impl InterfaceInner {
             _ if handled_by_raw_socket => None,
 
             _ => {
-                // Send back as much of the original payload as we can.
-                let payload_len =
-                    icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
-                let icmp_reply_repr = Icmpv6Repr::ParamProblem {
-                    reason: Icmpv6ParamProblem::UnrecognizedNxtHdr,
-                    // The offending packet is after the IPv6 header.
-                    pointer: ipv6_repr.buffer_len() as u32,
-                    header: ipv6_repr,
-                    data: &ip_payload[0..payload_len],
+                // Point at the Next Header field holding the unrecognized value: the one of
+                // the IPv6 header, or the one that starts the Hop-by-Hop header, which is the
+                // only extension header processed before.
+                let pointer = if ip_payload.len() == ipv6_payload.len() {
+                    IPV6_NEXT_HEADER_OFFSET
+                } else {
+                    ipv6_repr.buffer_len()
                 };
-                self.icmpv6_reply(ipv6_repr, icmp_reply_repr)
+                self.icmpv6_param_problem(
+                    ipv6_repr,
+                    ipv6_payload,
+                    Icmpv6ParamProblem::UnrecognizedNxtHdr,
+                    pointer,
+                )
             }
         }
     }
 
+    /// Build a Parameter Problem message about the packet with the header `ipv6_repr` and
+    /// the payload `ipv6_payload`, pointing at the octet at offset `pointer` of the packet.
+    fn icmpv6_param_problem<'frame>(
+        &mut self,
+        ipv6_repr: Ipv6Repr,
+        ipv6_payload: &'frame [u8],
+        reason: Icmpv6ParamProblem,
+        pointer: usize,
+    ) -> Option<Packet<'frame>> {
+        // Send back as much of the original payload as we can.
+        let payload_len =
+            icmp_reply_payload_len(ipv6_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
+        let icmp_reply_repr = Icmpv6Repr::ParamProblem {
+            reason,
+            pointer: pointer as u32,
+            header: ipv6_repr,
+            data: &ipv6_payload[0..payload_len],
+        };
+        self.icmpv6_error(ipv6_repr, icmp_reply_repr)
+    }
+
     pub(super) fn process_icmpv6<'frame>(
         &mut self,
         _sockets: &mut SocketSet,
@@ -326,6 +403,12 @@ impl InterfaceInner {
 
         match icmp_repr {
             // Respond to echo requests.
+            Icmpv6Repr::EchoRequest { .. }
+                if !self.echo_broadcast && ip_repr.dst_addr.is_multicast() =>
+            {
+                net_debug!("ignoring echo request to {}", ip_repr.dst_addr);
+                None
+            }
             Icmpv6Repr::EchoRequest {
                 ident,
                 seq_no,
@@ -376,15 +459,28 @@ impl InterfaceInner {
                 flags,
             } => {
                 let ip_addr = ip_repr.src_addr.into();
+                let lladdr = match lladdr {
+                    Some(lladdr) => Some(check!(lladdr.parse(self.caps.medium))),
+                    None => None,
+                };
+
+                // Another host advertising one of our addresses (RFC 4862 § 5.4.4).
+                if self.has_ip_addr(target_addr) && lladdr != Some(self.hardware_addr) {
+                    net_debug!("ndisc: neighbor advertises our address {}", target_addr);
+                    self.events.push(Event::AddressConflict {
+                        addr: target_addr.into(),
+                        hardware_addr: lladdr,
+                    });
+                }
+
                 if let Some(lladdr) = lladdr {
-                    let lladdr = check!(lladdr.parse(self.caps.medium));
                     if !lladdr.is_unicast() || !target_addr.is_unicast() {
                         return None;
                     }
                     if flags.contains(NdiscNeighborFlags::OVERRIDE)
                         || !self.neighbor_cache.lookup(&ip_addr, self.now).found()
                     {
-                        self.neighbor_cache.fill(ip_addr, lladdr, self.now)
+                        self.fill_neighbor(ip_addr, lladdr, self.now)
                     }
                 }
                 None
@@ -394,25 +490,72 @@ impl InterfaceInner {
                 lladdr,
                 ..
             } => {
+                let dad = ip_repr.src_addr.is_unspecified();
+
+                // Another host running Duplicate Address Detection for one of our
+                // addresses (RFC 4862 § 5.4.3).
+                if dad && self.has_ip_addr(target_addr) {
+                    net_debug!(
+                        "ndisc: duplicate address detection for our address {}",
+                        target_addr
+                    );
+                    self.events.push(Event::AddressConflict {
+                        addr: target_addr.into(),
+                        hardware_addr: None,
+                    });
+                }
+
+                // A solicitation from the unspecified address must not carry a
+                // link-layer address option, and there is no neighbor to learn.
+                if dad && lladdr.is_some() {
+                    return None;
+                }
+
                 if let Some(lladdr) = lladdr {
                     let lladdr = check!(lladdr.parse(self.caps.medium));
                     if !lladdr.is_unicast() || !target_addr.is_unicast() {
                         return None;
                     }
-                    self.neighbor_cache
-                        .fill(ip_repr.src_addr.into(), lladdr, self.now);
+                    self.fill_neighbor(ip_repr.src_addr.into(), lladdr, self.now);
                 }
 
-                if self.has_solicited_node(ip_repr.dst_addr) && self.has_ip_addr(target_addr) {
+                let for_us =
+                    self.has_solicited_node(ip_repr.dst_addr) && self.has_ip_addr(target_addr);
+                let proxied = self.is_proxied(target_addr)
+                    && ip_repr.dst_addr == target_addr.solicited_node();
+                if for_us || proxied {
+                    // Answer Duplicate Address Detection to all nodes, so the other host
+                    // sees our address is taken (RFC 4861 § 7.2.4).
+                    let (flags, dst_addr) = if dad {
+                        (
+                            NdiscNeighborFlags::OVERRIDE,
+                            Ipv6Address::LINK_LOCAL_ALL_NODES,
+                        )
+                    } else {
+                        (NdiscNeighborFlags::SOLICITED, ip_repr.src_addr)
+                    };
+                    // Proxied advertisements come from one of our own addresses, not from
+                    // the target (RFC 4861 § 7.2.2).
+                    let src_addr = if for_us {
+                        target_addr
+                    } else {
+                        match self.get_source_address_ipv6(&dst_addr) {
+                            Some(addr) => addr,
+                            None => {
+                                net_debug!("ndisc: no source address to proxy {}", target_addr);
+                                return None;
+                            }
+                        }
+                    };
                     let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
-                        flags: NdiscNeighborFlags::SOLICITED,
+                        flags,
                         target_addr,
                         #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
                         lladdr: Some(self.hardware_addr.into()),
                     });
                     let ip_repr = Ipv6Repr {
-                        src_addr: target_addr,
-                        dst_addr: ip_repr.src_addr,
+                        src_addr,
+                        dst_addr,
                         next_header: IpProtocol::Icmpv6,
                         hop_limit: 0xff,
                         payload_len: advert.buffer_len(),
@@ -422,10 +565,80 @@ impl InterfaceInner {
                     None
                 }
             }
+            NdiscRepr::Redirect {
+                target_addr,
+                dest_addr,
+                lladdr,
+                ..
+            } => {
+                // Only the router currently used for the destination may redirect it, and
+                // only to another router on the link or to the destination itself
+                // (RFC 4861 § 8.1).
+                if !self.accept_redirects {
+                    net_debug!("ndisc: ignoring redirect for {}", dest_addr);
+                    return None;
+                }
+                if !ip_repr.src_addr.is_link_local()
+                    || dest_addr.is_multicast()
+                    || !(target_addr.is_link_local() || target_addr == dest_addr)
+                    || self.route(&dest_addr.into(), self.now) != Some(ip_repr.src_addr.into())
+                {
+                    net_debug!("ndisc: ignoring invalid redirect for {}", dest_addr);
+                    return None;
+                }
+
+                if let Some(lladdr) = lladdr {
+                    let lladdr = check!(lladdr.parse(self.caps.medium));
+                    if lladdr.is_unicast() {
+                        self.fill_neighbor(target_addr.into(), lladdr, self.now);
+                    }
+                }
+
+                net_debug!("ndisc: redirecting {} via {}", dest_addr, target_addr);
+                self.routes
+                    .redirect(dest_addr.into(), target_addr.into(), self.now);
+                self.events.push(Event::RoutesChanged);
+                None
+            }
             _ => None,
         }
     }
 
+    /// Build an ICMPv6 error message in response to the packet with the header `ipv6_repr`,
+    /// unless too many error messages were sent recently (RFC 4443 § 2.4 (f)).
+    ///
+    /// Error messages are limited with a token bucket, which allows bursts of
+    /// `ICMPV6_ERROR_BURST` messages, and one more message every `ICMPV6_ERROR_INTERVAL`.
+    pub(super) fn icmpv6_error<'frame, 'icmp: 'frame>(
+        &mut self,
+        ipv6_repr: Ipv6Repr,
+        icmp_repr: Icmpv6Repr<'icmp>,
+    ) -> Option<Packet<'frame>> {
+        if self.now < self.icmpv6_error_refilled_at {
+            // The clock went backwards.
+            self.icmpv6_error_refilled_at = self.now;
+        }
+        let refills = (self.now - self.icmpv6_error_refilled_at).total_micros()
+            / ICMPV6_ERROR_INTERVAL.total_micros();
+        if refills > 0 {
+            let tokens = (self.icmpv6_error_tokens as u64 + refills).min(ICMPV6_ERROR_BURST as u64);
+            self.icmpv6_error_tokens = tokens as u8;
+            self.icmpv6_error_refilled_at +=
+                Duration::from_micros(refills * ICMPV6_ERROR_INTERVAL.total_micros());
+        }
+
+        if self.icmpv6_error_tokens == 0 {
+            net_debug!(
+                "icmpv6: rate limiting error message to {}",
+                ipv6_repr.src_addr
+            );
+            return None;
+        }
+        self.icmpv6_error_tokens -= 1;
+
+        self.icmpv6_reply(ipv6_repr, icmp_repr)
+    }
+
     pub(super) fn icmpv6_reply<'frame, 'icmp: 'frame>(
         &self,
         ipv6_repr: Ipv6Repr,
@@ -19,9 +19,15 @@ mod sixlowpan;
 
 #[cfg(feature = "proto-igmp")]
 mod igmp;
+#[cfg(feature = "alloc")]
+mod inject;
 #[cfg(feature = "socket-tcp")]
 mod tcp;
-#[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
+#[cfg(any(
+    feature = "socket-udp",
+    feature = "socket-dns",
+    feature = "socket-tftp"
+))]
 mod udp;
 
 #[cfg(feature = "proto-igmp")]
@@ -32,6 +38,7 @@ use super::packet::*;
 use core::result::Result;
 use heapless::{LinearMap, Vec};
 
+use super::event::{DropReason, Event, EventQueue};
 #[cfg(feature = "_proto-fragmentation")]
 use super::fragmentation::FragKey;
 #[cfg(any(feature = "proto-ipv4", feature = "proto-sixlowpan"))]
@@ -39,18 +46,18 @@ use super::fragmentation::PacketAssemblerSet;
 use super::fragmentation::{Fragmenter, FragmentsBuffer};
 
 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
-use super::neighbor::{Answer as NeighborAnswer, Cache as NeighborCache};
-use super::socket_set::SocketSet;
+use super::neighbor::{Answer as NeighborAnswer, Cache as NeighborCache, Filled as NeighborFilled};
+use super::socket_set::{Item, SocketHandle, SocketSet};
 use crate::config::{
-    IFACE_MAX_ADDR_COUNT, IFACE_MAX_MULTICAST_GROUP_COUNT,
+    IFACE_MAX_ADDR_COUNT, IFACE_MAX_MULTICAST_GROUP_COUNT, IFACE_MAX_PROXY_ADDR_COUNT,
     IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT,
 };
 use crate::iface::Routes;
 use crate::phy::PacketMeta;
 use crate::phy::{ChecksumCapabilities, Device, DeviceCapabilities, Medium, RxToken, TxToken};
-use crate::rand::Rand;
+use crate::rand::{Rand, RandSource};
 use crate::socket::*;
-use crate::time::{Duration, Instant};
+use crate::time::{Clock, Duration, Instant};
 
 use crate::wire::*;
 
@@ -76,10 +83,11 @@ use check;
 /// The network interface logically owns a number of other data structures; to avoid
 /// a dependency on heap allocation, it instead owns a `BorrowMut<[T]>`, which can be
 /// a `&mut [T]`, or `Vec<T>` if a heap is available.
-pub struct Interface {
-    pub(crate) inner: InterfaceInner,
+pub struct Interface<'a> {
+    pub(crate) inner: InterfaceInner<'a>,
     fragments: FragmentsBuffer,
     fragmenter: Fragmenter,
+    clock: Option<&'a dyn Clock>,
 }
 
 /// The device independent part of an Ethernet network interface.
@@ -89,10 +97,16 @@ pub struct Interface {
 /// the `device` mutably until they're used, which makes it impossible to call other
 /// methods on the `Interface` in this time (since its `device` field is borrowed
 /// exclusively). However, it is still possible to call methods on its `inner` field.
-pub struct InterfaceInner {
+pub struct InterfaceInner<'a> {
     caps: DeviceCapabilities,
     now: Instant,
-    rand: Rand,
+    rand: Rand<'a>,
+    /// Key for TCP initial sequence numbers, chosen when the first one is needed.
+    #[cfg(feature = "socket-tcp")]
+    tcp_isn_secret: Option<u64>,
+    /// Key for automatic IPv6 Flow Labels, chosen when the first one is needed.
+    #[cfg(any(feature = "socket-tcp", feature = "socket-udp", feature = "socket-raw"))]
+    flow_label_secret: Option<u64>,
 
     #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
     neighbor_cache: NeighborCache,
@@ -101,6 +115,8 @@ pub struct InterfaceInner {
     sequence_no: u8,
     #[cfg(feature = "medium-ieee802154")]
     pan_id: Option<Ieee802154Pan>,
+    #[cfg(feature = "medium-ieee802154")]
+    short_addr: Option<Ieee802154Address>,
     #[cfg(feature = "proto-ipv4-fragmentation")]
     ipv4_id: u16,
     #[cfg(feature = "proto-sixlowpan")]
@@ -109,9 +125,23 @@ pub struct InterfaceInner {
     #[cfg(feature = "proto-sixlowpan-fragmentation")]
     tag: u16,
     ip_addrs: Vec<IpCidr, IFACE_MAX_ADDR_COUNT>,
+    proxy_addrs: Vec<IpCidr, IFACE_MAX_PROXY_ADDR_COUNT>,
     #[cfg(feature = "proto-ipv4")]
     any_ip: bool,
+    echo_broadcast: bool,
+    accept_redirects: bool,
+    events: EventQueue,
     routes: Routes,
+    egress_policy: EgressPolicy,
+    unspecified_source_policy: UnspecifiedSourcePolicy,
+    /// Tokens left for sending ICMPv6 error messages, see `icmpv6_error`.
+    #[cfg(feature = "proto-ipv6")]
+    icmpv6_error_tokens: u8,
+    /// When `icmpv6_error_tokens` was last refilled.
+    #[cfg(feature = "proto-ipv6")]
+    icmpv6_error_refilled_at: Instant,
+    /// The last socket that transmitted a packet, see [EgressPolicy::RoundRobin].
+    last_egress: Option<SocketHandle>,
     #[cfg(feature = "proto-igmp")]
     ipv4_multicast_groups: LinearMap<Ipv4Address, (), IFACE_MAX_MULTICAST_GROUP_COUNT>,
     /// When to report for (all or) the next multicast group membership via IGMP
@@ -121,7 +151,7 @@ pub struct InterfaceInner {
 
 /// Configuration structure used for creating a network interface.
 #[non_exhaustive]
-pub struct Config {
+pub struct Config<'a> {
     /// Random seed.
     ///
     /// It is strongly recommended that the random seed is different on each boot,
@@ -130,6 +160,22 @@ pub struct Config {
     /// The seed doesn't have to be cryptographically secure.
     pub random_seed: u64,
 
+    /// Source of random numbers, e.g. a hardware random number generator.
+    ///
+    /// If set, it is used instead of the built-in pseudo-random number generator, and
+    /// [`random_seed`](Self::random_seed) is ignored. This is recommended when a secure
+    /// source is available, since the random numbers are used for TCP initial sequence
+    /// numbers. The source is borrowed for as long as the interface exists.
+    pub rand_source: Option<&'a mut dyn RandSource>,
+
+    /// Source of the current time.
+    ///
+    /// If set, the interface can be polled without passing a timestamp, see
+    /// [`Interface::poll_with_clock`], and the socket context returned by
+    /// [`Interface::context`] is brought up to its time, so that sockets see the same
+    /// time when e.g. connecting as they do when polled.
+    pub clock: Option<&'a dyn Clock>,
+
     /// Set the Hardware address the interface will use.
     ///
     /// # Panics
@@ -141,26 +187,84 @@ pub struct Config {
     /// **NOTE**: we use the same PAN ID for destination and source.
     #[cfg(feature = "medium-ieee802154")]
     pub pan_id: Option<Ieee802154Pan>,
+
+    /// Set the IEEE802.15.4 short address the interface will use.
+    ///
+    /// When set, it is used instead of the extended address from [`hardware_addr`](Self::hardware_addr)
+    /// as the source of outgoing frames.
+    ///
+    /// # Panics
+    /// Creating the interface panics if the address is not a unicast short address.
+    #[cfg(feature = "medium-ieee802154")]
+    pub short_addr: Option<Ieee802154Address>,
 }
 
-impl Config {
+impl<'a> Config<'a> {
     pub fn new(hardware_addr: HardwareAddress) -> Self {
         Config {
             random_seed: 0,
+            rand_source: None,
+            clock: None,
             hardware_addr,
             #[cfg(feature = "medium-ieee802154")]
             pan_id: None,
+            #[cfg(feature = "medium-ieee802154")]
+            short_addr: None,
         }
     }
 }
 
-impl Interface {
+/// The order in which sockets are given a chance to transmit.
+///
+/// Each time the interface polls its sockets for egress, every socket may transmit at
+/// most one packet, in turn, for as long as the device accepts packets. When the device
+/// accepts fewer packets per poll than there are sockets with data to send, the policy
+/// decides which sockets go first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum EgressPolicy {
+    /// Sockets are polled in the order of their handles, starting with the first one.
+    ///
+    /// A socket that always has data to send, such as a bulk TCP transfer, can keep the
+    /// sockets after it from transmitting when the device is congested.
+    #[default]
+    Fifo,
+    /// Sockets are polled in the order of their handles, starting after the last socket
+    /// that transmitted a packet, so that every socket with data to send gets its turn.
+    RoundRobin,
+}
+
+/// Which incoming packets with an unspecified source address are accepted.
+///
+/// Hosts that don't have an address yet send packets from the unspecified address
+/// (`0.0.0.0` or `::`), but other packets from it are most likely spoofed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum UnspecifiedSourcePolicy {
+    /// Accept only the packets of protocols that legitimately send them, which are UDP
+    /// datagrams to the DHCP client and server ports and IGMP membership reports for IPv4,
+    /// and the Neighbor Solicitations of Duplicate Address Detection for IPv6.
+    #[default]
+    Restricted,
+    /// Accept all packets with an unspecified source address.
+    Accept,
+    /// Drop all packets with an unspecified source address.
+    ///
+    /// This disables DHCP replies that are sent from the unspecified address, and the
+    /// detection of other hosts claiming our IPv6 addresses during Duplicate Address
+    /// Detection.
+    Drop,
+}
+
+impl<'a> Interface<'a> {
     /// Create a network interface using the previously provided configuration.
     ///
     /// # Panics
     /// This function panics if the [`Config::hardware_address`] does not match
     /// the medium of the device.
-    pub fn new<D>(config: Config, device: &mut D, now: Instant) -> Self
+    pub fn new<D>(config: Config<'a>, device: &mut D, now: Instant) -> Self
     where
         D: Device + ?Sized,
     {
@@ -170,8 +274,12 @@ impl Interface {
             caps.medium,
             "The hardware address does not match the medium of the interface."
         );
+        #[cfg(feature = "medium-ieee802154")]
+        if let Some(addr) = &config.short_addr {
+            InterfaceInner::check_short_addr(addr);
+        }
 
-        let mut rand = Rand::new(config.random_seed);
+        let mut rand = Rand::with_source(config.random_seed, config.rand_source);
 
         #[cfg(feature = "medium-ieee802154")]
         let mut sequence_no;
@@ -216,14 +324,26 @@ impl Interface {
                 reassembly_timeout: Duration::from_secs(60),
             },
             fragmenter: Fragmenter::new(),
+            clock: config.clock,
             inner: InterfaceInner {
                 now,
                 caps,
                 hardware_addr: config.hardware_addr,
                 ip_addrs: Vec::new(),
+                proxy_addrs: Vec::new(),
                 #[cfg(feature = "proto-ipv4")]
                 any_ip: false,
+                echo_broadcast: false,
+                accept_redirects: false,
+                events: EventQueue::new(),
                 routes: Routes::new(),
+                egress_policy: EgressPolicy::Fifo,
+                unspecified_source_policy: UnspecifiedSourcePolicy::Restricted,
+                #[cfg(feature = "proto-ipv6")]
+                icmpv6_error_tokens: ipv6::ICMPV6_ERROR_BURST,
+                #[cfg(feature = "proto-ipv6")]
+                icmpv6_error_refilled_at: now,
+                last_egress: None,
                 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
                 neighbor_cache: NeighborCache::new(),
                 #[cfg(feature = "proto-igmp")]
@@ -234,6 +354,8 @@ impl Interface {
                 sequence_no,
                 #[cfg(feature = "medium-ieee802154")]
                 pan_id: config.pan_id,
+                #[cfg(feature = "medium-ieee802154")]
+                short_addr: config.short_addr,
                 #[cfg(feature = "proto-sixlowpan-fragmentation")]
                 tag,
                 #[cfg(feature = "proto-ipv4-fragmentation")]
@@ -241,17 +363,76 @@ impl Interface {
                 #[cfg(feature = "proto-sixlowpan")]
                 sixlowpan_address_context: Vec::new(),
                 rand,
+                #[cfg(feature = "socket-tcp")]
+                tcp_isn_secret: None,
+                #[cfg(any(
+                    feature = "socket-tcp",
+                    feature = "socket-udp",
+                    feature = "socket-raw"
+                ))]
+                flow_label_secret: None,
             },
         }
     }
 
     /// Get the socket context.
     ///
-    /// The context is needed for some socket methods.
-    pub fn context(&mut self) -> &mut InterfaceInner {
+    /// The context is needed for some socket methods. If the interface has a
+    /// [clock](Self::clock), the time of the context is set to the time of the clock.
+    pub fn context(&mut self) -> &mut InterfaceInner<'a> {
+        if let Some(clock) = self.clock {
+            self.inner.now = clock.now();
+        }
         &mut self.inner
     }
 
+    /// Get the clock of the interface, see [`Config::clock`].
+    pub fn clock(&self) -> Option<&'a dyn Clock> {
+        self.clock
+    }
+
+    /// Set the clock of the interface, see [`Config::clock`].
+    pub fn set_clock(&mut self, clock: Option<&'a dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Read the capabilities of the device again, and use them from now on.
+    ///
+    /// The capabilities of the device are read when the interface is created. Call this
+    /// function when they change, e.g. when a tunnel renegotiates its MTU. The new MTU
+    /// applies to every packet sent afterwards: TCP sockets, including already established
+    /// ones, shrink or grow their segments and the MSS they announce accordingly, and
+    /// packets larger than the MTU are fragmented according to it.
+    ///
+    /// # Panics
+    /// This function panics if the medium of the device changed.
+    pub fn update_capabilities<D>(&mut self, device: &D)
+    where
+        D: Device + ?Sized,
+    {
+        let caps = device.capabilities();
+        assert_eq!(
+            caps.medium, self.inner.caps.medium,
+            "The medium of the device changed."
+        );
+        if caps.max_transmission_unit != self.inner.caps.max_transmission_unit {
+            net_debug!(
+                "iface: device MTU changed from {} to {}",
+                self.inner.caps.max_transmission_unit,
+                caps.max_transmission_unit
+            );
+        }
+        self.inner.caps = caps;
+    }
+
+    /// Get the maximum size of the IP packets sent by the interface.
+    ///
+    /// This is derived from the MTU of the device, see
+    /// [update_capabilities](Self::update_capabilities).
+    pub fn ip_mtu(&self) -> usize {
+        self.inner.ip_mtu()
+    }
+
     /// Get the HardwareAddress address of the interface.
     ///
     /// # Panics
@@ -294,6 +475,43 @@ impl Interface {
         self.inner.hardware_addr = addr;
     }
 
+    /// Get the IEEE802.15.4 PAN ID of the interface.
+    #[cfg(feature = "medium-ieee802154")]
+    pub fn pan_id(&self) -> Option<Ieee802154Pan> {
+        self.inner.pan_id
+    }
+
+    /// Set the IEEE802.15.4 PAN ID of the interface.
+    ///
+    /// When set, frames addressed to a different (non-broadcast) PAN are dropped, and
+    /// outgoing frames carry it as both the source and destination PAN ID.
+    #[cfg(feature = "medium-ieee802154")]
+    pub fn set_pan_id(&mut self, pan_id: Option<Ieee802154Pan>) {
+        self.inner.pan_id = pan_id;
+    }
+
+    /// Get the IEEE802.15.4 short address of the interface.
+    #[cfg(feature = "medium-ieee802154")]
+    pub fn short_addr(&self) -> Option<Ieee802154Address> {
+        self.inner.short_addr
+    }
+
+    /// Set the IEEE802.15.4 short address of the interface.
+    ///
+    /// When a short address is assigned, it is used as the source address of outgoing
+    /// frames, which also lets 6LoWPAN elide IPv6 addresses derived from it
+    /// (`fe80::ff:fe00:XXXX`). Otherwise the extended address is used.
+    ///
+    /// # Panics
+    /// This function panics if the address is not a unicast short address.
+    #[cfg(feature = "medium-ieee802154")]
+    pub fn set_short_addr(&mut self, addr: Option<Ieee802154Address>) {
+        if let Some(addr) = &addr {
+            InterfaceInner::check_short_addr(addr);
+        }
+        self.inner.short_addr = addr;
+    }
+
     /// Get the IP addresses of the interface.
     pub fn ip_addrs(&self) -> &[IpCidr] {
         self.inner.ip_addrs.as_ref()
@@ -337,9 +555,23 @@ impl Interface {
     /// # Panics
     /// This function panics if any of the addresses are not unicast.
     pub fn update_ip_addrs<F: FnOnce(&mut Vec<IpCidr, IFACE_MAX_ADDR_COUNT>)>(&mut self, f: F) {
+        let old_addrs = self.inner.ip_addrs.clone();
         f(&mut self.inner.ip_addrs);
         InterfaceInner::flush_neighbor_cache(&mut self.inner);
-        InterfaceInner::check_ip_addrs(&self.inner.ip_addrs)
+        InterfaceInner::check_ip_addrs(&self.inner.ip_addrs);
+
+        if self.inner.events.enabled() {
+            for cidr in old_addrs.iter() {
+                if !self.inner.ip_addrs.contains(cidr) {
+                    self.inner.events.push(Event::AddressRemoved(*cidr));
+                }
+            }
+            for cidr in self.inner.ip_addrs.iter() {
+                if !old_addrs.contains(cidr) {
+                    self.inner.events.push(Event::AddressAdded(*cidr));
+                }
+            }
+        }
     }
 
     /// Check whether the interface has the given IP address assigned.
@@ -347,11 +579,41 @@ impl Interface {
         self.inner.has_ip_addr(addr)
     }
 
+    /// Get the address ranges the interface answers neighbor discovery for.
+    ///
+    /// See also the [update_proxy_addrs](#method.update_proxy_addrs) method.
+    pub fn proxy_addrs(&self) -> &[IpCidr] {
+        self.inner.proxy_addrs.as_ref()
+    }
+
+    /// Update the address ranges the interface answers neighbor discovery for.
+    ///
+    /// When an ARP request or an NDP Neighbor Solicitation arrives for an address that is
+    /// not assigned to the interface but falls within one of these ranges, the interface
+    /// replies with its own hardware address (proxy ARP, [RFC 1027], and proxy NDP,
+    /// [RFC 4861 § 7.2.8]). Packets subsequently sent to such addresses are not accepted
+    /// locally; forwarding them is up to the application.
+    ///
+    /// By default, no addresses are proxied.
+    ///
+    /// [RFC 1027]: https://tools.ietf.org/html/rfc1027
+    /// [RFC 4861 § 7.2.8]: https://tools.ietf.org/html/rfc4861#section-7.2.8
+    pub fn update_proxy_addrs<F: FnOnce(&mut Vec<IpCidr, IFACE_MAX_PROXY_ADDR_COUNT>)>(
+        &mut self,
+        f: F,
+    ) {
+        f(&mut self.inner.proxy_addrs);
+    }
+
     pub fn routes(&self) -> &Routes {
         &self.inner.routes
     }
 
+    /// Get the routing table for modification.
+    ///
+    /// If events are enabled, this records an [Event::RoutesChanged].
     pub fn routes_mut(&mut self) -> &mut Routes {
+        self.inner.events.push(Event::RoutesChanged);
         &mut self.inner.routes
     }
 
@@ -380,6 +642,101 @@ impl Interface {
         self.inner.any_ip
     }
 
+    /// Enable or disable replying to ICMP echo requests sent to a broadcast or
+    /// multicast address.
+    ///
+    /// This covers IPv4 broadcast and multicast destinations as well as IPv6
+    /// multicast destinations. Answering such requests lets a single spoofed packet
+    /// trigger a reply from every host on the link (a "smurf" amplification attack),
+    /// so it is disabled by default. Echo requests sent to a unicast address are
+    /// always answered.
+    pub fn set_echo_broadcast(&mut self, enabled: bool) {
+        self.inner.echo_broadcast = enabled;
+    }
+
+    /// Get whether ICMP echo requests sent to a broadcast or multicast address are answered.
+    ///
+    /// See [`set_echo_broadcast`](Self::set_echo_broadcast) for details.
+    pub fn echo_broadcast(&self) -> bool {
+        self.inner.echo_broadcast
+    }
+
+    /// Enable or disable following ICMP and ICMPv6 redirects.
+    ///
+    /// When enabled, a redirect from the router currently used for a destination installs
+    /// a route to that destination via the router it names, for 10 minutes. These routes
+    /// are kept apart from the [`routes`](Self::routes) of the application, and never take
+    /// their place. Redirects are disabled by default, since a host on the link that can
+    /// pose as the router could use them to divert traffic.
+    pub fn set_accept_redirects(&mut self, enabled: bool) {
+        self.inner.accept_redirects = enabled;
+    }
+
+    /// Get whether ICMP and ICMPv6 redirects are followed.
+    ///
+    /// See [`set_accept_redirects`](Self::set_accept_redirects) for details.
+    pub fn accept_redirects(&self) -> bool {
+        self.inner.accept_redirects
+    }
+
+    /// Set the order in which sockets are given a chance to transmit.
+    ///
+    /// See [`EgressPolicy`] for details. The default is [`EgressPolicy::Fifo`].
+    pub fn set_egress_policy(&mut self, policy: EgressPolicy) {
+        self.inner.egress_policy = policy;
+        self.inner.last_egress = None;
+    }
+
+    /// Get the order in which sockets are given a chance to transmit.
+    ///
+    /// See [`set_egress_policy`](Self::set_egress_policy) for details.
+    pub fn egress_policy(&self) -> EgressPolicy {
+        self.inner.egress_policy
+    }
+
+    /// Set which incoming packets with an unspecified source address are accepted.
+    ///
+    /// See [`UnspecifiedSourcePolicy`] for details. The default is
+    /// [`UnspecifiedSourcePolicy::Restricted`].
+    pub fn set_unspecified_source_policy(&mut self, policy: UnspecifiedSourcePolicy) {
+        self.inner.unspecified_source_policy = policy;
+    }
+
+    /// Get which incoming packets with an unspecified source address are accepted.
+    ///
+    /// See [`set_unspecified_source_policy`](Self::set_unspecified_source_policy) for
+    /// details.
+    pub fn unspecified_source_policy(&self) -> UnspecifiedSourcePolicy {
+        self.inner.unspecified_source_policy
+    }
+
+    /// Enable or disable recording of interface events.
+    ///
+    /// When enabled, the interface records what happens to it, such as addresses being
+    /// configured, neighbors being learned or evicted, address conflicts with other hosts,
+    /// and packets being dropped, in a queue that [next_event](Self::next_event) drains.
+    /// The queue holds up to `IFACE_MAX_EVENT_COUNT` events; when it is full, the oldest
+    /// event is discarded. Disabling events clears the queue.
+    ///
+    /// Events are disabled by default.
+    pub fn set_events_enabled(&mut self, enabled: bool) {
+        self.inner.events.set_enabled(enabled);
+    }
+
+    /// Get whether interface events are recorded.
+    ///
+    /// See [`set_events_enabled`](Self::set_events_enabled) for details.
+    pub fn events_enabled(&self) -> bool {
+        self.inner.events.enabled()
+    }
+
+    /// Remove and return the oldest recorded interface event.
+    ///
+    /// Call this in a loop after [poll](Self::poll) until it returns `None`.
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.inner.events.pop()
+    }
+
     /// Get the packet reassembly timeout.
     #[cfg(feature = "_proto-fragmentation")]
     pub fn reassembly_timeout(&self) -> Duration {
@@ -395,6 +752,90 @@ impl Interface {
         self.fragments.reassembly_timeout = timeout;
     }
 
+    /// Remove all entries from the neighbor cache.
+    ///
+    /// Pending lookups are forgotten as well, so the next packet sent to any neighbor
+    /// starts a new ARP or Neighbor Discovery exchange.
+    pub fn flush_neighbor_cache(&mut self) {
+        self.inner.flush_neighbor_cache()
+    }
+
+    /// Set how many times a neighbor that does not answer is solicited again, with ARP
+    /// requests or Neighbor Solicitations, before it is considered unreachable.
+    ///
+    /// Once a neighbor is unreachable, the packets sockets send to it are dropped instead
+    /// of waiting for it to be discovered, and TCP sockets that are connecting to it are
+    /// closed, until discovery starts over with the next packet sent to it one
+    /// [solicitation interval](Self::set_neighbor_solicit_interval) later. The same
+    /// policy applies to ARP and to Neighbor Discovery.
+    ///
+    /// By default, there is no limit, and packets wait for the neighbor indefinitely.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn set_neighbor_solicit_retries(&mut self, retries: Option<u8>) {
+        self.inner.neighbor_cache.set_solicit_retries(retries)
+    }
+
+    /// Get how many times a neighbor that does not answer is solicited again.
+    ///
+    /// See [`set_neighbor_solicit_retries`](Self::set_neighbor_solicit_retries) for details.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn neighbor_solicit_retries(&self) -> Option<u8> {
+        self.inner.neighbor_cache.solicit_retries()
+    }
+
+    /// Set the delay between two solicitations of a neighbor that does not answer.
+    ///
+    /// By default, the delay is 1s.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn set_neighbor_solicit_interval(&mut self, interval: Duration) {
+        self.inner.neighbor_cache.set_solicit_interval(interval)
+    }
+
+    /// Get the delay between two solicitations of a neighbor that does not answer.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn neighbor_solicit_interval(&self) -> Duration {
+        self.inner.neighbor_cache.solicit_interval()
+    }
+
+    /// Discard all partially reassembled packets, and the remaining fragments of any
+    /// packet that is being transmitted in fragments.
+    pub fn flush_reassembly(&mut self) {
+        #[cfg(feature = "_proto-fragmentation")]
+        {
+            self.fragments.assembler.clear();
+            self.fragmenter.reset();
+        }
+    }
+
+    /// Forget the routes installed by redirects, see
+    /// [set_accept_redirects](#method.set_accept_redirects).
+    ///
+    /// If events are enabled and there were any, this records an [Event::RoutesChanged].
+    pub fn flush_redirects(&mut self) {
+        if self.inner.routes.clear_redirects() {
+            self.inner.events.push(Event::RoutesChanged);
+        }
+    }
+
+    /// Reset the transient state of the interface, for example after the link went down
+    /// and up again, or the interface was moved to another network.
+    ///
+    /// This flushes the neighbor cache, the routes installed by redirects and all
+    /// reassembly state, see [flush_neighbor_cache](#method.flush_neighbor_cache),
+    /// [flush_redirects](#method.flush_redirects) and
+    /// [flush_reassembly](#method.flush_reassembly). The configuration of the interface,
+    /// such as its addresses, routes and multicast groups, and the sockets are left
+    /// untouched.
+    ///
+    /// The interface keeps no path MTU cache, since it sends every packet within the MTU
+    /// of the device, and does not run duplicate address detection, so there is nothing
+    /// else to reset.
+    pub fn reset(&mut self) {
+        self.flush_neighbor_cache();
+        self.flush_redirects();
+        self.flush_reassembly();
+    }
+
     /// Transmit packets queued in the given sockets, and receive packets queued
     /// in the device.
     ///
@@ -456,38 +897,88 @@ impl Interface {
         readiness_may_have_changed
     }
 
+    /// Like [poll](Self::poll), with the timestamp read from the [clock](Self::clock)
+    /// of the interface.
+    ///
+    /// Returns `None`, without polling, if the interface has no clock.
+    pub fn poll_with_clock<D>(
+        &mut self,
+        device: &mut D,
+        sockets: &mut SocketSet<'_>,
+    ) -> Option<bool>
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.clock?.now();
+        Some(self.poll(now, device, sockets))
+    }
+
     /// Return a _soft deadline_ for calling [poll] the next time.
     /// The [Instant] returned is the time at which you should call [poll] next.
     /// It is harmless (but wastes energy) to call it before the [Instant], and
     /// potentially harmful (impacting quality of service) to call it after the
     /// [Instant]
     ///
+    /// This is the same as [next_poll_at], except that it also advances the
+    /// interface's notion of the current time to `timestamp`.
+    ///
     /// [poll]: #method.poll
+    /// [next_poll_at]: #method.next_poll_at
     /// [Instant]: struct.Instant.html
     pub fn poll_at(&mut self, timestamp: Instant, sockets: &SocketSet<'_>) -> Option<Instant> {
         self.inner.now = timestamp;
+        self.next_poll_at(timestamp, sockets)
+    }
 
+    /// Return the next instant at which [poll] has work to do, or `None` if
+    /// it only needs to be called when a packet is received.
+    ///
+    /// This takes into account the timers of all sockets in the set (including
+    /// DHCP and DNS sockets), pending neighbor discovery, pending fragmented
+    /// transmissions, reassembly timeouts and IGMP reports.
+    ///
+    /// As this method only borrows the interface, socket timers are evaluated
+    /// against the time of the last [poll]; `timestamp` is used to check the
+    /// neighbor cache.
+    ///
+    /// [poll]: #method.poll
+    pub fn next_poll_at(&self, timestamp: Instant, sockets: &SocketSet<'_>) -> Option<Instant> {
         #[cfg(feature = "_proto-fragmentation")]
         if !self.fragmenter.is_empty() {
             return Some(Instant::from_millis(0));
         }
 
-        let inner = &mut self.inner;
+        let inner = &self.inner;
 
-        sockets
+        #[allow(unused_mut)]
+        let mut poll_at = sockets
             .items()
             .filter_map(move |item| {
                 let socket_poll_at = item.socket.poll_at(inner);
-                match item
-                    .meta
-                    .poll_at(socket_poll_at, |ip_addr| inner.has_neighbor(&ip_addr))
-                {
+                match item.meta.poll_at(socket_poll_at, |ip_addr| {
+                    inner.has_neighbor_at(&ip_addr, timestamp)
+                }) {
                     PollAt::Ingress => None,
                     PollAt::Time(instant) => Some(instant),
                     PollAt::Now => Some(Instant::from_millis(0)),
                 }
             })
-            .min()
+            .min();
+
+        #[cfg(feature = "_proto-fragmentation")]
+        {
+            poll_at = poll_at
+                .into_iter()
+                .chain(self.fragments.assembler.expires_at())
+                .min();
+        }
+
+        #[cfg(feature = "proto-igmp")]
+        {
+            poll_at = poll_at.into_iter().chain(self.inner.igmp_poll_at()).min();
+        }
+
+        poll_at
     }
 
     /// Return an _advisory wait time_ for calling [poll] the next time.
@@ -506,13 +997,23 @@ impl Interface {
         }
     }
 
+    /// Like [poll_delay](Self::poll_delay), with the timestamp read from the
+    /// [clock](Self::clock) of the interface.
+    ///
+    /// Returns `None` if the interface has no clock, and `Some` of the result of
+    /// [poll_delay](Self::poll_delay) otherwise.
+    pub fn poll_delay_with_clock(&mut self, sockets: &SocketSet<'_>) -> Option<Option<Duration>> {
+        let now = self.clock?.now();
+        Some(self.poll_delay(now, sockets))
+    }
+
     fn socket_ingress<D>(&mut self, device: &mut D, sockets: &mut SocketSet<'_>) -> bool
     where
         D: Device + ?Sized,
     {
         let mut processed_any = false;
 
-        while let Some((rx_token, tx_token)) = device.receive(self.inner.now) {
+        device.receive_many(self.inner.now, |rx_token, tx_token| {
             let rx_meta = rx_token.meta();
             rx_token.consume(|frame| {
                 if frame.is_empty() {
@@ -532,6 +1033,7 @@ impl Interface {
                                 self.inner.dispatch(tx_token, packet, &mut self.fragmenter)
                             {
                                 net_debug!("Failed to send response: {:?}", err);
+                                self.inner.drop_response(err);
                             }
                         }
                     }
@@ -548,6 +1050,7 @@ impl Interface {
                                 &mut self.fragmenter,
                             ) {
                                 net_debug!("Failed to send response: {:?}", err);
+                                self.inner.drop_response(err);
                             }
                         }
                     }
@@ -566,13 +1069,15 @@ impl Interface {
                                 &mut self.fragmenter,
                             ) {
                                 net_debug!("Failed to send response: {:?}", err);
+                                self.inner.drop_response(err);
                             }
                         }
                     }
                 }
                 processed_any = true;
             });
-        }
+            true
+        });
 
         processed_any
     }
@@ -585,100 +1090,149 @@ impl Interface {
 
         enum EgressError {
             Exhausted,
-            Dispatch(DispatchError),
+            Dispatch,
         }
 
         let mut emitted_any = false;
-        for item in sockets.items_mut() {
-            if !item
-                .meta
-                .egress_permitted(self.inner.now, |ip_addr| self.inner.has_neighbor(&ip_addr))
-            {
-                continue;
-            }
-
-            let mut neighbor_addr = None;
-            let mut respond = |inner: &mut InterfaceInner, meta: PacketMeta, response: Packet| {
-                neighbor_addr = Some(response.ip_repr().dst_addr());
-                let t = device.transmit(inner.now).ok_or_else(|| {
-                    net_debug!("failed to transmit IP: device exhausted");
-                    EgressError::Exhausted
-                })?;
-
-                inner
-                    .dispatch_ip(t, meta, response, &mut self.fragmenter)
-                    .map_err(EgressError::Dispatch)?;
-
-                emitted_any = true;
+        let start_after = match self.inner.egress_policy {
+            EgressPolicy::Fifo => None,
+            EgressPolicy::RoundRobin => self.inner.last_egress,
+        };
+        let mut items = sockets.items_mut_after(start_after);
+        // Only ask the device for a token once a socket has something to send.
+        let mut next_item = Self::next_egress_item(&mut self.inner, &mut items);
+        if next_item.is_none() {
+            return false;
+        }
+        device.transmit_many(self.inner.now, |tx_token| {
+            let mut tx_token = Some(tx_token);
+            while let Some(item) = next_item.take() {
+                let mut neighbor_addr = None;
+                let mut neighbor_unreachable = false;
+                let mut respond = |inner: &mut InterfaceInner,
+                                   meta: PacketMeta,
+                                   response: Packet|
+                 -> Result<(), EgressError> {
+                    neighbor_addr = Some(response.ip_repr().dst_addr());
+                    // A socket that emits a second packet gets it back, and sends it the
+                    // next time around.
+                    let t = tx_token.take().ok_or_else(|| {
+                        net_debug!("failed to transmit IP: device exhausted");
+                        EgressError::Exhausted
+                    })?;
+
+                    match inner.dispatch_ip(t, meta, response, &mut self.fragmenter) {
+                        Ok(()) => emitted_any = true,
+                        // The socket would only retry the packet once discovery starts over,
+                        // so report it as sent and drop it instead.
+                        Err(err @ DispatchError::NeighborUnreachable) => {
+                            inner.drop_response(err);
+                            neighbor_unreachable = true;
+                        }
+                        Err(_) => return Err(EgressError::Dispatch),
+                    }
 
-                Ok(())
-            };
+                    Ok(())
+                };
 
-            let result = match &mut item.socket {
-                #[cfg(feature = "socket-raw")]
-                Socket::Raw(socket) => socket.dispatch(&mut self.inner, |inner, (ip, raw)| {
-                    respond(
-                        inner,
-                        PacketMeta::default(),
-                        Packet::new(ip, IpPayload::Raw(raw)),
-                    )
-                }),
-                #[cfg(feature = "socket-icmp")]
-                Socket::Icmp(socket) => {
-                    socket.dispatch(&mut self.inner, |inner, response| match response {
-                        #[cfg(feature = "proto-ipv4")]
-                        (IpRepr::Ipv4(ipv4_repr), IcmpRepr::Ipv4(icmpv4_repr)) => respond(
-                            inner,
-                            PacketMeta::default(),
-                            Packet::new_ipv4(ipv4_repr, IpPayload::Icmpv4(icmpv4_repr)),
-                        ),
-                        #[cfg(feature = "proto-ipv6")]
-                        (IpRepr::Ipv6(ipv6_repr), IcmpRepr::Ipv6(icmpv6_repr)) => respond(
-                            inner,
-                            PacketMeta::default(),
-                            Packet::new_ipv6(ipv6_repr, IpPayload::Icmpv6(icmpv6_repr)),
-                        ),
-                        #[allow(unreachable_patterns)]
-                        _ => unreachable!(),
-                    })
-                }
-                #[cfg(feature = "socket-udp")]
-                Socket::Udp(socket) => {
-                    socket.dispatch(&mut self.inner, |inner, meta, (ip, udp, payload)| {
-                        respond(inner, meta, Packet::new(ip, IpPayload::Udp(udp, payload)))
-                    })
-                }
-                #[cfg(feature = "socket-tcp")]
-                Socket::Tcp(socket) => socket.dispatch(&mut self.inner, |inner, (ip, tcp)| {
-                    respond(
-                        inner,
-                        PacketMeta::default(),
-                        Packet::new(ip, IpPayload::Tcp(tcp)),
-                    )
-                }),
-                #[cfg(feature = "socket-dhcpv4")]
-                Socket::Dhcpv4(socket) => {
-                    socket.dispatch(&mut self.inner, |inner, (ip, udp, dhcp)| {
-                        respond(
-                            inner,
-                            PacketMeta::default(),
-                            Packet::new_ipv4(ip, IpPayload::Dhcpv4(udp, dhcp)),
-                        )
-                    })
-                }
-                #[cfg(feature = "socket-dns")]
-                Socket::Dns(socket) => socket.dispatch(&mut self.inner, |inner, (ip, udp, dns)| {
-                    respond(
-                        inner,
-                        PacketMeta::default(),
-                        Packet::new(ip, IpPayload::Udp(udp, dns)),
-                    )
-                }),
-            };
+                let result = match &mut item.socket {
+                    #[cfg(feature = "socket-raw")]
+                    Socket::Raw(socket) => {
+                        let dont_frag = socket.dont_fragment();
+                        let verbatim = socket.sends_verbatim();
+                        socket.dispatch(&mut self.inner, |inner, label, (ip, raw)| {
+                            let payload = if verbatim {
+                                IpPayload::Verbatim(raw)
+                            } else {
+                                IpPayload::Raw(raw)
+                            };
+                            let mut packet = Packet::new(ip, payload);
+                            // Fragmenting a verbatim packet would require rewriting its header.
+                            packet.set_dont_frag(dont_frag || verbatim);
+                            packet.set_flow_label(label);
+                            respond(inner, PacketMeta::default(), packet)
+                        })
+                    }
+                    #[cfg(feature = "socket-icmp")]
+                    Socket::Icmp(socket) => {
+                        socket.dispatch(&mut self.inner, |inner, response| match response {
+                            #[cfg(feature = "proto-ipv4")]
+                            (IpRepr::Ipv4(ipv4_repr), IcmpRepr::Ipv4(icmpv4_repr)) => respond(
+                                inner,
+                                PacketMeta::default(),
+                                Packet::new_ipv4(ipv4_repr, IpPayload::Icmpv4(icmpv4_repr)),
+                            ),
+                            #[cfg(feature = "proto-ipv6")]
+                            (IpRepr::Ipv6(ipv6_repr), IcmpRepr::Ipv6(icmpv6_repr)) => respond(
+                                inner,
+                                PacketMeta::default(),
+                                Packet::new_ipv6(ipv6_repr, IpPayload::Icmpv6(icmpv6_repr)),
+                            ),
+                            #[allow(unreachable_patterns)]
+                            _ => unreachable!(),
+                        })
+                    }
+                    #[cfg(feature = "socket-udp")]
+                    Socket::Udp(socket) => {
+                        let dont_frag = socket.dont_fragment();
+                        let flow_label = socket.flow_label();
+                        socket.dispatch(&mut self.inner, |inner, meta, (ip, udp, payload)| {
+                            let label = flow_label.value(inner, &ip, udp.src_port, udp.dst_port);
+                            let mut packet = Packet::new(ip, IpPayload::Udp(udp, payload));
+                            packet.set_dont_frag(dont_frag);
+                            packet.set_flow_label(label);
+                            respond(inner, meta, packet)
+                        })
+                    }
+                    #[cfg(feature = "socket-tcp")]
+                    Socket::Tcp(socket) => {
+                        let dont_frag = socket.dont_fragment();
+                        let flow_label = socket.flow_label();
+                        let result = socket.dispatch(&mut self.inner, |inner, (ip, tcp)| {
+                            let label = flow_label.value(inner, &ip, tcp.src_port, tcp.dst_port);
+                            let mut packet = Packet::new(ip, IpPayload::Tcp(tcp));
+                            packet.set_df_flag(dont_frag);
+                            packet.set_flow_label(label);
+                            respond(inner, PacketMeta::default(), packet)
+                        });
+                        if neighbor_unreachable {
+                            socket.neighbor_unreachable();
+                        }
+                        result
+                    }
+                    #[cfg(feature = "socket-dhcpv4")]
+                    Socket::Dhcpv4(socket) => {
+                        socket.dispatch(&mut self.inner, |inner, (ip, udp, dhcp)| {
+                            respond(
+                                inner,
+                                PacketMeta::default(),
+                                Packet::new_ipv4(ip, IpPayload::Dhcpv4(udp, dhcp)),
+                            )
+                        })
+                    }
+                    #[cfg(feature = "socket-dns")]
+                    Socket::Dns(socket) => {
+                        socket.dispatch(&mut self.inner, |inner, (ip, udp, dns)| {
+                            respond(
+                                inner,
+                                PacketMeta::default(),
+                                Packet::new(ip, IpPayload::Udp(udp, dns)),
+                            )
+                        })
+                    }
+                    #[cfg(feature = "socket-tftp")]
+                    Socket::Tftp(socket) => {
+                        socket.dispatch(&mut self.inner, |inner, (ip, udp, tftp)| {
+                            respond(
+                                inner,
+                                PacketMeta::default(),
+                                Packet::new(ip, IpPayload::Tftp(udp, tftp)),
+                            )
+                        })
+                    }
+                };
 
-            match result {
-                Err(EgressError::Exhausted) => break, // Device buffer full.
-                Err(EgressError::Dispatch(_)) => {
+                if let Err(EgressError::Dispatch) = result {
                     // `NeighborCache` already takes care of rate limiting the neighbor discovery
                     // requests from the socket. However, without an additional rate limiting
                     // mechanism, we would spin on every socket that has yet to discover its
@@ -686,16 +1240,45 @@ impl Interface {
                     item.meta.neighbor_missing(
                         self.inner.now,
                         neighbor_addr.expect("non-IP response packet"),
+                        self.inner.neighbor_retry_delay(),
                     );
                 }
-                Ok(()) => {}
+
+                next_item = Self::next_egress_item(&mut self.inner, &mut items);
+                if tx_token.is_none() {
+                    self.inner.last_egress = Some(item.meta.handle);
+                    // The token was used, ask the device for another one if needed.
+                    return next_item.is_some();
+                }
             }
-        }
+            false
+        });
         emitted_any
     }
+
+    /// Return the next socket that is allowed to, and wants to, transmit a packet now.
+    fn next_egress_item<'i, 's: 'i>(
+        inner: &mut InterfaceInner,
+        items: &mut impl Iterator<Item = &'i mut Item<'s>>,
+    ) -> Option<&'i mut Item<'s>> {
+        for item in items {
+            if !item
+                .meta
+                .egress_permitted(inner.now, |ip_addr| inner.has_neighbor(&ip_addr))
+            {
+                continue;
+            }
+            match item.socket.poll_at(inner) {
+                PollAt::Now => return Some(item),
+                PollAt::Time(instant) if instant <= inner.now => return Some(item),
+                _ => {}
+            }
+        }
+        None
+    }
 }
 
-impl InterfaceInner {
+impl<'a> InterfaceInner<'a> {
     #[allow(unused)] // unused depending on which sockets are enabled
     pub(crate) fn now(&self) -> Instant {
         self.now
@@ -718,7 +1301,7 @@ impl InterfaceInner {
     }
 
     #[allow(unused)] // unused depending on which sockets are enabled, and in tests
-    pub(crate) fn rand(&mut self) -> &mut Rand {
+    pub(crate) fn rand(&mut self) -> &mut Rand<'a> {
         &mut self.rand
     }
 
@@ -732,12 +1315,47 @@ impl InterfaceInner {
         }
     }
 
+    #[cfg(feature = "socket-tcp")]
+    pub(crate) fn tcp_isn_secret(&mut self) -> u64 {
+        match self.tcp_isn_secret {
+            Some(secret) => secret,
+            None => {
+                let secret = (self.rand.rand_u32() as u64) << 32 | self.rand.rand_u32() as u64;
+                self.tcp_isn_secret = Some(secret);
+                secret
+            }
+        }
+    }
+
+    #[cfg(any(feature = "socket-tcp", feature = "socket-udp", feature = "socket-raw"))]
+    pub(crate) fn flow_label_secret(&mut self) -> u64 {
+        match self.flow_label_secret {
+            Some(secret) => secret,
+            None => {
+                let secret = (self.rand.rand_u32() as u64) << 32 | self.rand.rand_u32() as u64;
+                self.flow_label_secret = Some(secret);
+                secret
+            }
+        }
+    }
+
     #[cfg(test)]
     #[allow(unused)] // unused depending on which sockets are enabled
     pub(crate) fn set_now(&mut self, now: Instant) {
         self.now = now
     }
 
+    #[cfg(test)]
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn set_rand_source(&mut self, source: &'a mut dyn RandSource) {
+        self.rand = Rand::with_source(0, Some(source))
+    }
+
+    #[cfg(all(test, feature = "socket-tcp"))]
+    pub(crate) fn set_tcp_isn_secret(&mut self, secret: u64) {
+        self.tcp_isn_secret = Some(secret)
+    }
+
     #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
     fn check_hardware_addr(addr: &HardwareAddress) {
         if !addr.is_unicast() {
@@ -745,6 +1363,15 @@ impl InterfaceInner {
         }
     }
 
+    #[cfg(feature = "medium-ieee802154")]
+    fn check_short_addr(addr: &Ieee802154Address) {
+        // 0xfffe means "no short address assigned", and 0xffff is broadcast.
+        match addr {
+            Ieee802154Address::Short(bytes) if bytes[0] != 0xff || bytes[1] < 0xfe => {}
+            _ => panic!("IEEE802.15.4 address {addr} is not a unicast short address"),
+        }
+    }
+
     fn check_ip_addrs(addrs: &[IpCidr]) {
         for cidr in addrs {
             if !cidr.address().is_unicast() && !cidr.address().is_unspecified() {
@@ -759,6 +1386,18 @@ impl InterfaceInner {
         self.ip_addrs.iter().any(|probe| probe.address() == addr)
     }
 
+    /// Check whether the interface answers neighbor discovery for the given IP address
+    /// on behalf of another host.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    fn is_proxied<T: Into<IpAddress>>(&self, addr: T) -> bool {
+        let addr = addr.into();
+        !self.has_ip_addr(addr)
+            && self
+                .proxy_addrs
+                .iter()
+                .any(|cidr| cidr.contains_addr(&addr))
+    }
+
     /// Check whether the interface listens to given destination multicast IP address.
     ///
     /// If built without feature `proto-igmp` this function will
@@ -775,7 +1414,9 @@ impl InterfaceInner {
             #[cfg(feature = "proto-rpl")]
             IpAddress::Ipv6(Ipv6Address::LINK_LOCAL_ALL_RPL_NODES) => true,
             #[cfg(feature = "proto-ipv6")]
-            IpAddress::Ipv6(addr) => self.has_solicited_node(addr),
+            IpAddress::Ipv6(addr) => {
+                self.has_solicited_node(addr) || self.has_proxied_solicited_node(addr)
+            }
             #[allow(unreachable_patterns)]
             _ => false,
         }
@@ -890,12 +1531,16 @@ impl InterfaceInner {
     }
 
     fn has_neighbor(&self, addr: &IpAddress) -> bool {
-        match self.route(addr, self.now) {
+        self.has_neighbor_at(addr, self.now)
+    }
+
+    fn has_neighbor_at(&self, addr: &IpAddress, timestamp: Instant) -> bool {
+        match self.route(addr, timestamp) {
             Some(_routed_addr) => match self.caps.medium {
                 #[cfg(feature = "medium-ethernet")]
-                Medium::Ethernet => self.neighbor_cache.lookup(&_routed_addr, self.now).found(),
+                Medium::Ethernet => self.neighbor_cache.lookup(&_routed_addr, timestamp).found(),
                 #[cfg(feature = "medium-ieee802154")]
-                Medium::Ieee802154 => self.neighbor_cache.lookup(&_routed_addr, self.now).found(),
+                Medium::Ieee802154 => self.neighbor_cache.lookup(&_routed_addr, timestamp).found(),
                 #[cfg(feature = "medium-ip")]
                 Medium::Ip => true,
             },
@@ -972,6 +1617,10 @@ impl InterfaceInner {
         match self.neighbor_cache.lookup(&dst_addr, self.now) {
             NeighborAnswer::Found(hardware_addr) => return Ok((hardware_addr, tx_token)),
             NeighborAnswer::RateLimited => return Err(DispatchError::NeighborPending),
+            NeighborAnswer::Failed => {
+                net_debug!("address {} did not answer neighbor discovery", dst_addr);
+                return Err(DispatchError::NeighborUnreachable);
+            }
             _ => (), // XXX
         }
 
@@ -1043,15 +1692,63 @@ impl InterfaceInner {
         }
 
         // The request got dispatched, limit the rate on the cache.
-        self.neighbor_cache.limit_rate(self.now);
+        self.neighbor_cache.limit_rate(dst_addr, self.now);
         Err(DispatchError::NeighborPending)
     }
 
+    fn drop_response(&mut self, err: DispatchError) {
+        let reason = match err {
+            DispatchError::NoRoute => DropReason::NoRoute,
+            DispatchError::NeighborPending => DropReason::NeighborPending,
+            DispatchError::NeighborUnreachable => DropReason::NeighborUnreachable,
+        };
+        self.events.push(Event::PacketDropped(reason));
+    }
+
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    fn fill_neighbor(
+        &mut self,
+        addr: IpAddress,
+        hardware_addr: HardwareAddress,
+        timestamp: Instant,
+    ) {
+        match self.neighbor_cache.fill(addr, hardware_addr, timestamp) {
+            NeighborFilled::Refreshed => {}
+            NeighborFilled::Added => self.events.push(Event::NeighborAdded {
+                addr,
+                hardware_addr,
+            }),
+            NeighborFilled::Evicted(old_addr, old_hardware_addr) => {
+                self.events.push(Event::NeighborEvicted {
+                    addr: old_addr,
+                    hardware_addr: old_hardware_addr,
+                });
+                self.events.push(Event::NeighborAdded {
+                    addr,
+                    hardware_addr,
+                });
+            }
+        }
+    }
+
     fn flush_neighbor_cache(&mut self) {
         #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
         self.neighbor_cache.flush()
     }
 
+    /// Return how long a socket that is waiting for a neighbor to be discovered waits
+    /// before sending to it again.
+    fn neighbor_retry_delay(&self) -> Duration {
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        {
+            self.neighbor_cache.solicit_interval()
+        }
+        #[cfg(not(any(feature = "medium-ethernet", feature = "medium-ieee802154")))]
+        {
+            Duration::from_millis(1_000)
+        }
+    }
+
     fn dispatch_ip<Tx: TxToken>(
         &mut self,
         // NOTE(unused_mut): tx_token isn't always mutated, depending on
@@ -1084,6 +1781,15 @@ impl InterfaceInner {
 
         let caps = self.caps.clone();
 
+        let dont_frag = packet.dont_frag();
+        #[cfg(feature = "proto-ipv6")]
+        let flow_label = packet.flow_label();
+        if dont_frag && ip_repr.buffer_len() > self.caps.ip_mtu() {
+            net_debug!("packet is larger than the MTU and must not be fragmented, dropping");
+            self.events.push(Event::PacketDropped(DropReason::TooLarge));
+            return Ok(());
+        }
+
         #[cfg(feature = "proto-ipv4-fragmentation")]
         let ipv4_id = self.next_ipv4_frag_ident();
 
@@ -1133,8 +1839,32 @@ impl InterfaceInner {
         };
 
         // Emit function for the IP header and payload.
-        let emit_ip = |repr: &IpRepr, mut tx_buffer: &mut [u8]| {
-            repr.emit(&mut tx_buffer, &self.caps.checksum);
+        let emit_ip = |repr: &IpRepr, tx_buffer: &mut [u8]| {
+            #[cfg(feature = "socket-raw")]
+            if let IpPayload::Verbatim(raw_packet) = packet.payload() {
+                tx_buffer.copy_from_slice(raw_packet);
+                return;
+            }
+
+            match repr {
+                #[cfg(feature = "proto-ipv4")]
+                IpRepr::Ipv4(repr) => {
+                    let mut ipv4_packet = Ipv4Packet::new_unchecked(&mut *tx_buffer);
+                    repr.emit(&mut ipv4_packet, &self.caps.checksum);
+                    if !packet.df_flag() {
+                        ipv4_packet.set_dont_frag(false);
+                        if self.caps.checksum.ipv4.tx() {
+                            ipv4_packet.fill_checksum();
+                        }
+                    }
+                }
+                #[cfg(feature = "proto-ipv6")]
+                IpRepr::Ipv6(repr) => {
+                    let mut packet = Ipv6Packet::new_unchecked(&mut *tx_buffer);
+                    repr.emit(&mut packet);
+                    packet.set_flow_label(flow_label);
+                }
+            }
 
             let payload = &mut tx_buffer[repr.header_len()..];
             packet.emit_payload(repr, payload, &caps)
@@ -1146,16 +1876,19 @@ impl InterfaceInner {
             #[cfg(feature = "proto-ipv4")]
             IpRepr::Ipv4(repr) => {
                 // If we have an IPv4 packet, then we need to check if we need to fragment it.
-                if total_ip_len > self.caps.max_transmission_unit {
+                if total_ip_len > self.caps.ip_mtu() {
                     #[cfg(feature = "proto-ipv4-fragmentation")]
                     {
                         net_debug!("start fragmentation");
 
-                        // Calculate how much we will send now (including the Ethernet header).
-                        let tx_len = self.caps.max_transmission_unit;
-
+                        // The payload of every fragment but the last one must be a multiple
+                        // of 8 octets, since the fragment offset is counted in 8-octet units.
                         let ip_header_len = repr.buffer_len();
-                        let first_frag_ip_len = self.caps.ip_mtu();
+                        let first_frag_ip_len =
+                            ip_header_len + ((self.caps.ip_mtu() - ip_header_len) & !7);
+
+                        // Calculate how much we will send now (including the Ethernet header).
+                        let tx_len = total_len - total_ip_len + first_frag_ip_len;
 
                         if frag.buffer.len() < total_ip_len {
                             net_debug!(
@@ -1263,4 +1996,8 @@ enum DispatchError {
     /// the neighbor for it yet. Discovery has been initiated, dispatch
     /// should be retried later.
     NeighborPending,
+    /// We do have a route to dispatch this packet, but the neighbor for it
+    /// did not answer any of the discovery requests. Retrying starts discovery
+    /// over once the neighbor has been unreachable for a while.
+    NeighborUnreachable,
 }
@@ -5,7 +5,7 @@ use crate::wire::Result;
 // TODO: lower. Should be (6lowpan mtu) - (min 6lowpan header size) + (max ipv6 header size)
 pub(crate) const MAX_DECOMPRESSED_LEN: usize = 1500;
 
-impl Interface {
+impl Interface<'_> {
     /// Process fragments that still need to be sent for 6LoWPAN packets.
     ///
     /// This function returns a boolean value indicating whether any packets were
@@ -49,7 +49,7 @@ impl Interface {
     }
 }
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
     /// Get the next tag for a 6LoWPAN fragment.
     #[cfg(feature = "proto-sixlowpan-fragmentation")]
     fn get_sixlowpan_fragment_tag(&mut self) -> u16 {
@@ -299,6 +299,13 @@ impl InterfaceInner {
             Packet::Ipv6(packet) => packet,
         };
 
+        // Compressing the header would change it, so a verbatim packet can't be sent.
+        #[cfg(feature = "socket-raw")]
+        if let IpPayload::Verbatim(_) = packet.payload {
+            net_debug!("dispatch_sixlowpan: dropping verbatim packet");
+            return;
+        }
+
         // First we calculate the size we are going to need. If the size is bigger than the MTU,
         // then we use fragmentation.
         let (total_size, compressed_size, uncompressed_size) =
@@ -528,6 +535,20 @@ impl InterfaceInner {
                     checksum_caps,
                 );
             }
+            #[cfg(feature = "socket-tftp")]
+            IpPayload::Tftp(udp_repr, tftp_repr) => {
+                let udp_repr = SixlowpanUdpNhcRepr(*udp_repr);
+                udp_repr.emit(
+                    &mut SixlowpanUdpNhcPacket::new_unchecked(
+                        &mut buffer[..udp_repr.header_len() + tftp_repr.buffer_len()],
+                    ),
+                    &iphc_repr.src_addr,
+                    &iphc_repr.dst_addr,
+                    tftp_repr.buffer_len(),
+                    |buf| tftp_repr.emit(&mut TftpPacket::new_unchecked(buf)),
+                    checksum_caps,
+                );
+            }
             #[cfg(feature = "socket-tcp")]
             IpPayload::Tcp(tcp_repr) => {
                 tcp_repr.emit(
@@ -636,6 +657,15 @@ impl InterfaceInner {
 
                 total_size += udp_hdr.header_len() + payload.len();
             }
+            #[cfg(feature = "socket-tftp")]
+            IpPayload::Tftp(udp_hdr, tftp_repr) => {
+                uncompressed_hdr_size += udp_hdr.header_len();
+
+                let udp_hdr = SixlowpanUdpNhcRepr(udp_hdr);
+                compressed_hdr_size += udp_hdr.header_len();
+
+                total_size += udp_hdr.header_len() + tftp_repr.buffer_len();
+            }
             _ => {
                 total_size += packet.header.payload_len;
             }
@@ -850,6 +880,8 @@ mod tests {
             fragment: None,
             #[cfg(feature = "proto-ipv6-routing")]
             routing: None,
+            dont_frag: false,
+            flow_label: 0,
             payload: IpPayload::Icmpv6(Icmpv6Repr::Rpl(RplRepr::DestinationAdvertisementObject {
                 rpl_instance_id: RplInstanceId::Global(30),
                 expect_ack: false,
@@ -931,6 +963,8 @@ mod tests {
             fragment: None,
             #[cfg(feature = "proto-ipv6-routing")]
             routing: None,
+            dont_frag: false,
+            flow_label: 0,
             payload: IpPayload::Icmpv6(Icmpv6Repr::Rpl(RplRepr::DestinationAdvertisementObject {
                 rpl_instance_id: RplInstanceId::Global(30),
                 expect_ack: false,
@@ -2,7 +2,7 @@ use super::*;
 
 use crate::socket::tcp::Socket;
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
     pub(crate) fn process_tcp<'frame>(
         &mut self,
         sockets: &mut SocketSet,
@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::vec::Vec;
+
+use super::*;
+
+type Frames = VecDeque<Vec<u8>>;
+
+/// A device that doesn't support batching, and thus uses the default
+/// `receive_many` and `transmit_many` implementations.
+#[derive(Default)]
+struct MockDevice {
+    rx_queue: Frames,
+    tx_queue: Frames,
+}
+
+struct MockRxToken(Vec<u8>);
+
+impl RxToken for MockRxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.0)
+    }
+}
+
+struct QueueTxToken<'a>(&'a mut Frames);
+
+impl TxToken for QueueTxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buffer = vec![0; len];
+        let result = f(&mut buffer);
+        self.0.push_back(buffer);
+        result
+    }
+}
+
+impl Device for MockDevice {
+    type RxToken<'a> = MockRxToken;
+    type TxToken<'a> = QueueTxToken<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        self.rx_queue
+            .pop_front()
+            .map(|buffer| (MockRxToken(buffer), QueueTxToken(&mut self.tx_queue)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(QueueTxToken(&mut self.tx_queue))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            medium: Medium::Ip,
+            max_transmission_unit: 1500,
+            ..DeviceCapabilities::default()
+        }
+    }
+}
+
+/// A device that hands out all pending packets at once, and records the size
+/// of every batch.
+#[derive(Default)]
+struct BatchingDevice {
+    inner: MockDevice,
+    rx_batches: Vec<usize>,
+    tx_batches: Vec<usize>,
+}
+
+impl Device for BatchingDevice {
+    type RxToken<'a> = MockRxToken;
+    type TxToken<'a> = QueueTxToken<'a>;
+
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        self.inner.receive(timestamp)
+    }
+
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        self.inner.transmit(timestamp)
+    }
+
+    fn receive_many<F>(&mut self, _timestamp: Instant, mut f: F) -> usize
+    where
+        F: FnMut(Self::RxToken<'_>, Self::TxToken<'_>) -> bool,
+    {
+        let mut batch = mem::take(&mut self.inner.rx_queue);
+        self.rx_batches.push(batch.len());
+
+        let mut count = 0;
+        while let Some(buffer) = batch.pop_front() {
+            count += 1;
+            if !f(MockRxToken(buffer), QueueTxToken(&mut self.inner.tx_queue)) {
+                break;
+            }
+        }
+        batch.append(&mut self.inner.rx_queue);
+        self.inner.rx_queue = batch;
+        count
+    }
+
+    fn transmit_many<F>(&mut self, _timestamp: Instant, mut f: F) -> usize
+    where
+        F: FnMut(Self::TxToken<'_>) -> bool,
+    {
+        let mut batch = Frames::new();
+
+        let mut count = 1;
+        while f(QueueTxToken(&mut batch)) {
+            count += 1;
+        }
+
+        self.tx_batches.push(batch.len());
+        self.inner.tx_queue.append(&mut batch);
+        count
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+fn echo_request(seq_no: u16) -> Vec<u8> {
+    let icmp_repr = Icmpv4Repr::EchoRequest {
+        ident: 0x1234,
+        seq_no,
+        data: &[0xaa, 0x00, 0x00, 0xff],
+    };
+    let ip_repr = Ipv4Repr {
+        src_addr: Ipv4Address([192, 168, 1, 2]),
+        dst_addr: Ipv4Address([192, 168, 1, 1]),
+        next_header: IpProtocol::Icmp,
+        payload_len: icmp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+
+    let mut bytes = vec![0; ip_repr.buffer_len() + icmp_repr.buffer_len()];
+    ip_repr.emit(
+        &mut Ipv4Packet::new_unchecked(&mut bytes[..]),
+        &ChecksumCapabilities::default(),
+    );
+    icmp_repr.emit(
+        &mut Icmpv4Packet::new_unchecked(&mut bytes[ip_repr.buffer_len()..]),
+        &ChecksumCapabilities::default(),
+    );
+    bytes
+}
+
+#[test]
+fn test_receive_batch() {
+    let (mut iface, mut sockets, _) = setup(Medium::Ip);
+
+    let mut device = BatchingDevice::default();
+    for seq_no in 0..3 {
+        device.inner.rx_queue.push_back(echo_request(seq_no));
+    }
+
+    assert!(iface.poll(Instant::ZERO, &mut device, &mut sockets));
+
+    // All packets were received in a single batch, the next batch is empty.
+    assert_eq!(device.rx_batches, vec![3, 0]);
+    assert!(device.inner.rx_queue.is_empty());
+    assert_eq!(device.inner.tx_queue.len(), 3);
+}
+
+#[test]
+#[cfg(feature = "socket-udp")]
+fn test_transmit_batch() {
+    use crate::socket::udp;
+
+    let (mut iface, mut sockets, _) = setup(Medium::Ip);
+
+    for port in [1234, 1235] {
+        let mut socket = udp::Socket::new(
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        );
+        socket.bind(port).unwrap();
+        socket
+            .send_slice(b"hello", (Ipv4Address([192, 168, 1, 2]), 4321))
+            .unwrap();
+        sockets.add(socket);
+    }
+
+    let mut device = BatchingDevice::default();
+    assert!(iface.poll(Instant::ZERO, &mut device, &mut sockets));
+
+    // Both sockets emitted their packet in the same batch, and no batch is started once
+    // there is nothing left to send.
+    assert_eq!(device.tx_batches, vec![2]);
+    assert_eq!(device.inner.tx_queue.len(), 2);
+
+    assert!(!iface.poll(Instant::ZERO, &mut device, &mut sockets));
+    assert_eq!(device.tx_batches, vec![2]);
+}
+
+#[test]
+#[cfg(feature = "socket-udp")]
+fn test_batch_fallback() {
+    use crate::socket::udp;
+
+    let (mut iface, mut sockets, _) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+    );
+    socket.bind(1234).unwrap();
+    socket
+        .send_slice(b"hello", (Ipv4Address([192, 168, 1, 2]), 4321))
+        .unwrap();
+    sockets.add(socket);
+
+    let mut device = MockDevice::default();
+    for seq_no in 0..3 {
+        device.rx_queue.push_back(echo_request(seq_no));
+    }
+
+    assert!(iface.poll(Instant::ZERO, &mut device, &mut sockets));
+
+    // Three echo replies and one UDP packet.
+    assert!(device.rx_queue.is_empty());
+    assert_eq!(device.tx_queue.len(), 4);
+}
@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+use super::*;
+use crate::socket::udp;
+
+/// A device that transmits a limited number of packets, e.g. because its transmit
+/// ring is full, and receives nothing.
+struct CongestedDevice {
+    tx_budget: usize,
+    tx_queue: VecDeque<Vec<u8>>,
+}
+
+struct NoRxToken;
+
+impl RxToken for NoRxToken {
+    fn consume<R, F>(self, _f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        unreachable!()
+    }
+}
+
+struct QueueTxToken<'a>(&'a mut VecDeque<Vec<u8>>);
+
+impl TxToken for QueueTxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buffer = vec![0; len];
+        let result = f(&mut buffer);
+        self.0.push_back(buffer);
+        result
+    }
+}
+
+impl Device for CongestedDevice {
+    type RxToken<'a> = NoRxToken;
+    type TxToken<'a> = QueueTxToken<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        None
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        if self.tx_budget == 0 {
+            return None;
+        }
+        self.tx_budget -= 1;
+        Some(QueueTxToken(&mut self.tx_queue))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            medium: Medium::Ip,
+            max_transmission_unit: 1500,
+            ..DeviceCapabilities::default()
+        }
+    }
+}
+
+/// Return the source ports of the packets the interface sends when the device only
+/// accepts one packet per poll, with a bulk socket on port 1000 that has four packets
+/// to send and an interactive socket on port 2000 that has one.
+fn contend(policy: EgressPolicy) -> Vec<u16> {
+    let (mut iface, mut sockets, _) = setup(Medium::Ip);
+    iface.set_egress_policy(policy);
+
+    for (port, count) in [(1000, 4), (2000, 1)] {
+        let mut socket = udp::Socket::new(
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 64]),
+        );
+        socket.bind(port).unwrap();
+        for _ in 0..count {
+            socket
+                .send_slice(b"data", (Ipv4Address([192, 168, 1, 2]), 4321))
+                .unwrap();
+        }
+        sockets.add(socket);
+    }
+
+    let mut device = CongestedDevice {
+        tx_budget: 0,
+        tx_queue: VecDeque::new(),
+    };
+    for _ in 0..5 {
+        device.tx_budget = 1;
+        iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    }
+
+    device
+        .tx_queue
+        .iter()
+        .map(|frame| {
+            let packet = Ipv4Packet::new_checked(&frame[..]).unwrap();
+            UdpPacket::new_checked(packet.payload()).unwrap().src_port()
+        })
+        .collect()
+}
+
+#[test]
+fn test_egress_fifo() {
+    // The bulk socket is polled first every time, and sends all its packets before the
+    // interactive socket gets a chance.
+    assert_eq!(
+        contend(EgressPolicy::Fifo),
+        vec![1000, 1000, 1000, 1000, 2000]
+    );
+}
+
+#[test]
+fn test_egress_round_robin() {
+    // The interactive socket sends its packet right after the first packet of the bulk
+    // socket.
+    assert_eq!(
+        contend(EgressPolicy::RoundRobin),
+        vec![1000, 2000, 1000, 1000, 1000]
+    );
+}
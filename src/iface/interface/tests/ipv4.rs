@@ -393,6 +393,19 @@ fn test_handle_ipv4_broadcast(#[case] medium: Medium) {
     let expected_packet =
         Packet::new_ipv4(expected_ipv4_repr, IpPayload::Icmpv4(expected_icmpv4_repr));
 
+    // Broadcast echo requests are ignored by default
+    assert!(!iface.echo_broadcast());
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &frame,
+            &mut iface.fragments
+        ),
+        None
+    );
+
+    iface.set_echo_broadcast(true);
     assert_eq!(
         iface.inner.process_ipv4(
             &mut sockets,
@@ -461,6 +474,193 @@ fn test_handle_valid_arp_request(#[case] medium: Medium) {
     );
 }
 
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_handle_arp_request_secondary_addrs(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(IpAddress::v4(192, 168, 1, 10), 24))
+            .unwrap();
+        addrs
+            .push(IpCidr::new(IpAddress::v4(10, 0, 0, 1), 24))
+            .unwrap();
+    });
+
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    let mut request = |remote_ip_addr: Ipv4Address, local_ip_addr: Ipv4Address| {
+        let mut eth_bytes = vec![0u8; 42];
+        let repr = ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: remote_hw_addr,
+            source_protocol_addr: remote_ip_addr,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: local_ip_addr,
+        };
+
+        let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+        frame.set_dst_addr(EthernetAddress::BROADCAST);
+        frame.set_src_addr(remote_hw_addr);
+        frame.set_ethertype(EthernetProtocol::Arp);
+        repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+
+        assert_eq!(
+            iface.inner.process_ethernet(
+                &mut sockets,
+                PacketMeta::default(),
+                frame.into_inner(),
+                &mut iface.fragments
+            ),
+            Some(EthernetPacket::Arp(ArpRepr::EthernetIpv4 {
+                operation: ArpOperation::Reply,
+                source_hardware_addr: local_hw_addr,
+                source_protocol_addr: local_ip_addr,
+                target_hardware_addr: remote_hw_addr,
+                target_protocol_addr: remote_ip_addr
+            }))
+        );
+    };
+
+    // Every address is answered for, on the prefix of the first address as well as on
+    // another one.
+    let remote_ip_addr = Ipv4Address::new(192, 168, 1, 2);
+    request(remote_ip_addr, Ipv4Address::new(192, 168, 1, 1));
+    request(remote_ip_addr, Ipv4Address::new(192, 168, 1, 10));
+    request(Ipv4Address::new(10, 0, 0, 2), Ipv4Address::new(10, 0, 0, 1));
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_events_arp(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let local_ip_addr = Ipv4Address([192, 168, 1, 1]);
+    let remote_ip_addr = Ipv4Address([192, 168, 1, 5]);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    let arp_request = |source_protocol_addr, target_protocol_addr| {
+        let mut eth_bytes = vec![0u8; 42];
+        let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+        frame.set_dst_addr(EthernetAddress::BROADCAST);
+        frame.set_src_addr(remote_hw_addr);
+        frame.set_ethertype(EthernetProtocol::Arp);
+        ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: remote_hw_addr,
+            source_protocol_addr,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr,
+        }
+        .emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+        eth_bytes
+    };
+
+    // Nothing is recorded until events are enabled.
+    iface.update_ip_addrs(|addrs| addrs.truncate(1));
+    assert_eq!(iface.next_event(), None);
+
+    iface.set_events_enabled(true);
+    let added_cidr = IpCidr::new(IpAddress::v4(192, 168, 2, 1), 24);
+    iface.update_ip_addrs(|addrs| addrs.push(added_cidr).unwrap());
+
+    let frame = arp_request(remote_ip_addr, local_ip_addr);
+    assert_eq!(
+        iface
+            .process_frame(Instant::ZERO, &mut sockets, &frame)
+            .len(),
+        1
+    );
+
+    // A host announcing our address is recorded, but otherwise processed as usual.
+    let frame = arp_request(local_ip_addr, local_ip_addr);
+    assert_eq!(
+        iface
+            .process_frame(Instant::ZERO, &mut sockets, &frame)
+            .len(),
+        1
+    );
+
+    assert_eq!(iface.next_event(), Some(Event::AddressAdded(added_cidr)));
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::NeighborAdded {
+            addr: remote_ip_addr.into(),
+            hardware_addr: remote_hw_addr.into(),
+        })
+    );
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::AddressConflict {
+            addr: local_ip_addr.into(),
+            hardware_addr: Some(remote_hw_addr.into()),
+        })
+    );
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::NeighborAdded {
+            addr: local_ip_addr.into(),
+            hardware_addr: remote_hw_addr.into(),
+        })
+    );
+    assert_eq!(iface.next_event(), None);
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_process_frame_arp_request(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let local_ip_addr = Ipv4Address([0x7f, 0x00, 0x00, 0x01]);
+    let remote_ip_addr = Ipv4Address([0x7f, 0x00, 0x00, 0x02]);
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    let mut eth_bytes = vec![0u8; 42];
+    let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+    frame.set_dst_addr(EthernetAddress::BROADCAST);
+    frame.set_src_addr(remote_hw_addr);
+    frame.set_ethertype(EthernetProtocol::Arp);
+    ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: remote_hw_addr,
+        source_protocol_addr: remote_ip_addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: local_ip_addr,
+    }
+    .emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+
+    let tx = iface.process_frame(Instant::ZERO, &mut sockets, &eth_bytes);
+    assert_eq!(tx.len(), 1);
+
+    let frame = EthernetFrame::new_checked(&tx[0][..]).unwrap();
+    assert_eq!(frame.dst_addr(), remote_hw_addr);
+    assert_eq!(frame.src_addr(), local_hw_addr);
+    assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+    assert_eq!(
+        ArpRepr::parse(&ArpPacket::new_checked(frame.payload()).unwrap()),
+        Ok(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Reply,
+            source_hardware_addr: local_hw_addr,
+            source_protocol_addr: local_ip_addr,
+            target_hardware_addr: remote_hw_addr,
+            target_protocol_addr: remote_ip_addr,
+        })
+    );
+
+    // An ARP request for someone else produces nothing.
+    let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+    ArpPacket::new_unchecked(frame.payload_mut())
+        .set_target_protocol_addr(&[0x7f, 0x00, 0x00, 0x03]);
+    assert!(iface
+        .process_frame(Instant::ZERO, &mut sockets, &eth_bytes)
+        .is_empty());
+}
+
 #[rstest]
 #[case(Medium::Ethernet)]
 #[cfg(feature = "medium-ethernet")]
@@ -510,6 +710,113 @@ fn test_handle_other_arp_request(#[case] medium: Medium) {
     );
 }
 
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_handle_proxied_arp_request(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+    iface.update_proxy_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(IpAddress::v4(127, 0, 0, 8), 29))
+            .unwrap();
+    });
+
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let remote_ip_addr = Ipv4Address([0x7f, 0x00, 0x00, 0x02]);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    let request = |target_protocol_addr| {
+        let mut eth_bytes = vec![0u8; 42];
+        let repr = ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: remote_hw_addr,
+            source_protocol_addr: remote_ip_addr,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr,
+        };
+
+        let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+        frame.set_dst_addr(EthernetAddress::BROADCAST);
+        frame.set_src_addr(remote_hw_addr);
+        frame.set_ethertype(EthernetProtocol::Arp);
+        let mut packet = ArpPacket::new_unchecked(frame.payload_mut());
+        repr.emit(&mut packet);
+        eth_bytes
+    };
+
+    // Ensure an ARP Request for a proxied address is answered with our hardware address
+    let proxied_ip_addr = Ipv4Address([0x7f, 0x00, 0x00, 0x0a]);
+    assert_eq!(
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &request(proxied_ip_addr),
+            &mut iface.fragments
+        ),
+        Some(EthernetPacket::Arp(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Reply,
+            source_hardware_addr: local_hw_addr,
+            source_protocol_addr: proxied_ip_addr,
+            target_hardware_addr: remote_hw_addr,
+            target_protocol_addr: remote_ip_addr
+        }))
+    );
+
+    // Ensure an ARP Request for an address outside the proxied range is ignored
+    assert_eq!(
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &request(Ipv4Address([0x7f, 0x00, 0x00, 0x14])),
+            &mut iface.fragments
+        ),
+        None
+    );
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_handle_proxied_gratuitous_arp(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+    iface.update_proxy_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(IpAddress::v4(127, 0, 0, 0), 24))
+            .unwrap();
+    });
+
+    let mut eth_bytes = vec![0u8; 42];
+
+    let remote_ip_addr = Ipv4Address([0x7f, 0x00, 0x00, 0x02]);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    let repr = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: remote_hw_addr,
+        source_protocol_addr: remote_ip_addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: remote_ip_addr,
+    };
+
+    let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+    frame.set_dst_addr(EthernetAddress::BROADCAST);
+    frame.set_src_addr(remote_hw_addr);
+    frame.set_ethertype(EthernetProtocol::Arp);
+    let mut packet = ArpPacket::new_unchecked(frame.payload_mut());
+    repr.emit(&mut packet);
+
+    // Ensure a host announcing a proxied address is not answered on its own behalf
+    assert_eq!(
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            frame.into_inner(),
+            &mut iface.fragments
+        ),
+        None
+    );
+}
+
 #[rstest]
 #[case(Medium::Ethernet)]
 #[cfg(feature = "medium-ethernet")]
@@ -943,6 +1250,82 @@ fn test_raw_socket_with_udp_socket(#[case] medium: Medium) {
     );
 }
 
+#[test]
+#[cfg(all(feature = "socket-raw", feature = "medium-ip"))]
+fn test_raw_socket_verbatim() {
+    use crate::wire::IpVersion;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    let mut raw_socket = raw::Socket::new(
+        IpVersion::Ipv4,
+        IpProtocol::Unknown(0xfd),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY], vec![0; 64]),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY], vec![0; 64]),
+    );
+    raw_socket.set_verbatim(true);
+    let handle = sockets.add(raw_socket);
+
+    // A header with an option, a TTL of 1, an arbitrary identification and a
+    // deliberately wrong checksum.
+    #[rustfmt::skip]
+    let packet: [u8; 28] = [
+        0x46, 0x00, 0x00, 0x1c,
+        0x12, 0x34, 0x00, 0x00,
+        0x01, 0xfd, 0xbe, 0xef,
+        0xc0, 0xa8, 0x01, 0x01,
+        0xc0, 0xa8, 0x01, 0x02,
+        0x01, 0x01, 0x01, 0x00,
+        0xaa, 0xbb, 0xcc, 0xdd,
+    ];
+    sockets
+        .get_mut::<raw::Socket>(handle)
+        .send_slice(&packet)
+        .unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    assert_eq!(device.queue.pop_front().unwrap(), &packet[..]);
+}
+
+#[test]
+#[cfg(all(feature = "socket-raw", feature = "medium-ip"))]
+fn test_raw_socket_no_hdrincl() {
+    use crate::wire::IpVersion;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    let mut raw_socket = raw::Socket::new(
+        IpVersion::Ipv4,
+        IpProtocol::Unknown(0xfd),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY], vec![0; 64]),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY], vec![0; 64]),
+    );
+    raw_socket.set_hdrincl(false);
+    raw_socket.set_hop_limit(Some(3));
+    assert_eq!(
+        raw_socket.send_slice(&[0xaa; 4]),
+        Err(raw::SendError::Unaddressable)
+    );
+    raw_socket
+        .connect(Ipv4Address::new(192, 168, 1, 2))
+        .unwrap();
+    raw_socket.send_slice(&[0xaa, 0xbb, 0xcc, 0xdd]).unwrap();
+    sockets.add(raw_socket);
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    let packet = Ipv4Packet::new_checked(&frame[..]).unwrap();
+    assert!(packet.verify_checksum());
+    assert_eq!(
+        Ipv4Repr::parse(&packet, &ChecksumCapabilities::default()).unwrap(),
+        Ipv4Repr {
+            src_addr: Ipv4Address::new(192, 168, 1, 1),
+            dst_addr: Ipv4Address::new(192, 168, 1, 2),
+            next_header: IpProtocol::Unknown(0xfd),
+            payload_len: 4,
+            hop_limit: 3,
+        }
+    );
+    assert_eq!(packet.payload(), &[0xaa, 0xbb, 0xcc, 0xdd]);
+}
+
 #[rstest]
 #[case(Medium::Ip)]
 #[cfg(all(feature = "socket-udp", feature = "medium-ip"))]
@@ -1016,3 +1399,749 @@ fn test_icmp_reply_size(#[case] medium: Medium) {
         ))
     );
 }
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_arp_pending_limit(#[case] medium: Medium) {
+    let (mut iface, _sockets, mut device) = setup(medium);
+
+    let local_ip_addr = IpAddress::v4(192, 168, 1, 1);
+    let remote_ip_addrs = [
+        IpAddress::v4(192, 168, 1, 2),
+        IpAddress::v4(192, 168, 1, 3),
+        IpAddress::v4(192, 168, 1, 4),
+    ];
+    assert_eq!(crate::config::IFACE_NEIGHBOR_PENDING_COUNT, 2);
+
+    let mut lookup = |iface: &mut Interface, timestamp, remote_ip_addr| {
+        iface.inner.now = timestamp;
+        let tx_token = device.transmit(timestamp).unwrap();
+        let result = iface
+            .inner
+            .lookup_hardware_addr(
+                tx_token,
+                &local_ip_addr,
+                &remote_ip_addr,
+                &mut iface.fragmenter,
+            )
+            .map(|(hardware_addr, _)| hardware_addr);
+        assert_eq!(result, Err(DispatchError::NeighborPending));
+        match device.queue.pop_front() {
+            Some(frame) => {
+                let frame = EthernetFrame::new_checked(&frame).unwrap();
+                let arp_repr = ArpRepr::parse(&ArpPacket::new_checked(frame.payload()).unwrap());
+                match arp_repr.unwrap() {
+                    ArpRepr::EthernetIpv4 {
+                        operation: ArpOperation::Request,
+                        target_protocol_addr,
+                        ..
+                    } => Some(IpAddress::Ipv4(target_protocol_addr)),
+                    repr => panic!("unexpected ARP packet {repr:?}"),
+                }
+            }
+            None => None,
+        }
+    };
+
+    // Only two neighbors are resolved at a time.
+    let t0 = Instant::ZERO;
+    assert_eq!(
+        lookup(&mut iface, t0, remote_ip_addrs[0]),
+        Some(remote_ip_addrs[0])
+    );
+    assert_eq!(
+        lookup(&mut iface, t0, remote_ip_addrs[1]),
+        Some(remote_ip_addrs[1])
+    );
+    assert_eq!(lookup(&mut iface, t0, remote_ip_addrs[2]), None);
+
+    // Pending neighbors are not asked for again right away.
+    let t1 = Instant::from_millis(500);
+    for remote_ip_addr in remote_ip_addrs {
+        assert_eq!(lookup(&mut iface, t1, remote_ip_addr), None);
+    }
+
+    // Once the lookups time out, the slots are available again.
+    let t2 = Instant::from_millis(1000);
+    assert_eq!(
+        lookup(&mut iface, t2, remote_ip_addrs[2]),
+        Some(remote_ip_addrs[2])
+    );
+    assert_eq!(
+        lookup(&mut iface, t2, remote_ip_addrs[0]),
+        Some(remote_ip_addrs[0])
+    );
+    assert_eq!(lookup(&mut iface, t2, remote_ip_addrs[1]), None);
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(
+    feature = "socket-udp",
+    feature = "proto-ipv4-fragmentation",
+    feature = "medium-ip"
+))]
+#[case(Medium::Ethernet)]
+#[cfg(all(
+    feature = "socket-udp",
+    feature = "proto-ipv4-fragmentation",
+    feature = "medium-ethernet"
+))]
+fn test_udp_fragmentation(#[case] medium: Medium) {
+    use crate::socket::udp;
+    use crate::wire::IpEndpoint;
+
+    let payload: std::vec::Vec<u8> = (0..3072).map(|i| i as u8).collect();
+    let local_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 1), 4000);
+    let remote_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 2), 5000);
+
+    let udp_socket = |len| {
+        udp::Socket::new(
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; len]),
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; len]),
+        )
+    };
+
+    let (mut iface, mut sockets, mut device) = setup(medium);
+    #[cfg(feature = "medium-ethernet")]
+    if medium == Medium::Ethernet {
+        // Both interfaces have the same hardware address.
+        let hardware_addr = iface.hardware_addr();
+        iface
+            .inner
+            .neighbor_cache
+            .fill(remote_endpoint.addr, hardware_addr, Instant::ZERO);
+    }
+    let handle = sockets.add(udp_socket(payload.len()));
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.bind(local_endpoint).unwrap();
+    socket.send_slice(&payload, remote_endpoint).unwrap();
+
+    // The testing device loops packets back, so don't let the interface receive them.
+    let mut fragments = std::vec::Vec::new();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    fragments.extend(device.queue.pop_front());
+    while iface.ipv4_egress(&mut device) {
+        fragments.extend(device.queue.pop_front());
+    }
+
+    let ip_packet = |frame: &[u8]| -> std::vec::Vec<u8> {
+        match medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => EthernetFrame::new_checked(frame)
+                .unwrap()
+                .payload()
+                .to_vec(),
+            _ => frame.to_vec(),
+        }
+    };
+
+    // A 1500-octet MTU leaves room for 1480 octets of payload per fragment; the UDP
+    // header and the data are 3080 octets.
+    assert_eq!(fragments.len(), 3);
+    let ident = Ipv4Packet::new_checked(ip_packet(&fragments[0]))
+        .unwrap()
+        .ident();
+    for (fragment, (offset, len, more_frags)) in
+        fragments
+            .iter()
+            .zip([(0, 1480, true), (1480, 1480, true), (2960, 120, false)])
+    {
+        let packet = ip_packet(fragment);
+        let packet = Ipv4Packet::new_checked(&packet[..]).unwrap();
+        assert_eq!(packet.src_addr(), Ipv4Address([192, 168, 1, 1]));
+        assert_eq!(packet.dst_addr(), Ipv4Address([192, 168, 1, 2]));
+        assert_eq!(packet.next_header(), IpProtocol::Udp);
+        assert_eq!(packet.ident(), ident);
+        assert_eq!(packet.frag_offset(), offset);
+        assert_eq!(packet.more_frags(), more_frags);
+        assert!(!packet.dont_frag());
+        assert_eq!(packet.payload().len(), len);
+        assert!(packet.verify_checksum());
+    }
+
+    // The peer reassembles the datagram.
+    let (mut peer, mut peer_sockets, mut peer_device) = setup(medium);
+    peer.update_ip_addrs(|addrs| {
+        addrs[0] = IpCidr::new(remote_endpoint.addr, 24);
+    });
+    let peer_handle = peer_sockets.add(udp_socket(payload.len()));
+    peer_sockets
+        .get_mut::<udp::Socket>(peer_handle)
+        .bind(remote_endpoint)
+        .unwrap();
+    for fragment in fragments {
+        peer_device.queue.push_back(fragment).unwrap();
+    }
+    peer.poll(Instant::ZERO, &mut peer_device, &mut peer_sockets);
+
+    let peer_socket = peer_sockets.get_mut::<udp::Socket>(peer_handle);
+    let (data, meta) = peer_socket.recv().unwrap();
+    assert_eq!(data, &payload[..]);
+    assert_eq!(meta.endpoint, local_endpoint);
+}
+
+#[test]
+#[cfg(all(
+    feature = "socket-udp",
+    feature = "proto-ipv4-fragmentation",
+    feature = "medium-ip"
+))]
+fn test_udp_dont_fragment() {
+    use crate::iface::DropReason;
+    use crate::socket::udp;
+    use crate::wire::IpEndpoint;
+
+    let local_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 1), 4000);
+    let remote_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 2), 5000);
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    iface.set_events_enabled(true);
+    let handle = sockets.add(udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 2048]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 2048]),
+    ));
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.bind(local_endpoint).unwrap();
+    socket.set_dont_fragment(iface.context(), true);
+
+    // A datagram that doesn't fit in the MTU is rejected right away, even before the
+    // socket has sent anything. The largest datagram that fits is still sent.
+    assert_eq!(
+        socket.send_slice(&[0xaa; 1473], remote_endpoint),
+        Err(udp::SendError::TooLarge)
+    );
+    socket.send_slice(&[0xaa; 1472], remote_endpoint).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    let packet = Ipv4Packet::new_checked(&frame[..]).unwrap();
+    assert_eq!(packet.total_len(), 1500);
+    assert!(packet.dont_frag());
+    assert!(packet.verify_checksum());
+
+    // Without the option, the datagram is fragmented, and datagrams that fit are still sent
+    // with the DF flag.
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.set_dont_fragment(iface.context(), false);
+    socket.send_slice(&[0xaa; 1473], remote_endpoint).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    let packet = Ipv4Packet::new_checked(&frame[..]).unwrap();
+    assert!(packet.more_frags());
+    assert!(!packet.dont_frag());
+    while iface.ipv4_egress(&mut device) {}
+    device.queue.clear();
+
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.send_slice(&[0xaa; 100], remote_endpoint).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    let packet = Ipv4Packet::new_checked(&frame[..]).unwrap();
+    assert_eq!(packet.total_len(), 128);
+    assert!(packet.dont_frag());
+    assert!(packet.verify_checksum());
+
+    // A datagram enqueued before the option was set is dropped instead of fragmented.
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.send_slice(&[0xaa; 1473], remote_endpoint).unwrap();
+    socket.set_dont_fragment(iface.context(), true);
+    iface.socket_egress(&mut device, &mut sockets);
+    assert!(device.queue.is_empty());
+    assert!(!iface.ipv4_egress(&mut device));
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::PacketDropped(DropReason::TooLarge))
+    );
+}
+
+#[test]
+#[cfg(all(feature = "socket-tcp", feature = "medium-ip"))]
+fn test_tcp_dont_fragment() {
+    use crate::socket::tcp;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    let handle = sockets.add(tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; 64]),
+        tcp::SocketBuffer::new(vec![0; 64]),
+    ));
+
+    // By default, segments are sent with the DF flag.
+    let socket = sockets.get_mut::<tcp::Socket>(handle);
+    assert!(socket.dont_fragment());
+    socket
+        .connect(iface.context(), (IpAddress::v4(192, 168, 1, 2), 80), 49500)
+        .unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    let packet = Ipv4Packet::new_checked(&frame[..]).unwrap();
+    assert!(packet.dont_frag());
+    assert!(packet.verify_checksum());
+
+    // Without the option, the DF flag is clear.
+    let socket = sockets.get_mut::<tcp::Socket>(handle);
+    socket.set_dont_fragment(false);
+    socket.abort();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    let packet = Ipv4Packet::new_checked(&frame[..]).unwrap();
+    assert!(!packet.dont_frag());
+    assert!(packet.verify_checksum());
+}
+
+#[test]
+#[cfg(all(feature = "socket-udp", feature = "medium-ethernet"))]
+fn test_reset_flushes_neighbor_cache() {
+    use crate::socket::udp;
+    use crate::wire::IpEndpoint;
+
+    let local_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 1), 4000);
+    let remote_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 2), 5000);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x02]);
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    iface.inner.neighbor_cache.fill(
+        remote_endpoint.addr,
+        HardwareAddress::Ethernet(remote_hw_addr),
+        Instant::ZERO,
+    );
+    let handle = sockets.add(udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 64]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 64]),
+    ));
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.bind(local_endpoint).unwrap();
+
+    // With the neighbor known, the datagram goes out directly.
+    socket.send_slice(b"abcdef", remote_endpoint).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.ethertype(), EthernetProtocol::Ipv4);
+    assert_eq!(frame.dst_addr(), remote_hw_addr);
+
+    iface.reset();
+    assert!(!iface
+        .inner
+        .neighbor_cache
+        .lookup(&remote_endpoint.addr, iface.inner.now)
+        .found());
+
+    // The addresses and the socket survive the reset, but the neighbor has to be
+    // resolved again.
+    assert!(iface.has_ip_addr(local_endpoint.addr));
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    assert!(socket.is_open());
+    socket.send_slice(b"abcdef", remote_endpoint).unwrap();
+    iface.socket_egress(&mut device, &mut sockets);
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+    assert_eq!(
+        ArpRepr::parse(&ArpPacket::new_checked(frame.payload()).unwrap()),
+        Ok(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: iface.hardware_addr().ethernet_or_panic(),
+            source_protocol_addr: Ipv4Address::new(192, 168, 1, 1),
+            target_hardware_addr: EthernetAddress::BROADCAST,
+            target_protocol_addr: Ipv4Address::new(192, 168, 1, 2),
+        })
+    );
+}
+
+#[test]
+#[cfg(all(feature = "socket-udp", feature = "medium-ethernet"))]
+fn test_neighbor_solicit_retries() {
+    use crate::iface::DropReason;
+    use crate::socket::udp;
+    use crate::wire::IpEndpoint;
+
+    let local_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 1), 4000);
+    let remote_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 2), 5000);
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    iface.set_events_enabled(true);
+    iface.set_neighbor_solicit_retries(Some(2));
+    iface.set_neighbor_solicit_interval(Duration::from_millis(500));
+    let handle = sockets.add(udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 64]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 64]),
+    ));
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.bind(local_endpoint).unwrap();
+    socket.send_slice(b"abcdef", remote_endpoint).unwrap();
+    socket.send_slice(b"ghijkl", remote_endpoint).unwrap();
+
+    let mut egress = |iface: &mut Interface, sockets: &mut SocketSet, millis| {
+        iface.inner.now = Instant::from_millis(millis);
+        iface.socket_egress(&mut device, sockets);
+        let mut solicits = 0;
+        while let Some(frame) = device.queue.pop_front() {
+            let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+            assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+            solicits += 1;
+        }
+        solicits
+    };
+
+    // The neighbor, which never answers, is asked for once and then twice again.
+    let mut solicited_at = vec![];
+    for millis in (0..2000).step_by(100) {
+        for _ in 0..egress(&mut iface, &mut sockets, millis) {
+            solicited_at.push(millis);
+        }
+    }
+    assert_eq!(solicited_at, [0, 500, 1000]);
+
+    // Both datagrams were then dropped.
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::PacketDropped(DropReason::NeighborUnreachable))
+    );
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::PacketDropped(DropReason::NeighborUnreachable))
+    );
+    assert_eq!(iface.next_event(), None);
+
+    // Later datagrams start discovery over.
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.send_slice(b"abcdef", remote_endpoint).unwrap();
+    socket.send_slice(b"ghijkl", remote_endpoint).unwrap();
+    assert_eq!(egress(&mut iface, &mut sockets, 2000), 1);
+}
+
+#[test]
+#[cfg(all(feature = "socket-udp", feature = "medium-ethernet"))]
+fn test_poll_with_clock() {
+    use crate::socket::udp;
+    use crate::time::ManualClock;
+    use crate::wire::IpEndpoint;
+
+    let local_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 1), 4000);
+    let remote_endpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 2), 5000);
+
+    let clock = ManualClock::new(Instant::ZERO);
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    iface.set_neighbor_solicit_interval(Duration::from_millis(500));
+    let handle = sockets.add(udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 64]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 64]),
+    ));
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.bind(local_endpoint).unwrap();
+    socket.send_slice(b"abcdef", remote_endpoint).unwrap();
+
+    // Without a clock, the interface can't tell the time, and does nothing.
+    assert_eq!(iface.poll_with_clock(&mut device, &mut sockets), None);
+    assert_eq!(iface.poll_delay_with_clock(&sockets), None);
+    assert!(device.queue.is_empty());
+    iface.set_clock(Some(&clock));
+
+    let mut poll = |iface: &mut Interface, sockets: &mut SocketSet| {
+        assert!(iface.poll_with_clock(&mut device, sockets).is_some());
+        let mut solicits = 0;
+        while let Some(frame) = device.queue.pop_front() {
+            let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+            assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+            solicits += 1;
+        }
+        solicits
+    };
+
+    // The neighbor is solicited again only once the clock is past the interval.
+    assert_eq!(poll(&mut iface, &mut sockets), 1);
+    assert_eq!(
+        iface.poll_delay_with_clock(&sockets),
+        Some(Some(Duration::from_millis(500)))
+    );
+    clock.advance(Duration::from_millis(499));
+    assert_eq!(poll(&mut iface, &mut sockets), 0);
+    assert_eq!(
+        iface.poll_delay_with_clock(&sockets),
+        Some(Some(Duration::from_millis(1)))
+    );
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(poll(&mut iface, &mut sockets), 1);
+
+    // The socket context shows the time of the clock, not that of the last poll.
+    clock.advance(Duration::from_millis(100));
+    assert_eq!(iface.context().now(), Instant::from_millis(600));
+
+    // A jump far into the future is handled like any other passing of time.
+    clock.set(Instant::from_secs(3600));
+    assert_eq!(poll(&mut iface, &mut sockets), 1);
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_handle_icmp_redirect(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let gateway = Ipv4Address::new(192, 168, 1, 254);
+    let better_gateway = Ipv4Address::new(192, 168, 1, 253);
+    let dst_addr = Ipv4Address::new(10, 0, 0, 1);
+    iface.routes_mut().add_default_ipv4_route(gateway).unwrap();
+    iface.set_events_enabled(true);
+
+    let mut process_redirect = |iface: &mut Interface, src_addr| {
+        let icmp_repr = Icmpv4Repr::Redirect {
+            reason: Icmpv4Redirect::Host,
+            gateway: better_gateway,
+            header: Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 1),
+                dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: 8,
+                hop_limit: 64,
+            },
+            data: &[0; 8],
+        };
+        let mut bytes = vec![0; icmp_repr.buffer_len()];
+        icmp_repr.emit(
+            &mut Icmpv4Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        let ip_repr = Ipv4Repr {
+            src_addr,
+            dst_addr: Ipv4Address::new(192, 168, 1, 1),
+            next_header: IpProtocol::Icmp,
+            payload_len: bytes.len(),
+            hop_limit: 64,
+        };
+        assert_eq!(
+            iface.inner.process_icmpv4(&mut sockets, ip_repr, &bytes),
+            None
+        );
+    };
+
+    // Redirects are ignored by default.
+    process_redirect(&mut iface, gateway);
+    assert_eq!(
+        iface.inner.route(&dst_addr.into(), Instant::ZERO),
+        Some(gateway.into())
+    );
+    assert_eq!(iface.next_event(), None);
+
+    // Once enabled, a redirect that doesn't come from the current gateway is dropped.
+    iface.set_accept_redirects(true);
+    process_redirect(&mut iface, Ipv4Address::new(192, 168, 1, 2));
+    assert_eq!(
+        iface.inner.route(&dst_addr.into(), Instant::ZERO),
+        Some(gateway.into())
+    );
+    assert_eq!(iface.next_event(), None);
+
+    // A redirect from the current gateway installs a route for the destination only.
+    process_redirect(&mut iface, gateway);
+    assert_eq!(
+        iface.inner.route(&dst_addr.into(), Instant::ZERO),
+        Some(better_gateway.into())
+    );
+    assert_eq!(
+        iface
+            .inner
+            .route(&Ipv4Address::new(10, 0, 0, 2).into(), Instant::ZERO),
+        Some(gateway.into())
+    );
+    assert_eq!(iface.next_event(), Some(Event::RoutesChanged));
+
+    // Resetting the interface forgets the route, but not the default route.
+    iface.reset();
+    assert_eq!(
+        iface.inner.route(&dst_addr.into(), Instant::ZERO),
+        Some(gateway.into())
+    );
+    assert_eq!(iface.next_event(), Some(Event::RoutesChanged));
+    iface.reset();
+    assert_eq!(iface.next_event(), None);
+}
+
+#[test]
+#[cfg(all(feature = "socket-tcp", feature = "medium-ip", feature = "alloc"))]
+fn test_tcp_device_mtu_change() {
+    use crate::socket::tcp;
+    use std::vec::Vec;
+
+    /// A loopback device whose MTU can be changed, which records the size of every
+    /// packet it carries.
+    struct MtuLoopback {
+        inner: Loopback,
+        mtu: usize,
+        sizes: Vec<usize>,
+    }
+
+    impl Device for MtuLoopback {
+        type RxToken<'a> = <Loopback as Device>::RxToken<'a>;
+        type TxToken<'a> = <Loopback as Device>::TxToken<'a>;
+
+        fn capabilities(&self) -> DeviceCapabilities {
+            DeviceCapabilities {
+                max_transmission_unit: self.mtu,
+                ..self.inner.capabilities()
+            }
+        }
+
+        fn receive(
+            &mut self,
+            timestamp: Instant,
+        ) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+            self.sizes
+                .extend(self.inner.queue.front().map(|packet| packet.len()));
+            self.inner.receive(timestamp)
+        }
+
+        fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+            self.inner.transmit(timestamp)
+        }
+    }
+
+    let mut device = MtuLoopback {
+        inner: Loopback::new(Medium::Ip),
+        mtu: 1500,
+        sizes: Vec::new(),
+    };
+    let mut iface = Interface::new(Config::new(HardwareAddress::Ip), &mut device, Instant::ZERO);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v4(127, 0, 0, 1), 8))
+            .unwrap();
+    });
+    let mut sockets = SocketSet::new(vec![]);
+
+    let mut server = tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; 8192]),
+        tcp::SocketBuffer::new(vec![0; 8192]),
+    );
+    server.set_ack_delay(None);
+    server.listen(1234).unwrap();
+    let server = sockets.add(server);
+
+    let mut client = tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; 8192]),
+        tcp::SocketBuffer::new(vec![0; 8192]),
+    );
+    client.set_nagle_enabled(false);
+    client
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1234), 65000)
+        .unwrap();
+    let client = sockets.add(client);
+
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(
+        sockets.get_mut::<tcp::Socket>(client).state(),
+        tcp::State::Established
+    );
+
+    let mut transfer = |iface: &mut Interface, device: &mut MtuLoopback| {
+        device.sizes.clear();
+        sockets
+            .get_mut::<tcp::Socket>(client)
+            .send_slice(&[0xaa; 2000])
+            .unwrap();
+        iface.poll(Instant::ZERO, device, &mut sockets);
+        let server = sockets.get_mut::<tcp::Socket>(server);
+        assert_eq!(server.recv(|data| (data.len(), data.len())), Ok(2000));
+        device.sizes.iter().copied().max().unwrap()
+    };
+
+    assert_eq!(transfer(&mut iface, &mut device), 1500);
+
+    // The device MTU is lowered, the established connection sends smaller segments.
+    device.mtu = 576;
+    assert_eq!(transfer(&mut iface, &mut device), 1500);
+    iface.update_capabilities(&device);
+    assert_eq!(iface.ip_mtu(), 576);
+    assert_eq!(transfer(&mut iface, &mut device), 576);
+}
+
+#[test]
+#[cfg(all(feature = "socket-udp", feature = "medium-ip"))]
+fn test_unspecified_source_policy() {
+    use crate::iface::UnspecifiedSourcePolicy;
+    use crate::socket::udp;
+
+    fn udp_packet(dst_addr: Ipv4Address, src_port: u16, dst_port: u16) -> std::vec::Vec<u8> {
+        let udp_repr = UdpRepr { src_port, dst_port };
+        let ipv4_repr = Ipv4Repr {
+            src_addr: Ipv4Address::UNSPECIFIED,
+            dst_addr,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + 4,
+            hop_limit: 64,
+        };
+        let mut bytes = vec![0; ipv4_repr.buffer_len() + ipv4_repr.payload_len];
+        ipv4_repr.emit(
+            &mut Ipv4Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        udp_repr.emit(
+            &mut UdpPacket::new_unchecked(&mut bytes[ipv4_repr.buffer_len()..]),
+            &ipv4_repr.src_addr.into(),
+            &ipv4_repr.dst_addr.into(),
+            4,
+            |buf| buf.copy_from_slice(b"abcd"),
+            &ChecksumCapabilities::default(),
+        );
+        bytes
+    }
+
+    let (mut iface, mut sockets, _) = setup(Medium::Ip);
+    let mut bind = |port| {
+        let mut socket = udp::Socket::new(
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 16]),
+            udp::PacketBuffer::new(vec![], vec![]),
+        );
+        socket.bind(port).unwrap();
+        sockets.add(socket)
+    };
+    let dhcp_handle = bind(68);
+    let server_handle = bind(67);
+    let other_handle = bind(1234);
+
+    // A request sent to a DHCP server and a reply sent to a DHCP client are accepted,
+    // other datagrams are dropped.
+    let discover = udp_packet(Ipv4Address::BROADCAST, 68, 67);
+    let offer = udp_packet(Ipv4Address::BROADCAST, 67, 68);
+    let other = udp_packet(Ipv4Address::new(192, 168, 1, 1), 67, 1234);
+    for data in [&discover, &offer, &other] {
+        let packet = Ipv4Packet::new_checked(&data[..]).unwrap();
+        assert_eq!(
+            iface.inner.process_ipv4(
+                &mut sockets,
+                PacketMeta::default(),
+                &packet,
+                &mut iface.fragments
+            ),
+            None
+        );
+    }
+    assert!(sockets.get_mut::<udp::Socket>(server_handle).recv().is_ok());
+    assert!(sockets.get_mut::<udp::Socket>(dhcp_handle).recv().is_ok());
+    assert!(!sockets.get_mut::<udp::Socket>(other_handle).can_recv());
+
+    // Or all of them are accepted.
+    iface.set_unspecified_source_policy(UnspecifiedSourcePolicy::Accept);
+    let packet = Ipv4Packet::new_checked(&other[..]).unwrap();
+    iface.inner.process_ipv4(
+        &mut sockets,
+        PacketMeta::default(),
+        &packet,
+        &mut iface.fragments,
+    );
+    assert!(sockets.get_mut::<udp::Socket>(other_handle).recv().is_ok());
+
+    // Or none of them.
+    iface.set_unspecified_source_policy(UnspecifiedSourcePolicy::Drop);
+    let packet = Ipv4Packet::new_checked(&offer[..]).unwrap();
+    iface.inner.process_ipv4(
+        &mut sockets,
+        PacketMeta::default(),
+        &packet,
+        &mut iface.fragments,
+    );
+    assert!(!sockets.get_mut::<udp::Socket>(dhcp_handle).can_recv());
+}
@@ -167,7 +167,7 @@ fn hop_by_hop_discard_param_problem(#[case] medium: Medium) {
         },
         IpPayload::Icmpv6(Icmpv6Repr::ParamProblem {
             reason: Icmpv6ParamProblem::UnrecognizedOption,
-            pointer: 40,
+            pointer: 42,
             header: Ipv6Repr {
                 src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2),
                 dst_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
@@ -224,7 +224,7 @@ fn hop_by_hop_discard_with_multicast(#[case] medium: Medium) {
         },
         IpPayload::Icmpv6(Icmpv6Repr::ParamProblem {
             reason: Icmpv6ParamProblem::UnrecognizedOption,
-            pointer: 40,
+            pointer: 42,
             header: Ipv6Repr {
                 src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2),
                 dst_addr: Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 0, 1),
@@ -251,6 +251,53 @@ fn hop_by_hop_discard_with_multicast(#[case] medium: Medium) {
     );
 }
 
+#[test]
+#[cfg(feature = "medium-ip")]
+fn hop_by_hop_param_problem_pointer() {
+    fn param_problem(hbh: &[u8; 8]) -> Option<(Icmpv6ParamProblem, u32, std::vec::Vec<u8>)> {
+        let mut data = vec![
+            0x60, 0x0, 0x0, 0x0, 0x0, 0x8, 0x0, 0x40, 0xfd, 0xbe, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2, 0xfd, 0xbe, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x1,
+        ];
+        data.extend_from_slice(hbh);
+
+        let (mut iface, mut sockets, _device) = setup(Medium::Ip);
+        let response = iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&data[..]).unwrap(),
+        );
+        match response?.payload() {
+            IpPayload::Icmpv6(Icmpv6Repr::ParamProblem {
+                reason,
+                pointer,
+                data,
+                ..
+            }) => Some((*reason, *pointer, data.to_vec())),
+            _ => None,
+        }
+    }
+
+    // - Pad1
+    // - Unknown option (skip)
+    // - Unknown option (discard + ParamProblem), at offset 45
+    // - Pad1
+    let hbh = [0x3b, 0x0, 0x0, 0x1e, 0x0, 0x9e, 0x0, 0x0];
+    assert_eq!(
+        param_problem(&hbh),
+        Some((Icmpv6ParamProblem::UnrecognizedOption, 45, hbh.to_vec()))
+    );
+
+    // - Unknown option (skip)
+    // followed by an unknown protocol, whose value is at offset 40.
+    let hbh = [0xc, 0x0, 0x1e, 0x4, 0x0, 0x0, 0x0, 0x0];
+    assert_eq!(
+        param_problem(&hbh),
+        Some((Icmpv6ParamProblem::UnrecognizedNxtHdr, 40, hbh.to_vec()))
+    );
+}
+
 #[rstest]
 #[case::ip(Medium::Ip)]
 #[cfg(feature = "medium-ip")]
@@ -370,6 +417,110 @@ fn icmp_echo_request(#[case] medium: Medium) {
     );
 }
 
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn icmp_echo_request_multicast(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let echo_repr = Icmpv6Repr::EchoRequest {
+        ident: 42,
+        seq_no: 420,
+        data: b"Lorem Ipsum",
+    };
+    let reply_repr = Icmpv6Repr::EchoReply {
+        ident: 42,
+        seq_no: 420,
+        data: b"Lorem Ipsum",
+    };
+
+    let multicast_repr = Ipv6Repr {
+        src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2),
+        dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+        next_header: IpProtocol::Icmpv6,
+        payload_len: echo_repr.buffer_len(),
+        hop_limit: 64,
+    };
+    let unicast_repr = Ipv6Repr {
+        dst_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+        ..multicast_repr
+    };
+
+    let mut multicast_bytes = vec![0u8; multicast_repr.buffer_len() + echo_repr.buffer_len()];
+    let mut unicast_bytes = multicast_bytes.clone();
+    for (ip_repr, bytes) in [
+        (multicast_repr, &mut multicast_bytes),
+        (unicast_repr, &mut unicast_bytes),
+    ] {
+        let mut packet = Ipv6Packet::new_unchecked(&mut bytes[..]);
+        ip_repr.emit(&mut packet);
+        echo_repr.emit(
+            &ip_repr.src_addr,
+            &ip_repr.dst_addr,
+            &mut Icmpv6Packet::new_unchecked(packet.payload_mut()),
+            &ChecksumCapabilities::default(),
+        );
+    }
+
+    let unicast_reply = Some(Packet::new_ipv6(
+        Ipv6Repr {
+            src_addr: unicast_repr.dst_addr,
+            dst_addr: unicast_repr.src_addr,
+            ..unicast_repr
+        },
+        IpPayload::Icmpv6(reply_repr),
+    ));
+    let multicast_reply = Some(Packet::new_ipv6(
+        Ipv6Repr {
+            src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+            dst_addr: multicast_repr.src_addr,
+            ..multicast_repr
+        },
+        IpPayload::Icmpv6(reply_repr),
+    ));
+
+    // Multicast echo requests are ignored by default, unicast ones are always answered.
+    assert!(!iface.echo_broadcast());
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&multicast_bytes[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&unicast_bytes[..]).unwrap()
+        ),
+        unicast_reply
+    );
+
+    iface.set_echo_broadcast(true);
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&multicast_bytes[..]).unwrap()
+        ),
+        multicast_reply
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&unicast_bytes[..]).unwrap()
+        ),
+        unicast_reply
+    );
+}
+
 #[rstest]
 #[case::ip(Medium::Ip)]
 #[cfg(feature = "medium-ip")]
@@ -441,7 +592,7 @@ fn unknown_proto_with_multicast_dst_address(#[case] medium: Medium) {
         },
         IpPayload::Icmpv6(Icmpv6Repr::ParamProblem {
             reason: Icmpv6ParamProblem::UnrecognizedNxtHdr,
-            pointer: 40,
+            pointer: 6,
             header: Ipv6Repr {
                 src_addr: Ipv6Address::from_parts(&[0xfdbe, 0, 0, 0, 0, 0, 0, 0x0002]),
                 dst_addr: Ipv6Address::from_parts(&[0xff02, 0, 0, 0, 0, 0, 0, 0x0001]),
@@ -490,7 +641,7 @@ fn unknown_proto(#[case] medium: Medium) {
         },
         IpPayload::Icmpv6(Icmpv6Repr::ParamProblem {
             reason: Icmpv6ParamProblem::UnrecognizedNxtHdr,
-            pointer: 40,
+            pointer: 6,
             header: Ipv6Repr {
                 src_addr: Ipv6Address::from_parts(&[0xfdbe, 0, 0, 0, 0, 0, 0, 0x0002]),
                 dst_addr: Ipv6Address::from_parts(&[0xfdbe, 0, 0, 0, 0, 0, 0, 0x0001]),
@@ -514,6 +665,55 @@ fn unknown_proto(#[case] medium: Medium) {
     );
 }
 
+#[test]
+#[cfg(feature = "medium-ip")]
+fn icmpv6_error_rate_limit() {
+    let data = [
+        0x60, 0x0, 0x0, 0x0, 0x0, 0x0, 0xc, 0x40, 0xfd, 0xbe, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2, 0xfd, 0xbe, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        0x0, 0x0, 0x0, 0x0, 0x1,
+    ];
+    let packet = Ipv6Packet::new_checked(&data[..]).unwrap();
+
+    let (mut iface, mut sockets, _device) = setup(Medium::Ip);
+
+    // A burst of errors is answered, then errors are dropped.
+    for _ in 0..10 {
+        assert!(iface
+            .inner
+            .process_ipv6(&mut sockets, PacketMeta::default(), &packet)
+            .is_some());
+    }
+    assert_eq!(
+        iface
+            .inner
+            .process_ipv6(&mut sockets, PacketMeta::default(), &packet),
+        None
+    );
+
+    // One more error can be sent after a while.
+    iface.inner.now = Instant::from_millis(150);
+    assert!(iface
+        .inner
+        .process_ipv6(&mut sockets, PacketMeta::default(), &packet)
+        .is_some());
+    assert_eq!(
+        iface
+            .inner
+            .process_ipv6(&mut sockets, PacketMeta::default(), &packet),
+        None
+    );
+
+    // The bucket refills completely after a long time.
+    iface.inner.now = Instant::from_secs(10);
+    for _ in 0..10 {
+        assert!(iface
+            .inner
+            .process_ipv6(&mut sockets, PacketMeta::default(), &packet)
+            .is_some());
+    }
+}
+
 #[rstest]
 #[case::ethernet(Medium::Ethernet)]
 #[cfg(feature = "medium-ethernet")]
@@ -753,6 +953,93 @@ fn test_handle_valid_ndisc_request(#[case] medium: Medium) {
     );
 }
 
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_handle_proxied_ndisc_request(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+    iface.update_proxy_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(
+                IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 0x0100),
+                120,
+            ))
+            .unwrap();
+    });
+
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let remote_ip_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    let solicitation = |target_addr: Ipv6Address| {
+        let mut eth_bytes = vec![0u8; 86];
+        let solicit = Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+            target_addr,
+            lladdr: Some(remote_hw_addr.into()),
+        });
+        let ip_repr = IpRepr::Ipv6(Ipv6Repr {
+            src_addr: remote_ip_addr,
+            dst_addr: target_addr.solicited_node(),
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 0xff,
+            payload_len: solicit.buffer_len(),
+        });
+
+        let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+        frame.set_dst_addr(EthernetAddress([0x33, 0x33, 0x00, 0x00, 0x00, 0x00]));
+        frame.set_src_addr(remote_hw_addr);
+        frame.set_ethertype(EthernetProtocol::Ipv6);
+        ip_repr.emit(frame.payload_mut(), &ChecksumCapabilities::default());
+        solicit.emit(
+            &remote_ip_addr,
+            &target_addr.solicited_node(),
+            &mut Icmpv6Packet::new_unchecked(&mut frame.payload_mut()[ip_repr.header_len()..]),
+            &ChecksumCapabilities::default(),
+        );
+        eth_bytes
+    };
+
+    let proxied_ip_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0x0105);
+    let icmpv6_expected = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+        flags: NdiscNeighborFlags::SOLICITED,
+        target_addr: proxied_ip_addr,
+        lladdr: Some(local_hw_addr.into()),
+    });
+    let ipv6_expected = Ipv6Repr {
+        // The advertisement comes from our own address on the link of the remote host.
+        src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+        dst_addr: remote_ip_addr,
+        next_header: IpProtocol::Icmpv6,
+        hop_limit: 0xff,
+        payload_len: icmpv6_expected.buffer_len(),
+    };
+
+    // Ensure a Neighbor Solicitation for a proxied address triggers a Neighbor Advertisement
+    assert_eq!(
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &solicitation(proxied_ip_addr),
+            &mut iface.fragments
+        ),
+        Some(EthernetPacket::Ip(Packet::new_ipv6(
+            ipv6_expected,
+            IpPayload::Icmpv6(icmpv6_expected)
+        )))
+    );
+
+    // Ensure a Neighbor Solicitation for an address outside the proxied range is ignored
+    assert_eq!(
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &solicitation(Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0x0205)),
+            &mut iface.fragments
+        ),
+        None
+    );
+}
+
 #[rstest]
 #[case(Medium::Ip)]
 #[cfg(feature = "medium-ip")]
@@ -984,3 +1271,363 @@ fn get_source_address() {
         Some(OWN_GLOBAL_UNICAST_ADDR1)
     );
 }
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_events_duplicate_address_detection(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let local_ip_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    iface.set_events_enabled(true);
+    let added_cidr = IpCidr::new(IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 2), 64);
+    iface.update_ip_addrs(|addrs| addrs.push(added_cidr).unwrap());
+
+    // Another host probes for our address.
+    let mut eth_bytes = vec![0u8; 78];
+    let solicit = Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+        target_addr: local_ip_addr,
+        lladdr: None,
+    });
+    let ip_repr = IpRepr::Ipv6(Ipv6Repr {
+        src_addr: Ipv6Address::UNSPECIFIED,
+        dst_addr: local_ip_addr.solicited_node(),
+        next_header: IpProtocol::Icmpv6,
+        hop_limit: 0xff,
+        payload_len: solicit.buffer_len(),
+    });
+    let mut frame = EthernetFrame::new_unchecked(&mut eth_bytes);
+    frame.set_dst_addr(EthernetAddress([0x33, 0x33, 0xff, 0x00, 0x00, 0x01]));
+    frame.set_src_addr(remote_hw_addr);
+    frame.set_ethertype(EthernetProtocol::Ipv6);
+    ip_repr.emit(frame.payload_mut(), &ChecksumCapabilities::default());
+    solicit.emit(
+        &Ipv6Address::UNSPECIFIED,
+        &local_ip_addr.solicited_node(),
+        &mut Icmpv6Packet::new_unchecked(&mut frame.payload_mut()[ip_repr.header_len()..]),
+        &ChecksumCapabilities::default(),
+    );
+
+    // The probe is answered to all nodes.
+    let tx = iface.process_frame(Instant::ZERO, &mut sockets, &eth_bytes);
+    assert_eq!(tx.len(), 1);
+    let frame = EthernetFrame::new_checked(&tx[0][..]).unwrap();
+    assert_eq!(frame.src_addr(), local_hw_addr);
+    assert_eq!(
+        parse_ipv6(frame.payload()),
+        Ok(Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: local_ip_addr,
+                dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+                next_header: IpProtocol::Icmpv6,
+                hop_limit: 0xff,
+                payload_len: 32,
+            },
+            IpPayload::Icmpv6(Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+                flags: NdiscNeighborFlags::OVERRIDE,
+                target_addr: local_ip_addr,
+                lladdr: Some(local_hw_addr.into()),
+            }))
+        ))
+    );
+
+    assert_eq!(iface.next_event(), Some(Event::AddressAdded(added_cidr)));
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::AddressConflict {
+            addr: local_ip_addr.into(),
+            hardware_addr: None,
+        })
+    );
+    assert_eq!(iface.next_event(), None);
+
+    // Unless all packets from the unspecified address are dropped.
+    iface.set_unspecified_source_policy(UnspecifiedSourcePolicy::Drop);
+    let tx = iface.process_frame(Instant::ZERO, &mut sockets, &eth_bytes);
+    assert!(tx.is_empty());
+    assert_eq!(iface.next_event(), None);
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_handle_redirect(#[case] medium: Medium) {
+    let (mut iface, _sockets, _device) = setup(medium);
+
+    let router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0x100);
+    let better_router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0x200);
+    let better_router_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x02, 0x00]);
+    let dest_addr = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    iface.routes_mut().add_default_ipv6_route(router).unwrap();
+    iface.set_events_enabled(true);
+    iface.set_accept_redirects(true);
+
+    let redirect = |src_addr, target_addr| {
+        let repr = NdiscRepr::Redirect {
+            target_addr,
+            dest_addr,
+            lladdr: Some(better_router_hw_addr.into()),
+            redirected_hdr: None,
+        };
+        let ip_repr = Ipv6Repr {
+            src_addr,
+            dst_addr: Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 0xff,
+            payload_len: Icmpv6Repr::Ndisc(repr).buffer_len(),
+        };
+        (ip_repr, repr)
+    };
+
+    // A redirect that doesn't come from the current router is ignored.
+    let (ip_repr, repr) = redirect(
+        Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0x300),
+        better_router,
+    );
+    assert_eq!(iface.inner.process_ndisc(ip_repr, repr), None);
+    assert_eq!(
+        iface.inner.route(&dest_addr.into(), Instant::ZERO),
+        Some(router.into())
+    );
+
+    // So is a redirect to a router that isn't on the link.
+    let global_router = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x200);
+    let (ip_repr, repr) = redirect(router, global_router);
+    assert_eq!(iface.inner.process_ndisc(ip_repr, repr), None);
+    assert_eq!(
+        iface.inner.route(&dest_addr.into(), Instant::ZERO),
+        Some(router.into())
+    );
+    assert_eq!(iface.next_event(), None);
+
+    // A valid redirect changes the first hop for the destination only, and the
+    // link-layer address of the new router is learned.
+    let (ip_repr, repr) = redirect(router, better_router);
+    assert_eq!(iface.inner.process_ndisc(ip_repr, repr), None);
+    assert_eq!(
+        iface.inner.route(&dest_addr.into(), Instant::ZERO),
+        Some(better_router.into())
+    );
+    assert_eq!(
+        iface.inner.route(
+            &Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2).into(),
+            Instant::ZERO
+        ),
+        Some(router.into())
+    );
+    assert_eq!(
+        iface.next_event(),
+        Some(Event::NeighborAdded {
+            addr: better_router.into(),
+            hardware_addr: better_router_hw_addr.into(),
+        })
+    );
+    assert_eq!(iface.next_event(), Some(Event::RoutesChanged));
+
+    // Only the new router may redirect the destination again.
+    let (ip_repr, repr) = redirect(router, dest_addr);
+    assert_eq!(iface.inner.process_ndisc(ip_repr, repr), None);
+    assert_eq!(
+        iface.inner.route(&dest_addr.into(), Instant::ZERO),
+        Some(better_router.into())
+    );
+    let (ip_repr, repr) = redirect(better_router, dest_addr);
+    assert_eq!(iface.inner.process_ndisc(ip_repr, repr), None);
+    assert_eq!(
+        iface.inner.route(&dest_addr.into(), Instant::ZERO),
+        Some(dest_addr.into())
+    );
+}
+
+#[test]
+#[cfg(all(feature = "socket-udp", feature = "medium-ip"))]
+fn test_udp_flow_label() {
+    use crate::socket::udp;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let udp_socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 64]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 64]),
+    );
+    let handle = sockets.add(udp_socket);
+    sockets.get_mut::<udp::Socket>(handle).bind(1234).unwrap();
+
+    let remote_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let mut send = |iface: &mut Interface, sockets: &mut SocketSet<'_>, port: u16| {
+        let socket = sockets.get_mut::<udp::Socket>(handle);
+        socket
+            .send_slice(b"abcd", IpEndpoint::new(remote_addr.into(), port))
+            .unwrap();
+        assert!(iface.socket_egress(&mut device, sockets));
+        let frame = device.queue.pop_front().unwrap();
+        Ipv6Packet::new_checked(&frame[..]).unwrap().flow_label()
+    };
+
+    // By default, packets don't belong to a flow.
+    assert_eq!(send(&mut iface, &mut sockets, 5678), 0);
+
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.set_flow_label(FlowLabel::Fixed(0x12345));
+    assert_eq!(send(&mut iface, &mut sockets, 5678), 0x12345);
+
+    // Automatic labels are stable for a remote endpoint, and differ between them.
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.set_flow_label(FlowLabel::Auto);
+    let label = send(&mut iface, &mut sockets, 5678);
+    assert_ne!(label, 0);
+    assert!(label <= FlowLabel::MAX);
+    assert_eq!(send(&mut iface, &mut sockets, 5678), label);
+    assert_ne!(send(&mut iface, &mut sockets, 5679), label);
+}
+
+#[test]
+#[cfg(all(
+    feature = "socket-udp",
+    feature = "medium-ip",
+    feature = "packetmeta-flow-label"
+))]
+fn test_udp_rx_flow_label() {
+    use crate::socket::udp;
+
+    let (mut iface, mut sockets, _device) = setup(Medium::Ip);
+
+    let udp_socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 64]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 64]),
+    );
+    let handle = sockets.add(udp_socket);
+    sockets.get_mut::<udp::Socket>(handle).bind(1234).unwrap();
+
+    let remote_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let udp_repr = UdpRepr {
+        src_port: 5678,
+        dst_port: 1234,
+    };
+    let ipv6_repr = Ipv6Repr {
+        src_addr: remote_addr,
+        dst_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+        next_header: IpProtocol::Udp,
+        payload_len: udp_repr.header_len() + 4,
+        hop_limit: 64,
+    };
+    let mut data = vec![0; ipv6_repr.buffer_len() + ipv6_repr.payload_len];
+    let mut packet = Ipv6Packet::new_unchecked(&mut data[..]);
+    ipv6_repr.emit(&mut packet);
+    packet.set_flow_label(0xabcde);
+    udp_repr.emit(
+        &mut UdpPacket::new_unchecked(packet.payload_mut()),
+        &ipv6_repr.src_addr.into(),
+        &ipv6_repr.dst_addr.into(),
+        4,
+        |buf| buf.copy_from_slice(b"efgh"),
+        &ChecksumCapabilities::default(),
+    );
+    let packet = Ipv6Packet::new_checked(&data[..]).unwrap();
+    assert_eq!(
+        iface
+            .inner
+            .process_ipv6(&mut sockets, PacketMeta::default(), &packet),
+        None
+    );
+
+    let (payload, meta) = sockets.get_mut::<udp::Socket>(handle).recv().unwrap();
+    assert_eq!(payload, b"efgh");
+    assert_eq!(meta.meta.flow_label, 0xabcde);
+}
+
+#[test]
+#[cfg(all(feature = "socket-raw", feature = "medium-ip"))]
+fn test_raw_socket_verbatim() {
+    use crate::socket::raw;
+    use crate::wire::IpVersion;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    let mut raw_socket = raw::Socket::new(
+        IpVersion::Ipv6,
+        IpProtocol::Unknown(0xfd),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY], vec![0; 64]),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY], vec![0; 64]),
+    );
+    raw_socket.set_verbatim(true);
+    let handle = sockets.add(raw_socket);
+
+    // A header with a traffic class, a Flow Label and a hop limit of 1.
+    let ipv6_repr = Ipv6Repr {
+        src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+        dst_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2),
+        next_header: IpProtocol::Unknown(0xfd),
+        payload_len: 4,
+        hop_limit: 1,
+    };
+    let mut data = vec![0; ipv6_repr.buffer_len() + ipv6_repr.payload_len];
+    let mut packet = Ipv6Packet::new_unchecked(&mut data[..]);
+    ipv6_repr.emit(&mut packet);
+    packet.set_traffic_class(0x2e);
+    packet.set_flow_label(0xabcde);
+    packet
+        .payload_mut()
+        .copy_from_slice(&[0xaa, 0xbb, 0xcc, 0xdd]);
+
+    sockets
+        .get_mut::<raw::Socket>(handle)
+        .send_slice(&data)
+        .unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    assert_eq!(device.queue.pop_front().unwrap(), &data[..]);
+}
+
+#[test]
+#[cfg(all(feature = "socket-raw", feature = "medium-ip"))]
+fn test_raw_socket_flow_label() {
+    use crate::socket::raw;
+    use crate::wire::IpVersion;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    let mut raw_socket = raw::Socket::new(
+        IpVersion::Ipv6,
+        IpProtocol::Unknown(0xfd),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; 2], vec![0; 128]),
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; 2], vec![0; 128]),
+    );
+    raw_socket.set_flow_label(FlowLabel::Fixed(0x12345));
+    let handle = sockets.add(raw_socket);
+
+    let remote_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let ipv6_repr = Ipv6Repr {
+        src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+        dst_addr: remote_addr,
+        next_header: IpProtocol::Unknown(0xfd),
+        payload_len: 4,
+        hop_limit: 64,
+    };
+    let mut data = vec![0; ipv6_repr.buffer_len() + ipv6_repr.payload_len];
+    let mut packet = Ipv6Packet::new_unchecked(&mut data[..]);
+    ipv6_repr.emit(&mut packet);
+    packet.set_flow_label(0xabcde);
+
+    // Packets that include their header keep its Flow Label.
+    let socket = sockets.get_mut::<raw::Socket>(handle);
+    socket.send_slice(&data).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    assert_eq!(
+        Ipv6Packet::new_checked(&frame[..]).unwrap().flow_label(),
+        0xabcde
+    );
+
+    // The others get the Flow Label of the socket.
+    let socket = sockets.get_mut::<raw::Socket>(handle);
+    socket.set_hdrincl(false);
+    socket.connect(remote_addr).unwrap();
+    socket.send_slice(&[0xaa, 0xbb, 0xcc, 0xdd]).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let frame = device.queue.pop_front().unwrap();
+    assert_eq!(
+        Ipv6Packet::new_checked(&frame[..]).unwrap().flow_label(),
+        0x12345
+    );
+}
@@ -1,3 +1,7 @@
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv4"))]
+mod batch;
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv4", feature = "socket-udp"))]
+mod egress;
 #[cfg(feature = "proto-ipv4")]
 mod ipv4;
 #[cfg(feature = "proto-ipv6")]
@@ -232,3 +236,42 @@ pub fn tcp_not_accepted() {
         None,
     );
 }
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp", feature = "proto-ipv4"))]
+fn test_next_poll_at_shared() {
+    use crate::socket::tcp;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    let tcp_handle = sockets.add(tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; 64]),
+        tcp::SocketBuffer::new(vec![0; 64]),
+    ));
+    sockets
+        .get_mut::<tcp::Socket>(tcp_handle)
+        .connect(
+            iface.context(),
+            (Ipv4Address::new(192, 168, 1, 2), 80),
+            49500,
+        )
+        .unwrap();
+
+    // The SYN is due right away.
+    let shared: &Interface = &iface;
+    assert_eq!(
+        shared.next_poll_at(Instant::ZERO, &sockets),
+        Some(Instant::ZERO)
+    );
+
+    // Once it is sent, its retransmission is due.
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    let shared: &Interface = &iface;
+    assert_eq!(
+        shared.next_poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_millis(700))
+    );
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_millis(700))
+    );
+}
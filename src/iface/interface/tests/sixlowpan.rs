@@ -273,6 +273,87 @@ fn test_echo_request_sixlowpan_128_bytes() {
     );
 }
 
+#[test]
+#[cfg(feature = "medium-ieee802154")]
+fn test_ieee802154_short_addr() {
+    let (mut iface, _sockets, mut device) = setup(Medium::Ieee802154);
+
+    let short_addr = Ieee802154Address::Short([0x12, 0x34]);
+    let remote_addr = Ieee802154Address::Short([0x56, 0x78]);
+    iface.set_pan_id(Some(Ieee802154Pan(0xbeef)));
+    iface.set_short_addr(Some(short_addr));
+    assert_eq!(iface.short_addr(), Some(short_addr));
+
+    let echo_repr = Icmpv6Repr::EchoRequest {
+        ident: 1,
+        seq_no: 1,
+        data: b"smoltcp",
+    };
+    let ipv6_repr = Ipv6Repr {
+        src_addr: Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0xff, 0xfe00, 0x1234),
+        dst_addr: Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0xff, 0xfe00, 0x5678),
+        next_header: IpProtocol::Icmpv6,
+        payload_len: echo_repr.buffer_len(),
+        hop_limit: 64,
+    };
+
+    let mut send = |iface: &mut Interface| {
+        let tx_token = device.transmit(Instant::ZERO).unwrap();
+        iface.inner.dispatch_ieee802154(
+            remote_addr,
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv6(ipv6_repr, IpPayload::Icmpv6(echo_repr)),
+            &mut iface.fragmenter,
+        );
+        device.queue.pop_front().unwrap()
+    };
+
+    // With a short address, it is used as the frame source, and the IPv6 source
+    // address derived from it is fully elided.
+    let frame = send(&mut iface);
+    let frame = Ieee802154Frame::new_checked(&frame[..]).unwrap();
+    let ieee802154_repr = Ieee802154Repr::parse(&frame).unwrap();
+    assert_eq!(ieee802154_repr.src_addr, Some(short_addr));
+    assert_eq!(ieee802154_repr.dst_addr, Some(remote_addr));
+    assert_eq!(ieee802154_repr.dst_pan_id, Some(Ieee802154Pan(0xbeef)));
+
+    let iphc = SixlowpanIphcPacket::new_checked(frame.payload().unwrap()).unwrap();
+    // IPHC base header and the inline next header; both addresses are elided.
+    assert_eq!(iphc.header_len(), 3);
+    let iphc_repr = SixlowpanIphcRepr::parse(
+        &iphc,
+        ieee802154_repr.src_addr,
+        ieee802154_repr.dst_addr,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(iphc_repr.src_addr, ipv6_repr.src_addr);
+    assert_eq!(iphc_repr.dst_addr, ipv6_repr.dst_addr);
+
+    // Without it, the extended address is used, and the source address no longer
+    // matches the link-layer address, so its last 16 bits are carried inline.
+    iface.set_short_addr(None);
+    let frame = send(&mut iface);
+    let frame = Ieee802154Frame::new_checked(&frame[..]).unwrap();
+    let ieee802154_repr = Ieee802154Repr::parse(&frame).unwrap();
+    assert_eq!(
+        ieee802154_repr.src_addr,
+        Some(Ieee802154Address::Extended([0x02; 8]))
+    );
+
+    let iphc = SixlowpanIphcPacket::new_checked(frame.payload().unwrap()).unwrap();
+    assert_eq!(iphc.header_len(), 5);
+}
+
+#[test]
+#[cfg(feature = "medium-ieee802154")]
+#[should_panic(expected = "is not a unicast short address")]
+fn test_ieee802154_short_addr_broadcast() {
+    let (mut iface, _sockets, _device) = setup(Medium::Ieee802154);
+    iface.set_short_addr(Some(Ieee802154Address::BROADCAST));
+}
+
 #[test]
 #[cfg(feature = "proto-sixlowpan-fragmentation")]
 fn test_sixlowpan_udp_with_fragmentation() {
@@ -3,10 +3,12 @@ use super::*;
 #[cfg(feature = "socket-dns")]
 use crate::socket::dns::Socket as DnsSocket;
 
+#[cfg(feature = "socket-tftp")]
+use crate::socket::tftp::Socket as TftpSocket;
 #[cfg(feature = "socket-udp")]
 use crate::socket::udp::Socket as UdpSocket;
 
-impl InterfaceInner {
+impl InterfaceInner<'_> {
     pub(super) fn process_udp<'frame>(
         &mut self,
         sockets: &mut SocketSet,
@@ -46,6 +48,17 @@ impl InterfaceInner {
             }
         }
 
+        #[cfg(feature = "socket-tftp")]
+        for tftp_socket in sockets
+            .items_mut()
+            .filter_map(|i| TftpSocket::downcast_mut(&mut i.socket))
+        {
+            if tftp_socket.accepts(&ip_repr, &udp_repr) {
+                tftp_socket.process(self, &ip_repr, &udp_repr, udp_packet.payload());
+                return None;
+            }
+        }
+
         // The packet wasn't handled by a socket, send an ICMP port unreachable packet.
         match ip_repr {
             #[cfg(feature = "proto-ipv4")]
@@ -72,7 +85,7 @@ impl InterfaceInner {
                     header: ipv6_repr,
                     data: &ip_payload[0..payload_len],
                 };
-                self.icmpv6_reply(ipv6_repr, icmpv6_reply_repr)
+                self.icmpv6_error(ipv6_repr, icmpv6_reply_repr)
             }
         }
     }
@@ -4,6 +4,7 @@ The `iface` module deals with the *network interfaces*. It filters incoming fram
 provides lookup and caching of hardware addresses, and handles management packets.
 */
 
+mod event;
 mod fragmentation;
 mod interface;
 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
@@ -16,9 +17,12 @@ mod socket_set;
 
 mod packet;
 
+pub use self::event::{DropReason, Event};
 #[cfg(feature = "proto-igmp")]
 pub use self::interface::MulticastError;
-pub use self::interface::{Config, Interface, InterfaceInner as Context};
+pub use self::interface::{
+    Config, EgressPolicy, Interface, InterfaceInner as Context, UnspecifiedSourcePolicy,
+};
 
 pub use self::route::{Route, RouteTableFull, Routes};
 pub use self::socket_set::{SocketHandle, SocketSet, SocketStorage};
@@ -3,7 +3,7 @@
 
 use heapless::LinearMap;
 
-use crate::config::IFACE_NEIGHBOR_CACHE_COUNT;
+use crate::config::{IFACE_NEIGHBOR_CACHE_COUNT, IFACE_NEIGHBOR_PENDING_COUNT};
 use crate::time::{Duration, Instant};
 use crate::wire::{HardwareAddress, IpAddress};
 
@@ -27,8 +27,12 @@ pub(crate) enum Answer {
     /// The neighbor address is not in the cache, or has expired.
     NotFound,
     /// The neighbor address is not in the cache, or has expired,
-    /// and a lookup has been made recently.
+    /// and either a lookup for it has been made recently, or too many
+    /// other lookups are still outstanding.
     RateLimited,
+    /// The neighbor address is not in the cache, and the configured number of
+    /// lookups for it went unanswered recently.
+    Failed,
 }
 
 impl Answer {
@@ -41,15 +45,43 @@ impl Answer {
     }
 }
 
+/// The effect of filling a neighbor cache entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) enum Filled {
+    /// The entry already mapped to the same hardware address and was refreshed.
+    Refreshed,
+    /// The entry is new, or now maps to a different hardware address.
+    Added,
+    /// The entry is new, and another entry was evicted to make room for it.
+    Evicted(IpAddress, HardwareAddress),
+}
+
+/// An outstanding lookup.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Pending {
+    /// The neighbor is not asked for again before this timestamp.
+    silent_until: Instant,
+    /// The number of lookups made so far.
+    solicits: u8,
+}
+
 /// A neighbor cache backed by a map.
+///
+/// Besides the neighbors themselves, the cache tracks the lookups that are still
+/// outstanding, so that at most `IFACE_NEIGHBOR_PENDING_COUNT` neighbors are being
+/// resolved at once, and each of them at most once per solicitation interval.
 #[derive(Debug)]
 pub struct Cache {
     storage: LinearMap<IpAddress, Neighbor, IFACE_NEIGHBOR_CACHE_COUNT>,
-    silent_until: Instant,
+    pending: LinearMap<IpAddress, Pending, IFACE_NEIGHBOR_PENDING_COUNT>,
+    solicit_interval: Duration,
+    solicit_retries: Option<u8>,
 }
 
 impl Cache {
-    /// Minimum delay between discovery requests, in milliseconds.
+    /// Default minimum delay between discovery requests, in milliseconds.
     pub(crate) const SILENT_TIME: Duration = Duration::from_millis(1_000);
 
     /// Neighbor entry lifetime, in milliseconds.
@@ -59,21 +91,39 @@ impl Cache {
     pub fn new() -> Self {
         Self {
             storage: LinearMap::new(),
-            silent_until: Instant::from_millis(0),
+            pending: LinearMap::new(),
+            solicit_interval: Self::SILENT_TIME,
+            solicit_retries: None,
         }
     }
 
+    pub(crate) fn solicit_interval(&self) -> Duration {
+        self.solicit_interval
+    }
+
+    pub(crate) fn set_solicit_interval(&mut self, interval: Duration) {
+        self.solicit_interval = interval;
+    }
+
+    pub(crate) fn solicit_retries(&self) -> Option<u8> {
+        self.solicit_retries
+    }
+
+    pub(crate) fn set_solicit_retries(&mut self, retries: Option<u8>) {
+        self.solicit_retries = retries;
+    }
+
     pub fn fill(
         &mut self,
         protocol_addr: IpAddress,
         hardware_addr: HardwareAddress,
         timestamp: Instant,
-    ) {
+    ) -> Filled {
         debug_assert!(protocol_addr.is_unicast());
         debug_assert!(hardware_addr.is_unicast());
 
         let expires_at = timestamp + Self::ENTRY_LIFETIME;
-        self.fill_with_expiration(protocol_addr, hardware_addr, expires_at);
+        self.fill_with_expiration(protocol_addr, hardware_addr, expires_at)
     }
 
     pub fn fill_with_expiration(
@@ -81,10 +131,12 @@ impl Cache {
         protocol_addr: IpAddress,
         hardware_addr: HardwareAddress,
         expires_at: Instant,
-    ) {
+    ) -> Filled {
         debug_assert!(protocol_addr.is_unicast());
         debug_assert!(hardware_addr.is_unicast());
 
+        self.pending.remove(&protocol_addr);
+
         let neighbor = Neighbor {
             expires_at,
             hardware_addr,
@@ -98,10 +150,14 @@ impl Cache {
                         hardware_addr,
                         old_neighbor.hardware_addr
                     );
+                    Filled::Added
+                } else {
+                    Filled::Refreshed
                 }
             }
             Ok(None) => {
                 net_trace!("filled {} => {} (was empty)", protocol_addr, hardware_addr);
+                Filled::Added
             }
             Err((protocol_addr, neighbor)) => {
                 // If we're going down this branch, it means the cache is full, and we need to evict an entry.
@@ -112,7 +168,7 @@ impl Cache {
                     .expect("empty neighbor cache storage")
                     .0;
 
-                let _old_neighbor = self.storage.remove(&old_protocol_addr).unwrap();
+                let old_neighbor = self.storage.remove(&old_protocol_addr).unwrap();
                 match self.storage.insert(protocol_addr, neighbor) {
                     Ok(None) => {
                         net_trace!(
@@ -120,8 +176,9 @@ impl Cache {
                             protocol_addr,
                             hardware_addr,
                             old_protocol_addr,
-                            _old_neighbor.hardware_addr
+                            old_neighbor.hardware_addr
                         );
+                        Filled::Evicted(old_protocol_addr, old_neighbor.hardware_addr)
                     }
                     // We've covered everything else above.
                     _ => unreachable!(),
@@ -143,19 +200,68 @@ impl Cache {
             }
         }
 
-        if timestamp < self.silent_until {
+        if let Some(pending) = self.pending.get(protocol_addr) {
+            if timestamp < pending.silent_until {
+                return Answer::RateLimited;
+            }
+            let exhausted =
+                matches!(self.solicit_retries, Some(retries) if pending.solicits > retries);
+            if exhausted && !self.is_stale(pending, timestamp) {
+                return Answer::Failed;
+            }
+        }
+
+        let outstanding = self
+            .pending
+            .values()
+            .filter(|pending| timestamp < pending.silent_until)
+            .count();
+        if outstanding >= self.pending.capacity() {
             Answer::RateLimited
         } else {
             Answer::NotFound
         }
     }
 
-    pub(crate) fn limit_rate(&mut self, timestamp: Instant) {
-        self.silent_until = timestamp + Self::SILENT_TIME;
+    /// Return whether a lookup was last retried so long ago that a new lookup starts over,
+    /// rather than counting as another retry.
+    fn is_stale(&self, pending: &Pending, timestamp: Instant) -> bool {
+        timestamp >= pending.silent_until + self.solicit_interval
+    }
+
+    /// Record that a lookup for `protocol_addr` has just been made.
+    pub(crate) fn limit_rate(&mut self, protocol_addr: IpAddress, timestamp: Instant) {
+        let solicits = match self.pending.remove(&protocol_addr) {
+            Some(pending) if !self.is_stale(&pending, timestamp) => pending.solicits,
+            _ => 0,
+        };
+        let expired = self
+            .pending
+            .iter()
+            .find(|(_, pending)| timestamp >= pending.silent_until)
+            .map(|(&addr, _)| addr);
+        if self.pending.len() == self.pending.capacity() {
+            match expired {
+                Some(addr) => {
+                    self.pending.remove(&addr);
+                }
+                None => {
+                    net_debug!("too many outstanding neighbor lookups");
+                    return;
+                }
+            }
+        }
+
+        let pending = Pending {
+            silent_until: timestamp + self.solicit_interval,
+            solicits: solicits.saturating_add(1),
+        };
+        let _ = self.pending.insert(protocol_addr, pending);
     }
 
     pub(crate) fn flush(&mut self) {
-        self.storage.clear()
+        self.storage.clear();
+        self.pending.clear();
     }
 }
 
@@ -238,6 +344,14 @@ mod test {
             cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(0)),
             Answer::Found(HADDR_B)
         );
+        assert_eq!(
+            cache.fill(MOCK_IP_ADDR_1.into(), HADDR_B, Instant::from_millis(0)),
+            Filled::Refreshed
+        );
+        assert_eq!(
+            cache.fill(MOCK_IP_ADDR_1.into(), HADDR_A, Instant::from_millis(0)),
+            Filled::Added
+        );
     }
 
     #[test]
@@ -255,7 +369,10 @@ mod test {
             .lookup(&MOCK_IP_ADDR_4.into(), Instant::from_millis(1000))
             .found());
 
-        cache.fill(MOCK_IP_ADDR_4.into(), HADDR_D, Instant::from_millis(300));
+        assert_eq!(
+            cache.fill(MOCK_IP_ADDR_4.into(), HADDR_D, Instant::from_millis(300)),
+            Filled::Evicted(MOCK_IP_ADDR_2.into(), HADDR_B)
+        );
         assert!(!cache
             .lookup(&MOCK_IP_ADDR_2.into(), Instant::from_millis(1000))
             .found());
@@ -274,7 +391,7 @@ mod test {
             Answer::NotFound
         );
 
-        cache.limit_rate(Instant::from_millis(0));
+        cache.limit_rate(MOCK_IP_ADDR_1.into(), Instant::from_millis(0));
         assert_eq!(
             cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(100)),
             Answer::RateLimited
@@ -285,6 +402,91 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_pending_limit() {
+        let mut cache = Cache::new();
+        assert_eq!(IFACE_NEIGHBOR_PENDING_COUNT, 2);
+
+        cache.limit_rate(MOCK_IP_ADDR_1.into(), Instant::from_millis(0));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_2.into(), Instant::from_millis(0)),
+            Answer::NotFound
+        );
+        cache.limit_rate(MOCK_IP_ADDR_2.into(), Instant::from_millis(500));
+
+        // Both slots are taken, so no other neighbor can be resolved.
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_3.into(), Instant::from_millis(500)),
+            Answer::RateLimited
+        );
+
+        // Resolving a neighbor frees its slot.
+        cache.fill(MOCK_IP_ADDR_1.into(), HADDR_A, Instant::from_millis(600));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_3.into(), Instant::from_millis(600)),
+            Answer::NotFound
+        );
+        cache.limit_rate(MOCK_IP_ADDR_3.into(), Instant::from_millis(600));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_4.into(), Instant::from_millis(600)),
+            Answer::RateLimited
+        );
+
+        // So does a lookup timing out.
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_4.into(), Instant::from_millis(1500)),
+            Answer::NotFound
+        );
+        cache.limit_rate(MOCK_IP_ADDR_4.into(), Instant::from_millis(1500));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_4.into(), Instant::from_millis(1500)),
+            Answer::RateLimited
+        );
+    }
+
+    #[test]
+    fn test_solicit_retries() {
+        let mut cache = Cache::new();
+        cache.set_solicit_interval(Duration::from_millis(200));
+        cache.set_solicit_retries(Some(1));
+
+        cache.limit_rate(MOCK_IP_ADDR_1.into(), Instant::from_millis(0));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(100)),
+            Answer::RateLimited
+        );
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(200)),
+            Answer::NotFound
+        );
+        cache.limit_rate(MOCK_IP_ADDR_1.into(), Instant::from_millis(200));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(300)),
+            Answer::RateLimited
+        );
+
+        // Both lookups went unanswered.
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(400)),
+            Answer::Failed
+        );
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(599)),
+            Answer::Failed
+        );
+
+        // A lookup long after the last one starts over.
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(600)),
+            Answer::NotFound
+        );
+        cache.limit_rate(MOCK_IP_ADDR_1.into(), Instant::from_millis(600));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(800)),
+            Answer::NotFound
+        );
+    }
+
     #[test]
     fn test_flush() {
         let mut cache = Cache::new();
@@ -34,6 +34,8 @@ impl<'p> Packet<'p> {
     pub(crate) fn new_ipv4(ip_repr: Ipv4Repr, payload: IpPayload<'p>) -> Self {
         Self::Ipv4(PacketV4 {
             header: ip_repr,
+            dont_frag: false,
+            df_flag: true,
             payload,
         })
     }
@@ -48,10 +50,76 @@ impl<'p> Packet<'p> {
             fragment: None,
             #[cfg(feature = "proto-ipv6-routing")]
             routing: None,
+            dont_frag: false,
+            flow_label: 0,
             payload,
         })
     }
 
+    /// Set whether the packet has to be dropped rather than fragmented if it is larger than
+    /// the MTU.
+    #[cfg(any(feature = "socket-udp", feature = "socket-raw"))]
+    pub(crate) fn set_dont_frag(&mut self, dont_frag: bool) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            Packet::Ipv4(p) => p.dont_frag = dont_frag,
+            #[cfg(feature = "proto-ipv6")]
+            Packet::Ipv6(p) => p.dont_frag = dont_frag,
+        }
+    }
+
+    /// Set whether the packet is sent with the DF flag if it is not fragmented. This does
+    /// nothing for IPv6 packets.
+    #[cfg(feature = "socket-tcp")]
+    #[allow(unused_variables)]
+    pub(crate) fn set_df_flag(&mut self, df_flag: bool) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            Packet::Ipv4(p) => p.df_flag = df_flag,
+            #[cfg(feature = "proto-ipv6")]
+            Packet::Ipv6(_) => (),
+        }
+    }
+
+    /// Set the Flow Label of the packet. This does nothing for IPv4 packets.
+    #[cfg(any(feature = "socket-tcp", feature = "socket-udp", feature = "socket-raw"))]
+    #[allow(unused_variables)]
+    pub(crate) fn set_flow_label(&mut self, flow_label: u32) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            Packet::Ipv4(_) => (),
+            #[cfg(feature = "proto-ipv6")]
+            Packet::Ipv6(p) => p.flow_label = flow_label,
+        }
+    }
+
+    #[cfg(feature = "proto-ipv6")]
+    pub(crate) fn flow_label(&self) -> u32 {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            Packet::Ipv4(_) => 0,
+            Packet::Ipv6(p) => p.flow_label,
+        }
+    }
+
+    pub(crate) fn dont_frag(&self) -> bool {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            Packet::Ipv4(p) => p.dont_frag,
+            #[cfg(feature = "proto-ipv6")]
+            Packet::Ipv6(p) => p.dont_frag,
+        }
+    }
+
+    #[cfg(feature = "proto-ipv4")]
+    pub(crate) fn df_flag(&self) -> bool {
+        match self {
+            Packet::Ipv4(p) => p.df_flag,
+            #[cfg(feature = "proto-ipv6")]
+            Packet::Ipv6(_) => false,
+        }
+    }
+
     pub(crate) fn ip_repr(&self) -> IpRepr {
         match self {
             #[cfg(feature = "proto-ipv4")]
@@ -100,6 +168,10 @@ impl<'p> Packet<'p> {
             }
             #[cfg(feature = "socket-raw")]
             IpPayload::Raw(raw_packet) => payload.copy_from_slice(raw_packet),
+            #[cfg(feature = "socket-raw")]
+            IpPayload::Verbatim(raw_packet) => {
+                payload.copy_from_slice(&raw_packet[_ip_repr.header_len()..])
+            }
             #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
             IpPayload::Udp(udp_repr, inner_payload) => udp_repr.emit(
                 &mut UdpPacket::new_unchecked(payload),
@@ -145,6 +217,15 @@ impl<'p> Packet<'p> {
                 |buf| dhcp_repr.emit(&mut DhcpPacket::new_unchecked(buf)).unwrap(),
                 &caps.checksum,
             ),
+            #[cfg(feature = "socket-tftp")]
+            IpPayload::Tftp(udp_repr, tftp_repr) => udp_repr.emit(
+                &mut UdpPacket::new_unchecked(payload),
+                &_ip_repr.src_addr(),
+                &_ip_repr.dst_addr(),
+                tftp_repr.buffer_len(),
+                |buf| tftp_repr.emit(&mut TftpPacket::new_unchecked(buf)),
+                &caps.checksum,
+            ),
         }
     }
 }
@@ -154,6 +235,8 @@ impl<'p> Packet<'p> {
 #[cfg(feature = "proto-ipv4")]
 pub(crate) struct PacketV4<'p> {
     header: Ipv4Repr,
+    dont_frag: bool,
+    df_flag: bool,
     payload: IpPayload<'p>,
 }
 
@@ -168,6 +251,8 @@ pub(crate) struct PacketV6<'p> {
     pub(crate) fragment: Option<Ipv6FragmentRepr>,
     #[cfg(feature = "proto-ipv6-routing")]
     pub(crate) routing: Option<Ipv6RoutingRepr<'p>>,
+    pub(crate) dont_frag: bool,
+    pub(crate) flow_label: u32,
     pub(crate) payload: IpPayload<'p>,
 }
 
@@ -182,12 +267,17 @@ pub(crate) enum IpPayload<'p> {
     Icmpv6(Icmpv6Repr<'p>),
     #[cfg(feature = "socket-raw")]
     Raw(&'p [u8]),
+    /// A whole IP packet, header included, to be sent byte for byte.
+    #[cfg(feature = "socket-raw")]
+    Verbatim(&'p [u8]),
     #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
     Udp(UdpRepr, &'p [u8]),
     #[cfg(feature = "socket-tcp")]
     Tcp(TcpRepr<'p>),
     #[cfg(feature = "socket-dhcpv4")]
     Dhcpv4(UdpRepr, DhcpRepr<'p>),
+    #[cfg(feature = "socket-tftp")]
+    Tftp(UdpRepr, TftpRepr<'p>),
 }
 
 impl<'p> IpPayload<'p> {
@@ -206,8 +296,12 @@ impl<'p> IpPayload<'p> {
             Self::Tcp(_) => SixlowpanNextHeader::Uncompressed(IpProtocol::Tcp),
             #[cfg(feature = "socket-udp")]
             Self::Udp(..) => SixlowpanNextHeader::Compressed,
+            #[cfg(feature = "socket-tftp")]
+            Self::Tftp(..) => SixlowpanNextHeader::Compressed,
             #[cfg(feature = "socket-raw")]
             Self::Raw(_) => todo!(),
+            #[cfg(feature = "socket-raw")]
+            Self::Verbatim(_) => unreachable!(),
         }
     }
 }
@@ -1,6 +1,14 @@
 use heapless::Vec;
 
 use crate::config::IFACE_MAX_ROUTE_COUNT;
+#[cfg(any(
+    feature = "proto-ipv4",
+    all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    )
+))]
+use crate::time::Duration;
 use crate::time::Instant;
 use crate::wire::{IpAddress, IpCidr};
 #[cfg(feature = "proto-ipv4")]
@@ -63,10 +71,24 @@ impl Route {
     }
 }
 
+/// How long a route installed by a redirect is used, unless another redirect renews it.
+#[cfg(any(
+    feature = "proto-ipv4",
+    all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    )
+))]
+const REDIRECT_LIFETIME: Duration = Duration::from_secs(600);
+
 /// A routing table.
+///
+/// Routes installed by redirects are kept apart from the routes of the application, so
+/// that they never take their place, and are not visible to [update](Self::update).
 #[derive(Debug)]
 pub struct Routes {
     storage: Vec<Route, IFACE_MAX_ROUTE_COUNT>,
+    redirects: Vec<Route, IFACE_MAX_ROUTE_COUNT>,
 }
 
 impl Routes {
@@ -74,6 +96,7 @@ impl Routes {
     pub fn new() -> Self {
         Self {
             storage: Vec::new(),
+            redirects: Vec::new(),
         }
     }
 
@@ -146,11 +169,58 @@ impl Routes {
         }
     }
 
+    /// Route `addr` via `via_router` for a while, replacing the route to `addr` installed by
+    /// an earlier redirect, if any.
+    ///
+    /// When there is no room left for routes installed by redirects, the one that expires
+    /// first is evicted.
+    #[cfg(any(
+        feature = "proto-ipv4",
+        all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        )
+    ))]
+    pub(crate) fn redirect(&mut self, addr: IpAddress, via_router: IpAddress, timestamp: Instant) {
+        let prefix_len = match addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(_) => 32,
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(_) => 128,
+        };
+        let cidr = IpCidr::new(addr, prefix_len);
+        let route = Route {
+            cidr,
+            via_router,
+            preferred_until: None,
+            expires_at: Some(timestamp + REDIRECT_LIFETIME),
+        };
+
+        self.redirects
+            .retain(|r| r.cidr != cidr && r.expires_at.map_or(true, |t| t >= timestamp));
+        if self.redirects.is_full() {
+            let first = (0..self.redirects.len()).min_by_key(|&i| self.redirects[i].expires_at);
+            if let Some(i) = first {
+                self.redirects.remove(i);
+            }
+        }
+        // This only fails if there is no room for redirects at all.
+        let _ = self.redirects.push(route);
+    }
+
+    /// Remove the routes installed by redirects, and return whether there were any.
+    pub(crate) fn clear_redirects(&mut self) -> bool {
+        let any = !self.redirects.is_empty();
+        self.redirects.clear();
+        any
+    }
+
     pub(crate) fn lookup(&self, addr: &IpAddress, timestamp: Instant) -> Option<IpAddress> {
         assert!(addr.is_unicast());
 
         self.storage
             .iter()
+            .chain(self.redirects.iter())
             // Keep only matching routes
             .filter(|route| {
                 if let Some(expires_at) = route.expires_at {
@@ -324,4 +394,68 @@ mod test {
             Some(ADDR_2A.into())
         );
     }
+
+    #[test]
+    #[cfg(any(
+        feature = "proto-ipv4",
+        all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        )
+    ))]
+    fn test_redirect() {
+        let mut routes = Routes::new();
+        let route = Route {
+            cidr: cidr_1().into(),
+            via_router: ADDR_1A.into(),
+            preferred_until: None,
+            expires_at: None,
+        };
+        routes.update(|storage| {
+            storage.push(route).unwrap();
+        });
+
+        routes.redirect(ADDR_1B.into(), ADDR_2A.into(), Instant::from_secs(0));
+        assert_eq!(
+            routes.lookup(&ADDR_1B.into(), Instant::from_secs(0)),
+            Some(ADDR_2A.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_1C.into(), Instant::from_secs(0)),
+            Some(ADDR_1A.into())
+        );
+
+        // A redirected route expires, unless it is renewed.
+        routes.redirect(ADDR_1C.into(), ADDR_2A.into(), Instant::from_secs(0));
+        routes.redirect(ADDR_1C.into(), ADDR_2B.into(), Instant::from_secs(300));
+        assert_eq!(
+            routes.lookup(&ADDR_1B.into(), Instant::from_secs(601)),
+            Some(ADDR_1A.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_1C.into(), Instant::from_secs(601)),
+            Some(ADDR_2B.into())
+        );
+
+        // Redirects never take the place of other routes. When there is no room left for
+        // them, the one that expires first is evicted. There is room for four of them in the
+        // tests, so 1C is evicted.
+        for addr in [ADDR_1A, ADDR_2A, ADDR_2B] {
+            routes.redirect(addr.into(), ADDR_2A.into(), Instant::from_secs(700));
+        }
+        routes.redirect(ADDR_1B.into(), ADDR_2B.into(), Instant::from_secs(701));
+        assert_eq!(
+            routes.lookup(&ADDR_1B.into(), Instant::from_secs(701)),
+            Some(ADDR_2B.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_1C.into(), Instant::from_secs(701)),
+            Some(ADDR_1A.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_2A.into(), Instant::from_secs(701)),
+            Some(ADDR_2A.into())
+        );
+        routes.update(|storage| assert_eq!(storage.len(), 1));
+    }
 }
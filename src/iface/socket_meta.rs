@@ -39,12 +39,6 @@ pub(crate) struct Meta {
 }
 
 impl Meta {
-    /// Minimum delay between neighbor discovery requests for this particular
-    /// socket, in milliseconds.
-    ///
-    /// See also `iface::NeighborCache::SILENT_TIME`.
-    pub(crate) const DISCOVERY_SILENT_TIME: Duration = Duration::from_millis(1_000);
-
     pub(crate) fn poll_at<F>(&self, socket_poll_at: PollAt, has_neighbor: F) -> PollAt
     where
         F: Fn(IpAddress) -> bool,
@@ -88,16 +82,23 @@ impl Meta {
         }
     }
 
-    pub(crate) fn neighbor_missing(&mut self, timestamp: Instant, neighbor: IpAddress) {
+    /// Stop polling the socket until either `neighbor` is discovered, or `silent_time`
+    /// passes; this should match the delay between neighbor discovery requests.
+    pub(crate) fn neighbor_missing(
+        &mut self,
+        timestamp: Instant,
+        neighbor: IpAddress,
+        silent_time: Duration,
+    ) {
         net_trace!(
             "{}: neighbor {} missing, silencing until t+{}",
             self.handle,
             neighbor,
-            Self::DISCOVERY_SILENT_TIME
+            silent_time
         );
         self.neighbor_state = NeighborState::Waiting {
             neighbor,
-            silent_until: timestamp + Self::DISCOVERY_SILENT_TIME,
+            silent_until: timestamp + silent_time,
         };
     }
 }
@@ -148,4 +148,17 @@ impl<'a> SocketSet<'a> {
     pub(crate) fn items_mut(&mut self) -> impl Iterator<Item = &mut Item<'a>> + '_ {
         self.sockets.iter_mut().filter_map(|x| x.inner.as_mut())
     }
+
+    /// Iterate every socket in this set, starting after the socket `handle` and wrapping
+    /// around, or from the first socket if `handle` is `None`.
+    pub(crate) fn items_mut_after(
+        &mut self,
+        handle: Option<SocketHandle>,
+    ) -> impl Iterator<Item = &mut Item<'a>> + '_ {
+        let start = handle.map_or(0, |handle| (handle.0 + 1).min(self.sockets.len()));
+        let (head, tail) = self.sockets.split_at_mut(start);
+        tail.iter_mut()
+            .chain(head.iter_mut())
+            .filter_map(|x| x.inner.as_mut())
+    }
 }
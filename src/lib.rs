@@ -107,9 +107,10 @@ compile_error!("You must enable at least one of the following features: proto-ip
         feature = "socket-icmp",
         feature = "socket-dhcpv4",
         feature = "socket-dns",
+        feature = "socket-tftp",
     ))
 ))]
-compile_error!("If you enable the socket feature, you must enable at least one of the following features: socket-raw, socket-udp, socket-tcp, socket-icmp, socket-dhcpv4, socket-dns");
+compile_error!("If you enable the socket feature, you must enable at least one of the following features: socket-raw, socket-udp, socket-tcp, socket-icmp, socket-dhcpv4, socket-dns, socket-tftp");
 
 #[cfg(all(
     feature = "socket",
@@ -127,7 +128,6 @@ compile_error!("You must enable at most one of the following features: defmt, lo
 #[macro_use]
 mod macros;
 mod parsers;
-mod rand;
 
 #[cfg(test)]
 pub mod config {
@@ -135,15 +135,18 @@ pub mod config {
     pub const ASSEMBLER_MAX_SEGMENT_COUNT: usize = 4;
     pub const DNS_MAX_NAME_SIZE: usize = 255;
     pub const DNS_MAX_RESULT_COUNT: usize = 1;
-    pub const DNS_MAX_SERVER_COUNT: usize = 1;
-    pub const FRAGMENTATION_BUFFER_SIZE: usize = 1500;
+    pub const DNS_MAX_SERVER_COUNT: usize = 2;
+    pub const FRAGMENTATION_BUFFER_SIZE: usize = 4096;
     pub const IFACE_MAX_ADDR_COUNT: usize = 8;
+    pub const IFACE_MAX_EVENT_COUNT: usize = 8;
     pub const IFACE_MAX_MULTICAST_GROUP_COUNT: usize = 4;
+    pub const IFACE_MAX_PROXY_ADDR_COUNT: usize = 4;
     pub const IFACE_MAX_ROUTE_COUNT: usize = 4;
     pub const IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT: usize = 4;
     pub const IFACE_NEIGHBOR_CACHE_COUNT: usize = 3;
+    pub const IFACE_NEIGHBOR_PENDING_COUNT: usize = 2;
     pub const REASSEMBLY_BUFFER_COUNT: usize = 4;
-    pub const REASSEMBLY_BUFFER_SIZE: usize = 1500;
+    pub const REASSEMBLY_BUFFER_SIZE: usize = 4096;
     pub const RPL_RELATIONS_BUFFER_COUNT: usize = 16;
     pub const RPL_PARENTS_BUFFER_COUNT: usize = 8;
     pub const IPV6_HBH_MAX_OPTIONS: usize = 2;
@@ -163,6 +166,7 @@ pub mod config {
 pub mod iface;
 
 pub mod phy;
+pub mod rand;
 #[cfg(feature = "socket")]
 pub mod socket;
 pub mod storage;
@@ -0,0 +1,224 @@
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use crate::phy::{self, Device, DeviceCapabilities, Medium};
+use crate::time::Instant;
+
+/// The frames travelling in one direction of a channel.
+#[derive(Debug)]
+struct Queue {
+    frames: VecDeque<Vec<u8>>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl Queue {
+    fn new(capacity: usize) -> Rc<RefCell<Queue>> {
+        Rc::new(RefCell::new(Queue {
+            frames: VecDeque::new(),
+            capacity,
+            dropped: 0,
+        }))
+    }
+
+    fn is_full(&self) -> bool {
+        self.frames.len() >= self.capacity
+    }
+}
+
+/// One end of an in-memory channel.
+///
+/// A channel connects two devices, created together by [ChannelDevice::pair]; every frame
+/// transmitted through one end is received through the other end, in FIFO order. Each
+/// direction holds a bounded number of frames, like the ring of a network card. When the
+/// peer's queue is full, [transmit](Device::transmit) applies backpressure by returning
+/// no token, which makes the interface try again later. The transmit token returned along
+/// with a receive token is handed out regardless, so frames sent through it while the
+/// peer's queue is full are dropped, and counted by [dropped](#method.dropped).
+#[derive(Debug)]
+pub struct ChannelDevice {
+    tx: Rc<RefCell<Queue>>,
+    rx: Rc<RefCell<Queue>>,
+    medium: Medium,
+}
+
+impl ChannelDevice {
+    /// Create the two ends of a channel, each direction of which holds up to `capacity`
+    /// frames.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is zero.
+    pub fn pair(medium: Medium, capacity: usize) -> (ChannelDevice, ChannelDevice) {
+        assert!(capacity > 0, "a channel must hold at least one frame");
+
+        let a_to_b = Queue::new(capacity);
+        let b_to_a = Queue::new(capacity);
+        let a = ChannelDevice {
+            tx: a_to_b.clone(),
+            rx: b_to_a.clone(),
+            medium,
+        };
+        let b = ChannelDevice {
+            tx: b_to_a,
+            rx: a_to_b,
+            medium,
+        };
+        (a, b)
+    }
+
+    /// Return the number of frames transmitted through this end that the peer has not
+    /// received yet.
+    pub fn tx_queue_len(&self) -> usize {
+        self.tx.borrow().frames.len()
+    }
+
+    /// Return the number of frames transmitted by the peer that this end has not
+    /// received yet.
+    pub fn rx_queue_len(&self) -> usize {
+        self.rx.borrow().frames.len()
+    }
+
+    /// Return the number of frames transmitted through this end that were dropped because
+    /// the peer's queue was full.
+    pub fn dropped(&self) -> usize {
+        self.tx.borrow().dropped
+    }
+}
+
+impl Device for ChannelDevice {
+    type RxToken<'a> = RxToken;
+    type TxToken<'a> = TxToken<'a>;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            max_transmission_unit: 65535,
+            medium: self.medium,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let buffer = self.rx.borrow_mut().frames.pop_front()?;
+        let rx = RxToken { buffer };
+        let tx = TxToken { queue: &self.tx };
+        Some((rx, tx))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        if self.tx.borrow().is_full() {
+            return None;
+        }
+        Some(TxToken { queue: &self.tx })
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken {
+    buffer: Vec<u8>,
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.buffer)
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<'a> {
+    queue: &'a RefCell<Queue>,
+}
+
+impl phy::TxToken for TxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buffer = alloc::vec![0; len];
+        let result = f(&mut buffer);
+
+        let mut queue = self.queue.borrow_mut();
+        if queue.is_full() {
+            net_debug!("channel: peer queue full, dropping frame");
+            queue.dropped += 1;
+        } else {
+            queue.frames.push_back(buffer);
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "medium-ethernet"))]
+mod test {
+    use super::*;
+    use crate::phy::{RxToken as _, TxToken as _};
+
+    fn send(device: &mut ChannelDevice, data: &[u8]) -> bool {
+        match device.transmit(Instant::ZERO) {
+            Some(token) => {
+                token.consume(data.len(), |buffer| buffer.copy_from_slice(data));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn recv(device: &mut ChannelDevice) -> Option<Vec<u8>> {
+        let (token, _) = device.receive(Instant::ZERO)?;
+        Some(token.consume(|buffer| buffer.to_vec()))
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let (mut a, mut b) = ChannelDevice::pair(Medium::Ethernet, 2);
+
+        assert!(send(&mut a, b"abc"));
+        assert!(send(&mut a, b"def"));
+        assert_eq!((a.tx_queue_len(), a.rx_queue_len()), (2, 0));
+        assert_eq!((b.tx_queue_len(), b.rx_queue_len()), (0, 2));
+
+        // The peer's queue is full, so no token is handed out.
+        assert!(a.transmit(Instant::ZERO).is_none());
+        assert_eq!(a.tx_queue_len(), 2);
+        assert_eq!(a.dropped(), 0);
+
+        // Receiving a frame makes room for another one, in the same order.
+        assert_eq!(recv(&mut b), Some(b"abc".to_vec()));
+        assert_eq!((a.tx_queue_len(), b.rx_queue_len()), (1, 1));
+        assert!(send(&mut a, b"ghi"));
+        assert_eq!(recv(&mut b), Some(b"def".to_vec()));
+        assert_eq!(recv(&mut b), Some(b"ghi".to_vec()));
+        assert_eq!(recv(&mut b), None);
+        assert_eq!(a.tx_queue_len(), 0);
+
+        // The other direction is independent.
+        assert!(send(&mut b, b"jkl"));
+        assert_eq!((b.tx_queue_len(), a.rx_queue_len()), (1, 1));
+        assert_eq!(recv(&mut a), Some(b"jkl".to_vec()));
+    }
+
+    #[test]
+    fn test_dropped_on_full() {
+        let (mut a, mut b) = ChannelDevice::pair(Medium::Ethernet, 1);
+
+        assert!(send(&mut b, b"abc"));
+        assert!(send(&mut a, b"def"));
+
+        // The transmit token that comes with a receive token is used while the peer's
+        // queue is full.
+        let (rx, tx) = a.receive(Instant::ZERO).unwrap();
+        assert_eq!(rx.consume(|buffer| buffer.to_vec()), b"abc");
+        tx.consume(3, |buffer| buffer.copy_from_slice(b"ghi"));
+        assert_eq!(a.dropped(), 1);
+        assert_eq!(b.dropped(), 0);
+        assert_eq!(a.tx_queue_len(), 1);
+
+        assert_eq!(recv(&mut b), Some(b"def".to_vec()));
+        assert_eq!(recv(&mut b), None);
+    }
+}
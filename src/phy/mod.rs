@@ -5,8 +5,12 @@ for transmitting and receiving frames, [Device](trait.Device.html)
 and implementations of it:
 
   * the [_loopback_](struct.Loopback.html), for zero dependency testing;
+  * the [ChannelDevice](struct.ChannelDevice.html) pair, to connect two interfaces
+    through bounded in-memory queues;
   * _middleware_ [Tracer](struct.Tracer.html) and
     [FaultInjector](struct.FaultInjector.html), to facilitate debugging;
+  * the [TunnelDevice](struct.TunnelDevice.html), to apply an
+    [Encapsulator](trait.Encapsulator.html) over another device;
   * _adapters_ [RawSocket](struct.RawSocket.html) and
     [TunTapInterface](struct.TunTapInterface.html), to transmit and receive frames
     on the host OS.
@@ -96,6 +100,8 @@ use crate::time::Instant;
 ))]
 mod sys;
 
+#[cfg(feature = "alloc")]
+mod channel;
 mod delay_injector;
 mod fault_injector;
 mod fuzz_injector;
@@ -105,9 +111,11 @@ mod pcap_writer;
 #[cfg(all(feature = "phy-raw_socket", unix))]
 mod raw_socket;
 mod tracer;
+#[cfg(feature = "alloc")]
+mod tunnel;
 #[cfg(all(
     feature = "phy-tuntap_interface",
-    any(target_os = "linux", target_os = "android")
+    any(target_os = "linux", target_os = "android", target_os = "macos")
 ))]
 mod tuntap_interface;
 
@@ -117,6 +125,8 @@ mod tuntap_interface;
 ))]
 pub use self::sys::wait;
 
+#[cfg(feature = "alloc")]
+pub use self::channel::ChannelDevice;
 pub use self::delay_injector::DelayInjector;
 pub use self::fault_injector::FaultInjector;
 pub use self::fuzz_injector::{FuzzInjector, Fuzzer};
@@ -126,9 +136,11 @@ pub use self::pcap_writer::{PcapLinkType, PcapMode, PcapSink, PcapWriter};
 #[cfg(all(feature = "phy-raw_socket", unix))]
 pub use self::raw_socket::RawSocket;
 pub use self::tracer::Tracer;
+#[cfg(feature = "alloc")]
+pub use self::tunnel::{Encapsulator, LengthPrefix, TunnelDevice};
 #[cfg(all(
     feature = "phy-tuntap_interface",
-    any(target_os = "linux", target_os = "android")
+    any(target_os = "linux", target_os = "android", target_os = "macos")
 ))]
 pub use self::tuntap_interface::TunTapInterface;
 
@@ -164,6 +176,12 @@ pub use self::tuntap_interface::TunTapInterface;
 pub struct PacketMeta {
     #[cfg(feature = "packetmeta-id")]
     pub id: u32,
+    /// The Flow Label of a received IPv6 packet, or zero if it doesn't belong to any flow.
+    ///
+    /// This is ignored when sending; the Flow Label of outgoing packets is chosen by the
+    /// `set_flow_label` method of the socket.
+    #[cfg(feature = "packetmeta-flow-label")]
+    pub flow_label: u32,
 }
 
 /// A description of checksum behavior for a particular protocol.
@@ -362,6 +380,56 @@ pub trait Device {
     /// arbitrary moment in time, such as system startup.
     fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>>;
 
+    /// Receive a batch of packets.
+    ///
+    /// The closure `f` is called with a token pair, as returned by [`receive`](Device::receive),
+    /// for every packet in the batch. The batch ends when the device has no more packets
+    /// available or when `f` returns `false`. Returns the number of token pairs that were
+    /// passed to `f`.
+    ///
+    /// The default implementation calls [`receive`](Device::receive) until it returns `None`.
+    /// Devices that are able to receive several packets at once (e.g. using `recvmmsg` or
+    /// io_uring) can override this method to do so with a single system call.
+    fn receive_many<F>(&mut self, timestamp: Instant, mut f: F) -> usize
+    where
+        F: FnMut(Self::RxToken<'_>, Self::TxToken<'_>) -> bool,
+    {
+        let mut count = 0;
+        while let Some((rx_token, tx_token)) = self.receive(timestamp) {
+            count += 1;
+            if !f(rx_token, tx_token) {
+                break;
+            }
+        }
+        count
+    }
+
+    /// Transmit a batch of packets.
+    ///
+    /// The closure `f` is called with a transmit token, as returned by
+    /// [`transmit`](Device::transmit), for every packet the device is able to send.
+    /// The closure returns `true` if it wants to send another packet, and `false` once
+    /// it has nothing left to send, in which case the token passed to it may be dropped
+    /// without being consumed. Returns the number of tokens that were passed to `f`.
+    ///
+    /// The default implementation calls [`transmit`](Device::transmit) until it returns `None`.
+    /// Devices that are able to send several packets at once (e.g. using `sendmmsg` or
+    /// io_uring) can override this method to queue the consumed tokens and flush them with
+    /// a single system call.
+    fn transmit_many<F>(&mut self, timestamp: Instant, mut f: F) -> usize
+    where
+        F: FnMut(Self::TxToken<'_>) -> bool,
+    {
+        let mut count = 0;
+        while let Some(tx_token) = self.transmit(timestamp) {
+            count += 1;
+            if !f(tx_token) {
+                break;
+            }
+        }
+        count
+    }
+
     /// Get a description of device capabilities.
     fn capabilities(&self) -> DeviceCapabilities;
 }
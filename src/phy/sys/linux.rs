@@ -1,6 +1,7 @@
 #![allow(unused)]
 
 pub const SIOCGIFMTU: libc::c_ulong = 0x8921;
+pub const SIOCSIFMTU: libc::c_ulong = 0x8922;
 pub const SIOCGIFINDEX: libc::c_ulong = 0x8933;
 pub const ETH_P_ALL: libc::c_short = 0x0003;
 pub const ETH_P_IEEE802154: libc::c_short = 0x00F6;
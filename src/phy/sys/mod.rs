@@ -24,6 +24,8 @@ pub mod raw_socket;
     any(target_os = "linux", target_os = "android")
 ))]
 pub mod tuntap_interface;
+#[cfg(all(feature = "phy-tuntap_interface", target_os = "macos"))]
+pub mod utun;
 
 #[cfg(all(
     feature = "phy-raw_socket",
@@ -41,6 +43,8 @@ pub use self::raw_socket::RawSocketDesc;
     any(target_os = "linux", target_os = "android")
 ))]
 pub use self::tuntap_interface::TunTapInterfaceDesc;
+#[cfg(all(feature = "phy-tuntap_interface", target_os = "macos"))]
+pub use self::utun::UtunDesc as TunTapInterfaceDesc;
 
 /// Wait until given file descriptor becomes readable, but no longer than given timeout.
 pub fn wait(fd: RawFd, duration: Option<Duration>) -> io::Result<()> {
@@ -6,6 +6,9 @@ use std::os::unix::io::{AsRawFd, RawFd};
 #[derive(Debug)]
 pub struct TunTapInterfaceDesc {
     lower: libc::c_int,
+    /// The request naming the interface, if it was attached by name.
+    ifreq: Option<ifreq>,
+    medium: Medium,
     mtu: usize,
 }
 
@@ -32,11 +35,21 @@ impl TunTapInterfaceDesc {
         Self::attach_interface_ifreq(lower, medium, &mut ifreq)?;
         let mtu = Self::mtu_ifreq(medium, &mut ifreq)?;
 
-        Ok(TunTapInterfaceDesc { lower, mtu })
+        Ok(TunTapInterfaceDesc {
+            lower,
+            ifreq: Some(ifreq),
+            medium,
+            mtu,
+        })
     }
 
-    pub fn from_fd(fd: RawFd, mtu: usize) -> io::Result<TunTapInterfaceDesc> {
-        Ok(TunTapInterfaceDesc { lower: fd, mtu })
+    pub fn from_fd(fd: RawFd, medium: Medium, mtu: usize) -> io::Result<TunTapInterfaceDesc> {
+        Ok(TunTapInterfaceDesc {
+            lower: fd,
+            ifreq: None,
+            medium,
+            mtu,
+        })
     }
 
     fn attach_interface_ifreq(
@@ -56,7 +69,9 @@ impl TunTapInterfaceDesc {
         ifreq_ioctl(lower, ifr, imp::TUNSETIFF).map(|_| ())
     }
 
-    fn mtu_ifreq(medium: Medium, ifr: &mut ifreq) -> io::Result<usize> {
+    /// Issue an interface ioctl such as `SIOCGIFMTU`, which has to be done on a socket
+    /// rather than on the TUN/TAP file descriptor.
+    fn socket_ifreq_ioctl(ifr: &mut ifreq, cmd: libc::c_ulong) -> io::Result<libc::c_int> {
         let lower = unsafe {
             let lower = libc::socket(libc::AF_INET, libc::SOCK_DGRAM, libc::IPPROTO_IP);
             if lower == -1 {
@@ -65,33 +80,65 @@ impl TunTapInterfaceDesc {
             lower
         };
 
-        let ip_mtu = ifreq_ioctl(lower, ifr, imp::SIOCGIFMTU).map(|mtu| mtu as usize);
+        let res = ifreq_ioctl(lower, ifr, cmd);
 
         unsafe {
             libc::close(lower);
         }
 
         // Propagate error after close, to ensure we always close.
-        let ip_mtu = ip_mtu?;
+        res
+    }
 
-        // SIOCGIFMTU returns the IP MTU (typically 1500 bytes.)
-        // smoltcp counts the entire Ethernet packet in the MTU, so add the Ethernet header size to it.
-        let mtu = match medium {
+    // SIOCGIFMTU and SIOCSIFMTU use the IP MTU (typically 1500 bytes.)
+    // smoltcp counts the entire Ethernet packet in the MTU, so add the Ethernet header size to it.
+    fn header_len(medium: Medium) -> usize {
+        match medium {
             #[cfg(feature = "medium-ip")]
-            Medium::Ip => ip_mtu,
+            Medium::Ip => 0,
             #[cfg(feature = "medium-ethernet")]
-            Medium::Ethernet => ip_mtu + EthernetFrame::<&[u8]>::header_len(),
+            Medium::Ethernet => EthernetFrame::<&[u8]>::header_len(),
             #[cfg(feature = "medium-ieee802154")]
             Medium::Ieee802154 => todo!(),
-        };
+        }
+    }
 
-        Ok(mtu)
+    fn mtu_ifreq(medium: Medium, ifr: &mut ifreq) -> io::Result<usize> {
+        let ip_mtu = Self::socket_ifreq_ioctl(ifr, imp::SIOCGIFMTU)? as usize;
+        Ok(ip_mtu + Self::header_len(medium))
     }
 
-    pub fn interface_mtu(&self) -> io::Result<usize> {
+    /// Return the MTU of the interface, read again from the OS if the interface was
+    /// attached by name.
+    pub fn interface_mtu(&mut self) -> io::Result<usize> {
+        if let Some(ifreq) = &mut self.ifreq {
+            self.mtu = Self::mtu_ifreq(self.medium, ifreq)?;
+        }
         Ok(self.mtu)
     }
 
+    /// Change the MTU of the interface, and return the MTU the OS actually assigned.
+    pub fn set_interface_mtu(&mut self, mtu: usize) -> io::Result<usize> {
+        let header_len = Self::header_len(self.medium);
+        let ifreq = match &mut self.ifreq {
+            Some(ifreq) => ifreq,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "interface was not attached by name",
+                ))
+            }
+        };
+        let ip_mtu = mtu
+            .checked_sub(header_len)
+            .and_then(|ip_mtu| libc::c_int::try_from(ip_mtu).ok())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+        ifreq.ifr_data = ip_mtu;
+        Self::socket_ifreq_ioctl(ifreq, imp::SIOCSIFMTU)?;
+        self.interface_mtu()
+    }
+
     pub fn recv(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
         unsafe {
             let len = libc::read(
@@ -0,0 +1,256 @@
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use crate::phy::Medium;
+
+// Definitions from <sys/kern_control.h>, <sys/sys_domain.h>, <net/if_utun.h> and
+// <sys/sockio.h>, see https://github.com/apple/darwin-xnu.
+const AF_SYSTEM: libc::c_int = 32;
+const AF_SYS_CONTROL: u16 = 2;
+const SYSPROTO_CONTROL: libc::c_int = 2;
+const MAX_KCTL_NAME: usize = 96;
+/// get the control ID of a kernel control by name
+const CTLIOCGINFO: libc::c_ulong = 0xc0644e03;
+/// get the name of the interface of a utun socket
+const UTUN_OPT_IFNAME: libc::c_int = 2;
+const UTUN_CONTROL_NAME: &[u8] = b"com.apple.net.utun_control";
+/// get interface MTU
+const SIOCGIFMTU: libc::c_ulong = 0xc0206933;
+/// set interface MTU
+const SIOCSIFMTU: libc::c_ulong = 0x80206934;
+
+#[repr(C)]
+struct ctl_info {
+    ctl_id: u32,
+    ctl_name: [libc::c_char; MAX_KCTL_NAME],
+}
+
+#[repr(C)]
+struct sockaddr_ctl {
+    sc_len: u8,
+    sc_family: u8,
+    ss_sysaddr: u16,
+    sc_id: u32,
+    sc_unit: u32,
+    sc_reserved: [u32; 5],
+}
+
+/// The part of `struct ifreq` used by `SIOCGIFMTU` and `SIOCSIFMTU`, padded to the full
+/// size of the structure, which is encoded in the ioctl numbers.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ifreq_mtu {
+    ifr_name: [libc::c_char; libc::IF_NAMESIZE],
+    ifr_mtu: libc::c_int,
+    _pad: [u8; 12],
+}
+
+macro_rules! try_libc {
+    ($expr:expr) => {
+        match unsafe { $expr } {
+            -1 => return Err(io::Error::last_os_error()),
+            res => res,
+        }
+    };
+}
+
+/// A macOS utun interface.
+///
+/// utun interfaces carry IP packets, each prefixed with the address family of the packet
+/// as a 4-byte big-endian integer; the prefix is added and removed here.
+#[derive(Debug)]
+pub struct UtunDesc {
+    lower: libc::c_int,
+    /// The name the interface was given by the OS, if it was attached by name.
+    ifreq: Option<ifreq_mtu>,
+    mtu: usize,
+}
+
+impl AsRawFd for UtunDesc {
+    fn as_raw_fd(&self) -> RawFd {
+        self.lower
+    }
+}
+
+impl UtunDesc {
+    /// Create the utun interface called `name`, which has to be `utunN`, or have the OS
+    /// pick a free unit if `name` is `utun`.
+    pub fn new(name: &str, medium: Medium) -> io::Result<UtunDesc> {
+        Self::check_medium(medium)?;
+        let unit = match name.strip_prefix("utun") {
+            Some("") => 0,
+            Some(unit) => unit
+                .parse::<u32>()
+                .ok()
+                .and_then(|unit| unit.checked_add(1))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?,
+            None => return Err(io::Error::from(io::ErrorKind::InvalidInput)),
+        };
+
+        let lower = try_libc!(libc::socket(AF_SYSTEM, libc::SOCK_DGRAM, SYSPROTO_CONTROL));
+        // From here on, `desc` closes the socket if anything goes wrong.
+        let mut desc = UtunDesc {
+            lower,
+            ifreq: None,
+            mtu: 0,
+        };
+
+        let mut info = ctl_info {
+            ctl_id: 0,
+            ctl_name: [0; MAX_KCTL_NAME],
+        };
+        for (i, byte) in UTUN_CONTROL_NAME.iter().enumerate() {
+            info.ctl_name[i] = *byte as libc::c_char
+        }
+        try_libc!(libc::ioctl(lower, CTLIOCGINFO, &mut info as *mut ctl_info));
+
+        let addr = sockaddr_ctl {
+            sc_len: mem::size_of::<sockaddr_ctl>() as u8,
+            sc_family: AF_SYSTEM as u8,
+            ss_sysaddr: AF_SYS_CONTROL,
+            sc_id: info.ctl_id,
+            sc_unit: unit,
+            sc_reserved: [0; 5],
+        };
+        try_libc!(libc::connect(
+            lower,
+            &addr as *const sockaddr_ctl as *const libc::sockaddr,
+            mem::size_of::<sockaddr_ctl>() as libc::socklen_t,
+        ));
+
+        let flags = try_libc!(libc::fcntl(lower, libc::F_GETFL));
+        try_libc!(libc::fcntl(lower, libc::F_SETFL, flags | libc::O_NONBLOCK));
+
+        let mut ifreq = ifreq_mtu {
+            ifr_name: [0; libc::IF_NAMESIZE],
+            ifr_mtu: 0,
+            _pad: [0; 12],
+        };
+        let mut len = libc::IF_NAMESIZE as libc::socklen_t;
+        try_libc!(libc::getsockopt(
+            lower,
+            SYSPROTO_CONTROL,
+            UTUN_OPT_IFNAME,
+            ifreq.ifr_name.as_mut_ptr() as *mut libc::c_void,
+            &mut len,
+        ));
+        desc.ifreq = Some(ifreq);
+        desc.interface_mtu()?;
+
+        Ok(desc)
+    }
+
+    pub fn from_fd(fd: RawFd, medium: Medium, mtu: usize) -> io::Result<UtunDesc> {
+        Self::check_medium(medium)?;
+        Ok(UtunDesc {
+            lower: fd,
+            ifreq: None,
+            mtu,
+        })
+    }
+
+    fn check_medium(medium: Medium) -> io::Result<()> {
+        match medium {
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => Ok(()),
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "utun interfaces only support Medium::Ip",
+            )),
+        }
+    }
+
+    /// Issue an interface ioctl, which has to be done on an IP socket.
+    fn socket_ifreq_ioctl(ifr: &mut ifreq_mtu, cmd: libc::c_ulong) -> io::Result<()> {
+        let lower = try_libc!(libc::socket(
+            libc::AF_INET,
+            libc::SOCK_DGRAM,
+            libc::IPPROTO_IP
+        ));
+        let res = unsafe { libc::ioctl(lower, cmd, ifr as *mut ifreq_mtu) };
+        let err = io::Error::last_os_error();
+        unsafe {
+            libc::close(lower);
+        }
+
+        // Propagate error after close, to ensure we always close.
+        if res == -1 {
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Return the MTU of the interface, read again from the OS if the interface was
+    /// attached by name.
+    pub fn interface_mtu(&mut self) -> io::Result<usize> {
+        if let Some(ifreq) = &mut self.ifreq {
+            Self::socket_ifreq_ioctl(ifreq, SIOCGIFMTU)?;
+            self.mtu = ifreq.ifr_mtu as usize;
+        }
+        Ok(self.mtu)
+    }
+
+    /// Change the MTU of the interface, and return the MTU the OS actually assigned.
+    pub fn set_interface_mtu(&mut self, mtu: usize) -> io::Result<usize> {
+        let ifreq = match &mut self.ifreq {
+            Some(ifreq) => ifreq,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "interface was not attached by name",
+                ))
+            }
+        };
+        ifreq.ifr_mtu =
+            libc::c_int::try_from(mtu).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Self::socket_ifreq_ioctl(ifreq, SIOCSIFMTU)?;
+        self.interface_mtu()
+    }
+
+    pub fn recv(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut family = [0u8; 4];
+        let mut iov = [
+            libc::iovec {
+                iov_base: family.as_mut_ptr() as *mut libc::c_void,
+                iov_len: family.len(),
+            },
+            libc::iovec {
+                iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buffer.len(),
+            },
+        ];
+        let len = try_libc!(libc::readv(self.lower, iov.as_mut_ptr(), iov.len() as _));
+        Ok((len as usize).saturating_sub(family.len()))
+    }
+
+    pub fn send(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let family = match buffer.first().map(|byte| byte >> 4) {
+            Some(4) => libc::AF_INET,
+            Some(6) => libc::AF_INET6,
+            _ => return Err(io::Error::from(io::ErrorKind::InvalidInput)),
+        };
+        let family = (family as u32).to_be_bytes();
+        let iov = [
+            libc::iovec {
+                iov_base: family.as_ptr() as *mut libc::c_void,
+                iov_len: family.len(),
+            },
+            libc::iovec {
+                iov_base: buffer.as_ptr() as *mut libc::c_void,
+                iov_len: buffer.len(),
+            },
+        ];
+        let len = try_libc!(libc::writev(self.lower, iov.as_ptr(), iov.len() as _));
+        Ok((len as usize).saturating_sub(family.len()))
+    }
+}
+
+impl Drop for UtunDesc {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.lower);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use alloc::vec::Vec;
+
+use crate::phy::{self, Device, DeviceCapabilities, PacketMeta};
+use crate::time::Instant;
+
+/// An encapsulation, such as a tunnel or a link-layer header, applied by a [TunnelDevice].
+pub trait Encapsulator {
+    /// Return the number of octets that [encap](#tymethod.encap) adds to a frame.
+    ///
+    /// The maximum transmission unit of the tunnel device is that of the underlying
+    /// device, reduced by this amount.
+    fn overhead(&self) -> usize;
+
+    /// Encapsulate the frame `inner`, writing the result into the empty buffer `out`.
+    fn encap(&mut self, inner: &[u8], out: &mut Vec<u8>);
+
+    /// Decapsulate `frame` and return the frame it carries, or `None` if `frame` is not
+    /// a valid encapsulated frame, in which case it is dropped.
+    fn decap<'f>(&mut self, frame: &'f [u8]) -> Option<&'f [u8]>;
+}
+
+/// An encapsulation that prefixes each frame with its length, as a big-endian 16-bit integer.
+///
+/// This is mostly useful for testing, and for carrying frames over byte streams.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LengthPrefix;
+
+impl Encapsulator for LengthPrefix {
+    fn overhead(&self) -> usize {
+        2
+    }
+
+    fn encap(&mut self, inner: &[u8], out: &mut Vec<u8>) {
+        out.extend_from_slice(&(inner.len() as u16).to_be_bytes());
+        out.extend_from_slice(inner);
+    }
+
+    fn decap<'f>(&mut self, frame: &'f [u8]) -> Option<&'f [u8]> {
+        let (len, payload) = match frame {
+            [hi, lo, payload @ ..] => (u16::from_be_bytes([*hi, *lo]) as usize, payload),
+            _ => return None,
+        };
+        payload.get(..len)
+    }
+}
+
+/// A tunnel device.
+///
+/// A tunnel device encapsulates every frame transmitted through it with an [Encapsulator]
+/// before passing it to the underlying device, and decapsulates every frame received from
+/// the underlying device. Frames that fail to decapsulate are dropped. Tunnel devices can
+/// be stacked to apply several encapsulations, the outermost one being applied by the
+/// tunnel device closest to the hardware.
+#[derive(Debug)]
+pub struct TunnelDevice<D: Device, E: Encapsulator> {
+    inner: D,
+    encapsulator: E,
+}
+
+impl<D: Device, E: Encapsulator> TunnelDevice<D, E> {
+    /// Create a tunnel device applying `encapsulator` over the device `inner`.
+    pub fn new(inner: D, encapsulator: E) -> TunnelDevice<D, E> {
+        TunnelDevice {
+            inner,
+            encapsulator,
+        }
+    }
+
+    /// Return a reference to the encapsulator.
+    pub fn encapsulator(&self) -> &E {
+        &self.encapsulator
+    }
+
+    /// Return a mutable reference to the encapsulator.
+    pub fn encapsulator_mut(&mut self) -> &mut E {
+        &mut self.encapsulator
+    }
+
+    /// Get a reference to the underlying device.
+    ///
+    /// Even if the device offers reading through a standard reference, it is inadvisable to
+    /// directly read from the device as doing so will circumvent the decapsulation.
+    pub fn get_ref(&self) -> &D {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying device.
+    ///
+    /// It is inadvisable to directly read from the device as doing so will circumvent the
+    /// decapsulation.
+    pub fn get_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    /// Return the underlying device, consuming the tunnel device.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: Device, E: Encapsulator> Device for TunnelDevice<D, E> {
+    type RxToken<'a>
+        = RxToken
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken<'a, D::TxToken<'a>, E>
+    where
+        Self: 'a;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = self.inner.capabilities();
+        caps.max_transmission_unit = caps
+            .max_transmission_unit
+            .saturating_sub(self.encapsulator.overhead());
+        caps
+    }
+
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let (rx_token, token) = self.inner.receive(timestamp)?;
+        let meta = phy::RxToken::meta(&rx_token);
+        let encapsulator = &mut self.encapsulator;
+        let buffer = phy::RxToken::consume(rx_token, |frame| {
+            encapsulator.decap(frame).map(|inner| inner.to_vec())
+        });
+        match buffer {
+            Some(buffer) => Some((
+                RxToken { buffer, meta },
+                TxToken {
+                    token,
+                    encapsulator,
+                },
+            )),
+            None => {
+                net_debug!("tunnel: dropping frame that failed to decapsulate");
+                None
+            }
+        }
+    }
+
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        let encapsulator = &mut self.encapsulator;
+        self.inner.transmit(timestamp).map(|token| TxToken {
+            token,
+            encapsulator,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken {
+    buffer: Vec<u8>,
+    meta: PacketMeta,
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.buffer)
+    }
+
+    fn meta(&self) -> PacketMeta {
+        self.meta
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<'a, Tx: phy::TxToken, E: Encapsulator> {
+    token: Tx,
+    encapsulator: &'a mut E,
+}
+
+impl<Tx: phy::TxToken, E: Encapsulator> phy::TxToken for TxToken<'_, Tx, E> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut inner = alloc::vec![0; len];
+        let result = f(&mut inner);
+
+        let mut frame = Vec::with_capacity(len + self.encapsulator.overhead());
+        self.encapsulator.encap(&inner, &mut frame);
+        self.token
+            .consume(frame.len(), |buffer| buffer.copy_from_slice(&frame));
+        result
+    }
+
+    fn set_meta(&mut self, meta: PacketMeta) {
+        self.token.set_meta(meta)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::phy::{RxToken as _, TxToken as _};
+
+    /// An encapsulation that prefixes each frame with a fixed tag.
+    struct Tag(u8);
+
+    impl Encapsulator for Tag {
+        fn overhead(&self) -> usize {
+            1
+        }
+
+        fn encap(&mut self, inner: &[u8], out: &mut Vec<u8>) {
+            out.push(self.0);
+            out.extend_from_slice(inner);
+        }
+
+        fn decap<'f>(&mut self, frame: &'f [u8]) -> Option<&'f [u8]> {
+            match frame {
+                [tag, inner @ ..] if *tag == self.0 => Some(inner),
+                _ => None,
+            }
+        }
+    }
+
+    fn send<D: Device>(device: &mut D, data: &[u8]) {
+        let token = device.transmit(Instant::ZERO).unwrap();
+        token.consume(data.len(), |buffer| buffer.copy_from_slice(data));
+    }
+
+    fn recv<D: Device>(device: &mut D) -> Option<Vec<u8>> {
+        let (token, _) = device.receive(Instant::ZERO)?;
+        Some(token.consume(|buffer| buffer.to_vec()))
+    }
+
+    #[test]
+    fn test_length_prefix() {
+        let mut out = Vec::new();
+        LengthPrefix.encap(b"abc", &mut out);
+        assert_eq!(out, b"\x00\x03abc");
+        assert_eq!(LengthPrefix.decap(&out), Some(&b"abc"[..]));
+        assert_eq!(LengthPrefix.decap(b"\x00\x03ab"), None);
+        assert_eq!(LengthPrefix.decap(b"\x00"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "medium-ip")]
+    fn test_stacked() {
+        use crate::phy::{Loopback, Medium};
+
+        let loopback = Loopback::new(Medium::Ip);
+        let mut device = TunnelDevice::new(TunnelDevice::new(loopback, Tag(0xaa)), LengthPrefix);
+        assert_eq!(device.capabilities().max_transmission_unit, 65535 - 3);
+
+        // The inner encapsulation is applied first, and the outer one around it.
+        send(&mut device, b"abc");
+        assert_eq!(
+            device.get_ref().get_ref().queue.front().unwrap(),
+            b"\xaa\x00\x03abc"
+        );
+        assert_eq!(recv(&mut device), Some(b"abc".to_vec()));
+
+        // Frames that fail to decapsulate at any level are dropped.
+        device
+            .get_mut()
+            .get_mut()
+            .queue
+            .push_back(b"\xbb\x00\x03abc".to_vec());
+        device
+            .get_mut()
+            .get_mut()
+            .queue
+            .push_back(b"\xaa\x00\x05abc".to_vec());
+        send(&mut device, b"def");
+        assert_eq!(recv(&mut device), None);
+        assert_eq!(recv(&mut device), None);
+        assert_eq!(recv(&mut device), Some(b"def".to_vec()));
+        assert_eq!(recv(&mut device), None);
+
+        // The transmit token handed out with a received frame encapsulates too.
+        send(&mut device, b"ghi");
+        let (rx, tx) = device.receive(Instant::ZERO).unwrap();
+        assert_eq!(rx.consume(|buffer| buffer.to_vec()), b"ghi");
+        tx.consume(3, |buffer| buffer.copy_from_slice(b"jkl"));
+        assert_eq!(
+            device.get_ref().get_ref().queue.front().unwrap(),
+            b"\xaa\x00\x03jkl"
+        );
+    }
+}
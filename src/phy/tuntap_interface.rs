@@ -8,6 +8,9 @@ use crate::phy::{self, sys, Device, DeviceCapabilities, Medium};
 use crate::time::Instant;
 
 /// A virtual TUN (IP) or TAP (Ethernet) interface.
+///
+/// On macOS, this is a utun interface, which only supports [Medium::Ip]. The interface
+/// has to be called `utunN`, or `utun` to let the OS pick a free unit.
 #[derive(Debug)]
 pub struct TunTapInterface {
     lower: Rc<RefCell<sys::TunTapInterfaceDesc>>,
@@ -27,8 +30,11 @@ impl TunTapInterface {
     /// If `name` is a persistent interface configured with UID of the current user,
     /// no special privileges are needed. Otherwise, this requires superuser privileges
     /// or a corresponding capability set on the executable.
+    ///
+    /// On macOS, utun interfaces are not persistent, and creating one always requires
+    /// superuser privileges.
     pub fn new(name: &str, medium: Medium) -> io::Result<TunTapInterface> {
-        let lower = sys::TunTapInterfaceDesc::new(name, medium)?;
+        let mut lower = sys::TunTapInterfaceDesc::new(name, medium)?;
         let mtu = lower.interface_mtu()?;
         Ok(TunTapInterface {
             lower: Rc::new(RefCell::new(lower)),
@@ -42,13 +48,48 @@ impl TunTapInterface {
     /// On platforms like Android, a file descriptor to a tun interface is exposed.
     /// On these platforms, a TunTapInterface cannot be instantiated with a name.
     pub fn from_fd(fd: RawFd, medium: Medium, mtu: usize) -> io::Result<TunTapInterface> {
-        let lower = sys::TunTapInterfaceDesc::from_fd(fd, mtu)?;
+        let lower = sys::TunTapInterfaceDesc::from_fd(fd, medium, mtu)?;
         Ok(TunTapInterface {
             lower: Rc::new(RefCell::new(lower)),
             mtu,
             medium,
         })
     }
+
+    /// Attaches to a TUN/TAP interface called `name`, or creates it if it does not exist,
+    /// and sets its MTU.
+    ///
+    /// See [new](#method.new) and [set_mtu](#method.set_mtu).
+    pub fn with_mtu(name: &str, medium: Medium, mtu: usize) -> io::Result<TunTapInterface> {
+        let mut iface = Self::new(name, medium)?;
+        iface.set_mtu(mtu)?;
+        Ok(iface)
+    }
+
+    /// Return the MTU of the interface, as reported by the OS.
+    ///
+    /// The MTU is counted the same way as [DeviceCapabilities::max_transmission_unit],
+    /// i.e. it includes the Ethernet header for TAP interfaces. The value is also used for
+    /// the capabilities of the device from now on, in case it was changed outside of smoltcp.
+    ///
+    /// For an interface attached by file descriptor, this is the MTU given to
+    /// [from_fd](#method.from_fd).
+    pub fn mtu(&mut self) -> io::Result<usize> {
+        self.mtu = self.lower.borrow_mut().interface_mtu()?;
+        Ok(self.mtu)
+    }
+
+    /// Change the MTU of the interface.
+    ///
+    /// The MTU is counted the same way as [DeviceCapabilities::max_transmission_unit],
+    /// i.e. it includes the Ethernet header for TAP interfaces. The capabilities of the
+    /// device report the MTU the OS actually assigned afterwards. Changing the MTU usually
+    /// requires superuser privileges, and is not supported for an interface attached by
+    /// file descriptor.
+    pub fn set_mtu(&mut self, mtu: usize) -> io::Result<()> {
+        self.mtu = self.lower.borrow_mut().set_interface_mtu(mtu)?;
+        Ok(())
+    }
 }
 
 impl Device for TunTapInterface {
@@ -1,17 +1,42 @@
-#![allow(unsafe_code)]
-#![allow(unused)]
+/*! Random number generation.
 
-#[derive(Debug)]
-pub(crate) struct Rand {
+The `rand` module contains the [RandSource] trait, which the interface uses to obtain
+random numbers, e.g. for TCP initial sequence numbers, ephemeral ports and DNS query
+IDs, and [Pcg32], a small pseudo-random number generator that is used unless the
+application provides a source of its own in [Config::rand_source].
+
+[Config::rand_source]: crate::iface::Config::rand_source
+*/
+
+use core::fmt;
+
+/// A source of random numbers.
+///
+/// Implement this trait to let the interface use e.g. a hardware random number generator.
+/// The numbers are used for TCP initial sequence numbers, so a cryptographically secure
+/// source makes connections harder to hijack.
+pub trait RandSource {
+    /// Return a random 32-bit number.
+    fn rand_u32(&mut self) -> u32;
+}
+
+/// A pseudo-random number generator that works without the standard library.
+///
+/// It is not cryptographically secure, and its output is entirely determined by its seed.
+#[derive(Debug, Clone)]
+pub struct Pcg32 {
     state: u64,
 }
 
-impl Rand {
-    pub(crate) const fn new(seed: u64) -> Self {
+impl Pcg32 {
+    /// Create a generator from the given seed.
+    pub const fn new(seed: u64) -> Self {
         Self { state: seed }
     }
+}
 
-    pub(crate) fn rand_u32(&mut self) -> u32 {
+impl RandSource for Pcg32 {
+    fn rand_u32(&mut self) -> u32 {
         // sPCG32 from https://www.pcg-random.org/paper.html
         // see also https://nullprogram.com/blog/2017/09/21/
         const M: u64 = 0xbb2efcec3c39611d;
@@ -23,6 +48,47 @@ impl Rand {
         let shift = 29 - (s >> 61);
         (s >> shift) as u32
     }
+}
+
+/// The random number generator of an interface: the application-provided source, or
+/// the built-in generator if there is none.
+#[allow(unused)]
+pub(crate) struct Rand<'a> {
+    prng: Pcg32,
+    source: Option<&'a mut dyn RandSource>,
+}
+
+impl fmt::Debug for Rand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Rand")
+            .field("prng", &self.prng)
+            .field("source", &self.source.is_some())
+            .finish()
+    }
+}
+
+#[allow(unused)]
+impl<'a> Rand<'a> {
+    pub(crate) const fn new(seed: u64) -> Self {
+        Self {
+            prng: Pcg32::new(seed),
+            source: None,
+        }
+    }
+
+    pub(crate) fn with_source(seed: u64, source: Option<&'a mut dyn RandSource>) -> Self {
+        Self {
+            prng: Pcg32::new(seed),
+            source,
+        }
+    }
+
+    pub(crate) fn rand_u32(&mut self) -> u32 {
+        match &mut self.source {
+            Some(source) => source.rand_u32(),
+            None => self.prng.rand_u32(),
+        }
+    }
 
     pub(crate) fn rand_u16(&mut self) -> u16 {
         let n = self.rand_u32();
@@ -38,3 +104,114 @@ impl Rand {
         }
     }
 }
+
+/// Hash the concatenation of `parts` with a secret key, using [SipHash-2-4].
+///
+/// SipHash is a pseudorandom function, so the result can't be predicted, even from the
+/// results for other inputs, without knowing the secret. The secret is used as the first
+/// half of the 128-bit key, and the second half is zero.
+///
+/// [SipHash-2-4]: https://www.aumasson.jp/siphash/siphash.pdf
+#[cfg(any(feature = "socket-tcp", feature = "socket-udp", feature = "socket-raw"))]
+pub(crate) const fn keyed_hash(secret: u64, parts: &[&[u8]]) -> u64 {
+    siphash(secret, 0, parts)
+}
+
+#[cfg(any(
+    test,
+    feature = "socket-tcp",
+    feature = "socket-udp",
+    feature = "socket-raw"
+))]
+const fn siphash(k0: u64, k1: u64, parts: &[&[u8]]) -> u64 {
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    // Compress the input 8 octets at a time, in little-endian order.
+    let mut len = 0;
+    let mut m = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        let mut j = 0;
+        while j < parts[i].len() {
+            m |= (parts[i][j] as u64) << (8 * (len % 8));
+            len += 1;
+            if len % 8 == 0 {
+                v = sip_compress(v, m);
+                m = 0;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    v = sip_compress(v, m | (len as u64) << 56);
+
+    v[2] ^= 0xff;
+    v = sip_round(sip_round(sip_round(sip_round(v))));
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[cfg(any(
+    test,
+    feature = "socket-tcp",
+    feature = "socket-udp",
+    feature = "socket-raw"
+))]
+const fn sip_compress(mut v: [u64; 4], m: u64) -> [u64; 4] {
+    v[3] ^= m;
+    v = sip_round(sip_round(v));
+    v[0] ^= m;
+    v
+}
+
+#[cfg(any(
+    test,
+    feature = "socket-tcp",
+    feature = "socket-udp",
+    feature = "socket-raw"
+))]
+const fn sip_round(mut v: [u64; 4]) -> [u64; 4] {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+    v
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY: (u64, u64) = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+
+    #[test]
+    fn test_siphash_vectors() {
+        // From appendix A of the SipHash paper and the reference implementation.
+        let input: std::vec::Vec<u8> = (0..15).collect();
+        assert_eq!(siphash(KEY.0, KEY.1, &[]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(siphash(KEY.0, KEY.1, &[&input]), 0xa129_ca61_49be_45e5);
+    }
+
+    #[test]
+    fn test_siphash_parts() {
+        let input: std::vec::Vec<u8> = (0..15).collect();
+        assert_eq!(
+            siphash(
+                KEY.0,
+                KEY.1,
+                &[&input[..3], &[], &input[3..11], &input[11..]]
+            ),
+            siphash(KEY.0, KEY.1, &[&input]),
+        );
+    }
+}
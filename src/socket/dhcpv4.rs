@@ -287,7 +287,7 @@ impl<'a> Socket<'a> {
         self.client_port = client_port;
     }
 
-    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+    pub(crate) fn poll_at(&self, _cx: &Context) -> PollAt {
         let t = match &self.state {
             ClientState::Discovering(state) => state.retry_at,
             ClientState::Requesting(state) => state.retry_at,
@@ -789,7 +789,7 @@ mod test {
 
     struct TestSocket {
         socket: Socket<'static>,
-        cx: Context,
+        cx: Context<'static>,
     }
 
     impl Deref for TestSocket {
@@ -1404,6 +1404,71 @@ mod test {
         recv!(s, time 875_000, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_REBIND)]);
     }
 
+    #[test]
+    #[cfg(all(feature = "medium-ethernet", feature = "socket-tcp"))]
+    fn test_next_poll_at_with_tcp() {
+        use crate::socket::tcp;
+        use crate::wire::{
+            ArpOperation, ArpPacket, ArpRepr, EthernetFrame, EthernetProtocol, EthernetRepr,
+        };
+
+        const REMOTE_IP: Ipv4Address = Ipv4Address([192, 168, 1, 2]);
+        const REMOTE_MAC: EthernetAddress = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x03]);
+
+        let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+        sockets.add(socket_bound(Medium::Ethernet).socket);
+        let tcp_handle = sockets.add(tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; 64]),
+        ));
+
+        // Only the renewal is pending.
+        assert_eq!(
+            iface.next_poll_at(Instant::ZERO, &sockets),
+            Some(Instant::from_secs(500))
+        );
+
+        // Make the TCP peer resolvable, so that its SYN is sent right away.
+        let eth_repr = EthernetRepr {
+            src_addr: REMOTE_MAC,
+            dst_addr: MY_MAC,
+            ethertype: EthernetProtocol::Arp,
+        };
+        let arp_repr = ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Reply,
+            source_hardware_addr: REMOTE_MAC,
+            source_protocol_addr: REMOTE_IP,
+            target_hardware_addr: MY_MAC,
+            target_protocol_addr: Ipv4Address([192, 168, 1, 1]),
+        };
+        let mut frame = vec![0; eth_repr.buffer_len() + arp_repr.buffer_len()];
+        let mut eth_frame = EthernetFrame::new_unchecked(&mut frame[..]);
+        eth_repr.emit(&mut eth_frame);
+        arp_repr.emit(&mut ArpPacket::new_unchecked(eth_frame.payload_mut()));
+        device
+            .queue
+            .push_back(heapless::Vec::from_slice(&frame).unwrap())
+            .unwrap();
+
+        // The SYN retransmission is due before the renewal.
+        sockets
+            .get_mut::<tcp::Socket>(tcp_handle)
+            .connect(iface.context(), (REMOTE_IP, 80), 49500)
+            .unwrap();
+        iface.poll(Instant::from_secs(499), &mut device, &mut sockets);
+        assert_eq!(
+            iface.next_poll_at(Instant::from_secs(499), &sockets),
+            Some(Instant::from_millis(499_700))
+        );
+
+        // The next SYN retransmission is due after the renewal.
+        iface.poll(Instant::from_millis(499_700), &mut device, &mut sockets);
+        assert_eq!(
+            iface.next_poll_at(Instant::from_millis(499_700), &sockets),
+            Some(Instant::from_secs(500))
+        );
+    }
+
     #[rstest]
     #[case::ip(Medium::Ethernet)]
     #[cfg(feature = "medium-ethernet")]
@@ -110,7 +110,9 @@ struct PendingQuery {
     timeout_at: Option<Instant>,
     retransmit_at: Instant,
     delay: Duration,
+    deadline: Option<Instant>,
 
+    first_server: usize,
     server_idx: usize,
     mdns: MulticastDns,
 }
@@ -138,10 +140,14 @@ pub struct QueryHandle(usize);
 #[derive(Debug)]
 pub struct Socket<'a> {
     servers: Vec<IpAddress, DNS_MAX_SERVER_COUNT>,
+    /// Index of the server new queries are sent to first.
+    preferred_server: usize,
     queries: ManagedSlice<'a, Option<DnsQuery>>,
 
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+    server_timeout: Duration,
+    query_timeout: Option<Duration>,
 }
 
 impl<'a> Socket<'a> {
@@ -156,8 +162,11 @@ impl<'a> Socket<'a> {
     {
         Socket {
             servers: Vec::from_slice(servers).unwrap(),
+            preferred_server: 0,
             queries: queries.into(),
             hop_limit: None,
+            server_timeout: RETRANSMIT_TIMEOUT,
+            query_timeout: None,
         }
     }
 
@@ -168,6 +177,43 @@ impl<'a> Socket<'a> {
     /// Panics if `servers.len() > MAX_SERVER_COUNT`
     pub fn update_servers(&mut self, servers: &[IpAddress]) {
         self.servers = Vec::from_slice(servers).unwrap();
+        self.preferred_server = 0;
+    }
+
+    /// Return how long a query waits for an answer from a server before trying the next one.
+    ///
+    /// See also the [set_server_timeout](#method.set_server_timeout) method
+    pub fn server_timeout(&self) -> Duration {
+        self.server_timeout
+    }
+
+    /// Set how long a query waits for an answer from a server before trying the next one.
+    ///
+    /// A query is sent to the servers in order, and retransmitted to each of them until it
+    /// is answered or this timeout expires. A query fails once every server has timed out.
+    /// The servers are tried starting from the one that last answered, so a server that
+    /// stops responding is only tried again once the others time out as well.
+    ///
+    /// By default, the server timeout is 10s.
+    pub fn set_server_timeout(&mut self, timeout: Duration) {
+        self.server_timeout = timeout
+    }
+
+    /// Return the maximum duration of a query.
+    ///
+    /// See also the [set_query_timeout](#method.set_query_timeout) method
+    pub fn query_timeout(&self) -> Option<Duration> {
+        self.query_timeout
+    }
+
+    /// Set the maximum duration of a query, across all servers.
+    ///
+    /// A query that is not answered within this duration fails, even if it did not time out
+    /// on every server yet. This applies to queries started afterwards.
+    ///
+    /// By default, there is no limit, and a query only fails once every server has timed out.
+    pub fn set_query_timeout(&mut self, timeout: Option<Duration>) {
+        self.query_timeout = timeout
     }
 
     /// Return the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
@@ -286,6 +332,7 @@ impl<'a> Socket<'a> {
         mdns: MulticastDns,
     ) -> Result<QueryHandle, StartQueryError> {
         let handle = self.find_free_query().ok_or(StartQueryError::NoFreeSlot)?;
+        let deadline = self.query_timeout.map(|timeout| cx.now() + timeout);
 
         self.queries[handle.0] = Some(DnsQuery {
             state: State::Pending(PendingQuery {
@@ -296,6 +343,8 @@ impl<'a> Socket<'a> {
                 delay: RETRANSMIT_DELAY,
                 timeout_at: None,
                 retransmit_at: Instant::ZERO,
+                deadline,
+                first_server: self.preferred_server,
                 server_idx: 0,
                 mdns,
             }),
@@ -510,6 +559,15 @@ impl<'a> Socket<'a> {
                     }
                 }
 
+                // Send later queries to the server that answered this one first.
+                let server = self
+                    .servers
+                    .iter()
+                    .position(|server| *server == ip_repr.src_addr());
+                if let (MulticastDns::Disabled, Some(idx)) = (&pq.mdns, server) {
+                    self.preferred_server = idx;
+                }
+
                 q.set_state(if addresses.is_empty() {
                     State::Failure
                 } else {
@@ -530,6 +588,7 @@ impl<'a> Socket<'a> {
         F: FnOnce(&mut Context, (IpRepr, UdpRepr, &[u8])) -> Result<(), E>,
     {
         let hop_limit = self.hop_limit.unwrap_or(64);
+        let server_timeout = self.server_timeout;
 
         for q in self.queries.iter_mut().flatten() {
             if let State::Pending(pq) = &mut q.state {
@@ -550,21 +609,26 @@ impl<'a> Socket<'a> {
                 let timeout = if let Some(timeout) = pq.timeout_at {
                     timeout
                 } else {
-                    let v = cx.now() + RETRANSMIT_TIMEOUT;
+                    let v = cx.now() + server_timeout;
                     pq.timeout_at = Some(v);
                     v
                 };
 
                 // Check timeout
-                if timeout < cx.now() {
+                if timeout <= cx.now() {
                     // DNS timeout
-                    pq.timeout_at = Some(cx.now() + RETRANSMIT_TIMEOUT);
+                    pq.timeout_at = Some(cx.now() + server_timeout);
                     pq.retransmit_at = Instant::ZERO;
                     pq.delay = RETRANSMIT_DELAY;
 
                     // Try next server. We check below whether we've tried all servers.
                     pq.server_idx += 1;
                 }
+                if matches!(pq.deadline, Some(deadline) if deadline <= cx.now()) {
+                    net_trace!("query timed out.");
+                    q.set_state(State::Failure);
+                    continue;
+                }
                 // Check if we've run out of servers to try.
                 if pq.server_idx >= servers.len() {
                     net_trace!("already tried all servers.");
@@ -572,8 +636,17 @@ impl<'a> Socket<'a> {
                     continue;
                 }
 
+                // Start from the preferred server, and wrap around.
+                let dst_addr = match pq.mdns {
+                    #[cfg(feature = "socket-mdns")]
+                    MulticastDns::Enabled => servers[pq.server_idx],
+                    MulticastDns::Disabled => {
+                        servers[(pq.first_server + pq.server_idx) % servers.len()]
+                    }
+                };
+
                 // Check so the IP address is valid
-                if servers[pq.server_idx].is_unspecified() {
+                if dst_addr.is_unspecified() {
                     net_trace!("invalid unspecified DNS server addr.");
                     q.set_state(State::Failure);
                     continue;
@@ -609,7 +682,6 @@ impl<'a> Socket<'a> {
                     dst_port,
                 };
 
-                let dst_addr = servers[pq.server_idx];
                 let src_addr = cx.get_source_address(&dst_addr).unwrap(); // TODO remove unwrap
                 let ip_repr = IpRepr::new(
                     src_addr,
@@ -644,7 +716,16 @@ impl<'a> Socket<'a> {
             .iter()
             .flatten()
             .filter_map(|q| match &q.state {
-                State::Pending(pq) => Some(PollAt::Time(pq.retransmit_at)),
+                State::Pending(pq) => {
+                    let mut poll_at = pq.retransmit_at;
+                    if let Some(timeout_at) = pq.timeout_at {
+                        poll_at = poll_at.min(timeout_at);
+                    }
+                    if let Some(deadline) = pq.deadline {
+                        poll_at = poll_at.min(deadline);
+                    }
+                    Some(PollAt::Time(poll_at))
+                }
                 State::Completed(_) => None,
                 State::Failure => None,
             })
@@ -697,3 +778,540 @@ fn copy_name<'a, const N: usize>(
 
     Ok(())
 }
+
+/// The largest query a [Responder] answers, which is the largest DNS message carried over
+/// UDP without extensions (RFC 1035 § 4.2.1).
+#[cfg(feature = "socket-udp")]
+const RESPONDER_MAX_QUERY_LEN: usize = 512;
+
+/// A DNS responder, answering the queries received on a UDP socket from a table of
+/// records.
+///
+/// The responder answers A and AAAA queries for the names in its table, and reports that
+/// any other name does not exist. It can also _hijack_ queries, like the DNS server of a
+/// captive portal: while hijacking is enabled, every query is answered with the redirect
+/// address, except for the names in the allowlist, which are answered from the table as
+/// usual. Hijacked answers have a TTL of zero, so that clients don't keep using the
+/// redirect address once hijacking stops.
+///
+/// The responder keeps no state of its own; call [process](#method.process) with a UDP
+/// socket bound to port 53 after every poll of the interface.
+#[cfg(feature = "socket-udp")]
+#[derive(Debug)]
+pub struct Responder<'a> {
+    records: &'a [(&'a str, IpAddress)],
+    allowlist: &'a [&'a str],
+    redirect: Option<IpAddress>,
+    ttl: u32,
+}
+
+#[cfg(feature = "socket-udp")]
+impl<'a> Responder<'a> {
+    /// Create a responder answering from the given table of names and addresses.
+    ///
+    /// A name may appear several times, with addresses of either family.
+    pub fn new(records: &'a [(&'a str, IpAddress)]) -> Responder<'a> {
+        Responder {
+            records,
+            allowlist: &[],
+            redirect: None,
+            ttl: 60,
+        }
+    }
+
+    /// Set the TTL, in seconds, of the answers from the table.
+    ///
+    /// By default, the TTL is 60 seconds.
+    pub fn set_ttl(&mut self, ttl: u32) {
+        self.ttl = ttl
+    }
+
+    /// Return the TTL of the answers from the table.
+    pub fn ttl(&self) -> u32 {
+        self.ttl
+    }
+
+    /// Start hijacking queries, answering them with `redirect`, or stop hijacking them if
+    /// `redirect` is `None`.
+    ///
+    /// While hijacking, queries for the other address family than that of `redirect` get
+    /// an empty answer.
+    ///
+    /// By default, queries are not hijacked.
+    pub fn set_hijack(&mut self, redirect: Option<IpAddress>) {
+        self.redirect = redirect
+    }
+
+    /// Return the address queries are hijacked to, if any.
+    pub fn hijack(&self) -> Option<IpAddress> {
+        self.redirect
+    }
+
+    /// Set the names that are answered from the table even while hijacking.
+    ///
+    /// Names are compared without regard to case, and only match themselves, not their
+    /// subdomains.
+    pub fn set_allowlist(&mut self, names: &'a [&'a str]) {
+        self.allowlist = names
+    }
+
+    /// Answer the queries queued in `socket`, and return how many were answered.
+    ///
+    /// Datagrams that are not queries, or that are larger than 512 octets, are dropped.
+    /// Answers that don't fit in the transmit buffer of the socket are dropped too.
+    pub fn process(&self, socket: &mut super::udp::Socket) -> usize {
+        let mut query = [0; RESPONDER_MAX_QUERY_LEN];
+        let mut answered = 0;
+        while socket.can_recv() {
+            let (len, meta) = match socket.recv_slice(&mut query) {
+                Ok(received) => received,
+                Err(_) => {
+                    net_debug!("dns: dropping query too large to answer");
+                    continue;
+                }
+            };
+            if self.answer(socket, &query[..len], meta.endpoint).is_some() {
+                answered += 1;
+            }
+        }
+        answered
+    }
+
+    fn answer(
+        &self,
+        socket: &mut super::udp::Socket,
+        query: &[u8],
+        endpoint: wire::IpEndpoint,
+    ) -> Option<()> {
+        let packet = Packet::new_checked(query).ok()?;
+        if packet.flags().contains(Flags::RESPONSE)
+            || packet.opcode() != Opcode::Query
+            || packet.question_count() != 1
+        {
+            net_debug!(
+                "dns: dropping datagram from {} that is not a query",
+                endpoint
+            );
+            return None;
+        }
+        let (_, question) = match Question::parse(packet.payload()) {
+            Ok(parsed) => parsed,
+            Err(_) => {
+                net_debug!("dns: dropping malformed query from {}", endpoint);
+                return None;
+            }
+        };
+
+        let matches = |name: &str| Self::name_matches(&packet, question.name, name);
+        let hijacked = self.redirect.is_some() && !self.allowlist.iter().any(|name| matches(name));
+        let (answers, ttl, rcode) = if hijacked {
+            (self.redirect, 0, Rcode::NoError)
+        } else if self.records.iter().any(|(name, _)| matches(name)) {
+            (None, self.ttl, Rcode::NoError)
+        } else {
+            (None, 0, Rcode::NXDomain)
+        };
+        let addrs = answers
+            .into_iter()
+            .chain(
+                self.records
+                    .iter()
+                    .filter(|(name, _)| !hijacked && matches(name))
+                    .map(|(_, addr)| *addr),
+            )
+            .filter(|addr| Self::record_data(*addr).type_() == question.type_);
+
+        let record = |addr| Record {
+            // A pointer to the name of the question, right after the header.
+            name: &[0xc0, 0x0c],
+            ttl,
+            data: Self::record_data(addr),
+        };
+        let question_len = query.len() - packet.payload().len() + question.buffer_len();
+        let len = question_len
+            + addrs
+                .clone()
+                .map(|addr| record(addr).buffer_len())
+                .sum::<usize>();
+        let buffer = match socket.send(len, endpoint) {
+            Ok(buffer) => buffer,
+            Err(_) => {
+                net_debug!("dns: dropping answer to {}, transmit buffer full", endpoint);
+                return None;
+            }
+        };
+
+        let mut response = Packet::new_unchecked(&mut buffer[..]);
+        response.set_transaction_id(packet.transaction_id());
+        response.set_flags(
+            Flags::RESPONSE | Flags::AUTHORITATIVE | (packet.flags() & Flags::RECURSION_DESIRED),
+        );
+        response.set_opcode(Opcode::Query);
+        response.set_rcode(rcode);
+        response.set_question_count(1);
+        response.set_answer_record_count(addrs.clone().count() as u16);
+        response.set_authority_record_count(0);
+        response.set_additional_record_count(0);
+        question.emit(response.payload_mut());
+
+        let mut offset = question_len;
+        for addr in addrs {
+            let record = record(addr);
+            record.emit(&mut buffer[offset..]);
+            offset += record.buffer_len();
+        }
+        Some(())
+    }
+
+    /// Return whether the name at `bytes` in `packet` is the dotted name `expected`.
+    fn name_matches(packet: &Packet<&[u8]>, bytes: &[u8], expected: &str) -> bool {
+        let mut labels = packet.parse_name(bytes);
+        for part in expected.trim_end_matches('.').split('.') {
+            match labels.next() {
+                Some(Ok(label)) if label.eq_ignore_ascii_case(part.as_bytes()) => {}
+                _ => return false,
+            }
+        }
+        labels.next().is_none()
+    }
+
+    fn record_data(addr: IpAddress) -> RecordData<'static> {
+        match addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(addr) => RecordData::A(addr),
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => RecordData::Aaaa(addr),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "proto-ipv4", feature = "medium-ip"))]
+mod test {
+    use super::*;
+    use crate::phy::Medium;
+    use crate::tests::setup;
+    use crate::wire::{Ipv4Address, Ipv4Repr};
+
+    const SERVER_1: IpAddress = IpAddress::Ipv4(Ipv4Address([192, 168, 1, 53]));
+    const SERVER_2: IpAddress = IpAddress::Ipv4(Ipv4Address([192, 168, 1, 54]));
+    const ANSWER: Ipv4Address = Ipv4Address([192, 0, 2, 1]);
+
+    /// Dispatch the socket, and return the server and the UDP port and transaction ID a
+    /// query was sent with, if any.
+    fn send(socket: &mut Socket, cx: &mut Context) -> Option<(IpAddress, u16, u16)> {
+        let mut sent = None;
+        socket
+            .dispatch(cx, |_, (ip_repr, udp_repr, payload)| {
+                let packet = Packet::new_checked(payload).unwrap();
+                sent = Some((
+                    ip_repr.dst_addr(),
+                    udp_repr.src_port,
+                    packet.transaction_id(),
+                ));
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        sent
+    }
+
+    /// Feed the socket an answer to the query for `example.com` from `server`.
+    fn answer(socket: &mut Socket, cx: &mut Context, server: IpAddress, port: u16, txid: u16) {
+        let mut payload = vec![];
+        payload.extend_from_slice(&txid.to_be_bytes());
+        payload.extend_from_slice(&[0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0]);
+        payload.extend_from_slice(b"\x07example\x03com\x00\x00\x01\x00\x01");
+        payload.extend_from_slice(&[0xc0, 0x0c, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4]);
+        payload.extend_from_slice(ANSWER.as_bytes());
+
+        let server = match server {
+            IpAddress::Ipv4(server) => server,
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        };
+        let ip_repr = IpRepr::Ipv4(Ipv4Repr {
+            src_addr: server,
+            dst_addr: Ipv4Address([192, 168, 1, 1]),
+            next_header: IpProtocol::Udp,
+            payload_len: 8 + payload.len(),
+            hop_limit: 64,
+        });
+        let udp_repr = UdpRepr {
+            src_port: DNS_PORT,
+            dst_port: port,
+        };
+        assert!(socket.accepts(&ip_repr, &udp_repr));
+        socket.process(cx, &ip_repr, &udp_repr, &payload);
+    }
+
+    /// Poll the socket every 100ms until `until`, and return when and to which server
+    /// queries were sent, along with the port and transaction ID of the last one.
+    fn poll_until(
+        socket: &mut Socket,
+        cx: &mut Context,
+        from: Instant,
+        until: Instant,
+    ) -> (std::vec::Vec<(Instant, IpAddress)>, Option<(u16, u16)>) {
+        let mut sent = vec![];
+        let mut last = None;
+        let mut now = from;
+        while now < until {
+            cx.set_now(now);
+            if let Some((server, port, txid)) = send(socket, cx) {
+                sent.push((now, server));
+                last = Some((port, txid));
+            }
+            now += Duration::from_millis(100);
+        }
+        (sent, last)
+    }
+
+    fn socket() -> Socket<'static> {
+        let mut socket = Socket::new(&[SERVER_1, SERVER_2], vec![]);
+        socket.set_server_timeout(Duration::from_millis(2_000));
+        socket
+    }
+
+    #[test]
+    fn test_next_server() {
+        let (mut iface, _, _) = setup(Medium::Ip);
+        let cx = iface.context();
+        let mut socket = socket();
+        socket.set_query_timeout(Some(Duration::from_millis(5_000)));
+
+        let handle = socket.start_query(cx, "example.com", Type::A).unwrap();
+
+        // The first server never answers, and is given up on after the server timeout.
+        let (sent, last) = poll_until(
+            &mut socket,
+            cx,
+            Instant::from_millis(0),
+            Instant::from_millis(2_500),
+        );
+        assert_eq!(
+            sent,
+            [
+                (Instant::from_millis(0), SERVER_1),
+                (Instant::from_millis(1_000), SERVER_1),
+                (Instant::from_millis(2_000), SERVER_2),
+            ]
+        );
+        assert_eq!(
+            socket.get_query_result(handle),
+            Err(GetQueryResultError::Pending)
+        );
+
+        // The second one answers.
+        let (port, txid) = last.unwrap();
+        answer(&mut socket, cx, SERVER_2, port, txid);
+        assert_eq!(
+            socket.get_query_result(handle).as_deref(),
+            Ok(&[IpAddress::Ipv4(ANSWER)][..])
+        );
+
+        // Later queries go to the server that answered first.
+        socket.start_query(cx, "example.com", Type::A).unwrap();
+        assert_eq!(
+            send(&mut socket, cx).map(|(server, ..)| server),
+            Some(SERVER_2)
+        );
+    }
+
+    #[test]
+    fn test_query_timeout() {
+        let (mut iface, _, _) = setup(Medium::Ip);
+        let cx = iface.context();
+        let mut socket = socket();
+        socket.set_query_timeout(Some(Duration::from_millis(2_500)));
+
+        let handle = socket.start_query(cx, "example.com", Type::A).unwrap();
+        let (sent, _) = poll_until(
+            &mut socket,
+            cx,
+            Instant::from_millis(0),
+            Instant::from_millis(2_500),
+        );
+        assert_eq!(sent.len(), 3);
+        assert_eq!(
+            socket.get_query_result(handle),
+            Err(GetQueryResultError::Pending)
+        );
+
+        // The socket is polled again at the deadline, when the query fails, although the
+        // second server did not time out yet.
+        assert_eq!(
+            socket.poll_at(cx),
+            PollAt::Time(Instant::from_millis(2_500))
+        );
+        cx.set_now(Instant::from_millis(2_500));
+        assert_eq!(send(&mut socket, cx), None);
+        assert_eq!(
+            socket.get_query_result(handle),
+            Err(GetQueryResultError::Failed)
+        );
+    }
+
+    #[cfg(feature = "socket-udp")]
+    mod responder {
+        use super::*;
+        use crate::phy::PacketMeta;
+        use crate::socket::udp;
+
+        const CLIENT: Ipv4Address = Ipv4Address([192, 168, 1, 2]);
+        const PORTAL: IpAddress = IpAddress::Ipv4(Ipv4Address([192, 168, 1, 1]));
+        const RECORDS: &[(&str, IpAddress)] = &[
+            ("portal.example", PORTAL),
+            ("Example.com", IpAddress::Ipv4(ANSWER)),
+        ];
+
+        fn server() -> udp::Socket<'static> {
+            let mut socket = udp::Socket::new(
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1024]),
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1024]),
+            );
+            socket.bind(DNS_PORT).unwrap();
+            socket
+        }
+
+        /// Feed the socket a query for `name`, with the transaction ID 0x1234.
+        fn query(socket: &mut udp::Socket, cx: &mut Context, name: &[u8], type_: Type) {
+            let mut payload = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+            payload.extend_from_slice(name);
+            payload.extend_from_slice(&u16::from(type_).to_be_bytes());
+            payload.extend_from_slice(&[0, 1]);
+
+            let ip_repr = IpRepr::Ipv4(Ipv4Repr {
+                src_addr: CLIENT,
+                dst_addr: Ipv4Address([192, 168, 1, 1]),
+                next_header: IpProtocol::Udp,
+                payload_len: 8 + payload.len(),
+                hop_limit: 64,
+            });
+            let udp_repr = UdpRepr {
+                src_port: 49152,
+                dst_port: DNS_PORT,
+            };
+            assert!(socket.accepts(cx, &ip_repr, &udp_repr));
+            socket.process(cx, PacketMeta::default(), &ip_repr, &udp_repr, &payload);
+        }
+
+        /// Dispatch the socket, and return the rcode and the answers of the response, along
+        /// with their TTL.
+        fn response(
+            socket: &mut udp::Socket,
+            cx: &mut Context,
+        ) -> (Rcode, std::vec::Vec<(IpAddress, u32)>) {
+            let mut response = None;
+            socket
+                .dispatch(cx, |_, _, (ip_repr, udp_repr, payload)| {
+                    assert_eq!(ip_repr.dst_addr(), IpAddress::Ipv4(CLIENT));
+                    assert_eq!(udp_repr.dst_port, 49152);
+                    let packet = Packet::new_checked(payload).unwrap();
+                    assert_eq!(packet.transaction_id(), 0x1234);
+                    assert!(packet.flags().contains(Flags::RESPONSE));
+                    assert!(packet.flags().contains(Flags::RECURSION_DESIRED));
+                    assert_eq!(packet.question_count(), 1);
+                    let (mut records, _) = Question::parse(packet.payload()).unwrap();
+                    let mut answers = vec![];
+                    for _ in 0..packet.answer_record_count() {
+                        let (rest, record) = Record::parse(records).unwrap();
+                        assert_eq!(record.name, &[0xc0, 0x0c]);
+                        let addr = match record.data {
+                            RecordData::A(addr) => IpAddress::Ipv4(addr),
+                            #[cfg(feature = "proto-ipv6")]
+                            RecordData::Aaaa(addr) => IpAddress::Ipv6(addr),
+                            _ => unreachable!(),
+                        };
+                        answers.push((addr, record.ttl));
+                        records = rest;
+                    }
+                    response = Some((packet.rcode(), answers));
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+            response.expect("no response sent")
+        }
+
+        #[test]
+        fn test_answer_from_records() {
+            let (mut iface, _, _) = setup(Medium::Ip);
+            let cx = iface.context();
+            let mut socket = server();
+            let responder = Responder::new(RECORDS);
+
+            query(&mut socket, cx, b"\x07EXAMPLE\x03com\x00", Type::A);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(
+                response(&mut socket, cx),
+                (Rcode::NoError, vec![(IpAddress::Ipv4(ANSWER), 60)])
+            );
+
+            query(&mut socket, cx, b"\x07example\x03com\x00", Type::Aaaa);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(response(&mut socket, cx), (Rcode::NoError, vec![]));
+
+            query(&mut socket, cx, b"\x07example\x03org\x00", Type::A);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(response(&mut socket, cx), (Rcode::NXDomain, vec![]));
+        }
+
+        #[test]
+        fn test_hijack() {
+            let (mut iface, _, _) = setup(Medium::Ip);
+            let cx = iface.context();
+            let mut socket = server();
+            let mut responder = Responder::new(RECORDS);
+            responder.set_hijack(Some(PORTAL));
+            responder.set_allowlist(&["example.com"]);
+            assert_eq!(responder.hijack(), Some(PORTAL));
+
+            // Names that are not in the allowlist, even unknown ones, lead to the portal.
+            query(&mut socket, cx, b"\x07example\x03org\x00", Type::A);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(
+                response(&mut socket, cx),
+                (Rcode::NoError, vec![(PORTAL, 0)])
+            );
+
+            // Subdomains of allowlisted names are hijacked too.
+            query(&mut socket, cx, b"\x03www\x07example\x03com\x00", Type::A);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(
+                response(&mut socket, cx),
+                (Rcode::NoError, vec![(PORTAL, 0)])
+            );
+
+            // There is no redirect address of the other family.
+            query(&mut socket, cx, b"\x07example\x03org\x00", Type::Aaaa);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(response(&mut socket, cx), (Rcode::NoError, vec![]));
+
+            query(&mut socket, cx, b"\x07example\x03com\x00", Type::A);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(
+                response(&mut socket, cx),
+                (Rcode::NoError, vec![(IpAddress::Ipv4(ANSWER), 60)])
+            );
+
+            responder.set_hijack(None);
+            query(&mut socket, cx, b"\x07example\x03org\x00", Type::A);
+            assert_eq!(responder.process(&mut socket), 1);
+            assert_eq!(response(&mut socket, cx), (Rcode::NXDomain, vec![]));
+        }
+
+        #[test]
+        fn test_drop_non_query() {
+            let (mut iface, _, _) = setup(Medium::Ip);
+            let cx = iface.context();
+            let mut socket = server();
+            let responder = Responder::new(RECORDS);
+
+            // A truncated datagram.
+            query(&mut socket, cx, b"\x07example", Type::A);
+            assert_eq!(responder.process(&mut socket), 0);
+            assert!(!socket.can_recv());
+            assert!(socket
+                .dispatch(cx, |_, _, _| -> Result<(), ()> { panic!("response sent") })
+                .is_ok());
+        }
+    }
+}
@@ -13,7 +13,7 @@ use crate::wire::IcmpRepr;
 use crate::wire::{Icmpv4Packet, Icmpv4Repr, Ipv4Repr};
 #[cfg(feature = "proto-ipv6")]
 use crate::wire::{Icmpv6Packet, Icmpv6Repr, Ipv6Repr};
-use crate::wire::{IpAddress, IpListenEndpoint, IpProtocol, IpRepr};
+use crate::wire::{IpAddress, IpEndpoint, IpListenEndpoint, IpProtocol, IpRepr};
 use crate::wire::{UdpPacket, UdpRepr};
 
 /// Error returned by [`Socket::bind`]
@@ -36,6 +36,24 @@ impl core::fmt::Display for BindError {
 #[cfg(feature = "std")]
 impl std::error::Error for BindError {}
 
+/// Error returned by [`Socket::connect`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConnectError {
+    Unaddressable,
+}
+
+impl core::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ConnectError::Unaddressable => write!(f, "unaddressable"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConnectError {}
+
 /// Error returned by [`Socket::send`]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -120,6 +138,8 @@ pub struct Socket<'a> {
     tx_buffer: PacketBuffer<'a>,
     /// The endpoint this socket is communicating with
     endpoint: Endpoint,
+    /// The only remote address this socket exchanges packets with, if connected.
+    peer: Option<IpAddress>,
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
     #[cfg(feature = "async")]
@@ -135,6 +155,7 @@ impl<'a> Socket<'a> {
             rx_buffer,
             tx_buffer,
             endpoint: Default::default(),
+            peer: None,
             hop_limit: None,
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
@@ -280,6 +301,58 @@ impl<'a> Socket<'a> {
         Ok(())
     }
 
+    /// Connect the socket to the given remote address.
+    ///
+    /// A connected socket only accepts Echo Request/Reply messages sent by `peer`, and
+    /// ICMP error messages about packets sent to `peer`; everything else is dropped.
+    /// Packets can then be sent to `peer` with [send_connected](#method.send_connected)
+    /// without naming it every time. Connecting again replaces the previous peer.
+    ///
+    /// This function returns `Err(ConnectError::Unaddressable)` if `peer` is not a unicast
+    /// address.
+    pub fn connect<T: Into<IpAddress>>(&mut self, peer: T) -> Result<(), ConnectError> {
+        let peer = peer.into();
+        if !peer.is_unicast() {
+            return Err(ConnectError::Unaddressable);
+        }
+
+        self.peer = Some(peer);
+        Ok(())
+    }
+
+    /// Disconnect the socket, accepting packets from any remote address again.
+    pub fn disconnect(&mut self) {
+        self.peer = None;
+    }
+
+    /// Return the remote address the socket is connected to, if any.
+    ///
+    /// See also the [connect](#method.connect) method.
+    #[inline]
+    pub fn peer(&self) -> Option<IpAddress> {
+        self.peer
+    }
+
+    /// Return the local endpoint, or None if the socket is not bound to a UDP port.
+    ///
+    /// The address of a socket bound to a UDP port on all addresses is reported as the
+    /// unspecified address. Sockets bound to an ICMP identifier have no local endpoint.
+    #[inline]
+    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
+        match self.endpoint {
+            Endpoint::Udp(endpoint) => Some(endpoint.or_unspecified()),
+            Endpoint::Ident(_) | Endpoint::Unspecified => None,
+        }
+    }
+
+    /// Return the remote endpoint, or None if not connected.
+    ///
+    /// The port of the endpoint is always 0. See also the [peer](#method.peer) method.
+    #[inline]
+    pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
+        self.peer.map(|peer| IpEndpoint::new(peer, 0))
+    }
+
     /// Check whether the transmit buffer is full.
     #[inline]
     pub fn can_send(&self) -> bool {
@@ -378,6 +451,25 @@ impl<'a> Socket<'a> {
         Ok(())
     }
 
+    /// Enqueue a packet to be sent to the connected peer, and return a pointer to its
+    /// payload.
+    ///
+    /// This function returns `Err(SendError::Unaddressable)` if the socket is not connected.
+    /// See also [send](#method.send) and [connect](#method.connect).
+    pub fn send_connected(&mut self, size: usize) -> Result<&mut [u8], SendError> {
+        let peer = self.peer.ok_or(SendError::Unaddressable)?;
+        self.send(size, peer)
+    }
+
+    /// Enqueue a packet to be sent to the connected peer, and fill it from a slice.
+    ///
+    /// See also [send_connected](#method.send_connected).
+    pub fn send_slice_connected(&mut self, data: &[u8]) -> Result<(), SendError> {
+        let packet_buf = self.send_connected(data.len())?;
+        packet_buf.copy_from_slice(data);
+        Ok(())
+    }
+
     /// Dequeue a packet received from a remote endpoint, and return the `IpAddress` as well
     /// as a pointer to the payload.
     ///
@@ -431,7 +523,9 @@ impl<'a> Socket<'a> {
                 &Endpoint::Udp(endpoint),
                 &Icmpv4Repr::DstUnreachable { data, header, .. }
                 | &Icmpv4Repr::TimeExceeded { data, header, .. },
-            ) if endpoint.addr.is_none() || endpoint.addr == Some(ip_repr.dst_addr.into()) => {
+            ) if (endpoint.addr.is_none() || endpoint.addr == Some(ip_repr.dst_addr.into()))
+                && self.peer_matches(header.dst_addr.into()) =>
+            {
                 let packet = UdpPacket::new_unchecked(data);
                 match UdpRepr::parse(
                     &packet,
@@ -448,7 +542,7 @@ impl<'a> Socket<'a> {
             // port.
             (&Endpoint::Ident(bound_ident), &Icmpv4Repr::EchoRequest { ident, .. })
             | (&Endpoint::Ident(bound_ident), &Icmpv4Repr::EchoReply { ident, .. }) => {
-                ident == bound_ident
+                ident == bound_ident && self.peer_matches(ip_repr.src_addr.into())
             }
             _ => false,
         }
@@ -473,7 +567,9 @@ impl<'a> Socket<'a> {
                 &Endpoint::Udp(endpoint),
                 &Icmpv6Repr::DstUnreachable { data, header, .. }
                 | &Icmpv6Repr::TimeExceeded { data, header, .. },
-            ) if endpoint.addr.is_none() || endpoint.addr == Some(ip_repr.dst_addr.into()) => {
+            ) if (endpoint.addr.is_none() || endpoint.addr == Some(ip_repr.dst_addr.into()))
+                && self.peer_matches(header.dst_addr.into()) =>
+            {
                 let packet = UdpPacket::new_unchecked(data);
                 match UdpRepr::parse(
                     &packet,
@@ -491,11 +587,16 @@ impl<'a> Socket<'a> {
             (
                 &Endpoint::Ident(bound_ident),
                 &Icmpv6Repr::EchoRequest { ident, .. } | &Icmpv6Repr::EchoReply { ident, .. },
-            ) => ident == bound_ident,
+            ) => ident == bound_ident && self.peer_matches(ip_repr.src_addr.into()),
             _ => false,
         }
     }
 
+    /// Check whether the given remote address is the connected peer, if any.
+    fn peer_matches(&self, addr: IpAddress) -> bool {
+        self.peer.map_or(true, |peer| peer == addr)
+    }
+
     #[cfg(feature = "proto-ipv4")]
     pub(crate) fn process_v4(
         &mut self,
@@ -642,7 +743,7 @@ impl<'a> Socket<'a> {
         }
     }
 
-    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+    pub(crate) fn poll_at(&self, _cx: &Context) -> PollAt {
         if self.tx_buffer.is_empty() {
             PollAt::Ingress
         } else {
@@ -875,6 +976,84 @@ mod test_ipv4 {
         assert!(!socket.accepts_v4(cx, &REMOTE_IPV4_REPR, &icmp_repr));
     }
 
+    #[test]
+    fn test_endpoints() {
+        let mut s = socket(buffer(0), buffer(0));
+        assert_eq!(s.local_endpoint(), None);
+        assert_eq!(s.remote_endpoint(), None);
+
+        assert_eq!(s.bind(Endpoint::Udp(LOCAL_END_V4.into())), Ok(()));
+        assert_eq!(s.local_endpoint(), Some(LOCAL_END_V4));
+        assert_eq!(s.connect(REMOTE_IPV4), Ok(()));
+        assert_eq!(
+            s.remote_endpoint(),
+            Some(IpEndpoint::new(REMOTE_IPV4.into(), 0))
+        );
+
+        let mut s = socket(buffer(0), buffer(0));
+        assert_eq!(s.bind(Endpoint::Udp(LOCAL_END_V4.port.into())), Ok(()));
+        assert_eq!(
+            s.local_endpoint(),
+            Some(IpEndpoint::new(
+                Ipv4Address::UNSPECIFIED.into(),
+                LOCAL_END_V4.port
+            ))
+        );
+
+        let mut s = socket(buffer(0), buffer(0));
+        assert_eq!(s.bind(Endpoint::Ident(0x1234)), Ok(()));
+        assert_eq!(s.local_endpoint(), None);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_connected_peer(#[case] medium: Medium) {
+        let (mut iface, _, _) = setup(medium);
+        let cx = iface.context();
+
+        let mut socket = socket(buffer(1), buffer(1));
+        assert_eq!(socket.bind(Endpoint::Ident(0x1234)), Ok(()));
+        assert_eq!(
+            socket.send_slice_connected(b"abcdef"),
+            Err(SendError::Unaddressable)
+        );
+        assert_eq!(
+            socket.connect(Ipv4Address::BROADCAST),
+            Err(ConnectError::Unaddressable)
+        );
+        assert_eq!(socket.connect(REMOTE_IPV4), Ok(()));
+        assert_eq!(socket.peer(), Some(REMOTE_IPV4.into()));
+
+        // Replies from the connected peer are delivered.
+        assert!(socket.accepts_v4(cx, &REMOTE_IPV4_REPR, &ECHOV4_REPR));
+
+        // Replies from another address are dropped.
+        let other_ip_repr = Ipv4Repr {
+            src_addr: Ipv4Address([192, 168, 1, 3]),
+            ..REMOTE_IPV4_REPR
+        };
+        assert!(!socket.accepts_v4(cx, &other_ip_repr, &ECHOV4_REPR));
+
+        socket.disconnect();
+        assert!(socket.accepts_v4(cx, &other_ip_repr, &ECHOV4_REPR));
+        assert_eq!(socket.connect(REMOTE_IPV4), Ok(()));
+
+        // Sending goes to the connected peer.
+        let mut bytes = [0xff; 24];
+        let mut packet = Icmpv4Packet::new_unchecked(&mut bytes);
+        ECHOV4_REPR.emit(&mut packet, &ChecksumCapabilities::default());
+        assert_eq!(socket.send_slice_connected(&*packet.into_inner()), Ok(()));
+        assert_eq!(
+            socket.dispatch(cx, |_, (ip_repr, icmp_repr)| {
+                assert_eq!(ip_repr, LOCAL_IPV4_REPR);
+                assert_eq!(icmp_repr, ECHOV4_REPR.into());
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+    }
+
     #[rstest]
     #[case::ethernet(Medium::Ethernet)]
     #[cfg(feature = "medium-ethernet")]
@@ -1169,6 +1348,51 @@ mod test_ipv6 {
         assert!(!socket.accepts_v6(cx, &REMOTE_IPV6_REPR, &icmp_repr));
     }
 
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_connected_peer(#[case] medium: Medium) {
+        let (mut iface, _, _) = setup(medium);
+        let cx = iface.context();
+
+        let mut socket = socket(buffer(1), buffer(1));
+        assert_eq!(socket.bind(Endpoint::Ident(0x1234)), Ok(()));
+        assert_eq!(
+            socket.connect(Ipv6Address::LINK_LOCAL_ALL_NODES),
+            Err(ConnectError::Unaddressable)
+        );
+        assert_eq!(socket.connect(REMOTE_IPV6), Ok(()));
+
+        // Replies from the connected peer are delivered.
+        assert!(socket.accepts_v6(cx, &REMOTE_IPV6_REPR, &ECHOV6_REPR));
+
+        // Replies from another address are dropped.
+        let other_ip_repr = Ipv6Repr {
+            src_addr: Ipv6Address([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]),
+            ..REMOTE_IPV6_REPR
+        };
+        assert!(!socket.accepts_v6(cx, &other_ip_repr, &ECHOV6_REPR));
+
+        // Sending goes to the connected peer.
+        let mut bytes = vec![0xff; 24];
+        let mut packet = Icmpv6Packet::new_unchecked(&mut bytes);
+        ECHOV6_REPR.emit(
+            &LOCAL_IPV6.into(),
+            &REMOTE_IPV6.into(),
+            &mut packet,
+            &ChecksumCapabilities::default(),
+        );
+        assert_eq!(socket.send_slice_connected(&*packet.into_inner()), Ok(()));
+        assert_eq!(
+            socket.dispatch(cx, |_, (ip_repr, icmp_repr)| {
+                assert_eq!(ip_repr, LOCAL_IPV6_REPR.into());
+                assert_eq!(icmp_repr, ECHOV6_REPR.into());
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+    }
+
     #[rstest]
     #[case::ethernet(Medium::Ethernet)]
     #[cfg(feature = "medium-ethernet")]
@@ -13,6 +13,9 @@ size for a buffer, allocate it, and let the networking stack use it.
 
 use crate::iface::Context;
 use crate::time::Instant;
+use crate::wire::IpEndpoint;
+#[cfg(any(feature = "socket-tcp", feature = "socket-udp", feature = "socket-raw"))]
+use crate::wire::IpRepr;
 
 #[cfg(feature = "socket-dhcpv4")]
 pub mod dhcpv4;
@@ -24,6 +27,8 @@ pub mod icmp;
 pub mod raw;
 #[cfg(feature = "socket-tcp")]
 pub mod tcp;
+#[cfg(feature = "socket-tftp")]
+pub mod tftp;
 #[cfg(feature = "socket-udp")]
 pub mod udp;
 
@@ -45,6 +50,76 @@ pub(crate) enum PollAt {
     Ingress,
 }
 
+/// How a socket chooses the Flow Label of the IPv6 packets it sends.
+///
+/// The Flow Label lets routers keep the packets of a flow on the same path without
+/// parsing the transport header, see [RFC 6437]. It is not used for IPv4 packets, nor
+/// for packets compressed with 6LoWPAN.
+///
+/// [RFC 6437]: https://tools.ietf.org/html/rfc6437
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlowLabel {
+    /// Send packets with a zero Flow Label, which means they do not belong to any flow.
+    #[default]
+    Zero,
+    /// Compute the Flow Label from the addresses, protocol and ports of each packet.
+    ///
+    /// The label is a keyed hash, so it is the same for every packet of a connection,
+    /// but differs between connections and can't be predicted by other hosts. It is
+    /// never zero.
+    Auto,
+    /// Send packets with this Flow Label, which must fit in 20 bits.
+    Fixed(u32),
+}
+
+impl FlowLabel {
+    /// The largest valid Flow Label.
+    pub const MAX: u32 = 0xfffff;
+
+    /// Panic if a fixed label doesn't fit in 20 bits.
+    #[cfg(any(feature = "socket-tcp", feature = "socket-udp", feature = "socket-raw"))]
+    pub(crate) fn check(self) {
+        if let FlowLabel::Fixed(label) = self {
+            assert!(
+                label <= Self::MAX,
+                "flow label {label:#x} doesn't fit in 20 bits"
+            );
+        }
+    }
+
+    /// Return the Flow Label of a packet with the given IP header and transport ports.
+    #[cfg(any(feature = "socket-tcp", feature = "socket-udp", feature = "socket-raw"))]
+    pub(crate) fn value(
+        self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        src_port: u16,
+        dst_port: u16,
+    ) -> u32 {
+        match self {
+            FlowLabel::Zero => 0,
+            FlowLabel::Fixed(label) => label,
+            FlowLabel::Auto => {
+                let hash = crate::rand::keyed_hash(
+                    cx.flow_label_secret(),
+                    &[
+                        ip_repr.src_addr().as_bytes(),
+                        ip_repr.dst_addr().as_bytes(),
+                        &[ip_repr.next_header().into()],
+                        &src_port.to_be_bytes(),
+                        &dst_port.to_be_bytes(),
+                    ],
+                );
+                match hash as u32 & Self::MAX {
+                    0 => 1,
+                    label => label,
+                }
+            }
+        }
+    }
+}
+
 /// A network socket.
 ///
 /// This enumeration abstracts the various types of sockets based on the IP protocol.
@@ -69,10 +144,58 @@ pub enum Socket<'a> {
     Dhcpv4(dhcpv4::Socket<'a>),
     #[cfg(feature = "socket-dns")]
     Dns(dns::Socket<'a>),
+    #[cfg(feature = "socket-tftp")]
+    Tftp(tftp::Socket<'a>),
 }
 
 impl<'a> Socket<'a> {
-    pub(crate) fn poll_at(&self, cx: &mut Context) -> PollAt {
+    /// Return the local endpoint of the socket, if it has one.
+    ///
+    /// Only TCP, UDP and ICMP sockets can have a local endpoint; see their
+    /// `local_endpoint` methods for details.
+    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
+        match self {
+            #[cfg(feature = "socket-raw")]
+            Socket::Raw(s) => s.local_endpoint(),
+            #[cfg(feature = "socket-icmp")]
+            Socket::Icmp(s) => s.local_endpoint(),
+            #[cfg(feature = "socket-udp")]
+            Socket::Udp(s) => s.local_endpoint(),
+            #[cfg(feature = "socket-tcp")]
+            Socket::Tcp(s) => s.local_endpoint(),
+            #[cfg(feature = "socket-dhcpv4")]
+            Socket::Dhcpv4(_) => None,
+            #[cfg(feature = "socket-dns")]
+            Socket::Dns(_) => None,
+            #[cfg(feature = "socket-tftp")]
+            Socket::Tftp(_) => None,
+        }
+    }
+
+    /// Return the remote endpoint of the socket, if it has one.
+    ///
+    /// Only TCP and ICMP sockets can have a remote endpoint; see their
+    /// `remote_endpoint` methods for details.
+    pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
+        match self {
+            #[cfg(feature = "socket-raw")]
+            Socket::Raw(s) => s.remote_endpoint(),
+            #[cfg(feature = "socket-icmp")]
+            Socket::Icmp(s) => s.remote_endpoint(),
+            #[cfg(feature = "socket-udp")]
+            Socket::Udp(s) => s.remote_endpoint(),
+            #[cfg(feature = "socket-tcp")]
+            Socket::Tcp(s) => s.remote_endpoint(),
+            #[cfg(feature = "socket-dhcpv4")]
+            Socket::Dhcpv4(_) => None,
+            #[cfg(feature = "socket-dns")]
+            Socket::Dns(_) => None,
+            #[cfg(feature = "socket-tftp")]
+            Socket::Tftp(_) => None,
+        }
+    }
+
+    pub(crate) fn poll_at(&self, cx: &Context) -> PollAt {
         match self {
             #[cfg(feature = "socket-raw")]
             Socket::Raw(s) => s.poll_at(cx),
@@ -86,6 +209,8 @@ impl<'a> Socket<'a> {
             Socket::Dhcpv4(s) => s.poll_at(cx),
             #[cfg(feature = "socket-dns")]
             Socket::Dns(s) => s.poll_at(cx),
+            #[cfg(feature = "socket-tftp")]
+            Socket::Tftp(s) => s.poll_at(cx),
         }
     }
 }
@@ -139,3 +264,5 @@ from_socket!(tcp::Socket<'a>, Tcp);
 from_socket!(dhcpv4::Socket<'a>, Dhcpv4);
 #[cfg(feature = "socket-dns")]
 from_socket!(dns::Socket<'a>, Dns);
+#[cfg(feature = "socket-tftp")]
+from_socket!(tftp::Socket<'a>, Tftp);
@@ -3,12 +3,14 @@ use core::cmp::min;
 use core::task::Waker;
 
 use crate::iface::Context;
-use crate::socket::PollAt;
+#[cfg(feature = "proto-ipv4")]
+use crate::phy::ChecksumCapabilities;
 #[cfg(feature = "async")]
 use crate::socket::WakerRegistration;
+use crate::socket::{FlowLabel, PollAt};
 
 use crate::storage::Empty;
-use crate::wire::{IpProtocol, IpRepr, IpVersion};
+use crate::wire::{IpAddress, IpEndpoint, IpProtocol, IpRepr, IpVersion};
 #[cfg(feature = "proto-ipv4")]
 use crate::wire::{Ipv4Packet, Ipv4Repr};
 #[cfg(feature = "proto-ipv6")]
@@ -34,17 +36,40 @@ impl core::fmt::Display for BindError {
 #[cfg(feature = "std")]
 impl std::error::Error for BindError {}
 
+/// Error returned by [`Socket::connect`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConnectError {
+    Unaddressable,
+}
+
+impl core::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ConnectError::Unaddressable => write!(f, "unaddressable"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConnectError {}
+
 /// Error returned by [`Socket::send`]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SendError {
+    Unaddressable,
     BufferFull,
+    /// The packet doesn't fit in the MTU, and the socket is set to not fragment it.
+    TooLarge,
 }
 
 impl core::fmt::Display for SendError {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
+            SendError::Unaddressable => write!(f, "unaddressable"),
             SendError::BufferFull => write!(f, "buffer full"),
+            SendError::TooLarge => write!(f, "too large"),
         }
     }
 }
@@ -88,6 +113,20 @@ pub struct Socket<'a> {
     ip_protocol: IpProtocol,
     rx_buffer: PacketBuffer<'a>,
     tx_buffer: PacketBuffer<'a>,
+    /// Whether outgoing packets must not be fragmented.
+    dont_frag: bool,
+    /// The IP MTU of the interface the socket sends packets on, once known.
+    ip_mtu: Option<usize>,
+    /// How the Flow Label of outgoing IPv6 packets is chosen.
+    flow_label: FlowLabel,
+    /// Whether outgoing packets include their IP header.
+    hdrincl: bool,
+    /// Whether outgoing packets that include their IP header are sent byte for byte.
+    verbatim: bool,
+    /// The remote address packets without an IP header are sent to.
+    peer: Option<IpAddress>,
+    /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
+    hop_limit: Option<u8>,
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
     #[cfg(feature = "async")]
@@ -108,6 +147,13 @@ impl<'a> Socket<'a> {
             ip_protocol,
             rx_buffer,
             tx_buffer,
+            dont_frag: false,
+            ip_mtu: None,
+            flow_label: FlowLabel::Zero,
+            hdrincl: true,
+            verbatim: false,
+            peer: None,
+            hop_limit: None,
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
             #[cfg(feature = "async")]
@@ -162,6 +208,172 @@ impl<'a> Socket<'a> {
         self.ip_protocol
     }
 
+    /// Return whether outgoing packets must not be fragmented.
+    ///
+    /// See also the [set_dont_fragment](#method.set_dont_fragment) method.
+    pub fn dont_fragment(&self) -> bool {
+        self.dont_frag
+    }
+
+    /// Set whether outgoing packets must not be fragmented.
+    ///
+    /// When set, [send](#method.send) rejects packets that don't fit in the MTU of the
+    /// interface of `cx` with [SendError::TooLarge], instead of letting them be fragmented.
+    /// Those enqueued before the option was set are dropped when the interface is polled,
+    /// which is reported as [DropReason::TooLarge] if interface events are enabled.
+    ///
+    /// Either way, IPv4 packets that are not fragmented are sent with the DF (don't
+    /// fragment) flag, unless they are sent verbatim. By default, packets may be
+    /// fragmented.
+    ///
+    /// [DropReason::TooLarge]: crate::iface::DropReason::TooLarge
+    pub fn set_dont_fragment(&mut self, cx: &Context, dont_frag: bool) {
+        self.dont_frag = dont_frag;
+        self.ip_mtu = Some(cx.ip_mtu());
+    }
+
+    /// Return how the Flow Label of outgoing IPv6 packets is chosen.
+    ///
+    /// See also the [set_flow_label](#method.set_flow_label) method.
+    pub fn flow_label(&self) -> FlowLabel {
+        self.flow_label
+    }
+
+    /// Set how the Flow Label of outgoing IPv6 packets is chosen.
+    ///
+    /// With [FlowLabel::Auto], the label is derived from the addresses and the
+    /// protocol of each packet. This only applies to packets sent without their header
+    /// [included](#method.set_hdrincl); the others keep the Flow Label of their header.
+    ///
+    /// By default, the Flow Label is zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a [fixed](FlowLabel::Fixed) Flow Label doesn't fit in
+    /// 20 bits.
+    pub fn set_flow_label(&mut self, flow_label: FlowLabel) {
+        flow_label.check();
+        self.flow_label = flow_label
+    }
+
+    /// Return whether outgoing packets include their IP header.
+    ///
+    /// See also the [set_hdrincl](#method.set_hdrincl) method.
+    pub fn hdrincl(&self) -> bool {
+        self.hdrincl
+    }
+
+    /// Set whether outgoing packets include their IP header.
+    ///
+    /// When set, every packet enqueued with [send](#method.send) starts with a complete IP
+    /// header, whose version and protocol must match the socket. Otherwise, it only holds
+    /// the payload, and is sent to the address the socket is
+    /// [connected](#method.connect) to, in an IP header filled in by the interface, using
+    /// the [hop limit](#method.set_hop_limit) of the socket.
+    ///
+    /// Packets already enqueued are sent the way the socket is set up when they are
+    /// dispatched. By default, packets include their IP header.
+    pub fn set_hdrincl(&mut self, hdrincl: bool) {
+        self.hdrincl = hdrincl
+    }
+
+    /// Return whether outgoing packets that include their IP header are sent verbatim.
+    ///
+    /// See also the [set_verbatim](#method.set_verbatim) method.
+    pub fn verbatim(&self) -> bool {
+        self.verbatim
+    }
+
+    /// Set whether outgoing packets that include their IP header are sent verbatim.
+    ///
+    /// When set, the packet is sent byte for byte as it was enqueued, including the
+    /// checksum, identification and options of its IP header; such packets are never
+    /// fragmented, and are dropped if they don't fit in the MTU of the interface or if
+    /// the interface would have to compress their header, as 6LoWPAN does. Otherwise, the
+    /// IP header of the packet is parsed and emitted again, with its checksum computed by
+    /// the interface. The checksum of the payload is never computed by the interface.
+    ///
+    /// This only applies if packets [include their IP header](#method.set_hdrincl).
+    /// By default, the IP header is emitted again.
+    pub fn set_verbatim(&mut self, verbatim: bool) {
+        self.verbatim = verbatim
+    }
+
+    /// Return whether packets are sent byte for byte as they were enqueued.
+    pub(crate) fn sends_verbatim(&self) -> bool {
+        self.hdrincl && self.verbatim
+    }
+
+    /// Connect the socket to the given remote address.
+    ///
+    /// Packets that don't [include their IP header](#method.set_hdrincl) are sent to
+    /// `peer`. Connecting again replaces the previous peer.
+    ///
+    /// This function returns `Err(ConnectError::Unaddressable)` if `peer` is not a unicast
+    /// address of the IP version of the socket.
+    pub fn connect<T: Into<IpAddress>>(&mut self, peer: T) -> Result<(), ConnectError> {
+        let peer = peer.into();
+        if !peer.is_unicast() || peer.version() != self.ip_version {
+            return Err(ConnectError::Unaddressable);
+        }
+
+        self.peer = Some(peer);
+        Ok(())
+    }
+
+    /// Disconnect the socket.
+    pub fn disconnect(&mut self) {
+        self.peer = None;
+    }
+
+    /// Return the remote address the socket is connected to, if any.
+    ///
+    /// See also the [connect](#method.connect) method.
+    #[inline]
+    pub fn peer(&self) -> Option<IpAddress> {
+        self.peer
+    }
+
+    /// Return the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
+    ///
+    /// See also the [set_hop_limit](#method.set_hop_limit) method
+    pub fn hop_limit(&self) -> Option<u8> {
+        self.hop_limit
+    }
+
+    /// Set the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets
+    /// that don't [include their IP header](#method.set_hdrincl).
+    ///
+    /// A socket without an explicitly set hop limit value uses the default [IANA recommended]
+    /// value (64).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a hop limit value of 0 is given. See [RFC 1122 § 3.2.1.7].
+    ///
+    /// [IANA recommended]: https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml
+    /// [RFC 1122 § 3.2.1.7]: https://tools.ietf.org/html/rfc1122#section-3.2.1.7
+    pub fn set_hop_limit(&mut self, hop_limit: Option<u8>) {
+        // A host MUST NOT send a datagram with a hop limit value of 0
+        if let Some(0) = hop_limit {
+            panic!("the time-to-live value of a packet must not be zero")
+        }
+
+        self.hop_limit = hop_limit
+    }
+
+    /// Return the local endpoint. Raw sockets have no endpoints, so this is always None.
+    #[inline]
+    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
+        None
+    }
+
+    /// Return the remote endpoint. Raw sockets have no endpoints, so this is always None.
+    #[inline]
+    pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
+        None
+    }
+
     /// Check whether the transmit buffer is full.
     #[inline]
     pub fn can_send(&self) -> bool {
@@ -207,9 +419,17 @@ impl<'a> Socket<'a> {
     /// If the buffer is filled in a way that does not match the socket's
     /// IP version or protocol, the packet will be silently dropped.
     ///
-    /// **Note:** The IP header is parsed and re-serialized, and may not match
-    /// the header actually transmitted bit for bit.
+    /// This function returns `Err(SendError::Unaddressable)` if packets don't
+    /// [include their IP header](#method.set_hdrincl) and the socket is not connected,
+    /// and `Err(SendError::TooLarge)` if the packet doesn't fit in the MTU and the socket
+    /// is set to [not fragment](#method.set_dont_fragment) it.
+    ///
+    /// **Note:** Unless the socket is set to send packets [verbatim](#method.set_verbatim),
+    /// the IP header is parsed and re-serialized, and may not match the header actually
+    /// transmitted bit for bit.
     pub fn send(&mut self, size: usize) -> Result<&mut [u8], SendError> {
+        self.check_addressable()?;
+        self.check_size(size)?;
         let packet_buf = self
             .tx_buffer
             .enqueue(size, ())
@@ -232,6 +452,7 @@ impl<'a> Socket<'a> {
     where
         F: FnOnce(&mut [u8]) -> usize,
     {
+        self.check_addressable()?;
         let size = self
             .tx_buffer
             .enqueue_with_infallible(max_size, (), f)
@@ -247,6 +468,29 @@ impl<'a> Socket<'a> {
         Ok(size)
     }
 
+    fn check_addressable(&self) -> Result<(), SendError> {
+        if !self.hdrincl && self.peer.is_none() {
+            return Err(SendError::Unaddressable);
+        }
+        Ok(())
+    }
+
+    fn check_size(&self, size: usize) -> Result<(), SendError> {
+        if self.dont_frag || self.sends_verbatim() {
+            if let Some(ip_mtu) = self.ip_mtu {
+                let header_len = if self.hdrincl {
+                    0
+                } else {
+                    self.ip_version.header_len()
+                };
+                if header_len + size > ip_mtu {
+                    return Err(SendError::TooLarge);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Enqueue a packet to send, and fill it from a slice.
     ///
     /// See also [send](#method.send).
@@ -367,14 +611,45 @@ impl<'a> Socket<'a> {
         self.rx_waker.wake();
     }
 
+    /// Dispatch the next packet, passing its Flow Label, IP header and payload to `emit`.
+    ///
+    /// If the socket [sends verbatim](#method.sends_verbatim), the whole packet is passed instead
+    /// of its payload, and the IP header only describes it.
     pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
     where
-        F: FnOnce(&mut Context, (IpRepr, &[u8])) -> Result<(), E>,
+        F: FnOnce(&mut Context, u32, (IpRepr, &[u8])) -> Result<(), E>,
     {
         let ip_protocol = self.ip_protocol;
+        let flow_label = self.flow_label;
         let ip_version = self.ip_version;
+        let hdrincl = self.hdrincl;
+        let verbatim = self.sends_verbatim();
+        let peer = self.peer;
+        let hop_limit = self.hop_limit.unwrap_or(64);
+        self.ip_mtu = Some(cx.ip_mtu());
         let _checksum_caps = &cx.checksum_caps();
         let res = self.tx_buffer.dequeue_with(|&mut (), buffer| {
+            if !hdrincl {
+                let dst_addr = match peer {
+                    Some(addr) => addr,
+                    None => {
+                        net_trace!("raw: socket is not connected, dropping.");
+                        return Ok(());
+                    }
+                };
+                let src_addr = match cx.get_source_address(&dst_addr) {
+                    Some(addr) => addr,
+                    None => {
+                        net_trace!("raw: cannot find suitable source address, dropping.");
+                        return Ok(());
+                    }
+                };
+                let ip_repr = IpRepr::new(src_addr, dst_addr, ip_protocol, buffer.len(), hop_limit);
+                let label = flow_label.value(cx, &ip_repr, 0, 0);
+                net_trace!("raw:{}:{}: sending", ip_version, ip_protocol);
+                return emit(cx, label, (ip_repr, buffer));
+            }
+
             match IpVersion::of_packet(buffer) {
                 #[cfg(feature = "proto-ipv4")]
                 Ok(IpVersion::Ipv4) => {
@@ -389,6 +664,27 @@ impl<'a> Socket<'a> {
                         net_trace!("raw: sent packet with wrong ip protocol, dropping.");
                         return Ok(());
                     }
+                    if verbatim {
+                        let packet = Ipv4Packet::new_unchecked(&*packet.into_inner());
+                        let mut ipv4_repr =
+                            match Ipv4Repr::parse(&packet, &ChecksumCapabilities::ignored()) {
+                                Ok(x) => x,
+                                Err(_) => {
+                                    net_trace!("raw: malformed ipv4 packet in queue, dropping.");
+                                    return Ok(());
+                                }
+                            };
+                        // Account for the options in the payload, so that the header describes
+                        // the length of the whole packet.
+                        let len = packet.total_len() as usize;
+                        ipv4_repr.payload_len = len - ipv4_repr.buffer_len();
+                        net_trace!("raw:{}:{}: sending verbatim", ip_version, ip_protocol);
+                        return emit(
+                            cx,
+                            0,
+                            (IpRepr::Ipv4(ipv4_repr), &packet.into_inner()[..len]),
+                        );
+                    }
                     if _checksum_caps.ipv4.tx() {
                         packet.fill_checksum();
                     } else {
@@ -406,7 +702,7 @@ impl<'a> Socket<'a> {
                         }
                     };
                     net_trace!("raw:{}:{}: sending", ip_version, ip_protocol);
-                    emit(cx, (IpRepr::Ipv4(ipv4_repr), packet.payload()))
+                    emit(cx, 0, (IpRepr::Ipv4(ipv4_repr), packet.payload()))
                 }
                 #[cfg(feature = "proto-ipv6")]
                 Ok(IpVersion::Ipv6) => {
@@ -422,6 +718,7 @@ impl<'a> Socket<'a> {
                         return Ok(());
                     }
                     let packet = Ipv6Packet::new_unchecked(&*packet.into_inner());
+                    let label = packet.flow_label();
                     let ipv6_repr = match Ipv6Repr::parse(&packet) {
                         Ok(x) => x,
                         Err(_) => {
@@ -430,8 +727,18 @@ impl<'a> Socket<'a> {
                         }
                     };
 
+                    if verbatim {
+                        let len = ipv6_repr.buffer_len() + ipv6_repr.payload_len;
+                        net_trace!("raw:{}:{}: sending verbatim", ip_version, ip_protocol);
+                        return emit(
+                            cx,
+                            label,
+                            (IpRepr::Ipv6(ipv6_repr), &packet.into_inner()[..len]),
+                        );
+                    }
+
                     net_trace!("raw:{}:{}: sending", ip_version, ip_protocol);
-                    emit(cx, (IpRepr::Ipv6(ipv6_repr), packet.payload()))
+                    emit(cx, label, (IpRepr::Ipv6(ipv6_repr), packet.payload()))
                 }
                 Err(_) => {
                     net_trace!("raw: sent packet with invalid IP version, dropping.");
@@ -450,7 +757,7 @@ impl<'a> Socket<'a> {
         }
     }
 
-    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+    pub(crate) fn poll_at(&self, _cx: &Context) -> PollAt {
         if self.tx_buffer.is_empty() {
             PollAt::Ingress
         } else {
@@ -573,7 +880,7 @@ mod test {
 
                     assert!(socket.can_send());
                     assert_eq!(
-                        socket.dispatch(&mut cx, |_, _| unreachable!()),
+                        socket.dispatch(&mut cx, |_, _, _| unreachable!()),
                         Ok::<_, ()>(())
                     );
 
@@ -582,7 +889,7 @@ mod test {
                     assert!(!socket.can_send());
 
                     assert_eq!(
-                        socket.dispatch(&mut cx, |_, (ip_repr, ip_payload)| {
+                        socket.dispatch(&mut cx, |_, _, (ip_repr, ip_payload)| {
                             assert_eq!(ip_repr, $hdr);
                             assert_eq!(ip_payload, &$payload);
                             Err(())
@@ -592,7 +899,7 @@ mod test {
                     assert!(!socket.can_send());
 
                     assert_eq!(
-                        socket.dispatch(&mut cx, |_, (ip_repr, ip_payload)| {
+                        socket.dispatch(&mut cx, |_, _, (ip_repr, ip_payload)| {
                             assert_eq!(ip_repr, $hdr);
                             assert_eq!(ip_payload, &$payload);
                             Ok::<_, ()>(())
@@ -699,13 +1006,19 @@ mod test {
             Ipv4Packet::new_unchecked(&mut wrong_version).set_version(6);
 
             assert_eq!(socket.send_slice(&wrong_version[..]), Ok(()));
-            assert_eq!(socket.dispatch(cx, |_, _| unreachable!()), Ok::<_, ()>(()));
+            assert_eq!(
+                socket.dispatch(cx, |_, _, _| unreachable!()),
+                Ok::<_, ()>(())
+            );
 
             let mut wrong_protocol = ipv4_locals::PACKET_BYTES;
             Ipv4Packet::new_unchecked(&mut wrong_protocol).set_next_header(IpProtocol::Tcp);
 
             assert_eq!(socket.send_slice(&wrong_protocol[..]), Ok(()));
-            assert_eq!(socket.dispatch(cx, |_, _| unreachable!()), Ok::<_, ()>(()));
+            assert_eq!(
+                socket.dispatch(cx, |_, _, _| unreachable!()),
+                Ok::<_, ()>(())
+            );
         }
         #[cfg(feature = "proto-ipv6")]
         {
@@ -717,13 +1030,19 @@ mod test {
             Ipv6Packet::new_unchecked(&mut wrong_version[..]).set_version(4);
 
             assert_eq!(socket.send_slice(&wrong_version[..]), Ok(()));
-            assert_eq!(socket.dispatch(cx, |_, _| unreachable!()), Ok::<_, ()>(()));
+            assert_eq!(
+                socket.dispatch(cx, |_, _, _| unreachable!()),
+                Ok::<_, ()>(())
+            );
 
             let mut wrong_protocol = ipv6_locals::PACKET_BYTES;
             Ipv6Packet::new_unchecked(&mut wrong_protocol[..]).set_next_header(IpProtocol::Tcp);
 
             assert_eq!(socket.send_slice(&wrong_protocol[..]), Ok(()));
-            assert_eq!(socket.dispatch(cx, |_, _| unreachable!()), Ok::<_, ()>(()));
+            assert_eq!(
+                socket.dispatch(cx, |_, _, _| unreachable!()),
+                Ok::<_, ()>(())
+            );
         }
     }
 
@@ -818,6 +1137,40 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_endpoints() {
+        #[cfg(feature = "proto-ipv4")]
+        let socket = ipv4_locals::socket(buffer(0), buffer(0));
+        #[cfg(not(feature = "proto-ipv4"))]
+        let socket = ipv6_locals::socket(buffer(0), buffer(0));
+        assert_eq!(socket.local_endpoint(), None);
+        assert_eq!(socket.remote_endpoint(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_connect() {
+        use crate::wire::Ipv4Address;
+
+        let mut socket = ipv4_locals::socket(buffer(0), buffer(1));
+        socket.set_hdrincl(false);
+        assert_eq!(socket.send_slice(&[0; 4]), Err(SendError::Unaddressable));
+        assert_eq!(
+            socket.connect(Ipv4Address::BROADCAST),
+            Err(ConnectError::Unaddressable)
+        );
+        #[cfg(feature = "proto-ipv6")]
+        assert_eq!(
+            socket.connect(crate::wire::Ipv6Address::LOOPBACK),
+            Err(ConnectError::Unaddressable)
+        );
+        assert_eq!(socket.connect(Ipv4Address::new(10, 0, 0, 1)), Ok(()));
+        assert_eq!(socket.peer(), Some(Ipv4Address::new(10, 0, 0, 1).into()));
+        assert_eq!(socket.send_slice(&[0; 4]), Ok(()));
+        socket.disconnect();
+        assert_eq!(socket.peer(), None);
+    }
+
     #[test]
     fn test_doesnt_accept_wrong_proto() {
         #[cfg(feature = "proto-ipv4")]
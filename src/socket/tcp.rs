@@ -7,9 +7,10 @@ use core::fmt::Display;
 use core::task::Waker;
 use core::{cmp, fmt, mem};
 
+use crate::rand::keyed_hash;
 #[cfg(feature = "async")]
 use crate::socket::WakerRegistration;
-use crate::socket::{Context, PollAt};
+use crate::socket::{Context, FlowLabel, PollAt};
 use crate::storage::{Assembler, RingBuffer};
 use crate::time::{Duration, Instant};
 use crate::wire::{
@@ -99,6 +100,56 @@ impl Display for RecvError {
 #[cfg(feature = "std")]
 impl std::error::Error for RecvError {}
 
+/// Error returned by [`Socket::serialize_state`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SerializeError {
+    InvalidState,
+    BufferTooSmall,
+}
+
+impl Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerializeError::InvalidState => write!(f, "invalid state"),
+            SerializeError::BufferTooSmall => write!(f, "buffer too small"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SerializeError {}
+
+/// Error returned by [`Socket::restore_state`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RestoreError {
+    InvalidState,
+    Malformed,
+    BufferTooSmall,
+}
+
+impl Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RestoreError::InvalidState => write!(f, "invalid state"),
+            RestoreError::Malformed => write!(f, "malformed state"),
+            RestoreError::BufferTooSmall => write!(f, "buffer too small"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RestoreError {}
+
+/// Metadata for data returned by [`Socket::recv_with_meta`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecvMeta {
+    /// Whether the returned data ends at the end of a segment that had the PSH flag set.
+    pub push: bool,
+}
+
 /// A TCP socket ring buffer.
 pub type SocketBuffer<'a> = RingBuffer<'a, u8>;
 
@@ -247,6 +298,108 @@ impl RttEstimator {
     }
 }
 
+/// Congestion control algorithm used by a TCP socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CongestionControl {
+    /// Only the remote window limits the amount of data in flight.
+    #[default]
+    None,
+    /// Slow start, congestion avoidance and fast retransmit as described in RFC 5681,
+    /// with the congestion window grown by Appropriate Byte Counting (RFC 3465).
+    Reno,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CongestionController {
+    algorithm: CongestionControl,
+    /// Congestion window, or None if no segment has been acknowledged or lost yet,
+    /// in which case the initial window is used.
+    cwnd: Option<usize>,
+    /// Slow start threshold.
+    ssthresh: usize,
+    /// Octets acknowledged during congestion avoidance that haven't grown the window yet.
+    bytes_acked: usize,
+}
+
+impl CongestionController {
+    fn new(algorithm: CongestionControl) -> Self {
+        Self {
+            algorithm,
+            cwnd: None,
+            ssthresh: usize::MAX,
+            bytes_acked: 0,
+        }
+    }
+
+    /// Return the amount of octets that may be in flight.
+    fn window(&self, smss: usize) -> usize {
+        match self.algorithm {
+            CongestionControl::None => usize::MAX,
+            // RFC 5681 § 3.1: IW = min(4*SMSS, max(2*SMSS, 4380 bytes))
+            CongestionControl::Reno => self
+                .cwnd
+                .unwrap_or_else(|| (4 * smss).min((2 * smss).max(4380))),
+        }
+    }
+
+    fn on_ack(&mut self, ack_len: usize, smss: usize) {
+        if self.algorithm == CongestionControl::None {
+            return;
+        }
+
+        let cwnd = self.window(smss);
+        let cwnd = if cwnd < self.ssthresh {
+            // RFC 3465 § 2.2: during slow start, grow the window by the number of octets
+            // acknowledged, but by at most L = 2*SMSS per ACK.
+            cwnd + ack_len.min(2 * smss)
+        } else {
+            // RFC 3465 § 2.1: during congestion avoidance, grow the window by SMSS once
+            // a full window of octets has been acknowledged.
+            self.bytes_acked += ack_len;
+            if self.bytes_acked >= cwnd {
+                self.bytes_acked -= cwnd;
+                cwnd + smss
+            } else {
+                cwnd
+            }
+        };
+        tcp_trace!("congestion: ack={} cwnd={}", ack_len, cwnd);
+        self.cwnd = Some(cwnd);
+    }
+
+    fn on_loss(&mut self, in_flight: usize, smss: usize) -> usize {
+        // RFC 5681 § 3.1, equation (4)
+        self.ssthresh = (in_flight / 2).max(2 * smss);
+        self.bytes_acked = 0;
+        self.ssthresh
+    }
+
+    fn on_retransmit(&mut self, in_flight: usize, smss: usize) {
+        if self.algorithm == CongestionControl::None {
+            return;
+        }
+
+        self.on_loss(in_flight, smss);
+        self.cwnd = Some(smss);
+        tcp_trace!(
+            "congestion: retransmit ssthresh={} cwnd={}",
+            self.ssthresh,
+            smss
+        );
+    }
+
+    fn on_fast_retransmit(&mut self, in_flight: usize, smss: usize) {
+        if self.algorithm == CongestionControl::None {
+            return;
+        }
+
+        let ssthresh = self.on_loss(in_flight, smss);
+        self.cwnd = Some(ssthresh);
+        tcp_trace!("congestion: fast retransmit ssthresh={}", ssthresh);
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 enum Timer {
@@ -264,7 +417,8 @@ enum Timer {
 }
 
 const ACK_DELAY_DEFAULT: Duration = Duration::from_millis(10);
-const CLOSE_DELAY: Duration = Duration::from_millis(10_000);
+const DEFAULT_MSL: Duration = Duration::from_millis(5_000);
+const MIN_MSL: Duration = Duration::from_millis(1_000);
 
 impl Timer {
     fn new() -> Timer {
@@ -356,9 +510,9 @@ impl Timer {
         *self = Timer::FastRetransmit
     }
 
-    fn set_for_close(&mut self, timestamp: Instant) {
+    fn set_for_close(&mut self, timestamp: Instant, msl: Duration) {
         *self = Timer::Close {
-            expires_at: timestamp + CLOSE_DELAY,
+            expires_at: timestamp + msl * 2,
         }
     }
 
@@ -401,6 +555,7 @@ pub struct Socket<'a> {
     state: State,
     timer: Timer,
     rtte: RttEstimator,
+    congestion: CongestionController,
     assembler: Assembler,
     rx_buffer: SocketBuffer<'a>,
     rx_fin_received: bool,
@@ -409,8 +564,20 @@ pub struct Socket<'a> {
     timeout: Option<Duration>,
     /// Interval at which keep-alive packets will be sent.
     keep_alive: Option<Duration>,
+    /// Number of retransmissions after which, if no progress is made, the connection is aborted.
+    max_retransmits: Option<u8>,
+    /// Upper bound on the retransmission timeout.
+    max_rto: Duration,
+    /// Number of retransmissions since the remote endpoint last acknowledged new data.
+    retransmit_count: u8,
+    /// Maximum segment lifetime. The TIME-WAIT state lasts for twice this duration.
+    msl: Duration,
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+    /// Whether outgoing IPv4 packets are sent with the DF flag.
+    dont_frag: bool,
+    /// How the Flow Label of outgoing IPv6 packets is chosen.
+    flow_label: FlowLabel,
     /// Address passed to listen(). Listen address is set when listen() is called and
     /// used every time the socket is reset back to the LISTEN state.
     listen_endpoint: IpListenEndpoint,
@@ -464,6 +631,39 @@ pub struct Socket<'a> {
     /// Nagle's Algorithm enabled.
     nagle: bool,
 
+    /// Whether the receive window is kept from moving past its right edge.
+    recv_paused: bool,
+    /// Upper bound on the advertised receive window, in octets.
+    window_clamp: Option<usize>,
+
+    /// Free space in the transmit buffer needed for the socket to be ready to send.
+    send_lowat: usize,
+    /// Data in the receive buffer needed for the socket to be ready to receive.
+    recv_lowat: usize,
+
+    /// Whether partial segments are held back until flushed.
+    cork: bool,
+    /// Octets before this sequence number are sent even when corked.
+    cork_flush_seq: Option<TcpSeqNumber>,
+
+    /// Whether data segments are spread over the round-trip time.
+    pacing: bool,
+    /// When pacing, no data segment is sent before this instant.
+    pacing_next_at: Instant,
+
+    /// Maximum number of out-of-order octets to buffer. If None, only the receive
+    /// window limits it.
+    out_of_order_limit: Option<usize>,
+
+    /// The sequence number right after the last received segment with the PSH flag set,
+    /// if that data hasn't been dequeued yet.
+    rx_push_seq: Option<TcpSeqNumber>,
+
+    /// Whether SYNs received while listening are answered with a SYN cookie.
+    syn_cookies: bool,
+    /// Key for the SYN cookie hash, chosen when the first cookie is sent.
+    syn_cookie_secret: Option<u64>,
+
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
     #[cfg(feature = "async")]
@@ -472,6 +672,96 @@ pub struct Socket<'a> {
 
 const DEFAULT_MSS: usize = 536;
 
+/// Version of the format produced by [`Socket::serialize_state`].
+const STATE_FORMAT_VERSION: u8 = 1;
+/// Length of the serialized state without the addresses and buffered data.
+const STATE_FIXED_LEN: usize = 3 + 2 * 2 + 3 * 4 + 2 + 4 + 2 + 2 + 1 + 3 * 4 + 2 * 4;
+
+/// Writes the serialized state of a socket into a buffer that is known to be large enough.
+struct StateWriter<'b> {
+    buffer: &'b mut [u8],
+    pos: usize,
+}
+
+impl StateWriter<'_> {
+    fn put(&mut self, data: &[u8]) {
+        self.buffer[self.pos..self.pos + data.len()].copy_from_slice(data);
+        self.pos += data.len();
+    }
+
+    fn put_u8(&mut self, value: u8) {
+        self.put(&[value])
+    }
+
+    fn put_u16(&mut self, value: u16) {
+        self.put(&value.to_be_bytes())
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.put(&value.to_be_bytes())
+    }
+
+    /// Write a length that fits in 32 bits, saturating otherwise.
+    fn put_len(&mut self, value: usize) {
+        self.put_u32(u32::try_from(value).unwrap_or(u32::MAX))
+    }
+}
+
+/// Reads the serialized state of a socket.
+struct StateReader<'b> {
+    buffer: &'b [u8],
+}
+
+impl<'b> StateReader<'b> {
+    fn take(&mut self, len: usize) -> Result<&'b [u8], RestoreError> {
+        if self.buffer.len() < len {
+            return Err(RestoreError::Malformed);
+        }
+        let (data, rest) = self.buffer.split_at(len);
+        self.buffer = rest;
+        Ok(data)
+    }
+
+    fn take_u8(&mut self) -> Result<u8, RestoreError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take_u16(&mut self) -> Result<u16, RestoreError> {
+        let data = self.take(2)?;
+        Ok(u16::from_be_bytes([data[0], data[1]]))
+    }
+
+    fn take_u32(&mut self) -> Result<u32, RestoreError> {
+        let data = self.take(4)?;
+        Ok(u32::from_be_bytes([data[0], data[1], data[2], data[3]]))
+    }
+
+    /// Read an address of the given family, 4 for IPv4 or 6 for IPv6.
+    fn take_addr(&mut self, family: u8) -> Result<IpAddress, RestoreError> {
+        match family {
+            #[cfg(feature = "proto-ipv4")]
+            4 => Ok(crate::wire::Ipv4Address::from_bytes(self.take(4)?).into()),
+            #[cfg(feature = "proto-ipv6")]
+            6 => Ok(crate::wire::Ipv6Address::from_bytes(self.take(16)?).into()),
+            _ => Err(RestoreError::Malformed),
+        }
+    }
+
+    /// Read a length written by [`StateWriter::put_len`].
+    fn take_len(&mut self) -> Result<usize, RestoreError> {
+        match self.take_u32()? {
+            u32::MAX => Ok(usize::MAX),
+            value => Ok(value as usize),
+        }
+    }
+}
+
+/// The MSS values a SYN cookie can encode.
+const SYN_COOKIE_MSS: [usize; 8] = [64, 536, 1200, 1220, 1300, 1380, 1440, 1460];
+/// How often the time counter of SYN cookies is incremented. A cookie is valid for one to
+/// two periods.
+const SYN_COOKIE_PERIOD: Duration = Duration::from_secs(64);
+
 impl<'a> Socket<'a> {
     #[allow(unused_comparisons)] // small usize platforms always pass rx_capacity check
     /// Create a socket using the given buffers.
@@ -495,13 +785,20 @@ impl<'a> Socket<'a> {
             state: State::Closed,
             timer: Timer::new(),
             rtte: RttEstimator::default(),
+            congestion: CongestionController::new(CongestionControl::None),
             assembler: Assembler::new(),
             tx_buffer,
             rx_buffer,
             rx_fin_received: false,
             timeout: None,
             keep_alive: None,
+            max_retransmits: None,
+            max_rto: Duration::from_millis(RTTE_MAX_RTO as u64),
+            retransmit_count: 0,
+            msl: DEFAULT_MSL,
             hop_limit: None,
+            dont_frag: true,
+            flow_label: FlowLabel::Zero,
             listen_endpoint: IpListenEndpoint::default(),
             tuple: None,
             local_seq_no: TcpSeqNumber::default(),
@@ -522,6 +819,18 @@ impl<'a> Socket<'a> {
             ack_delay_timer: AckDelayTimer::Idle,
             challenge_ack_timer: Instant::from_secs(0),
             nagle: true,
+            recv_paused: false,
+            window_clamp: None,
+            send_lowat: 1,
+            recv_lowat: 1,
+            cork: false,
+            cork_flush_seq: None,
+            pacing: false,
+            pacing_next_at: Instant::ZERO,
+            rx_push_seq: None,
+            out_of_order_limit: None,
+            syn_cookies: false,
+            syn_cookie_secret: None,
 
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
@@ -572,6 +881,27 @@ impl<'a> Socket<'a> {
         self.timeout
     }
 
+    /// Return the maximum number of retransmissions.
+    ///
+    /// See also the [set_max_retransmits](#method.set_max_retransmits) method.
+    pub fn max_retransmits(&self) -> Option<u8> {
+        self.max_retransmits
+    }
+
+    /// Return the maximum retransmission timeout.
+    ///
+    /// See also the [set_max_rto](#method.set_max_rto) method.
+    pub fn max_rto(&self) -> Duration {
+        self.max_rto
+    }
+
+    /// Return the maximum segment lifetime.
+    ///
+    /// See also the [set_msl](#method.set_msl) method.
+    pub fn msl(&self) -> Duration {
+        self.msl
+    }
+
     /// Return the ACK delay duration.
     ///
     /// See also the [set_ack_delay](#method.set_ack_delay) method.
@@ -586,6 +916,27 @@ impl<'a> Socket<'a> {
         self.nagle
     }
 
+    /// Return whether reception is paused.
+    ///
+    /// See also the [set_recv_paused](#method.set_recv_paused) method.
+    pub fn recv_paused(&self) -> bool {
+        self.recv_paused
+    }
+
+    /// Return whether the socket is corked.
+    ///
+    /// See also the [set_cork](#method.set_cork) method.
+    pub fn cork(&self) -> bool {
+        self.cork
+    }
+
+    /// Return whether SYN cookies are enabled.
+    ///
+    /// See also the [set_syn_cookies](#method.set_syn_cookies) method.
+    pub fn syn_cookies(&self) -> bool {
+        self.syn_cookies
+    }
+
     /// Return the current window field value, including scaling according to RFC 1323.
     ///
     /// Used in internal calculations as well as packet generation.
@@ -593,11 +944,41 @@ impl<'a> Socket<'a> {
     #[inline]
     fn scaled_window(&self) -> u16 {
         cmp::min(
-            self.rx_buffer.window() >> self.remote_win_shift as usize,
+            self.rx_window() >> self.remote_win_shift as usize,
             (1 << 16) - 1,
         ) as u16
     }
 
+    /// Return the receive window, in octets, before scaling.
+    ///
+    /// While reception is paused, the right edge of the window is kept where it was last
+    /// advertised, so the window closes as data arrives instead of opening as it is dequeued.
+    fn rx_window(&self) -> usize {
+        let window = match self.window_clamp {
+            Some(clamp) => self.rx_buffer.window().min(clamp),
+            None => self.rx_buffer.window(),
+        };
+        if !self.recv_paused {
+            return window;
+        }
+        let remote_last_ack = match self.remote_last_ack {
+            Some(remote_last_ack) => remote_last_ack,
+            None => return 0,
+        };
+        // The window is not scaled in SYNs.
+        let win_shift = match self.state {
+            State::SynSent | State::SynReceived => 0,
+            _ => self.remote_win_shift,
+        };
+        let right_edge = remote_last_ack + ((self.remote_last_win as usize) << win_shift);
+        let ack_number = self.remote_seq_no + self.rx_buffer.len();
+        if right_edge > ack_number {
+            cmp::min(window, right_edge - ack_number)
+        } else {
+            0
+        }
+    }
+
     /// Set the timeout duration.
     ///
     /// A socket with a timeout duration set will abort the connection if either of the following
@@ -613,6 +994,42 @@ impl<'a> Socket<'a> {
         self.timeout = duration
     }
 
+    /// Set the maximum number of retransmissions.
+    ///
+    /// A socket with a retransmission limit set will abort the connection if the retransmission
+    /// timer expires after that many consecutive retransmissions without the remote endpoint
+    /// acknowledging any new data.
+    ///
+    /// By default, there is no limit, and only the [timeout](#method.set_timeout) can abort
+    /// the connection.
+    pub fn set_max_retransmits(&mut self, count: Option<u8>) {
+        self.max_retransmits = count
+    }
+
+    /// Set the maximum retransmission timeout.
+    ///
+    /// The retransmission timeout grows as retransmissions happen without acknowledgement,
+    /// but never beyond this value.
+    ///
+    /// By default, the maximum retransmission timeout is 10s. Durations shorter than the
+    /// minimum retransmission timeout of 10ms are raised to it, so that a socket never
+    /// retransmits in a tight loop.
+    pub fn set_max_rto(&mut self, duration: Duration) {
+        self.max_rto = duration.max(Duration::from_millis(RTTE_MIN_RTO as u64))
+    }
+
+    /// Set the maximum segment lifetime (MSL).
+    ///
+    /// A socket stays in the TIME-WAIT state for twice the MSL after closing the connection,
+    /// to make sure that no delayed segments of the connection are still in flight. A shorter
+    /// MSL lets the socket be reused sooner, but is only safe on networks that are known to not
+    /// delay segments for that long.
+    ///
+    /// By default, the MSL is 5s. Values below 1s are raised to 1s.
+    pub fn set_msl(&mut self, duration: Duration) {
+        self.msl = duration.max(MIN_MSL)
+    }
+
     /// Set the ACK delay duration.
     ///
     /// By default, the ACK delay is set to 10ms.
@@ -636,6 +1053,214 @@ impl<'a> Socket<'a> {
         self.nagle = enabled
     }
 
+    /// Pause or resume reception.
+    ///
+    /// While reception is paused, the advertised receive window does not reopen when data
+    /// is dequeued from the receive buffer. The window already offered to the remote endpoint
+    /// is never withdrawn; it closes as the remote endpoint fills it, until the remote
+    /// endpoint has to stop sending. Resuming reception reopens the window to the free space
+    /// in the receive buffer and sends a window update.
+    ///
+    /// This lets the application apply backpressure without having to stop dequeuing data.
+    /// By default, reception is not paused.
+    pub fn set_recv_paused(&mut self, paused: bool) {
+        self.recv_paused = paused
+    }
+
+    /// Return the window clamp.
+    ///
+    /// See also the [set_window_clamp](#method.set_window_clamp) method.
+    pub fn window_clamp(&self) -> Option<usize> {
+        self.window_clamp
+    }
+
+    /// Set the window clamp, an upper bound on the advertised receive window, in octets.
+    ///
+    /// Similar to Linux's TCP_WINDOW_CLAMP option. By default, there is no clamp, and the
+    /// whole free space in the receive buffer is advertised.
+    ///
+    /// The window scaling factor offered to the remote endpoint is the smallest one able
+    /// to advertise the clamped window, so the clamp should be set before calling
+    /// [connect](#method.connect) or [listen](#method.listen). A clamp set later still
+    /// bounds the window, but doesn't change the scaling factor that was negotiated.
+    pub fn set_window_clamp(&mut self, clamp: Option<usize>) {
+        self.window_clamp = clamp;
+        if matches!(self.state, State::Closed | State::Listen) {
+            self.remote_win_shift = self.rx_win_shift();
+        }
+    }
+
+    /// Return the send low-water mark.
+    ///
+    /// See also the [set_send_lowat](#method.set_send_lowat) method.
+    pub fn send_lowat(&self) -> usize {
+        self.send_lowat
+    }
+
+    /// Set the send low-water mark.
+    ///
+    /// The socket is only reported as ready to send, by [can_send](#method.can_send) and by
+    /// waking the send waker, once at least this many octets are free in the transmit
+    /// buffer. Marks larger than the transmit buffer are treated as the size of the buffer,
+    /// and a mark of 0 is treated as 1.
+    ///
+    /// This only affects readiness; [send](#method.send) accepts data whenever there is room.
+    /// By default, the send low-water mark is 1 octet.
+    pub fn set_send_lowat(&mut self, octets: usize) {
+        self.send_lowat = octets.max(1)
+    }
+
+    /// Return the receive low-water mark.
+    ///
+    /// See also the [set_recv_lowat](#method.set_recv_lowat) method.
+    pub fn recv_lowat(&self) -> usize {
+        self.recv_lowat
+    }
+
+    /// Set the receive low-water mark.
+    ///
+    /// The socket is only reported as ready to receive, by [can_recv](#method.can_recv) and
+    /// by waking the receive waker, once at least this many octets are queued in the receive
+    /// buffer, or once the remote endpoint has closed the connection. Marks larger than the
+    /// receive buffer are treated as the size of the buffer, and a mark of 0 is treated as 1.
+    ///
+    /// This only affects readiness; [recv](#method.recv) returns any queued data.
+    /// By default, the receive low-water mark is 1 octet.
+    pub fn set_recv_lowat(&mut self, octets: usize) {
+        self.recv_lowat = octets.max(1)
+    }
+
+    /// Return the congestion control algorithm.
+    ///
+    /// See also the [set_congestion_control](#method.set_congestion_control) method.
+    pub fn congestion_control(&self) -> CongestionControl {
+        self.congestion.algorithm
+    }
+
+    /// Set the congestion control algorithm.
+    ///
+    /// By default, no congestion control is done, and only the remote window limits how
+    /// much data is in flight. Changing the algorithm restarts congestion control from
+    /// the initial window.
+    pub fn set_congestion_control(&mut self, algorithm: CongestionControl) {
+        if algorithm != self.congestion.algorithm {
+            self.congestion = CongestionController::new(algorithm);
+        }
+    }
+
+    /// Enable or disable SYN cookies. By default, they are disabled.
+    ///
+    /// Normally a listening socket moves to the SYN-RECEIVED state when it receives a SYN,
+    /// and cannot accept another connection until the handshake completes or times out.
+    /// A flood of SYNs that never complete can keep it occupied indefinitely.
+    ///
+    /// When enabled, a listening socket answers every SYN with a SYN|ACK whose sequence
+    /// number encodes the connection instead, as described in [RFC 4987 § 3.6], and stays
+    /// in the LISTEN state. The cookie encodes the MSS of the remote end, rounded down to
+    /// one of a few common values, and a time counter, so it is only valid for about two
+    /// minutes. An ACK that returns a valid cookie moves the socket directly to the
+    /// ESTABLISHED state.
+    ///
+    /// Connections established this way cannot use window scaling or selective
+    /// acknowledgements, since those options are not remembered.
+    ///
+    /// [RFC 4987 § 3.6]: https://tools.ietf.org/html/rfc4987#section-3.6
+    pub fn set_syn_cookies(&mut self, enabled: bool) {
+        self.syn_cookies = enabled
+    }
+
+    /// Enable or disable corking.
+    ///
+    /// Similar to Linux's TCP_CORK flag. By default, it is disabled.
+    ///
+    /// When enabled, only full-sized segments are transmitted, even if the remote window
+    /// would allow sending a smaller one. This allows e.g. the headers and the body of a
+    /// message written separately to be coalesced into as few segments as possible.
+    /// Data queued while corked is released by calling [flush](#method.flush), by
+    /// disabling corking, or by closing the socket.
+    pub fn set_cork(&mut self, enabled: bool) {
+        self.cork = enabled;
+        if !enabled {
+            self.cork_flush_seq = None;
+        }
+    }
+
+    /// Transmit all data currently in the transmit buffer, even if the socket is corked.
+    ///
+    /// Data enqueued after this call is held back again until the next flush.
+    /// Nagle's Algorithm and the remote window still apply.
+    pub fn flush(&mut self) {
+        if self.cork {
+            self.cork_flush_seq = Some(self.local_seq_no + self.tx_buffer.len());
+        }
+    }
+
+    /// Return whether pacing is enabled.
+    ///
+    /// See also the [set_pacing](#method.set_pacing) method.
+    pub fn pacing(&self) -> bool {
+        self.pacing
+    }
+
+    /// Enable or disable pacing. By default, it is disabled.
+    ///
+    /// Without pacing, the socket sends as many segments as the window allows back to back,
+    /// which can overflow small queues on the path. When enabled, data segments are spread
+    /// evenly over the smoothed round-trip time instead, so that a window's worth of data
+    /// takes about one round trip to send: after a segment of `n` octets, the next one is
+    /// held back for `rtt * n / window`, where the window is the smaller of the congestion
+    /// window and the remote window. [Interface::poll_at] returns the instant the next
+    /// segment is due.
+    ///
+    /// [Interface::poll_at]: crate::iface::Interface::poll_at
+    pub fn set_pacing(&mut self, enabled: bool) {
+        self.pacing = enabled;
+        if !enabled {
+            self.pacing_next_at = Instant::ZERO;
+        }
+    }
+
+    /// Return whether pacing holds back data segments at `timestamp`.
+    fn paced(&self, timestamp: Instant) -> bool {
+        self.pacing && timestamp < self.pacing_next_at
+    }
+
+    /// Return the delay after sending a data segment of `len` octets when pacing.
+    fn pacing_delay(&self, len: usize) -> Duration {
+        let window = self.send_win_len();
+        if window == 0 {
+            return Duration::ZERO;
+        }
+        let rtt_us = self.rtte.rtt as u64 * 1000;
+        Duration::from_micros(rtt_us.saturating_mul(len as u64) / window as u64)
+    }
+
+    /// Return the number of out-of-order octets currently buffered.
+    ///
+    /// These octets have been received, but cannot be read until the gap
+    /// in front of them is filled.
+    pub fn out_of_order_len(&self) -> usize {
+        self.assembler.data_len()
+    }
+
+    /// Return the maximum number of out-of-order octets that will be buffered.
+    ///
+    /// See also the [set_out_of_order_limit](#method.set_out_of_order_limit) method.
+    pub fn out_of_order_limit(&self) -> Option<usize> {
+        self.out_of_order_limit
+    }
+
+    /// Set the maximum number of out-of-order octets that will be buffered.
+    ///
+    /// Segments that would grow the out-of-order data beyond this limit are dropped,
+    /// and will have to be retransmitted by the remote endpoint. Segments that can be
+    /// delivered in order are always accepted.
+    ///
+    /// By default, there is no limit other than the receive window.
+    pub fn set_out_of_order_limit(&mut self, limit: Option<usize>) {
+        self.out_of_order_limit = limit
+    }
+
     /// Return the keep-alive interval.
     ///
     /// See also the [set_keep_alive](#method.set_keep_alive) method.
@@ -691,6 +1316,48 @@ impl<'a> Socket<'a> {
         self.hop_limit = hop_limit
     }
 
+    /// Return whether outgoing packets must not be fragmented.
+    ///
+    /// See also the [set_dont_fragment](#method.set_dont_fragment) method.
+    pub fn dont_fragment(&self) -> bool {
+        self.dont_frag
+    }
+
+    /// Set whether outgoing packets must not be fragmented.
+    ///
+    /// Segments are never larger than the MTU of the interface, so they are never
+    /// fragmented locally. This option sets the DF (don't fragment) flag of outgoing IPv4
+    /// packets, which stops routers from fragmenting segments that don't fit in the MTU of
+    /// a link, as needed for path MTU discovery. When cleared, routers may fragment them.
+    ///
+    /// By default, segments must not be fragmented.
+    pub fn set_dont_fragment(&mut self, dont_frag: bool) {
+        self.dont_frag = dont_frag
+    }
+
+    /// Return how the Flow Label of outgoing IPv6 packets is chosen.
+    ///
+    /// See also the [set_flow_label](#method.set_flow_label) method.
+    pub fn flow_label(&self) -> FlowLabel {
+        self.flow_label
+    }
+
+    /// Set how the Flow Label of outgoing IPv6 packets is chosen.
+    ///
+    /// With [FlowLabel::Auto], every segment of a connection carries the same
+    /// label, derived from its endpoints.
+    ///
+    /// By default, the Flow Label is zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a [fixed](FlowLabel::Fixed) Flow Label doesn't fit in
+    /// 20 bits.
+    pub fn set_flow_label(&mut self, flow_label: FlowLabel) {
+        flow_label.check();
+        self.flow_label = flow_label
+    }
+
     /// Return the local endpoint, or None if not connected.
     #[inline]
     pub fn local_endpoint(&self) -> Option<IpEndpoint> {
@@ -703,19 +1370,51 @@ impl<'a> Socket<'a> {
         Some(self.tuple?.remote)
     }
 
+    /// Return the maximum segment size advertised by the remote endpoint, or None if the
+    /// handshake has not completed.
+    ///
+    /// If the remote endpoint did not advertise a maximum segment size, the default of
+    /// 536 octets is returned.
+    pub fn remote_mss(&self) -> Option<usize> {
+        self.handshake_completed().then_some(self.remote_mss)
+    }
+
+    /// Return the window scaling factor advertised by the remote endpoint, or None if the
+    /// handshake has not completed or the remote endpoint does not support window scaling.
+    pub fn remote_window_scale(&self) -> Option<u8> {
+        self.remote_win_scale.filter(|_| self.handshake_completed())
+    }
+
+    fn handshake_completed(&self) -> bool {
+        !matches!(
+            self.state,
+            State::Closed | State::Listen | State::SynSent | State::SynReceived
+        )
+    }
+
     /// Return the connection state, in terms of the TCP state machine.
     #[inline]
     pub fn state(&self) -> State {
         self.state
     }
 
-    fn reset(&mut self) {
-        let rx_cap_log2 =
-            mem::size_of::<usize>() * 8 - self.rx_buffer.capacity().leading_zeros() as usize;
+    /// Return the window scaling factor to advertise, which is the smallest one that lets
+    /// the whole receive buffer, or the window clamp if it is smaller, be advertised.
+    fn rx_win_shift(&self) -> u8 {
+        let max_window = match self.window_clamp {
+            Some(clamp) => clamp.min(self.rx_buffer.capacity()),
+            None => self.rx_buffer.capacity(),
+        };
+        let log2 = mem::size_of::<usize>() * 8 - max_window.leading_zeros() as usize;
+        log2.saturating_sub(16) as u8
+    }
 
+    fn reset(&mut self) {
         self.state = State::Closed;
         self.timer = Timer::new();
         self.rtte = RttEstimator::default();
+        self.congestion = CongestionController::new(self.congestion.algorithm);
+        self.retransmit_count = 0;
         self.assembler = Assembler::new();
         self.tx_buffer.clear();
         self.rx_buffer.clear();
@@ -729,11 +1428,14 @@ impl<'a> Socket<'a> {
         self.remote_last_win = 0;
         self.remote_win_len = 0;
         self.remote_win_scale = None;
-        self.remote_win_shift = rx_cap_log2.saturating_sub(16) as u8;
+        self.remote_win_shift = self.rx_win_shift();
         self.remote_mss = DEFAULT_MSS;
         self.remote_last_ts = None;
         self.ack_delay_timer = AckDelayTimer::Idle;
         self.challenge_ack_timer = Instant::from_secs(0);
+        self.cork_flush_seq = None;
+        self.pacing_next_at = Instant::ZERO;
+        self.rx_push_seq = None;
 
         #[cfg(feature = "async")]
         {
@@ -868,20 +1570,38 @@ impl<'a> Socket<'a> {
         });
         self.set_state(State::SynSent);
 
-        let seq = Self::random_seq_no(cx);
+        let seq = Self::secure_seq_no(cx, self.tuple.unwrap());
         self.local_seq_no = seq;
         self.remote_last_seq = seq;
         Ok(())
     }
 
-    #[cfg(test)]
-    fn random_seq_no(_cx: &mut Context) -> TcpSeqNumber {
-        TcpSeqNumber(10000)
+    /// Choose an initial sequence number as described in [RFC 6528 § 3]: a clock ticking
+    /// every 4 microseconds, offset by a keyed hash of the 4-tuple. The sequence numbers of
+    /// unrelated connections can't be guessed from each other, yet those of successive
+    /// connections with the same 4-tuple keep increasing, so that segments of an old
+    /// connection aren't mistaken for segments of a new one.
+    ///
+    /// [RFC 6528 § 3]: https://tools.ietf.org/html/rfc6528#section-3
+    fn secure_seq_no(cx: &mut Context, tuple: Tuple) -> TcpSeqNumber {
+        let clock = (cx.now().total_micros() / 4) as u32;
+        let offset = Self::seq_no_offset(cx.tcp_isn_secret(), tuple);
+        TcpSeqNumber(clock.wrapping_add(offset) as i32)
     }
 
-    #[cfg(not(test))]
-    fn random_seq_no(cx: &mut Context) -> TcpSeqNumber {
-        TcpSeqNumber(cx.rand().rand_u32() as i32)
+    /// The keyed hash of the 4-tuple that offsets the clock in [`secure_seq_no`].
+    ///
+    /// [`secure_seq_no`]: Self::secure_seq_no
+    const fn seq_no_offset(secret: u64, tuple: Tuple) -> u32 {
+        keyed_hash(
+            secret,
+            &[
+                tuple.local.addr.as_bytes(),
+                tuple.remote.addr.as_bytes(),
+                &tuple.local.port.to_be_bytes(),
+                &tuple.remote.port.to_be_bytes(),
+            ],
+        ) as u32
     }
 
     /// Close the transmit half of the full-duplex connection.
@@ -923,6 +1643,16 @@ impl<'a> Socket<'a> {
         self.set_state(State::Closed);
     }
 
+    /// Give up on a connection attempt whose remote endpoint did not answer neighbor
+    /// discovery.
+    pub(crate) fn neighbor_unreachable(&mut self) {
+        if self.state == State::SynSent {
+            net_debug!("neighbor unreachable, aborting connection attempt");
+            self.reset();
+            self.set_state(State::Closed);
+        }
+    }
+
     /// Return whether the socket is passively listening for incoming connections.
     ///
     /// In terms of the TCP state machine, the socket must be in the `LISTEN` state.
@@ -1015,14 +1745,15 @@ impl<'a> Socket<'a> {
     }
 
     /// Check whether the transmit half of the full-duplex connection is open
-    /// (see [may_send](#method.may_send)), and the transmit buffer is not full.
+    /// (see [may_send](#method.may_send)), and the transmit buffer has at least
+    /// [send_lowat](#method.set_send_lowat) octets free.
     #[inline]
     pub fn can_send(&self) -> bool {
         if !self.may_send() {
             return false;
         }
 
-        !self.tx_buffer.is_full()
+        !self.tx_buffer.is_full() && self.send_lowat_reached()
     }
 
     /// Return the maximum number of bytes inside the recv buffer.
@@ -1038,14 +1769,25 @@ impl<'a> Socket<'a> {
     }
 
     /// Check whether the receive half of the full-duplex connection buffer is open
-    /// (see [may_recv](#method.may_recv)), and the receive buffer is not empty.
+    /// (see [may_recv](#method.may_recv)), and the receive buffer holds at least
+    /// [recv_lowat](#method.set_recv_lowat) octets, or isn't empty and the remote endpoint
+    /// has closed the connection.
     #[inline]
     pub fn can_recv(&self) -> bool {
         if !self.may_recv() {
             return false;
         }
 
-        !self.rx_buffer.is_empty()
+        !self.rx_buffer.is_empty() && self.recv_lowat_reached()
+    }
+
+    fn send_lowat_reached(&self) -> bool {
+        self.tx_buffer.window() >= self.send_lowat.min(self.tx_buffer.capacity())
+    }
+
+    fn recv_lowat_reached(&self) -> bool {
+        self.rx_fin_received
+            || self.rx_buffer.len() >= self.recv_lowat.min(self.rx_buffer.capacity())
     }
 
     fn send_impl<'b, F, R>(&'b mut self, f: F) -> Result<R, SendError>
@@ -1124,6 +1866,9 @@ impl<'a> Socket<'a> {
         let _old_length = self.rx_buffer.len();
         let (size, result) = f(&mut self.rx_buffer);
         self.remote_seq_no += size;
+        if matches!(self.rx_push_seq, Some(seq) if seq <= self.remote_seq_no) {
+            self.rx_push_seq = None;
+        }
         if size > 0 {
             #[cfg(any(test, feature = "verbose"))]
             tcp_trace!(
@@ -1165,6 +1910,25 @@ impl<'a> Socket<'a> {
         })
     }
 
+    /// Dequeue a sequence of received octets up to the next push boundary, and fill
+    /// a slice from it.
+    ///
+    /// This function behaves like [recv_slice](#method.recv_slice), except that it never
+    /// dequeues past the end of a segment that was received with the PSH flag set, and
+    /// reports in the returned [`RecvMeta`] whether the data ends exactly there. This can be
+    /// used as a hint for message boundaries; note that only the most recent push boundary
+    /// is remembered, so boundaries of segments pushed before it are not reported.
+    pub fn recv_with_meta(&mut self, data: &mut [u8]) -> Result<(usize, RecvMeta), RecvError> {
+        let push_seq = self.rx_push_seq;
+        let limit = match push_seq {
+            Some(seq) if seq > self.remote_seq_no => data.len().min(seq - self.remote_seq_no),
+            _ => data.len(),
+        };
+        let size = self.recv_slice(&mut data[..limit])?;
+        let push = size > 0 && push_seq == Some(self.remote_seq_no);
+        Ok((size, RecvMeta { push }))
+    }
+
     /// Peek at a sequence of received octets without removing them from
     /// the receive buffer, and return a pointer to it.
     ///
@@ -1204,6 +1968,174 @@ impl<'a> Socket<'a> {
         self.rx_buffer.len()
     }
 
+    /// Return the length of the state written by [serialize_state](#method.serialize_state).
+    pub fn serialized_state_len(&self) -> usize {
+        let addr_len = match self.tuple {
+            Some(tuple) => 2 * tuple.local.addr.as_bytes().len(),
+            None => 0,
+        };
+        STATE_FIXED_LEN + addr_len + self.rx_buffer.len() + self.tx_buffer.len()
+    }
+
+    /// Serialize the state of the connection into `buffer`, and return the length of
+    /// the serialized state.
+    ///
+    /// The serialized state includes the endpoints, the sequence numbers and windows, the
+    /// options negotiated with the remote endpoint, the congestion window, and the data in
+    /// the receive and transmit buffers. It does not depend on the memory layout or the
+    /// clock of the host, so that [restore_state](#method.restore_state) can continue the
+    /// connection in another process or on another device. Configuration set on the socket,
+    /// such as timeouts or Nagle's Algorithm, is not included.
+    ///
+    /// The connection must be synchronized and not in the TIME-WAIT state, or
+    /// `Err(SerializeError::InvalidState)` is returned. `buffer` must be at least
+    /// [serialized_state_len](#method.serialized_state_len) octets long, or
+    /// `Err(SerializeError::BufferTooSmall)` is returned.
+    ///
+    /// The socket itself is not changed. Once the connection has been restored elsewhere,
+    /// the socket should be dropped without [closing](#method.close) or
+    /// [aborting](#method.abort) it first, since either would signal the remote endpoint.
+    pub fn serialize_state(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+        let state = match self.state {
+            State::Established => 0,
+            State::FinWait1 => 1,
+            State::FinWait2 => 2,
+            State::CloseWait => 3,
+            State::Closing => 4,
+            State::LastAck => 5,
+            _ => return Err(SerializeError::InvalidState),
+        };
+        let tuple = self.tuple.unwrap();
+        let len = self.serialized_state_len();
+        if buffer.len() < len {
+            return Err(SerializeError::BufferTooSmall);
+        }
+
+        let mut writer = StateWriter { buffer, pos: 0 };
+        writer.put_u8(STATE_FORMAT_VERSION);
+        writer.put_u8(state);
+        writer.put_u8(match tuple.local.addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(_) => 4,
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(_) => 6,
+        });
+        writer.put(tuple.local.addr.as_bytes());
+        writer.put_u16(tuple.local.port);
+        writer.put(tuple.remote.addr.as_bytes());
+        writer.put_u16(tuple.remote.port);
+        writer.put_u32(self.local_seq_no.0 as u32);
+        writer.put_u32(self.remote_seq_no.0 as u32);
+        let remote_last_ack = self
+            .remote_last_ack
+            .unwrap_or(self.remote_seq_no + self.rx_buffer.len());
+        writer.put_u32(remote_last_ack.0 as u32);
+        writer.put_u16(self.remote_last_win);
+        writer.put_len(self.remote_win_len);
+        writer.put_u16(self.remote_mss.min(u16::MAX as usize) as u16);
+        writer.put_u8(self.remote_win_shift);
+        writer.put_u8(self.remote_win_scale.unwrap_or(u8::MAX));
+        writer.put_u8((self.remote_has_sack as u8) | (self.rx_fin_received as u8) << 1);
+        writer.put_len(self.congestion.cwnd.unwrap_or(0));
+        writer.put_len(self.congestion.ssthresh);
+        writer.put_len(self.congestion.bytes_acked);
+        for buffer in [&self.rx_buffer, &self.tx_buffer] {
+            writer.put_len(buffer.len());
+            let first = buffer.get_allocated(0, buffer.len());
+            writer.put(first);
+            writer.put(buffer.get_allocated(first.len(), buffer.len() - first.len()));
+        }
+        debug_assert_eq!(writer.pos, len);
+        Ok(len)
+    }
+
+    /// Continue a connection from the state serialized by
+    /// [serialize_state](#method.serialize_state).
+    ///
+    /// The socket must be closed, or `Err(RestoreError::InvalidState)` is returned. If the
+    /// serialized state can't be parsed, or uses a protocol that is not enabled,
+    /// `Err(RestoreError::Malformed)` is returned. If the data buffered in the serialized
+    /// socket does not fit in the buffers of this socket, `Err(RestoreError::BufferTooSmall)`
+    /// is returned. The socket is left closed on error.
+    ///
+    /// Round-trip time estimates and timers start over, and any data or FIN that was sent
+    /// but not acknowledged is retransmitted right away. Out-of-order data that was received
+    /// but not acknowledged is not part of the serialized state, and is retransmitted by the
+    /// remote endpoint.
+    pub fn restore_state(&mut self, state: &[u8]) -> Result<(), RestoreError> {
+        if self.state != State::Closed {
+            return Err(RestoreError::InvalidState);
+        }
+
+        let mut reader = StateReader { buffer: state };
+        if reader.take_u8()? != STATE_FORMAT_VERSION {
+            return Err(RestoreError::Malformed);
+        }
+        let state = match reader.take_u8()? {
+            0 => State::Established,
+            1 => State::FinWait1,
+            2 => State::FinWait2,
+            3 => State::CloseWait,
+            4 => State::Closing,
+            5 => State::LastAck,
+            _ => return Err(RestoreError::Malformed),
+        };
+        let family = reader.take_u8()?;
+        let local = IpEndpoint::new(reader.take_addr(family)?, reader.take_u16()?);
+        let remote = IpEndpoint::new(reader.take_addr(family)?, reader.take_u16()?);
+        let local_seq_no = TcpSeqNumber(reader.take_u32()? as i32);
+        let remote_seq_no = TcpSeqNumber(reader.take_u32()? as i32);
+        let remote_last_ack = TcpSeqNumber(reader.take_u32()? as i32);
+        let remote_last_win = reader.take_u16()?;
+        let remote_win_len = reader.take_len()?;
+        let remote_mss = reader.take_u16()? as usize;
+        let remote_win_shift = reader.take_u8()?;
+        let remote_win_scale = match reader.take_u8()? {
+            u8::MAX => None,
+            scale => Some(scale),
+        };
+        let flags = reader.take_u8()?;
+        let cwnd = match reader.take_len()? {
+            0 => None,
+            cwnd => Some(cwnd),
+        };
+        let ssthresh = reader.take_len()?;
+        let bytes_acked = reader.take_len()?;
+        let rx_len = reader.take_len()?;
+        let rx_data = reader.take(rx_len)?;
+        let tx_len = reader.take_len()?;
+        let tx_data = reader.take(tx_len)?;
+        if !reader.buffer.is_empty() || remote_win_shift > 14 {
+            return Err(RestoreError::Malformed);
+        }
+        if rx_len > self.rx_buffer.capacity() || tx_len > self.tx_buffer.capacity() {
+            return Err(RestoreError::BufferTooSmall);
+        }
+
+        self.reset();
+        let _ = self.rx_buffer.enqueue_slice(rx_data);
+        let _ = self.tx_buffer.enqueue_slice(tx_data);
+        self.rx_fin_received = flags & 0b10 != 0;
+        self.tuple = Some(Tuple { local, remote });
+        self.local_seq_no = local_seq_no;
+        self.remote_seq_no = remote_seq_no;
+        // Rewind to the acknowledged sequence number, so that everything in flight gets
+        // retransmitted, like after a retransmission timeout.
+        self.remote_last_seq = local_seq_no;
+        self.remote_last_ack = Some(remote_last_ack);
+        self.remote_last_win = remote_last_win;
+        self.remote_win_len = remote_win_len;
+        self.remote_win_shift = remote_win_shift;
+        self.remote_win_scale = remote_win_scale;
+        self.remote_has_sack = flags & 0b01 != 0;
+        self.remote_mss = remote_mss;
+        self.congestion.cwnd = cwnd;
+        self.congestion.ssthresh = ssthresh;
+        self.congestion.bytes_acked = bytes_acked;
+        self.set_state(state);
+        Ok(())
+    }
+
     fn set_state(&mut self, state: State) {
         if self.state != state {
             tcp_trace!("state={}=>{}", self.state, state);
@@ -1334,15 +2266,149 @@ impl<'a> Socket<'a> {
         Some(self.ack_reply(ip_repr, repr))
     }
 
-    pub(crate) fn accepts(&self, _cx: &mut Context, ip_repr: &IpRepr, repr: &TcpRepr) -> bool {
+    /// Compute the SYN cookie for a connection, see [`set_syn_cookies`](Self::set_syn_cookies).
+    ///
+    /// The top 5 bits are a time counter, the next 3 bits index `SYN_COOKIE_MSS`, and the
+    /// rest is a keyed hash of the connection, the remote initial sequence number, and
+    /// these two values, which can't be forged without the secret.
+    fn syn_cookie(
+        secret: u64,
+        ip_repr: &IpRepr,
+        repr: &TcpRepr,
+        remote_isn: TcpSeqNumber,
+        counter: u32,
+        mss_index: u32,
+    ) -> u32 {
+        let prefix = (counter & 0x1f) << 27 | (mss_index & 0x7) << 24;
+        let hash = keyed_hash(
+            secret,
+            &[
+                ip_repr.src_addr().as_bytes(),
+                ip_repr.dst_addr().as_bytes(),
+                &repr.src_port.to_be_bytes(),
+                &repr.dst_port.to_be_bytes(),
+                &remote_isn.0.to_be_bytes(),
+                &prefix.to_be_bytes(),
+            ],
+        );
+
+        prefix | (hash as u32 & 0x00ff_ffff)
+    }
+
+    fn syn_cookie_counter(cx: &mut Context) -> u32 {
+        (cx.now().total_millis() / SYN_COOKIE_PERIOD.total_millis() as i64) as u32 & 0x1f
+    }
+
+    /// Validate the SYN cookie returned by an ACK, and return the MSS it encodes.
+    fn syn_cookie_mss(&self, cx: &mut Context, ip_repr: &IpRepr, repr: &TcpRepr) -> Option<usize> {
+        let secret = self.syn_cookie_secret.filter(|_| self.syn_cookies)?;
+        if !matches!(repr.control, TcpControl::None | TcpControl::Psh) {
+            return None;
+        }
+
+        let cookie = (repr.ack_number? - 1).0 as u32;
+        let counter = cookie >> 27;
+        if Self::syn_cookie_counter(cx).wrapping_sub(counter) & 0x1f > 1 {
+            return None;
+        }
+        let mss_index = (cookie >> 24) & 0x7;
+
+        let remote_isn = repr.seq_number - 1;
+        if Self::syn_cookie(secret, ip_repr, repr, remote_isn, counter, mss_index) != cookie {
+            return None;
+        }
+        Some(SYN_COOKIE_MSS[mss_index as usize])
+    }
+
+    fn syn_cookie_reply(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        repr: &TcpRepr,
+    ) -> Option<(IpRepr, TcpRepr<'static>)> {
+        let remote_mss = match repr.max_seg_size {
+            Some(0) => {
+                tcp_trace!("received SYN with zero MSS, ignoring");
+                return None;
+            }
+            Some(max_seg_size) => max_seg_size as usize,
+            None => DEFAULT_MSS,
+        };
+        let mss_index = SYN_COOKIE_MSS
+            .iter()
+            .rposition(|&mss| mss <= remote_mss)
+            .unwrap_or(0);
+
+        let secret = match self.syn_cookie_secret {
+            Some(secret) => secret,
+            None => {
+                let secret = (cx.rand().rand_u32() as u64) << 32 | cx.rand().rand_u32() as u64;
+                self.syn_cookie_secret = Some(secret);
+                secret
+            }
+        };
+        let counter = Self::syn_cookie_counter(cx);
+        let cookie = Self::syn_cookie(
+            secret,
+            ip_repr,
+            repr,
+            repr.seq_number,
+            counter,
+            mss_index as u32,
+        );
+        tcp_trace!("received SYN, sending SYN|ACK with cookie {:08x}", cookie);
+
+        let (mut ip_reply_repr, mut reply_repr) = Self::reply(ip_repr, repr);
+        reply_repr.control = TcpControl::Syn;
+        reply_repr.seq_number = TcpSeqNumber(cookie as i32);
+        reply_repr.ack_number = Some(repr.seq_number + 1);
+        // window len must NOT be scaled in SYNs.
+        reply_repr.window_len = self.rx_window().min((1 << 16) - 1) as u16;
+        // Fill the MSS option. See RFC 6691 for an explanation of this calculation.
+        reply_repr.max_seg_size =
+            Some((cx.ip_mtu() - ip_repr.header_len() - TCP_HEADER_LEN) as u16);
+        ip_reply_repr.set_payload_len(reply_repr.buffer_len());
+        Some((ip_reply_repr, reply_repr))
+    }
+
+    /// Restore the SYN-RECEIVED state from the SYN cookie returned by an ACK.
+    fn accept_syn_cookie(&mut self, cx: &mut Context, ip_repr: &IpRepr, repr: &TcpRepr) {
+        let (remote_mss, ack_number) =
+            match (self.syn_cookie_mss(cx, ip_repr, repr), repr.ack_number) {
+                (Some(remote_mss), Some(ack_number)) => (remote_mss, ack_number),
+                _ => return,
+            };
+        tcp_trace!("received ACK with a valid SYN cookie");
+
+        self.tuple = Some(Tuple {
+            local: IpEndpoint::new(ip_repr.dst_addr(), repr.dst_port),
+            remote: IpEndpoint::new(ip_repr.src_addr(), repr.src_port),
+        });
+        self.local_seq_no = ack_number - 1;
+        self.remote_seq_no = repr.seq_number;
+        self.remote_last_seq = ack_number;
+        self.remote_last_ack = Some(repr.seq_number);
+        self.remote_last_win = self.rx_window().min((1 << 16) - 1) as u16;
+        self.remote_mss = remote_mss;
+        self.remote_has_sack = false;
+        self.remote_win_scale = None;
+        self.remote_win_shift = 0;
+        self.set_state(State::SynReceived);
+        self.timer.set_for_idle(cx.now(), self.keep_alive);
+    }
+
+    pub(crate) fn accepts(&self, cx: &mut Context, ip_repr: &IpRepr, repr: &TcpRepr) -> bool {
         if self.state == State::Closed {
             return false;
         }
 
         // If we're still listening for SYNs and the packet has an ACK, it cannot
-        // be destined to this socket, but another one may well listen on the same
-        // local endpoint.
-        if self.state == State::Listen && repr.ack_number.is_some() {
+        // be destined to this socket, unless it completes a handshake we answered
+        // with a SYN cookie. Another socket may well listen on the same local endpoint.
+        if self.state == State::Listen
+            && repr.ack_number.is_some()
+            && self.syn_cookie_mss(cx, ip_repr, repr).is_none()
+        {
             return false;
         }
 
@@ -1370,6 +2436,15 @@ impl<'a> Socket<'a> {
     ) -> Option<(IpRepr, TcpRepr<'static>)> {
         debug_assert!(self.accepts(cx, ip_repr, repr));
 
+        if self.state == State::Listen && self.syn_cookies {
+            if repr.control == TcpControl::Syn && repr.ack_number.is_none() {
+                return self.syn_cookie_reply(cx, ip_repr, repr);
+            }
+            if repr.ack_number.is_some() {
+                self.accept_syn_cookie(cx, ip_repr, repr);
+            }
+        }
+
         // Consider how much the sequence number space differs from the transmit buffer space.
         let (sent_syn, sent_fin) = match self.state {
             // In SYN-SENT or SYN-RECEIVED, we've just sent a SYN.
@@ -1484,6 +2559,17 @@ impl<'a> Socket<'a> {
         let segment_start = repr.seq_number;
         let segment_end = repr.seq_number + repr.payload.len();
 
+        // RFC 1337: a stray RST (e.g. an old duplicate) must not prematurely terminate
+        // the TIME-WAIT state ("TIME-WAIT assassination"). Only honor an RST whose sequence
+        // number is exactly the next expected one, and silently drop any other.
+        if self.state == State::TimeWait
+            && repr.control == TcpControl::Rst
+            && segment_start != window_start
+        {
+            net_debug!("ignoring RST in TIME-WAIT");
+            return None;
+        }
+
         let (payload, payload_offset) = match self.state {
             // In LISTEN and SYN-SENT states, we have not yet synchronized with the remote end.
             State::Listen | State::SynSent => (&[][..], 0),
@@ -1558,7 +2644,7 @@ impl<'a> Socket<'a> {
                     // If we're in the TIME-WAIT state, restart the TIME-WAIT timeout, since
                     // the remote end may not have realized we've closed the connection.
                     if self.state == State::TimeWait {
-                        self.timer.set_for_close(cx.now());
+                        self.timer.set_for_close(cx.now(), self.msl);
                     }
 
                     return self.challenge_ack_reply(cx, ip_repr, repr);
@@ -1643,7 +2729,7 @@ impl<'a> Socket<'a> {
                     local: IpEndpoint::new(ip_repr.dst_addr(), repr.dst_port),
                     remote: IpEndpoint::new(ip_repr.src_addr(), repr.src_port),
                 });
-                self.local_seq_no = Self::random_seq_no(cx);
+                self.local_seq_no = Self::secure_seq_no(cx, self.tuple.unwrap());
                 self.remote_seq_no = repr.seq_number + 1;
                 self.remote_last_seq = self.local_seq_no;
                 self.remote_has_sack = repr.sack_permitted;
@@ -1730,7 +2816,7 @@ impl<'a> Socket<'a> {
                 self.rx_fin_received = true;
                 if ack_of_fin {
                     self.set_state(State::TimeWait);
-                    self.timer.set_for_close(cx.now());
+                    self.timer.set_for_close(cx.now(), self.msl);
                 } else {
                     self.set_state(State::Closing);
                     self.timer.set_for_idle(cx.now(), self.keep_alive);
@@ -1747,14 +2833,14 @@ impl<'a> Socket<'a> {
                 self.remote_seq_no += 1;
                 self.rx_fin_received = true;
                 self.set_state(State::TimeWait);
-                self.timer.set_for_close(cx.now());
+                self.timer.set_for_close(cx.now(), self.msl);
             }
 
             // ACK packets in CLOSING state change it to TIME-WAIT.
             (State::Closing, TcpControl::None) => {
                 if ack_of_fin {
                     self.set_state(State::TimeWait);
-                    self.timer.set_for_close(cx.now());
+                    self.timer.set_for_close(cx.now(), self.msl);
                 } else {
                     self.timer.set_for_idle(cx.now(), self.keep_alive);
                 }
@@ -1804,10 +2890,13 @@ impl<'a> Socket<'a> {
                 self.tx_buffer.len() - ack_len
             );
             self.tx_buffer.dequeue_allocated(ack_len);
+            self.congestion.on_ack(ack_len, self.remote_mss);
 
             // There's new room available in tx_buffer, wake the waiting task if any.
             #[cfg(feature = "async")]
-            self.tx_waker.wake();
+            if self.send_lowat_reached() {
+                self.tx_waker.wake();
+            }
         }
 
         if let Some(ack_number) = repr.ack_number {
@@ -1844,6 +2933,8 @@ impl<'a> Socket<'a> {
 
                     if self.local_rx_dup_acks == 3 {
                         self.timer.set_for_fast_retransmit();
+                        self.congestion
+                            .on_fast_retransmit(self.in_flight(), self.remote_mss);
                         net_debug!("started fast retransmit");
                     }
                 }
@@ -1856,6 +2947,11 @@ impl<'a> Socket<'a> {
                     self.local_rx_last_ack = Some(ack_number);
                 }
             };
+            // The remote endpoint is making progress, so restart counting retransmissions.
+            if ack_number > self.local_seq_no {
+                self.retransmit_count = 0;
+            }
+
             // We've processed everything in the incoming segment, so advance the local
             // sequence number past it.
             self.local_seq_no = ack_number;
@@ -1876,6 +2972,25 @@ impl<'a> Socket<'a> {
 
         let assembler_was_empty = self.assembler.is_empty();
 
+        // Drop out-of-order segments that would exceed the configured limit.
+        // The segment at the start of the window is never dropped, as it is
+        // what allows the out-of-order data to be delivered.
+        if payload_offset != 0 {
+            if let Some(limit) = self.out_of_order_limit {
+                let mut assembler = self.assembler.clone();
+                if assembler.add(payload_offset, payload_len).is_ok()
+                    && assembler.data_len() > limit
+                {
+                    net_debug!(
+                        "assembler: out-of-order limit reached, dropping {} octets at offset {}",
+                        payload_len,
+                        payload_offset
+                    );
+                    return None;
+                }
+            }
+        }
+
         // Try adding payload octets to the assembler.
         let Ok(contig_len) = self
             .assembler
@@ -1898,6 +3013,16 @@ impl<'a> Socket<'a> {
         let len_written = self.rx_buffer.write_unallocated(payload_offset, payload);
         debug_assert!(len_written == payload_len);
 
+        // Remember where the pushed data ends, so that it can be reported as a boundary.
+        // If the segment was trimmed to the window, the boundary lies past the data we
+        // accepted and is only reached once the rest is received.
+        if repr.control == TcpControl::Psh {
+            let push_seq = repr.seq_number + repr.payload.len();
+            if !matches!(self.rx_push_seq, Some(seq) if seq >= push_seq) {
+                self.rx_push_seq = Some(push_seq);
+            }
+        }
+
         if contig_len != 0 {
             // Enqueue the contiguous data octets in front of the buffer.
             tcp_trace!(
@@ -1907,9 +3032,13 @@ impl<'a> Socket<'a> {
             );
             self.rx_buffer.enqueue_unallocated(contig_len);
 
-            // There's new data in rx_buffer, notify waiting task if any.
+            // There's new data in rx_buffer, notify waiting task if any. This is
+            // done for every segment, not only ones with the PSH flag set, so pushed
+            // data is always delivered right away, unless the low-water mark isn't reached.
             #[cfg(feature = "async")]
-            self.rx_waker.wake();
+            if self.recv_lowat_reached() {
+                self.rx_waker.wake();
+            }
         }
 
         if !self.assembler.is_empty() {
@@ -1962,7 +3091,23 @@ impl<'a> Socket<'a> {
         }
     }
 
-    fn seq_to_transmit(&self, cx: &mut Context) -> bool {
+    /// Return the amount of octets sent but not acknowledged yet.
+    fn in_flight(&self) -> usize {
+        if self.remote_last_seq > self.local_seq_no {
+            self.remote_last_seq - self.local_seq_no
+        } else {
+            0
+        }
+    }
+
+    /// Return the amount of octets after local_seq_no we're allowed to send, according to
+    /// both the remote window and the congestion window.
+    fn send_win_len(&self) -> usize {
+        self.remote_win_len
+            .min(self.congestion.window(self.remote_mss))
+    }
+
+    fn seq_to_transmit(&self, cx: &Context) -> bool {
         let ip_header_len = match self.tuple.unwrap().local.addr {
             #[cfg(feature = "proto-ipv4")]
             IpAddress::Ipv4(_) => crate::wire::IPV4_HEADER_LEN,
@@ -1986,7 +3131,7 @@ impl<'a> Socket<'a> {
 
         // max sequence number we can send.
         let max_send_seq =
-            self.local_seq_no + core::cmp::min(self.remote_win_len, self.tx_buffer.len());
+            self.local_seq_no + core::cmp::min(self.send_win_len(), self.tx_buffer.len());
 
         // Max amount of octets we can send.
         let max_send = if max_send_seq >= self.remote_last_seq {
@@ -2017,6 +3162,18 @@ impl<'a> Socket<'a> {
             can_send = false;
         }
 
+        // If we're corked, we don't send partial segments unless they contain
+        // data that has been flushed (or already sent once, and is being retransmitted).
+        let flushed = matches!(self.cork_flush_seq, Some(seq) if seq > self.remote_last_seq);
+        if self.cork && !can_send_full && !want_fin && !flushed {
+            can_send = false;
+        }
+
+        // If we're pacing, don't send data before the next segment is due.
+        if self.paced(cx.now()) {
+            can_send = false;
+        }
+
         // Can we actually send the FIN? We can send it if:
         // 1. We have unsent data that fits in the remote window.
         // 2. We have no unsent data.
@@ -2079,22 +3236,43 @@ impl<'a> Socket<'a> {
             self.set_state(State::Closed);
         } else if !self.seq_to_transmit(cx) {
             if let Some(retransmit_delta) = self.timer.should_retransmit(cx.now()) {
-                // If a retransmit timer expired, we should resend data starting at the last ACK.
-                net_debug!("retransmitting at t+{}", retransmit_delta);
-
-                // Rewind "last sequence number sent", as if we never
-                // had sent them. This will cause all data in the queue
-                // to be sent again.
-                self.remote_last_seq = self.local_seq_no;
+                if matches!(self.max_retransmits, Some(max) if self.retransmit_count >= max) {
+                    // If we've retransmitted too many times, we should abort the connection.
+                    net_debug!("retransmission limit exceeded");
+                    self.set_state(State::Closed);
+                } else {
+                    // If a retransmit timer expired, we should resend data starting at the last ACK.
+                    net_debug!("retransmitting at t+{}", retransmit_delta);
+                    self.retransmit_count = self.retransmit_count.saturating_add(1);
+
+                    // Don't let corking hold back data that was already sent.
+                    if self.cork {
+                        self.cork_flush_seq = Some(match self.cork_flush_seq {
+                            Some(seq) => seq.max(self.remote_last_seq),
+                            None => self.remote_last_seq,
+                        });
+                    }
 
-                // Clear the `should_retransmit` state. If we can't retransmit right
-                // now for whatever reason (like zero window), this avoids an
-                // infinite polling loop where `poll_at` returns `Now` but `dispatch`
-                // can't actually do anything.
-                self.timer.set_for_idle(cx.now(), self.keep_alive);
+                    self.congestion
+                        .on_retransmit(self.in_flight(), self.remote_mss);
+
+                    // Rewind "last sequence number sent", as if we never
+                    // had sent them. This will cause all data in the queue
+                    // to be sent again. Segments aren't remembered individually;
+                    // the data is cut from the transmit buffer again using the
+                    // current window and MSS, so the original segments may be
+                    // merged or split.
+                    self.remote_last_seq = self.local_seq_no;
+
+                    // Clear the `should_retransmit` state. If we can't retransmit right
+                    // now for whatever reason (like zero window), this avoids an
+                    // infinite polling loop where `poll_at` returns `Now` but `dispatch`
+                    // can't actually do anything.
+                    self.timer.set_for_idle(cx.now(), self.keep_alive);
 
-                // Inform RTTE, so that it can avoid bogus measurements.
-                self.rtte.on_retransmit();
+                    // Inform RTTE, so that it can avoid bogus measurements.
+                    self.rtte.on_retransmit();
+                }
             }
         }
 
@@ -2167,7 +3345,7 @@ impl<'a> Socket<'a> {
             State::SynSent | State::SynReceived => {
                 repr.control = TcpControl::Syn;
                 // window len must NOT be scaled in SYNs.
-                repr.window_len = self.rx_buffer.window().min((1 << 16) - 1) as u16;
+                repr.window_len = self.rx_window().min((1 << 16) - 1) as u16;
                 if self.state == State::SynSent {
                     repr.ack_number = None;
                     repr.window_scale = Some(self.remote_win_shift);
@@ -2189,9 +3367,9 @@ impl<'a> Socket<'a> {
                 // from the transmit buffer.
 
                 // Right edge of window, ie the max sequence number we're allowed to send.
-                let win_right_edge = self.local_seq_no + self.remote_win_len;
+                let win_right_edge = self.local_seq_no + self.send_win_len();
 
-                // Max amount of octets we're allowed to send according to the remote window.
+                // Max amount of octets we're allowed to send according to the window.
                 let win_limit = if win_right_edge >= self.remote_last_seq {
                     win_right_edge - self.remote_last_seq
                 } else {
@@ -2207,10 +3385,15 @@ impl<'a> Socket<'a> {
                 // 1. remote window
                 // 2. MSS the remote is willing to accept, probably determined by their MTU
                 // 3. MSS we can send, determined by our MTU.
-                let size = win_limit
+                let mut size = win_limit
                     .min(self.remote_mss)
                     .min(cx.ip_mtu() - ip_repr.header_len() - TCP_HEADER_LEN);
 
+                // Don't let an ACK carry data before the next segment is due.
+                if self.paced(cx.now()) {
+                    size = 0;
+                }
+
                 let offset = self.remote_last_seq - self.local_seq_no;
                 repr.payload = self.tx_buffer.get_allocated(offset, size);
 
@@ -2317,11 +3500,15 @@ impl<'a> Socket<'a> {
                 .on_send(cx.now(), repr.seq_number + repr.segment_len());
         }
 
+        if self.pacing && !repr.payload.is_empty() {
+            self.pacing_next_at = cx.now() + self.pacing_delay(repr.payload.len());
+        }
+
         if !self.seq_to_transmit(cx) && repr.segment_len() > 0 {
             // If we've transmitted all data we could (and there was something at all,
             // data or flag, to transmit, not just an ACK), wind up the retransmit timer.
-            self.timer
-                .set_for_retransmit(cx.now(), self.rtte.retransmission_timeout());
+            let rto = self.rtte.retransmission_timeout().min(self.max_rto);
+            self.timer.set_for_retransmit(cx.now(), rto);
         }
 
         if self.state == State::Closed {
@@ -2338,7 +3525,7 @@ impl<'a> Socket<'a> {
     }
 
     #[allow(clippy::if_same_then_else)]
-    pub(crate) fn poll_at(&self, cx: &mut Context) -> PollAt {
+    pub(crate) fn poll_at(&self, cx: &Context) -> PollAt {
         // The logic here mirrors the beginning of dispatch() closely.
         if self.tuple.is_none() {
             // No one to talk to, nothing to transmit.
@@ -2362,6 +3549,14 @@ impl<'a> Socket<'a> {
                 (true, AckDelayTimer::Immediate) => PollAt::Now,
             };
 
+            // If we're pacing, we need to poll when the next segment is due.
+            let unsent = self.in_flight() < self.tx_buffer.len();
+            let pacing_poll_at = if unsent && self.paced(cx.now()) {
+                PollAt::Time(self.pacing_next_at)
+            } else {
+                PollAt::Ingress
+            };
+
             let timeout_poll_at = match (self.remote_last_ts, self.timeout) {
                 // If we're transmitting or retransmitting data, we need to poll at the moment
                 // when the timeout would expire.
@@ -2371,10 +3566,15 @@ impl<'a> Socket<'a> {
             };
 
             // We wait for the earliest of our timers to fire.
-            *[self.timer.poll_at(), timeout_poll_at, delayed_ack_poll_at]
-                .iter()
-                .min()
-                .unwrap_or(&PollAt::Ingress)
+            *[
+                self.timer.poll_at(),
+                timeout_poll_at,
+                delayed_ack_poll_at,
+                pacing_poll_at,
+            ]
+            .iter()
+            .min()
+            .unwrap_or(&PollAt::Ingress)
         }
     }
 }
@@ -2396,6 +3596,7 @@ impl<'a> fmt::Write for Socket<'a> {
 #[cfg(all(test, feature = "medium-ip"))]
 mod test {
     use super::*;
+    use crate::rand::RandSource;
     use crate::wire::IpRepr;
     use core::i32;
     use std::ops::{Deref, DerefMut};
@@ -2423,7 +3624,10 @@ mod test {
         local: LOCAL_END,
         remote: REMOTE_END,
     };
-    const LOCAL_SEQ: TcpSeqNumber = TcpSeqNumber(10000);
+    /// The secret that initial sequence numbers are derived from in the tests.
+    const ISN_SECRET: u64 = 0x1234_5678_1234_5678;
+    /// The initial sequence number of `TUPLE` at time zero.
+    const LOCAL_SEQ: TcpSeqNumber = TcpSeqNumber(Socket::seq_no_offset(ISN_SECRET, TUPLE) as i32);
     const REMOTE_SEQ: TcpSeqNumber = TcpSeqNumber(-10001);
 
     cfg_if::cfg_if! {
@@ -2503,7 +3707,16 @@ mod test {
 
     struct TestSocket {
         socket: Socket<'static>,
-        cx: Context,
+        cx: Context<'static>,
+    }
+
+    /// A random number source that always returns the same number.
+    struct FixedRand(u32);
+
+    impl RandSource for FixedRand {
+        fn rand_u32(&mut self) -> u32 {
+            self.0
+        }
     }
 
     impl Deref for TestSocket {
@@ -2645,10 +3858,16 @@ mod test {
         let tx_buffer = SocketBuffer::new(vec![0; tx_len]);
         let mut socket = Socket::new(rx_buffer, tx_buffer);
         socket.set_ack_delay(None);
-        TestSocket {
-            socket,
-            cx: iface.inner,
-        }
+        let mut cx = iface.inner;
+        cx.set_tcp_isn_secret(ISN_SECRET);
+        TestSocket { socket, cx }
+    }
+
+    fn context_with_rand_source(rand: &mut FixedRand) -> Context<'_> {
+        let (iface, _, _) = crate::tests::setup(crate::phy::Medium::Ip);
+        let mut cx = iface.inner;
+        cx.set_rand_source(rand);
+        cx
     }
 
     fn socket_syn_received_with_buffer_sizes(tx_len: usize, rx_len: usize) -> TestSocket {
@@ -2723,7 +3942,7 @@ mod test {
             s.remote_last_ack = Some(REMOTE_SEQ + 1 + 1);
         }
         s.timer = Timer::Close {
-            expires_at: Instant::from_secs(1) + CLOSE_DELAY,
+            expires_at: Instant::from_secs(1) + DEFAULT_MSL * 2,
         };
         s
     }
@@ -2944,6 +4163,104 @@ mod test {
         sanity!(s, socket_syn_received());
     }
 
+    #[test]
+    fn test_listen_syn_cookie_flood() {
+        let mut s = socket_listen();
+        s.set_syn_cookies(true);
+
+        // Every SYN is answered, but the socket keeps listening.
+        for port in 0..16 {
+            let reply = send(
+                &mut s,
+                Instant::from_millis(0),
+                &TcpRepr {
+                    control: TcpControl::Syn,
+                    src_port: REMOTE_PORT + port,
+                    seq_number: REMOTE_SEQ + port as usize,
+                    ack_number: None,
+                    window_scale: Some(0),
+                    sack_permitted: true,
+                    ..SEND_TEMPL
+                },
+            )
+            .unwrap();
+            assert_eq!(reply.control, TcpControl::Syn);
+            assert_eq!(reply.dst_port, REMOTE_PORT + port);
+            assert_eq!(reply.ack_number, Some(REMOTE_SEQ + port as usize + 1));
+            assert_eq!(reply.window_scale, None);
+            assert!(!reply.sack_permitted);
+            assert_eq!(s.state, State::Listen);
+            assert_eq!(s.tuple, None);
+        }
+        recv_nothing!(s);
+    }
+
+    #[test]
+    fn test_listen_syn_cookie_handshake() {
+        let mut s = socket_listen();
+        s.set_syn_cookies(true);
+
+        let reply = send(
+            &mut s,
+            Instant::from_millis(0),
+            &TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                max_seg_size: Some(1400),
+                ..SEND_TEMPL
+            },
+        )
+        .unwrap();
+        assert_eq!(s.state, State::Listen);
+        let cookie = reply.seq_number;
+
+        // An ACK with the wrong cookie is not accepted.
+        let ack_repr = TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(cookie + 2),
+            ..SEND_TEMPL
+        };
+        assert!(!s.socket.accepts(&mut s.cx, &SEND_IP_TEMPL, &ack_repr));
+
+        // Neither is a valid cookie once it has expired.
+        let ack_repr = TcpRepr {
+            ack_number: Some(cookie + 1),
+            ..ack_repr
+        };
+        s.cx.set_now(Instant::from_secs(200));
+        assert!(!s.socket.accepts(&mut s.cx, &SEND_IP_TEMPL, &ack_repr));
+
+        // A valid cookie completes the handshake, with data.
+        send!(
+            s,
+            time 1000,
+            TcpRepr {
+                payload: &b"abcdef"[..],
+                ..ack_repr
+            }
+        );
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.tuple.unwrap().remote, REMOTE_END);
+        assert_eq!(s.local_seq_no, cookie + 1);
+        assert_eq!(s.remote_seq_no, REMOTE_SEQ + 1);
+        assert_eq!(s.remote_mss, 1380);
+        assert_eq!(s.recv_slice(&mut [0; 6]), Ok(6));
+
+        s.send_slice(b"012345").unwrap();
+        recv!(
+            s,
+            time 1000,
+            Ok(TcpRepr {
+                seq_number: cookie + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                payload: &b"012345"[..],
+                window_len: 64,
+                ..RECV_TEMPL
+            })
+        );
+    }
+
     #[test]
     fn test_listen_syn_reject_ack() {
         let mut s = socket_listen();
@@ -3010,6 +4327,80 @@ mod test {
         sanity!(s, socket_established());
     }
 
+    #[test]
+    fn test_remote_mss_and_window_scale() {
+        let mut s = socket_listen();
+        assert_eq!(s.remote_mss(), None);
+        assert_eq!(s.remote_window_scale(), None);
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                max_seg_size: Some(1400),
+                window_scale: Some(5),
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                window_scale: Some(0),
+                ..RECV_TEMPL
+            }]
+        );
+        assert_eq!(s.remote_mss(), None);
+        assert_eq!(s.remote_window_scale(), None);
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                window_len: 64,
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.remote_mss(), Some(1400));
+        assert_eq!(s.remote_window_scale(), Some(5));
+
+        // Without options, the default MSS is used and window scaling is not negotiated.
+        let mut s = socket_syn_sent();
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: None,
+                max_seg_size: Some(BASE_MSS),
+                window_scale: Some(0),
+                sack_permitted: true,
+                ..RECV_TEMPL
+            }]
+        );
+        assert_eq!(s.remote_mss(), None);
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.remote_mss(), Some(536));
+        assert_eq!(s.remote_window_scale(), None);
+
+        s.abort();
+        assert_eq!(s.remote_mss(), None);
+    }
+
     #[test]
     fn test_syn_received_ack_too_low() {
         let mut s = socket_syn_received();
@@ -3229,6 +4620,25 @@ mod test {
     // Tests for the SYN-SENT state.
     // =========================================================================================//
 
+    #[test]
+    fn test_endpoints() {
+        let mut s = socket();
+        assert_eq!(s.local_endpoint(), None);
+        assert_eq!(s.remote_endpoint(), None);
+
+        // A listening socket has no connection yet.
+        assert_eq!(s.listen(LOCAL_END), Ok(()));
+        assert_eq!(s.local_endpoint(), None);
+        assert_eq!(s.remote_endpoint(), None);
+
+        let mut s = socket();
+        s.socket
+            .connect(&mut s.cx, REMOTE_END, LOCAL_END)
+            .expect("Connect failed with valid parameters");
+        assert_eq!(s.local_endpoint(), Some(LOCAL_END));
+        assert_eq!(s.remote_endpoint(), Some(REMOTE_END));
+    }
+
     #[test]
     fn test_connect_validation() {
         let mut s = socket();
@@ -3288,7 +4698,60 @@ mod test {
     }
 
     #[test]
-    fn test_connect_unspecified_local() {
+    fn test_connect_uses_rand_source() {
+        let mut rand = FixedRand(0x0bad_cafe);
+        let mut cx = context_with_rand_source(&mut rand);
+        let mut s = socket();
+        s.socket
+            .connect(&mut cx, REMOTE_END, LOCAL_END.port)
+            .unwrap();
+        assert_eq!(
+            s.local_seq_no,
+            TcpSeqNumber(Socket::seq_no_offset(0x0bad_cafe_0bad_cafe, TUPLE) as i32)
+        );
+    }
+
+    #[test]
+    fn test_secure_seq_no_tuples() {
+        let mut s = socket();
+        let isn_a = Socket::secure_seq_no(&mut s.cx, TUPLE);
+        let isn_b = Socket::secure_seq_no(
+            &mut s.cx,
+            Tuple {
+                remote: IpEndpoint::new(REMOTE_END.addr, REMOTE_END.port + 1),
+                ..TUPLE
+            },
+        );
+        assert_eq!(Socket::secure_seq_no(&mut s.cx, TUPLE), isn_a);
+        // Neighbouring ports don't give neighbouring sequence numbers.
+        assert!((isn_a.0.wrapping_sub(isn_b.0)).unsigned_abs() > 1 << 16);
+    }
+
+    #[test]
+    fn test_secure_seq_no_clock() {
+        let mut s = socket();
+        let isn_0 = Socket::secure_seq_no(&mut s.cx, TUPLE);
+        s.cx.set_now(Instant::from_millis(4));
+        let isn_1 = Socket::secure_seq_no(&mut s.cx, TUPLE);
+        s.cx.set_now(Instant::from_secs(4));
+        let isn_2 = Socket::secure_seq_no(&mut s.cx, TUPLE);
+        assert_eq!(isn_1 - isn_0, 1_000);
+        assert_eq!(isn_2 - isn_0, 1_000_000);
+    }
+
+    #[test]
+    fn test_secure_seq_no_rand_source() {
+        let (mut rand1, mut rand2) = (FixedRand(1), FixedRand(2));
+        let mut cx1 = context_with_rand_source(&mut rand1);
+        let mut cx2 = context_with_rand_source(&mut rand2);
+        assert_ne!(
+            Socket::secure_seq_no(&mut cx1, TUPLE),
+            Socket::secure_seq_no(&mut cx2, TUPLE)
+        );
+    }
+
+    #[test]
+    fn test_connect_unspecified_local() {
         let mut s = socket();
         assert_eq!(s.socket.connect(&mut s.cx, REMOTE_END, 80), Ok(()));
     }
@@ -3682,6 +5145,235 @@ mod test {
         assert_eq!(s.rx_buffer.dequeue_many(6), &b"abcdef"[..]);
     }
 
+    #[test]
+    fn test_recv_with_meta_push_boundary() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Psh,
+                seq_number: REMOTE_SEQ + 1 + 3,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"def"[..],
+                ..SEND_TEMPL
+            }
+        );
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 6,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"gh"[..],
+                ..SEND_TEMPL
+            }
+        );
+
+        let mut buf = [0u8; 64];
+        assert_eq!(s.recv_with_meta(&mut buf), Ok((6, RecvMeta { push: true })));
+        assert_eq!(&buf[..6], b"abcdef");
+        assert_eq!(
+            s.recv_with_meta(&mut buf),
+            Ok((2, RecvMeta { push: false }))
+        );
+        assert_eq!(&buf[..2], b"gh");
+        assert_eq!(
+            s.recv_with_meta(&mut buf),
+            Ok((0, RecvMeta { push: false }))
+        );
+    }
+
+    #[test]
+    fn test_recv_with_meta_partial_read() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Psh,
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+
+        let mut buf = [0u8; 4];
+        assert_eq!(
+            s.recv_with_meta(&mut buf),
+            Ok((4, RecvMeta { push: false }))
+        );
+        assert_eq!(s.recv_with_meta(&mut buf), Ok((2, RecvMeta { push: true })));
+        assert_eq!(&buf[..2], b"ef");
+    }
+
+    #[test]
+    fn test_recv_with_meta_push_out_of_order() {
+        let mut s = socket_established();
+        // The pushed segment arrives before the one preceding it.
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Psh,
+                seq_number: REMOTE_SEQ + 1 + 3,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"def"[..],
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                window_len: 64,
+                sack_ranges: [None, None, None],
+                ..RECV_TEMPL
+            })
+        );
+        let mut buf = [0u8; 64];
+        assert_eq!(
+            s.recv_with_meta(&mut buf),
+            Ok((0, RecvMeta { push: false }))
+        );
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 58,
+                ..RECV_TEMPL
+            })
+        );
+        assert_eq!(s.recv_with_meta(&mut buf), Ok((6, RecvMeta { push: true })));
+        assert_eq!(&buf[..6], b"abcdef");
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_recv_push_wakes_receiver() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::task::Wake;
+
+        struct Flag(AtomicBool);
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let mut s = socket_established();
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        s.register_recv_waker(&flag.clone().into());
+
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Psh,
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert!(flag.0.load(Ordering::SeqCst));
+
+        let mut buf = [0u8; 64];
+        assert_eq!(s.recv_with_meta(&mut buf), Ok((3, RecvMeta { push: true })));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_recv_lowat_wakes_receiver() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::task::Wake;
+
+        struct Flag(AtomicBool);
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let mut s = socket_established();
+        s.set_recv_lowat(6);
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        s.register_recv_waker(&flag.clone().into());
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert!(!flag.0.load(Ordering::SeqCst));
+        assert!(!s.can_recv());
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 3,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"def"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert!(s.can_recv());
+    }
+
+    #[test]
+    fn test_lowat() {
+        let mut s = socket_established();
+        s.set_recv_lowat(4);
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert!(!s.can_recv());
+        // The data can be received anyway.
+        assert_eq!(s.peek(3), Ok(&b"abc"[..]));
+        // Once the remote endpoint closes the connection, the remaining data is ready.
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Fin,
+                seq_number: REMOTE_SEQ + 1 + 3,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            }
+        );
+        assert!(s.can_recv());
+
+        // Marks larger than the buffer are reached once the buffer is empty.
+        let mut s = socket_established();
+        s.set_send_lowat(1000);
+        assert!(s.can_send());
+        s.send_slice(b"abcdef").unwrap();
+        assert!(!s.can_send());
+    }
+
     #[test]
     fn test_peek_slice() {
         const BUF_SIZE: usize = 10;
@@ -4603,11 +6295,79 @@ mod test {
         assert_eq!(
             s.timer,
             Timer::Close {
-                expires_at: Instant::from_secs(5) + CLOSE_DELAY
+                expires_at: Instant::from_secs(5) + DEFAULT_MSL * 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_time_wait_ignores_stray_rst() {
+        let mut s = socket_time_wait(false);
+        // In the receive window, but not at the next expected sequence number.
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Rst,
+            seq_number: REMOTE_SEQ + 1 + 1 + 10,
+            ack_number: None,
+            ..SEND_TEMPL
+        });
+        // Out of the receive window.
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Rst,
+            seq_number: REMOTE_SEQ + 1 + 1 + 1000,
+            ack_number: None,
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::TimeWait);
+        // The TIME-WAIT timeout isn't restarted either.
+        assert_eq!(
+            s.timer,
+            Timer::Close {
+                expires_at: Instant::from_secs(1) + DEFAULT_MSL * 2
             }
         );
     }
 
+    #[test]
+    fn test_time_wait_exact_rst() {
+        let mut s = socket_time_wait(false);
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Rst,
+            seq_number: REMOTE_SEQ + 1 + 1,
+            ack_number: None,
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::Closed);
+    }
+
+    #[test]
+    fn test_time_wait_short_msl() {
+        let mut s = socket_fin_wait_2();
+        s.set_msl(Duration::from_millis(1_500));
+        send!(s, time 1_000, TcpRepr {
+            control: TcpControl::Fin,
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 1),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::TimeWait);
+        recv!(s, time 1_000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 1,
+            ack_number: Some(REMOTE_SEQ + 1 + 1),
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 3_999);
+        assert_eq!(s.state, State::TimeWait);
+        recv_nothing!(s, time 4_000);
+        assert_eq!(s.state, State::Closed);
+    }
+
+    #[test]
+    fn test_set_msl_minimum() {
+        let mut s = socket();
+        s.set_msl(Duration::from_millis(10));
+        assert_eq!(s.msl(), MIN_MSL);
+    }
+
     #[test]
     fn test_time_wait_timeout() {
         let mut s = socket_time_wait(false);
@@ -4910,6 +6670,69 @@ mod test {
         recv!(s, []);
     }
 
+    /// Dispatch a control segment from a socket, for delivery to another socket.
+    fn dispatch_segment(s: &mut TestSocket) -> (IpRepr, TcpRepr<'static>) {
+        let mut segment = None;
+        s.socket
+            .dispatch(&mut s.cx, |_, (ip_repr, tcp_repr)| {
+                assert!(tcp_repr.payload.is_empty());
+                net_trace!("dispatch: {}", tcp_repr);
+                segment = Some((
+                    ip_repr,
+                    TcpRepr {
+                        payload: &[],
+                        ..tcp_repr
+                    },
+                ));
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        segment.expect("a segment should be sent")
+    }
+
+    fn deliver_segment(s: &mut TestSocket, (ip_repr, tcp_repr): (IpRepr, TcpRepr)) {
+        assert!(s.socket.accepts(&mut s.cx, &ip_repr, &tcp_repr));
+        assert_eq!(s.socket.process(&mut s.cx, &ip_repr, &tcp_repr), None);
+    }
+
+    #[test]
+    fn test_simultaneous_close_two_sockets() {
+        let mut a = socket_established();
+        let mut b = socket_established();
+        b.tuple = Some(Tuple {
+            local: TUPLE.remote,
+            remote: TUPLE.local,
+        });
+        b.local_seq_no = REMOTE_SEQ + 1;
+        b.remote_seq_no = LOCAL_SEQ + 1;
+        b.remote_last_seq = REMOTE_SEQ + 1;
+        b.remote_last_ack = Some(LOCAL_SEQ + 1);
+
+        a.close();
+        b.close();
+        assert_eq!(a.state, State::FinWait1);
+        assert_eq!(b.state, State::FinWait1);
+
+        // Both FINs cross on the wire, so neither acknowledges the other.
+        let (a_fin, b_fin) = (dispatch_segment(&mut a), dispatch_segment(&mut b));
+        assert_eq!(a_fin.1.control, TcpControl::Fin);
+        assert_eq!(b_fin.1.control, TcpControl::Fin);
+        deliver_segment(&mut b, a_fin);
+        deliver_segment(&mut a, b_fin);
+        assert_eq!(a.state, State::Closing);
+        assert_eq!(b.state, State::Closing);
+
+        // Both sides acknowledge the FIN they received.
+        let (a_ack, b_ack) = (dispatch_segment(&mut a), dispatch_segment(&mut b));
+        deliver_segment(&mut b, a_ack);
+        deliver_segment(&mut a, b_ack);
+        assert_eq!(a.state, State::TimeWait);
+        assert_eq!(b.state, State::TimeWait);
+
+        recv_nothing!(a);
+        recv_nothing!(b);
+    }
+
     #[test]
     fn test_simultaneous_close_combined_fin_ack() {
         let mut s = socket_established();
@@ -5164,11 +6987,125 @@ mod test {
     }
 
     #[test]
-    fn test_data_retransmit_bursts() {
+    fn test_max_retransmits() {
         let mut s = socket_established();
-        s.remote_mss = 6;
-        s.send_slice(b"abcdef012345").unwrap();
-
+        s.set_max_retransmits(Some(2));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        for t in [700, 1400] {
+            recv_nothing!(s, time t - 1);
+            recv!(s, time t, Ok(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload:    &b"abcdef"[..],
+                ..RECV_TEMPL
+            }));
+        }
+        recv_nothing!(s, time 2099);
+        recv!(s, time 2100, Ok(TcpRepr {
+            control:    TcpControl::Rst,
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Closed);
+    }
+
+    #[test]
+    fn test_max_retransmits_reset_on_ack() {
+        let mut s = socket_established();
+        s.set_max_retransmits(Some(1));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 700, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+
+        // Acknowledging new data restarts the count.
+        send!(s, time 800, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 6),
+            ..SEND_TEMPL
+        });
+        s.send_slice(b"ghijkl").unwrap();
+        recv!(s, time 800, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"ghijkl"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 2000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"ghijkl"[..],
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Established);
+    }
+
+    #[test]
+    fn test_max_rto() {
+        let mut s = socket_established();
+        s.set_max_rto(Duration::from_millis(500));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        // Without the cap, the timeout would start at 700ms and keep growing.
+        for t in (500..=4000).step_by(500) {
+            recv_nothing!(s, time t - 1);
+            recv!(s, time t, Ok(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload:    &b"abcdef"[..],
+                ..RECV_TEMPL
+            }));
+        }
+    }
+
+    #[test]
+    fn test_max_rto_lower_bound() {
+        let mut s = socket_established();
+        s.set_max_rto(Duration::ZERO);
+        assert_eq!(s.max_rto(), Duration::from_millis(10));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 9);
+        recv!(s, time 10, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+    }
+
+    #[test]
+    fn test_data_retransmit_bursts() {
+        let mut s = socket_established();
+        s.remote_mss = 6;
+        s.send_slice(b"abcdef012345").unwrap();
+
         recv!(s, time 0, Ok(TcpRepr {
             control:    TcpControl::None,
             seq_number: LOCAL_SEQ + 1,
@@ -5204,6 +7141,90 @@ mod test {
         recv_nothing!(s, time 1550);
     }
 
+    #[test]
+    fn test_data_retransmit_repacketize_smaller_mss() {
+        let mut s = socket_established();
+        s.remote_mss = 6;
+        s.send_slice(b"abcdef012345").unwrap();
+
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::None,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"012345"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv_nothing!(s, time 0);
+
+        // The path MSS shrinks while both segments are lost.
+        s.remote_mss = 4;
+
+        recv!(s, time 1000, Ok(TcpRepr {
+            control:    TcpControl::None,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcd"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv!(s, time 1000, Ok(TcpRepr {
+            control:    TcpControl::None,
+            seq_number: LOCAL_SEQ + 1 + 4,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"ef01"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv!(s, time 1000, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1 + 8,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"2345"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv_nothing!(s, time 1000);
+    }
+
+    #[test]
+    fn test_data_retransmit_repacketize_merge() {
+        let mut s = socket_established();
+        s.remote_mss = 6;
+        s.set_nagle_enabled(false);
+
+        s.send_slice(b"abc").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abc"[..],
+            ..RECV_TEMPL
+        }), exact);
+        s.send_slice(b"def").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1 + 3,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"def"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv_nothing!(s, time 0);
+
+        // Both segments are lost, and are retransmitted as a single one.
+        recv!(s, time 1000, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv_nothing!(s, time 1000);
+    }
+
     #[test]
     fn test_data_retransmit_bursts_half_ack() {
         let mut s = socket_established();
@@ -6328,64 +8349,242 @@ mod test {
         assert!(s.window_to_update());
     }
 
-    // =========================================================================================//
-    // Tests for timeouts.
-    // =========================================================================================//
-
     #[test]
-    fn test_listen_timeout() {
-        let mut s = socket_listen();
-        s.set_timeout(Some(Duration::from_millis(100)));
-        assert_eq!(s.socket.poll_at(&mut s.cx), PollAt::Ingress);
-    }
+    fn test_recv_paused() {
+        let mut s = socket_established();
+        s.set_recv_paused(true);
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 58,
+                ..RECV_TEMPL
+            }]
+        );
+        // Dequeuing data does not reopen the window.
+        s.recv(|buffer| (buffer.len(), ())).unwrap();
+        assert!(!s.window_to_update());
+        recv_nothing!(s);
 
-    #[test]
-    fn test_connect_timeout() {
-        let mut s = socket();
-        s.local_seq_no = LOCAL_SEQ;
-        s.socket
-            .connect(&mut s.cx, REMOTE_END, LOCAL_END.port)
-            .unwrap();
-        s.set_timeout(Some(Duration::from_millis(100)));
-        recv!(s, time 150, Ok(TcpRepr {
-            control:    TcpControl::Syn,
-            seq_number: LOCAL_SEQ,
-            ack_number: None,
-            max_seg_size: Some(BASE_MSS),
-            window_scale: Some(0),
-            sack_permitted: true,
-            ..RECV_TEMPL
-        }));
-        assert_eq!(s.state, State::SynSent);
-        assert_eq!(
-            s.socket.poll_at(&mut s.cx),
-            PollAt::Time(Instant::from_millis(250))
+        // Filling the window that was offered closes it, even though the buffer has room.
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 6,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &[0; 58][..],
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 64),
+                window_len: 0,
+                ..RECV_TEMPL
+            }]
+        );
+        assert_eq!(s.rx_buffer.window(), 6);
+
+        // Resuming reopens the window.
+        s.set_recv_paused(false);
+        assert!(s.window_to_update());
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 64),
+                window_len: 6,
+                ..RECV_TEMPL
+            }]
         );
-        recv!(s, time 250, Ok(TcpRepr {
-            control:    TcpControl::Rst,
-            seq_number: LOCAL_SEQ + 1,
-            ack_number: Some(TcpSeqNumber(0)),
-            window_scale: None,
-            ..RECV_TEMPL
-        }));
-        assert_eq!(s.state, State::Closed);
     }
 
     #[test]
-    fn test_established_timeout() {
+    fn test_window_clamp() {
         let mut s = socket_established();
-        s.set_timeout(Some(Duration::from_millis(1000)));
-        recv_nothing!(s, time 250);
-        assert_eq!(
-            s.socket.poll_at(&mut s.cx),
-            PollAt::Time(Instant::from_millis(1250))
-        );
-        s.send_slice(b"abcdef").unwrap();
-        assert_eq!(s.socket.poll_at(&mut s.cx), PollAt::Now);
-        recv!(s, time 255, Ok(TcpRepr {
-            seq_number: LOCAL_SEQ + 1,
-            ack_number: Some(REMOTE_SEQ + 1),
-            payload:    &b"abcdef"[..],
+        s.set_window_clamp(Some(20));
+        assert_eq!(s.window_clamp(), Some(20));
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 20,
+                ..RECV_TEMPL
+            }]
+        );
+
+        // Once the free space in the buffer is below the clamp, it is advertised.
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 6,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &[0; 48][..],
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 54),
+                window_len: 10,
+                ..RECV_TEMPL
+            }]
+        );
+
+        // Dequeuing data reopens the window up to the clamp only.
+        s.recv(|buffer| (buffer.len(), ())).unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 54),
+                window_len: 20,
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_window_clamp_win_scale() {
+        let mut s = socket_with_buffer_sizes(64, 1048576);
+        assert_eq!(s.remote_win_shift, 5);
+
+        // The scaling factor offered is only as large as the clamped window needs.
+        s.set_window_clamp(Some(131072));
+        assert_eq!(s.remote_win_shift, 2);
+        s.set_window_clamp(Some(65535));
+        assert_eq!(s.remote_win_shift, 0);
+        s.set_window_clamp(None);
+        assert_eq!(s.remote_win_shift, 5);
+
+        s.set_window_clamp(Some(131072));
+        s.socket
+            .connect(&mut s.cx, REMOTE_END, LOCAL_END.port)
+            .unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: None,
+                max_seg_size: Some(BASE_MSS),
+                window_len: 65535,
+                window_scale: Some(2),
+                sack_permitted: true,
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: Some(LOCAL_SEQ + 1),
+                max_seg_size: Some(BASE_MSS - 80),
+                window_scale: Some(0),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+
+        // The scaled window never exceeds the clamp, and the clamp can still be lowered
+        // after the scaling factor was negotiated.
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                window_len: 32768,
+                ..RECV_TEMPL
+            }]
+        );
+        s.set_window_clamp(Some(4000));
+        assert_eq!(s.remote_win_shift, 2);
+        assert_eq!(s.scaled_window(), 1000);
+    }
+
+    // =========================================================================================//
+    // Tests for timeouts.
+    // =========================================================================================//
+
+    #[test]
+    fn test_listen_timeout() {
+        let mut s = socket_listen();
+        s.set_timeout(Some(Duration::from_millis(100)));
+        assert_eq!(s.socket.poll_at(&mut s.cx), PollAt::Ingress);
+    }
+
+    #[test]
+    fn test_connect_timeout() {
+        let mut s = socket();
+        s.local_seq_no = LOCAL_SEQ;
+        s.socket
+            .connect(&mut s.cx, REMOTE_END, LOCAL_END.port)
+            .unwrap();
+        s.set_timeout(Some(Duration::from_millis(100)));
+        recv!(s, time 150, Ok(TcpRepr {
+            control:    TcpControl::Syn,
+            seq_number: LOCAL_SEQ,
+            ack_number: None,
+            max_seg_size: Some(BASE_MSS),
+            window_scale: Some(0),
+            sack_permitted: true,
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::SynSent);
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_millis(250))
+        );
+        recv!(s, time 250, Ok(TcpRepr {
+            control:    TcpControl::Rst,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(TcpSeqNumber(0)),
+            window_scale: None,
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Closed);
+    }
+
+    #[test]
+    fn test_established_timeout() {
+        let mut s = socket_established();
+        s.set_timeout(Some(Duration::from_millis(1000)));
+        recv_nothing!(s, time 250);
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_millis(1250))
+        );
+        s.send_slice(b"abcdef").unwrap();
+        assert_eq!(s.socket.poll_at(&mut s.cx), PollAt::Now);
+        recv!(s, time 255, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
             ..RECV_TEMPL
         }));
         assert_eq!(
@@ -6668,6 +8867,188 @@ mod test {
         .unwrap();
     }
 
+    #[test]
+    fn test_in_order_and_reordered_delivery() {
+        const CHUNKS: [&[u8]; 4] = [b"abc", b"def", b"ghi", b"jkl"];
+
+        // Segments must be delivered in order, whichever order they arrive in.
+        for order in [
+            [0, 1, 2, 3],
+            [1, 0, 2, 3],
+            [0, 2, 1, 3],
+            [0, 1, 3, 2],
+            [3, 2, 1, 0],
+            [2, 0, 3, 1],
+        ] {
+            let mut s = socket_established();
+            let mut received = [false; 4];
+            for index in order {
+                let reply = send(
+                    &mut s,
+                    Instant::from_millis(0),
+                    &TcpRepr {
+                        seq_number: REMOTE_SEQ + 1 + index * 3,
+                        ack_number: Some(LOCAL_SEQ + 1),
+                        payload: CHUNKS[index],
+                        ..SEND_TEMPL
+                    },
+                );
+                received[index] = true;
+
+                let contiguous = received.iter().take_while(|r| **r).count() * 3;
+                assert_eq!(s.recv_queue(), contiguous, "order {order:?}");
+                let out_of_order = received.iter().filter(|r| **r).count() * 3 - contiguous;
+                assert_eq!(s.out_of_order_len(), out_of_order, "order {order:?}");
+                if let Some(reply) = reply {
+                    assert_eq!(
+                        reply.ack_number,
+                        Some(REMOTE_SEQ + 1 + contiguous),
+                        "order {order:?}"
+                    );
+                }
+            }
+            s.recv(|buffer| {
+                assert_eq!(buffer, b"abcdefghijkl", "order {order:?}");
+                (buffer.len(), ())
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_out_of_order_limit() {
+        let mut s = socket_established();
+        s.set_out_of_order_limit(Some(6));
+
+        // Feed every other 3-octet chunk, leaving holes in between.
+        for (offset, payload) in [(3, b"bbb"), (9, b"ddd")] {
+            let result = send(
+                &mut s,
+                Instant::from_millis(0),
+                &TcpRepr {
+                    seq_number: REMOTE_SEQ + 1 + offset,
+                    ack_number: Some(LOCAL_SEQ + 1),
+                    payload: &payload[..],
+                    ..SEND_TEMPL
+                },
+            );
+            assert!(result.is_some());
+        }
+        assert_eq!(s.out_of_order_len(), 6);
+
+        // Over the limit, dropped.
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 15,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"fff"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.out_of_order_len(), 6);
+
+        // Filling the first gap delivers data in order.
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"aaa"[..],
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 58,
+                ..RECV_TEMPL
+            })
+        );
+        assert_eq!(s.out_of_order_len(), 3);
+
+        // The previously dropped segment now fits.
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 15,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"fff"[..],
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 58,
+                ..RECV_TEMPL
+            })
+        );
+        assert_eq!(s.out_of_order_len(), 6);
+
+        // Filling the remaining gaps completes in-order delivery.
+        for (offset, payload) in [(6, b"ccc"), (12, b"eee")] {
+            send(
+                &mut s,
+                Instant::from_millis(0),
+                &TcpRepr {
+                    seq_number: REMOTE_SEQ + 1 + offset,
+                    ack_number: Some(LOCAL_SEQ + 1),
+                    payload: &payload[..],
+                    ..SEND_TEMPL
+                },
+            );
+        }
+        assert_eq!(s.out_of_order_len(), 0);
+        s.recv(|buffer| {
+            assert_eq!(buffer, b"aaabbbcccdddeeefff");
+            (buffer.len(), ())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_out_of_order_limit_many_segments() {
+        let mut s = socket_established_with_buffer_sizes(64, 1024);
+        s.set_out_of_order_limit(Some(100));
+
+        // Send many non-contiguous segments, in reverse order so that each one
+        // is out of order until the very first segment arrives.
+        for i in (1..40).rev() {
+            send(
+                &mut s,
+                Instant::from_millis(0),
+                &TcpRepr {
+                    seq_number: REMOTE_SEQ + 1 + i * 10,
+                    ack_number: Some(LOCAL_SEQ + 1),
+                    payload: &[i as u8; 10][..],
+                    ..SEND_TEMPL
+                },
+            );
+            assert!(s.out_of_order_len() <= 100);
+        }
+
+        // Retransmit everything in order; all of it is eventually delivered.
+        for i in 0..40 {
+            send(
+                &mut s,
+                Instant::from_millis(0),
+                &TcpRepr {
+                    seq_number: REMOTE_SEQ + 1 + i * 10,
+                    ack_number: Some(LOCAL_SEQ + 1),
+                    payload: &[i as u8; 10][..],
+                    ..SEND_TEMPL
+                },
+            );
+            assert!(s.out_of_order_len() <= 100);
+        }
+        assert_eq!(s.out_of_order_len(), 0);
+
+        let mut data = [0; 400];
+        assert_eq!(s.recv_slice(&mut data[..]), Ok(400));
+        for (i, chunk) in data.chunks(10).enumerate() {
+            assert_eq!(chunk, &[i as u8; 10][..]);
+        }
+    }
+
     #[test]
     fn test_buffer_wraparound_rx() {
         let mut s = socket_established();
@@ -7201,6 +9582,173 @@ mod test {
         }), exact);
     }
 
+    #[test]
+    fn test_cork() {
+        let mut s = socket_established();
+        s.set_nagle_enabled(false);
+        s.remote_mss = 6;
+        s.set_cork(true);
+
+        // Small writes are held back.
+        s.send_slice(b"ab").unwrap();
+        recv_nothing!(s, time 0);
+        s.send_slice(b"cd").unwrap();
+        recv_nothing!(s, time 0);
+
+        // Full segments are sent, the remainder is held back.
+        s.send_slice(b"efgh").unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..RECV_TEMPL
+            }]
+        );
+
+        // Flushing releases the partial segment.
+        s.send_slice(b"i").unwrap();
+        s.flush();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1 + 6,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"ghi"[..],
+                ..RECV_TEMPL
+            }]
+        );
+
+        // Data written after the flush is held back again.
+        s.send_slice(b"jk").unwrap();
+        recv_nothing!(s, time 0);
+
+        // Uncorking releases it.
+        s.set_cork(false);
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1 + 9,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"jk"[..],
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cork_close() {
+        let mut s = socket_established();
+        s.set_cork(true);
+        s.send_slice(b"abc").unwrap();
+        recv_nothing!(s, time 0);
+
+        // Closing releases held back data along with the FIN.
+        s.close();
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Fin,
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"abc"[..],
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cork_retransmit() {
+        let mut s = socket_established();
+        s.set_cork(true);
+        s.send_slice(b"abc").unwrap();
+        s.flush();
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abc"[..],
+            ..RECV_TEMPL
+        }));
+
+        // Corking doesn't hold back retransmissions. Since a segment is being sent
+        // anyway, data that was held back rides along with it.
+        s.send_slice(b"def").unwrap();
+        recv_nothing!(s, time 1050);
+        recv!(s, time 2000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 2050);
+    }
+
+    #[test]
+    fn test_pacing() {
+        let mut s = socket_established();
+        s.set_nagle_enabled(false);
+        s.remote_mss = 6;
+        s.remote_win_len = 60;
+        s.set_pacing(true);
+
+        // With a 300 ms round-trip time and a 60 octet window, 6 octet segments are
+        // released every 30 ms.
+        s.send_slice(b"abcdefghijklmnopqr").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 29);
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_millis(30))
+        );
+        recv!(s, time 30, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"ghijkl"[..],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 59);
+        recv!(s, time 60, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 12,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"mnopqr"[..],
+            ..RECV_TEMPL
+        }));
+
+        // Once everything is sent, only the retransmit timer, which was started with
+        // the first segment, is left to wait for.
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::ZERO + s.rtte.retransmission_timeout())
+        );
+
+        // Without pacing, segments are sent back to back.
+        s.set_pacing(false);
+        s.send_slice(b"stuvwxyz0123").unwrap();
+        recv!(
+            s,
+            [
+                TcpRepr {
+                    seq_number: LOCAL_SEQ + 1 + 18,
+                    ack_number: Some(REMOTE_SEQ + 1),
+                    payload: &b"stuvwx"[..],
+                    ..RECV_TEMPL
+                },
+                TcpRepr {
+                    seq_number: LOCAL_SEQ + 1 + 24,
+                    ack_number: Some(REMOTE_SEQ + 1),
+                    payload: &b"yz0123"[..],
+                    ..RECV_TEMPL
+                }
+            ]
+        );
+    }
+
     // =========================================================================================//
     // Tests for packet filtering.
     // =========================================================================================//
@@ -7309,4 +9857,257 @@ mod test {
             assert_eq!(r.retransmission_timeout(), Duration::from_millis(rto));
         }
     }
+
+    #[test]
+    fn test_congestion_slow_start_counts_bytes() {
+        let mut c = CongestionController::new(CongestionControl::Reno);
+        assert_eq!(c.window(100), 400);
+
+        // The window grows by the octets acknowledged...
+        c.on_ack(50, 100);
+        assert_eq!(c.window(100), 450);
+        // ...but by at most 2*SMSS per ACK.
+        c.on_ack(300, 100);
+        assert_eq!(c.window(100), 650);
+
+        // ACKs for a few octets each do not grow the window by a segment each.
+        for _ in 0..100 {
+            c.on_ack(1, 100);
+        }
+        assert_eq!(c.window(100), 750);
+    }
+
+    #[test]
+    fn test_congestion_avoidance_counts_bytes() {
+        let mut c = CongestionController::new(CongestionControl::Reno);
+        c.on_fast_retransmit(1000, 100);
+        assert_eq!(c.window(100), 500);
+
+        // The window grows by SMSS once a full window has been acknowledged, no matter
+        // how many ACKs that took.
+        for _ in 0..499 {
+            c.on_ack(1, 100);
+        }
+        assert_eq!(c.window(100), 500);
+        c.on_ack(1, 100);
+        assert_eq!(c.window(100), 600);
+        c.on_ack(600, 100);
+        assert_eq!(c.window(100), 700);
+
+        c.on_retransmit(700, 100);
+        assert_eq!(c.window(100), 100);
+        assert_eq!(c.ssthresh, 350);
+    }
+
+    #[test]
+    fn test_congestion_none() {
+        let mut c = CongestionController::new(CongestionControl::None);
+        c.on_ack(1000, 100);
+        c.on_retransmit(1000, 100);
+        assert_eq!(c.window(100), usize::MAX);
+    }
+
+    #[test]
+    fn test_congestion_window_limits_send() {
+        let mut s = socket_established();
+        s.set_congestion_control(CongestionControl::Reno);
+        s.remote_mss = 6;
+        s.send_slice(b"abcdef123456ABCDEFghijkl!@#$%^").unwrap();
+
+        // The initial window is 4 segments.
+        for (i, chunk) in [&b"abcdef"[..], b"123456", b"ABCDEF", b"ghijkl"]
+            .into_iter()
+            .enumerate()
+        {
+            recv!(s, time 1000, Ok(TcpRepr {
+                seq_number: LOCAL_SEQ + 1 + i * 6,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload:    chunk,
+                ..RECV_TEMPL
+            }));
+        }
+        recv_nothing!(s, time 1000);
+
+        // Acknowledging 3 octets both slides the window and grows it by 3 octets.
+        send!(s, time 1010, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 3),
+            ..SEND_TEMPL
+        });
+        recv!(s, time 1010, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 24,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"!@#$%^"[..],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 1010);
+    }
+
+    // =========================================================================================//
+    // Tests for migrating the connection state.
+    // =========================================================================================//
+
+    fn migrate(s: &TestSocket) -> TestSocket {
+        let mut buffer = vec![0; s.serialized_state_len()];
+        assert_eq!(s.serialize_state(&mut buffer), Ok(buffer.len()));
+        let mut s2 = socket();
+        assert_eq!(s2.restore_state(&buffer), Ok(()));
+        s2
+    }
+
+    #[test]
+    fn test_restore_state_continues_connection() {
+        let mut s = socket_established();
+        s.send_slice(b"abcdef").unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1 + 3),
+                payload: &b"xyz"[..],
+                ..SEND_TEMPL
+            }
+        );
+
+        let mut s2 = migrate(&s);
+        assert_eq!(s2.state, State::Established);
+        assert_eq!(s2.tuple, Some(TUPLE));
+        assert_eq!(s2.recv_queue(), 3);
+        assert_eq!(s2.send_queue(), 3);
+
+        // Unacknowledged data is retransmitted, and received data is acknowledged.
+        recv!(
+            s2,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1 + 3,
+                ack_number: Some(REMOTE_SEQ + 1 + 3),
+                window_len: 61,
+                payload: &b"def"[..],
+                ..RECV_TEMPL
+            }]
+        );
+        let mut data = [0; 3];
+        assert_eq!(s2.recv_slice(&mut data), Ok(3));
+        assert_eq!(&data, b"xyz");
+
+        send!(
+            s2,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 3,
+                ack_number: Some(LOCAL_SEQ + 1 + 6),
+                payload: &b"uvw"[..],
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s2,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1 + 6,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 61,
+                ..RECV_TEMPL
+            }]
+        );
+        assert_eq!(s2.send_queue(), 0);
+        assert_eq!(s2.recv_slice(&mut data), Ok(3));
+        assert_eq!(&data, b"uvw");
+
+        // Closing the restored socket finishes the connection in the usual way.
+        s2.close();
+        recv!(
+            s2,
+            [TcpRepr {
+                control: TcpControl::Fin,
+                seq_number: LOCAL_SEQ + 1 + 6,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                ..RECV_TEMPL
+            }]
+        );
+        assert_eq!(s2.state, State::FinWait1);
+    }
+
+    #[test]
+    fn test_restore_state_retransmits_fin() {
+        let mut s = socket_established();
+        s.close();
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Fin,
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                ..RECV_TEMPL
+            }]
+        );
+
+        let mut s2 = migrate(&s);
+        assert_eq!(s2.state, State::FinWait1);
+        recv!(
+            s2,
+            [TcpRepr {
+                control: TcpControl::Fin,
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s2,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1 + 1),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s2.state, State::FinWait2);
+    }
+
+    #[test]
+    fn test_serialize_state_errors() {
+        let s = socket_syn_received();
+        assert_eq!(
+            s.serialize_state(&mut [0; 128]),
+            Err(SerializeError::InvalidState)
+        );
+
+        let s = socket_established();
+        let len = s.serialized_state_len();
+        assert_eq!(
+            s.serialize_state(&mut vec![0; len - 1]),
+            Err(SerializeError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_restore_state_errors() {
+        let mut s = socket_established();
+        s.send_slice(b"abcdef").unwrap();
+        let mut buffer = vec![0; s.serialized_state_len()];
+        s.serialize_state(&mut buffer).unwrap();
+
+        assert_eq!(s.restore_state(&buffer), Err(RestoreError::InvalidState));
+
+        let mut s2 = socket();
+        assert_eq!(
+            s2.restore_state(&buffer[..buffer.len() - 1]),
+            Err(RestoreError::Malformed)
+        );
+        buffer[0] = 0;
+        assert_eq!(s2.restore_state(&buffer), Err(RestoreError::Malformed));
+        buffer[0] = STATE_FORMAT_VERSION;
+        assert_eq!(s2.state, State::Closed);
+
+        let mut s2 = socket_with_buffer_sizes(4, 64);
+        assert_eq!(s2.restore_state(&buffer), Err(RestoreError::BufferTooSmall));
+        assert_eq!(s2.state, State::Closed);
+    }
 }
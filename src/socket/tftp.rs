@@ -0,0 +1,1373 @@
+#[cfg(feature = "async")]
+use core::task::Waker;
+
+use heapless::Vec;
+
+use crate::iface::Context;
+use crate::socket::PollAt;
+#[cfg(feature = "async")]
+use crate::socket::WakerRegistration;
+use crate::storage::RingBuffer;
+use crate::time::{Duration, Instant};
+use crate::wire::{
+    IpAddress, IpEndpoint, IpProtocol, IpRepr, TftpErrorCode, TftpMode, TftpPacket, TftpRepr,
+    UdpRepr, TFTP_DEFAULT_BLOCK_SIZE, TFTP_MIN_BLOCK_SIZE, TFTP_SERVER_PORT,
+};
+
+/// The largest block size the socket can request. A block of this size fills a 1500 octet
+/// MTU when carried over IPv4.
+pub const MAX_BLOCK_SIZE: u16 = 1468;
+
+const MAX_FILENAME_LEN: usize = 128;
+const RETRANSMIT_TIMEOUT: Duration = Duration::from_millis(1_000);
+const MAX_RETRANSMITS: u8 = 5;
+
+/// A TFTP socket ring buffer.
+pub type SocketBuffer<'a> = RingBuffer<'a, u8>;
+
+/// Error returned by [`Socket::read_request`] and [`Socket::write_request`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RequestError {
+    /// A transfer is already in progress.
+    InvalidState,
+    InvalidName,
+    NameTooLong,
+    /// The buffer for the transfer can't hold a single block.
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RequestError::InvalidState => write!(f, "invalid state"),
+            RequestError::InvalidName => write!(f, "invalid name"),
+            RequestError::NameTooLong => write!(f, "name too long"),
+            RequestError::BufferTooSmall => write!(f, "buffer too small"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RequestError {}
+
+/// Error returned by [`Socket::send_slice`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SendError {
+    InvalidState,
+}
+
+impl core::fmt::Display for SendError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SendError::InvalidState => write!(f, "invalid state"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SendError {}
+
+/// Error returned by [`Socket::recv_slice`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecvError {
+    InvalidState,
+    Finished,
+}
+
+impl core::fmt::Display for RecvError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RecvError::InvalidState => write!(f, "invalid state"),
+            RecvError::Finished => write!(f, "transfer finished"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RecvError {}
+
+/// The reason a transfer failed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransferError {
+    /// The server stopped responding.
+    Timeout,
+    /// The server aborted the transfer with an ERROR packet.
+    Remote(TftpErrorCode),
+    /// The server violated the protocol, e.g. by increasing the block size.
+    Protocol,
+}
+
+/// The state of a TFTP socket.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum State {
+    /// No transfer has been started.
+    Idle,
+    /// A request was sent, and the server hasn't answered yet.
+    Requesting,
+    /// Blocks are being transferred.
+    Transferring,
+    /// The last block was transferred.
+    Finished,
+    /// The transfer was aborted.
+    Failed(TransferError),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Direction {
+    Read,
+    Write,
+}
+
+/// A Trivial File Transfer Protocol client socket.
+///
+/// The socket performs one transfer at a time, in octet mode, as described in [RFC 1350].
+/// For a read request, received blocks are written into the receive buffer and acknowledged
+/// as long as there is space to hold them; for a write request, data written into the transmit
+/// buffer is sent in blocks, the last of which is sent after calling [`close`](#method.close).
+///
+/// [RFC 1350]: https://tools.ietf.org/html/rfc1350
+#[derive(Debug)]
+pub struct Socket<'a> {
+    rx_buffer: SocketBuffer<'a>,
+    tx_buffer: SocketBuffer<'a>,
+    state: State,
+    direction: Direction,
+    filename: Vec<u8, MAX_FILENAME_LEN>,
+    /// The server endpoint. The port is replaced by the server's transfer identifier
+    /// once it answers the request.
+    remote_endpoint: Option<IpEndpoint>,
+    local_port: u16,
+    /// The block size requested from the server via the `blksize` option.
+    block_size: u16,
+    /// The block size in use for the current transfer.
+    transfer_block_size: u16,
+    /// The last block received (reads) or acknowledged (writes).
+    block: u16,
+    /// The length of the block sent but not yet acknowledged (writes).
+    in_flight: Option<usize>,
+    /// Whether a packet should be sent without waiting for the retransmission timer.
+    send_now: bool,
+    /// Whether a block was dropped because the receive buffer was full (reads). The
+    /// retransmission timer is stopped until there is room for a whole block again.
+    ack_withheld: bool,
+    tx_closed: bool,
+    retransmit_at: Instant,
+    retransmits: u8,
+    #[cfg(feature = "async")]
+    rx_waker: WakerRegistration,
+    #[cfg(feature = "async")]
+    tx_waker: WakerRegistration,
+}
+
+impl<'a> Socket<'a> {
+    /// Create a TFTP socket with the given buffers.
+    pub fn new(rx_buffer: SocketBuffer<'a>, tx_buffer: SocketBuffer<'a>) -> Socket<'a> {
+        Socket {
+            rx_buffer,
+            tx_buffer,
+            state: State::Idle,
+            direction: Direction::Read,
+            filename: Vec::new(),
+            remote_endpoint: None,
+            local_port: 0,
+            block_size: TFTP_DEFAULT_BLOCK_SIZE,
+            transfer_block_size: TFTP_DEFAULT_BLOCK_SIZE,
+            block: 0,
+            in_flight: None,
+            send_now: false,
+            ack_withheld: false,
+            tx_closed: false,
+            retransmit_at: Instant::ZERO,
+            retransmits: 0,
+            #[cfg(feature = "async")]
+            rx_waker: WakerRegistration::new(),
+            #[cfg(feature = "async")]
+            tx_waker: WakerRegistration::new(),
+        }
+    }
+
+    /// Return the block size requested for new transfers.
+    ///
+    /// See also the [set_block_size](#method.set_block_size) method.
+    pub fn block_size(&self) -> u16 {
+        self.block_size
+    }
+
+    /// Set the block size requested for new transfers.
+    ///
+    /// A block size other than the default is requested with the `blksize` option of
+    /// [RFC 2348]. The server may choose a smaller one, or ignore the option altogether,
+    /// in which case the default of 512 octets is used.
+    ///
+    /// By default, 512 octets are requested, and no option is sent.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the size is smaller than 8 or larger than [`MAX_BLOCK_SIZE`].
+    ///
+    /// [RFC 2348]: https://tools.ietf.org/html/rfc2348
+    pub fn set_block_size(&mut self, size: u16) {
+        if !(TFTP_MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&size) {
+            panic!("invalid TFTP block size {}", size)
+        }
+        self.block_size = size;
+    }
+
+    /// Return the state of the socket.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Start reading the file `filename` from the server at `server`.
+    ///
+    /// The receive buffer must be able to hold a whole block.
+    pub fn read_request(
+        &mut self,
+        cx: &mut Context,
+        server: IpAddress,
+        filename: &str,
+    ) -> Result<(), RequestError> {
+        self.start(cx, Direction::Read, server, filename)
+    }
+
+    /// Start writing the file `filename` to the server at `server`.
+    ///
+    /// The transmit buffer must be able to hold a whole block.
+    pub fn write_request(
+        &mut self,
+        cx: &mut Context,
+        server: IpAddress,
+        filename: &str,
+    ) -> Result<(), RequestError> {
+        self.start(cx, Direction::Write, server, filename)
+    }
+
+    fn start(
+        &mut self,
+        cx: &mut Context,
+        direction: Direction,
+        server: IpAddress,
+        filename: &str,
+    ) -> Result<(), RequestError> {
+        if matches!(self.state, State::Requesting | State::Transferring) {
+            return Err(RequestError::InvalidState);
+        }
+
+        let filename = filename.as_bytes();
+        if filename.is_empty() || filename.contains(&0) {
+            return Err(RequestError::InvalidName);
+        }
+
+        let buffer = match direction {
+            Direction::Read => &self.rx_buffer,
+            Direction::Write => &self.tx_buffer,
+        };
+        if buffer.capacity() < self.block_size.max(TFTP_DEFAULT_BLOCK_SIZE) as usize {
+            return Err(RequestError::BufferTooSmall);
+        }
+
+        self.filename = Vec::from_slice(filename).map_err(|_| RequestError::NameTooLong)?;
+        self.direction = direction;
+        self.remote_endpoint = Some(IpEndpoint::new(server, TFTP_SERVER_PORT));
+        self.local_port = cx.rand().rand_source_port();
+        self.transfer_block_size = TFTP_DEFAULT_BLOCK_SIZE;
+        self.block = 0;
+        self.in_flight = None;
+        self.send_now = true;
+        self.ack_withheld = false;
+        self.tx_closed = false;
+        self.retransmits = 0;
+        self.rx_buffer.clear();
+        self.tx_buffer.clear();
+        self.set_state(State::Requesting);
+        Ok(())
+    }
+
+    /// Abort the current transfer without notifying the server, and return to the idle state.
+    pub fn abort(&mut self) {
+        self.rx_buffer.clear();
+        self.tx_buffer.clear();
+        self.set_state(State::Idle);
+    }
+
+    /// Check whether there is received data to be read.
+    pub fn can_recv(&self) -> bool {
+        !self.rx_buffer.is_empty()
+    }
+
+    /// Dequeue received data, and copy it into the given slice.
+    ///
+    /// Returns `Err(RecvError::Finished)` once the whole file was received and read.
+    pub fn recv_slice(&mut self, data: &mut [u8]) -> Result<usize, RecvError> {
+        if self.direction != Direction::Read || self.state == State::Idle {
+            return Err(RecvError::InvalidState);
+        }
+        if self.rx_buffer.is_empty() {
+            match self.state {
+                State::Finished => return Err(RecvError::Finished),
+                State::Failed(_) => return Err(RecvError::InvalidState),
+                _ => {}
+            }
+        }
+        Ok(self.rx_buffer.dequeue_slice(data))
+    }
+
+    /// Check whether data can be written for sending.
+    pub fn can_send(&self) -> bool {
+        self.is_writing() && !self.tx_buffer.is_full()
+    }
+
+    /// Enqueue data to be sent, copying it from the given slice.
+    ///
+    /// Returns the number of octets enqueued, which can be less than the length of the slice
+    /// if the transmit buffer is full.
+    pub fn send_slice(&mut self, data: &[u8]) -> Result<usize, SendError> {
+        if !self.is_writing() {
+            return Err(SendError::InvalidState);
+        }
+        Ok(self.tx_buffer.enqueue_slice(data))
+    }
+
+    /// Mark the end of the file being written.
+    ///
+    /// Once all enqueued data has been sent, the transfer is completed with a final,
+    /// partial block.
+    pub fn close(&mut self) {
+        if self.is_writing() {
+            self.tx_closed = true;
+        }
+    }
+
+    fn is_writing(&self) -> bool {
+        self.direction == Direction::Write
+            && matches!(self.state, State::Requesting | State::Transferring)
+            && !self.tx_closed
+    }
+
+    /// Register a waker for receive operations.
+    ///
+    /// The waker is woken on state changes that might affect the return value
+    /// of `recv` method calls, such as receiving data, or the transfer finishing.
+    ///
+    /// Notes:
+    ///
+    /// - Only one waker can be registered at a time. If another waker was previously registered,
+    ///   it is overwritten and will no longer be woken.
+    /// - The Waker is woken only once. Once woken, you must register it again to receive more wakes.
+    #[cfg(feature = "async")]
+    pub fn register_recv_waker(&mut self, waker: &Waker) {
+        self.rx_waker.register(waker)
+    }
+
+    /// Register a waker for send operations.
+    ///
+    /// The waker is woken on state changes that might affect the return value
+    /// of `send` method calls, such as space becoming available in the transmit
+    /// buffer, or the transfer finishing.
+    ///
+    /// Notes:
+    ///
+    /// - Only one waker can be registered at a time. If another waker was previously registered,
+    ///   it is overwritten and will no longer be woken.
+    /// - The Waker is woken only once. Once woken, you must register it again to receive more wakes.
+    #[cfg(feature = "async")]
+    pub fn register_send_waker(&mut self, waker: &Waker) {
+        self.tx_waker.register(waker)
+    }
+
+    fn set_state(&mut self, state: State) {
+        if self.state != state {
+            net_trace!("tftp: state={:?}=>{:?}", self.state, state);
+        }
+        self.state = state;
+
+        #[cfg(feature = "async")]
+        {
+            self.rx_waker.wake();
+            self.tx_waker.wake();
+        }
+    }
+
+    pub(crate) fn accepts(&self, ip_repr: &IpRepr, udp_repr: &UdpRepr) -> bool {
+        let active = match self.state {
+            State::Requesting | State::Transferring => true,
+            // Keep answering retransmissions of the last block, in case our final ACK got lost.
+            State::Finished => self.direction == Direction::Read,
+            State::Idle | State::Failed(_) => false,
+        };
+
+        let remote_endpoint = match self.remote_endpoint {
+            Some(endpoint) if active => endpoint,
+            _ => return false,
+        };
+
+        udp_repr.dst_port == self.local_port
+            && ip_repr.src_addr() == remote_endpoint.addr
+            && (self.state == State::Requesting || udp_repr.src_port == remote_endpoint.port)
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        _cx: &mut Context,
+        ip_repr: &IpRepr,
+        udp_repr: &UdpRepr,
+        payload: &[u8],
+    ) {
+        debug_assert!(self.accepts(ip_repr, udp_repr));
+
+        let repr = match TftpPacket::new_checked(payload).and_then(|p| TftpRepr::parse(&p)) {
+            Ok(repr) => repr,
+            Err(_) => {
+                net_debug!("tftp: malformed packet");
+                return;
+            }
+        };
+        net_trace!("tftp: received {}", repr);
+
+        match (self.state, self.direction, repr) {
+            (_, _, TftpRepr::Error { code, .. }) => {
+                net_debug!("tftp: transfer aborted by server, code {:?}", code);
+                self.set_state(State::Failed(TransferError::Remote(code)));
+            }
+            (State::Requesting, _, TftpRepr::OptionAck { block_size }) => {
+                let block_size = block_size.unwrap_or(TFTP_DEFAULT_BLOCK_SIZE);
+                if block_size > self.block_size {
+                    // The server may only reduce the block size we asked for.
+                    net_debug!("tftp: server increased block size to {}", block_size);
+                    self.set_state(State::Failed(TransferError::Protocol));
+                    return;
+                }
+                self.establish(udp_repr.src_port, block_size);
+                // The option acknowledgment is answered by acknowledging block 0 when reading,
+                // and takes the place of the acknowledgment of block 0 when writing.
+                self.send_now = self.direction == Direction::Read;
+            }
+            (State::Requesting, Direction::Write, TftpRepr::Ack { block: 0 }) => {
+                self.establish(udp_repr.src_port, TFTP_DEFAULT_BLOCK_SIZE);
+            }
+            (State::Requesting, Direction::Read, TftpRepr::Data { block: 1, data }) => {
+                self.establish(udp_repr.src_port, TFTP_DEFAULT_BLOCK_SIZE);
+                self.process_data(1, data);
+            }
+            (
+                State::Transferring | State::Finished,
+                Direction::Read,
+                TftpRepr::Data { block, data },
+            ) => self.process_data(block, data),
+            (State::Transferring, Direction::Write, TftpRepr::Ack { block }) => {
+                self.process_ack(block)
+            }
+            _ => net_debug!("tftp: unexpected packet in state {:?}", self.state),
+        }
+    }
+
+    fn establish(&mut self, port: u16, block_size: u16) {
+        if let Some(endpoint) = self.remote_endpoint.as_mut() {
+            endpoint.port = port;
+        }
+        self.transfer_block_size = block_size;
+        self.send_now = false;
+        self.retransmits = 0;
+        self.set_state(State::Transferring);
+    }
+
+    fn process_data(&mut self, block: u16, data: &[u8]) {
+        if block == self.block {
+            // The server didn't get our acknowledgment, send it again.
+            self.send_now = true;
+            return;
+        }
+        if self.state != State::Transferring || block != self.block.wrapping_add(1) {
+            net_debug!("tftp: unexpected block {}", block);
+            return;
+        }
+        if data.len() > self.transfer_block_size as usize {
+            net_debug!("tftp: block {} larger than the block size", block);
+            self.set_state(State::Failed(TransferError::Protocol));
+            return;
+        }
+        if self.rx_buffer.window() < data.len() {
+            // Withhold the acknowledgment; the server will send the block again once its
+            // retransmission timer runs out.
+            net_trace!("tftp: rx buffer full, dropping block {}", block);
+            self.ack_withheld = true;
+            return;
+        }
+
+        let enqueued = self.rx_buffer.enqueue_slice(data);
+        debug_assert_eq!(enqueued, data.len());
+        self.block = block;
+        self.send_now = true;
+        self.ack_withheld = false;
+        self.retransmits = 0;
+
+        #[cfg(feature = "async")]
+        self.rx_waker.wake();
+
+        if data.len() < self.transfer_block_size as usize {
+            self.set_state(State::Finished);
+        }
+    }
+
+    fn process_ack(&mut self, block: u16) {
+        // Acknowledgments of earlier blocks are ignored rather than answered, to avoid
+        // the Sorcerer's Apprentice Syndrome described in RFC 1123 § 4.2.3.1.
+        let len = match self.in_flight {
+            Some(len) if block == self.block.wrapping_add(1) => len,
+            _ => return,
+        };
+
+        self.tx_buffer.dequeue_allocated(len);
+        self.block = block;
+        self.in_flight = None;
+        self.retransmits = 0;
+
+        #[cfg(feature = "async")]
+        self.tx_waker.wake();
+
+        if len < self.transfer_block_size as usize {
+            self.set_state(State::Finished);
+        }
+    }
+
+    /// Return whether an acknowledgment is withheld until the receive buffer has room
+    /// for a whole block.
+    fn rx_blocked(&self) -> bool {
+        self.ack_withheld && self.rx_buffer.window() < self.transfer_block_size as usize
+    }
+
+    /// Return the length of the next block to send, if one is ready.
+    fn next_block_len(&self) -> Option<usize> {
+        let block_size = self.transfer_block_size as usize;
+        let len = self.tx_buffer.len().min(block_size);
+        (len == block_size || self.tx_closed).then_some(len)
+    }
+
+    pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (IpRepr, UdpRepr, TftpRepr)) -> Result<(), E>,
+    {
+        let remote_endpoint = match self.remote_endpoint {
+            Some(endpoint) => endpoint,
+            None => return Ok(()),
+        };
+
+        if self.ack_withheld && !self.send_now && self.state == State::Transferring {
+            if self.rx_blocked() {
+                return Ok(());
+            }
+            // There is room again: wait for the server to send the block again, from now.
+            self.ack_withheld = false;
+            self.retransmits = 0;
+            self.retransmit_at = cx.now() + RETRANSMIT_TIMEOUT;
+            return Ok(());
+        }
+
+        let timer_expired = cx.now() >= self.retransmit_at;
+
+        // Decide what to send, and whether it is a retransmission.
+        let (retransmit, data_len) = match (self.state, self.direction) {
+            (State::Requesting, _) | (State::Transferring, Direction::Read) => {
+                if self.send_now {
+                    (false, None)
+                } else if timer_expired {
+                    (true, None)
+                } else {
+                    return Ok(());
+                }
+            }
+            (State::Finished, Direction::Read) if self.send_now => (false, None),
+            (State::Transferring, Direction::Write) => match self.in_flight {
+                Some(len) if timer_expired => (true, Some(len)),
+                Some(_) => return Ok(()),
+                None => match self.next_block_len() {
+                    Some(len) => (false, Some(len)),
+                    None => return Ok(()),
+                },
+            },
+            _ => return Ok(()),
+        };
+
+        if retransmit && self.retransmits >= MAX_RETRANSMITS {
+            net_debug!("tftp: transfer timed out");
+            self.set_state(State::Failed(TransferError::Timeout));
+            return Ok(());
+        }
+
+        if let Some(len) = data_len {
+            // A block is sent from a single slice of the transmit buffer.
+            if self.tx_buffer.get_allocated(0, len).len() < len {
+                self.tx_buffer.make_contiguous();
+            }
+        }
+        let repr = match (self.state, data_len) {
+            (State::Requesting, _) => {
+                let block_size =
+                    (self.block_size != TFTP_DEFAULT_BLOCK_SIZE).then_some(self.block_size);
+                match self.direction {
+                    Direction::Read => TftpRepr::ReadRequest {
+                        filename: &self.filename,
+                        mode: TftpMode::Octet,
+                        block_size,
+                    },
+                    Direction::Write => TftpRepr::WriteRequest {
+                        filename: &self.filename,
+                        mode: TftpMode::Octet,
+                        block_size,
+                    },
+                }
+            }
+            (_, Some(len)) => TftpRepr::Data {
+                block: self.block.wrapping_add(1),
+                data: self.tx_buffer.get_allocated(0, len),
+            },
+            (_, None) => TftpRepr::Ack { block: self.block },
+        };
+
+        let src_addr = match cx.get_source_address(&remote_endpoint.addr) {
+            Some(addr) => addr,
+            None => {
+                net_debug!("tftp: no source address for {}", remote_endpoint.addr);
+                return Ok(());
+            }
+        };
+        let udp_repr = UdpRepr {
+            src_port: self.local_port,
+            dst_port: remote_endpoint.port,
+        };
+        let ip_repr = IpRepr::new(
+            src_addr,
+            remote_endpoint.addr,
+            IpProtocol::Udp,
+            udp_repr.header_len() + repr.buffer_len(),
+            64,
+        );
+
+        net_trace!("tftp: sending {} to {}", repr, remote_endpoint);
+
+        emit(cx, (ip_repr, udp_repr, repr))?;
+
+        self.send_now = false;
+        self.retransmit_at = cx.now() + RETRANSMIT_TIMEOUT;
+        if retransmit {
+            self.retransmits += 1;
+        }
+        if data_len.is_some() {
+            self.in_flight = data_len;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn poll_at(&self, _cx: &Context) -> PollAt {
+        match (self.state, self.direction) {
+            (State::Requesting | State::Transferring | State::Finished, _) if self.send_now => {
+                PollAt::Now
+            }
+            (State::Transferring, Direction::Read) if self.ack_withheld => {
+                if self.rx_blocked() {
+                    PollAt::Ingress
+                } else {
+                    PollAt::Now
+                }
+            }
+            (State::Requesting, _) | (State::Transferring, Direction::Read) => {
+                PollAt::Time(self.retransmit_at)
+            }
+            (State::Transferring, Direction::Write) => match self.in_flight {
+                Some(_) => PollAt::Time(self.retransmit_at),
+                None if self.next_block_len().is_some() => PollAt::Now,
+                None => PollAt::Ingress,
+            },
+            _ => PollAt::Ingress,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "proto-ipv4")]
+mod test {
+    use std::ops::{Deref, DerefMut};
+    use std::vec;
+
+    use super::*;
+    use crate::phy::Medium;
+    use crate::rand::RandSource;
+    use crate::tests::setup;
+    use crate::wire::{Ipv4Address, Ipv4Repr};
+    use rstest::*;
+
+    // =========================================================================================//
+    // Helper functions
+
+    struct TestSocket {
+        socket: Socket<'static>,
+        cx: Context<'static>,
+    }
+
+    impl Deref for TestSocket {
+        type Target = Socket<'static>;
+        fn deref(&self) -> &Self::Target {
+            &self.socket
+        }
+    }
+
+    impl DerefMut for TestSocket {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.socket
+        }
+    }
+
+    fn send(s: &mut TestSocket, timestamp: Instant, src_port: u16, repr: TftpRepr) {
+        s.cx.set_now(timestamp);
+
+        net_trace!("send: {}", repr);
+
+        let mut payload = vec![0; repr.buffer_len()];
+        repr.emit(&mut TftpPacket::new_unchecked(&mut payload));
+
+        let udp_repr = UdpRepr {
+            src_port,
+            dst_port: s.local_port,
+        };
+        let ip_repr = IpRepr::Ipv4(Ipv4Repr {
+            src_addr: SERVER_IP,
+            dst_addr: MY_IP,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + payload.len(),
+            hop_limit: 64,
+        });
+
+        assert!(s.socket.accepts(&ip_repr, &udp_repr));
+        s.socket.process(&mut s.cx, &ip_repr, &udp_repr, &payload)
+    }
+
+    fn recv(s: &mut TestSocket, timestamp: Instant, reprs: &[(u16, TftpRepr)]) {
+        s.cx.set_now(timestamp);
+
+        let local_port = s.local_port;
+        let mut i = 0;
+
+        while s.socket.poll_at(&s.cx) <= PollAt::Time(timestamp) {
+            let _ = s
+                .socket
+                .dispatch(&mut s.cx, |_, (ip_repr, udp_repr, repr)| {
+                    assert_eq!(ip_repr.src_addr(), IpAddress::Ipv4(MY_IP));
+                    assert_eq!(ip_repr.dst_addr(), IpAddress::Ipv4(SERVER_IP));
+                    assert_eq!(
+                        ip_repr.payload_len(),
+                        udp_repr.header_len() + repr.buffer_len()
+                    );
+                    assert_eq!(udp_repr.src_port, local_port);
+
+                    net_trace!("recv: {}", repr);
+
+                    match reprs.get(i) {
+                        Some(&(dst_port, want_repr)) => {
+                            assert_eq!(udp_repr.dst_port, dst_port);
+                            assert_eq!(repr, want_repr);
+                        }
+                        None => panic!("Too many reprs emitted"),
+                    }
+                    i += 1;
+                    Ok::<_, ()>(())
+                });
+        }
+
+        assert_eq!(i, reprs.len());
+    }
+
+    macro_rules! send {
+        ($socket:ident, $port:expr, $repr:expr) =>
+            (send!($socket, time 0, $port, $repr));
+        ($socket:ident, time $time:expr, $port:expr, $repr:expr) =>
+            (send(&mut $socket, Instant::from_millis($time), $port, $repr));
+    }
+
+    macro_rules! recv {
+        ($socket:ident, $reprs:expr) => ({
+            recv!($socket, time 0, $reprs);
+        });
+        ($socket:ident, time $time:expr, $reprs:expr) => ({
+            recv(&mut $socket, Instant::from_millis($time), &$reprs);
+        });
+    }
+
+    // =========================================================================================//
+    // Constants
+
+    const MY_IP: Ipv4Address = Ipv4Address([192, 168, 1, 1]);
+    const SERVER_IP: Ipv4Address = Ipv4Address([192, 168, 1, 2]);
+    const SERVER_TID: u16 = 3000;
+
+    const RRQ: TftpRepr = TftpRepr::ReadRequest {
+        filename: b"firmware.bin",
+        mode: TftpMode::Octet,
+        block_size: None,
+    };
+
+    const WRQ: TftpRepr = TftpRepr::WriteRequest {
+        filename: b"firmware.bin",
+        mode: TftpMode::Octet,
+        block_size: None,
+    };
+
+    const fn ack(block: u16) -> TftpRepr<'static> {
+        TftpRepr::Ack { block }
+    }
+
+    fn socket(medium: Medium) -> TestSocket {
+        let (iface, _, _) = setup(medium);
+        let rx_buffer = SocketBuffer::new(vec![0; 2048]);
+        let tx_buffer = SocketBuffer::new(vec![0; 2048]);
+        TestSocket {
+            socket: Socket::new(rx_buffer, tx_buffer),
+            cx: iface.inner,
+        }
+    }
+
+    fn socket_reading(medium: Medium) -> TestSocket {
+        let mut s = socket(medium);
+        s.socket
+            .read_request(&mut s.cx, SERVER_IP.into(), "firmware.bin")
+            .unwrap();
+        s
+    }
+
+    fn socket_writing(medium: Medium) -> TestSocket {
+        let mut s = socket(medium);
+        s.socket
+            .write_request(&mut s.cx, SERVER_IP.into(), "firmware.bin")
+            .unwrap();
+        s
+    }
+
+    fn recv_all(s: &mut TestSocket) -> std::vec::Vec<u8> {
+        let mut data = vec![0; 4096];
+        let len = s.recv_slice(&mut data).unwrap();
+        data.truncate(len);
+        data
+    }
+
+    // =========================================================================================//
+    // Tests
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_local_port_from_rand_source(#[case] medium: Medium) {
+        struct FixedRand;
+
+        impl RandSource for FixedRand {
+            fn rand_u32(&mut self) -> u32 {
+                0x4321_0000
+            }
+        }
+
+        let mut rand = FixedRand;
+        let (iface, _, _) = setup(medium);
+        let mut cx = iface.inner;
+        cx.set_rand_source(&mut rand);
+        let mut s = socket(medium);
+        s.socket
+            .read_request(&mut cx, SERVER_IP.into(), "firmware.bin")
+            .unwrap();
+        assert_eq!(s.local_port, 0x4321);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read(#[case] medium: Medium) {
+        let mut s = socket_reading(medium);
+        assert_eq!(s.state(), State::Requesting);
+
+        recv!(s, [(TFTP_SERVER_PORT, RRQ)]);
+
+        let block1 = [0xaa; 512];
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 1,
+                data: &block1
+            }
+        );
+        assert_eq!(s.state(), State::Transferring);
+        recv!(s, [(SERVER_TID, ack(1))]);
+
+        let block2 = [0xbb; 100];
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 2,
+                data: &block2
+            }
+        );
+        assert_eq!(s.state(), State::Finished);
+        recv!(s, [(SERVER_TID, ack(2))]);
+
+        let data = recv_all(&mut s);
+        assert_eq!(&data[..512], &block1[..]);
+        assert_eq!(&data[512..], &block2[..]);
+        assert_eq!(s.recv_slice(&mut [0; 16]), Err(RecvError::Finished));
+
+        // Nothing more to send.
+        recv!(s, time 10_000, []);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read_block_size(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        s.set_block_size(1024);
+        s.socket
+            .read_request(&mut s.cx, SERVER_IP.into(), "firmware.bin")
+            .unwrap();
+
+        recv!(
+            s,
+            [(
+                TFTP_SERVER_PORT,
+                TftpRepr::ReadRequest {
+                    filename: b"firmware.bin",
+                    mode: TftpMode::Octet,
+                    block_size: Some(1024),
+                }
+            )]
+        );
+
+        // The server settles for a smaller block size.
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::OptionAck {
+                block_size: Some(800)
+            }
+        );
+        assert_eq!(s.state(), State::Transferring);
+        recv!(s, [(SERVER_TID, ack(0))]);
+
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 1,
+                data: &[0xaa; 800]
+            }
+        );
+        assert_eq!(s.state(), State::Transferring);
+        recv!(s, [(SERVER_TID, ack(1))]);
+
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 2,
+                data: &[0xbb; 10]
+            }
+        );
+        assert_eq!(s.state(), State::Finished);
+        recv!(s, [(SERVER_TID, ack(2))]);
+
+        assert_eq!(recv_all(&mut s).len(), 810);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read_block_size_increased(#[case] medium: Medium) {
+        let mut s = socket_reading(medium);
+        recv!(s, [(TFTP_SERVER_PORT, RRQ)]);
+
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::OptionAck {
+                block_size: Some(1024)
+            }
+        );
+        assert_eq!(s.state(), State::Failed(TransferError::Protocol));
+        recv!(s, time 10_000, []);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read_retransmit(#[case] medium: Medium) {
+        let mut s = socket_reading(medium);
+
+        recv!(s, [(TFTP_SERVER_PORT, RRQ)]);
+        recv!(s, time 500, []);
+        recv!(s, time 1_000, [(TFTP_SERVER_PORT, RRQ)]);
+
+        send!(s, time 1_100, SERVER_TID, TftpRepr::Data { block: 1, data: &[0xaa; 512] });
+        recv!(s, time 1_100, [(SERVER_TID, ack(1))]);
+
+        // The server didn't get our ACK and sends the block again.
+        send!(s, time 1_500, SERVER_TID, TftpRepr::Data { block: 1, data: &[0xaa; 512] });
+        recv!(s, time 1_500, [(SERVER_TID, ack(1))]);
+        assert_eq!(recv_all(&mut s).len(), 512);
+
+        // With the server silent, the ACK is retransmitted until we give up.
+        recv!(s, time 2_500, [(SERVER_TID, ack(1))]);
+        recv!(s, time 3_500, [(SERVER_TID, ack(1))]);
+        recv!(s, time 4_500, [(SERVER_TID, ack(1))]);
+        recv!(s, time 5_500, [(SERVER_TID, ack(1))]);
+        recv!(s, time 6_500, [(SERVER_TID, ack(1))]);
+        recv!(s, time 7_500, []);
+        assert_eq!(s.state(), State::Failed(TransferError::Timeout));
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read_rx_buffer_full(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        s.rx_buffer = SocketBuffer::new(vec![0; 600]);
+        s.socket
+            .read_request(&mut s.cx, SERVER_IP.into(), "firmware.bin")
+            .unwrap();
+
+        recv!(s, [(TFTP_SERVER_PORT, RRQ)]);
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 1,
+                data: &[0xaa; 512]
+            }
+        );
+        recv!(s, [(SERVER_TID, ack(1))]);
+
+        // The second block doesn't fit, so it isn't acknowledged.
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 2,
+                data: &[0xbb; 512]
+            }
+        );
+        recv!(s, []);
+
+        assert_eq!(recv_all(&mut s).len(), 512);
+        send!(s, time 1_000, SERVER_TID, TftpRepr::Data { block: 2, data: &[0xbb; 512] });
+        recv!(s, time 1_000, [(SERVER_TID, ack(2))]);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read_rx_buffer_full_no_timeout(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        s.rx_buffer = SocketBuffer::new(vec![0; 600]);
+        s.socket
+            .read_request(&mut s.cx, SERVER_IP.into(), "firmware.bin")
+            .unwrap();
+
+        recv!(s, [(TFTP_SERVER_PORT, RRQ)]);
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 1,
+                data: &[0xaa; 512]
+            }
+        );
+        recv!(s, [(SERVER_TID, ack(1))]);
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 2,
+                data: &[0xbb; 512]
+            }
+        );
+
+        // While the application doesn't read, the acknowledgment is neither retransmitted
+        // nor counted towards the timeout.
+        assert_eq!(s.poll_at(&s.cx), PollAt::Ingress);
+        recv!(s, time 10_000, []);
+        assert_eq!(s.state(), State::Transferring);
+
+        // Once there is room, the retransmission timer starts again.
+        assert_eq!(recv_all(&mut s).len(), 512);
+        recv!(s, time 10_000, []);
+        recv!(s, time 10_999, []);
+        recv!(s, time 11_000, [(SERVER_TID, ack(1))]);
+        send!(s, time 11_100, SERVER_TID, TftpRepr::Data { block: 2, data: &[0xbb; 512] });
+        recv!(s, time 11_100, [(SERVER_TID, ack(2))]);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read_error(#[case] medium: Medium) {
+        let mut s = socket_reading(medium);
+        recv!(s, [(TFTP_SERVER_PORT, RRQ)]);
+
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Error {
+                code: TftpErrorCode::FileNotFound,
+                message: b"no such file",
+            }
+        );
+        assert_eq!(
+            s.state(),
+            State::Failed(TransferError::Remote(TftpErrorCode::FileNotFound))
+        );
+        assert_eq!(s.recv_slice(&mut [0; 16]), Err(RecvError::InvalidState));
+        recv!(s, time 10_000, []);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_read_other_tid(#[case] medium: Medium) {
+        let mut s = socket_reading(medium);
+        recv!(s, [(TFTP_SERVER_PORT, RRQ)]);
+        send!(
+            s,
+            SERVER_TID,
+            TftpRepr::Data {
+                block: 1,
+                data: &[0xaa; 512]
+            }
+        );
+
+        // Once the transfer is established, packets from other ports are not for us.
+        let udp_repr = UdpRepr {
+            src_port: SERVER_TID + 1,
+            dst_port: s.local_port,
+        };
+        let ip_repr = IpRepr::Ipv4(Ipv4Repr {
+            src_addr: SERVER_IP,
+            dst_addr: MY_IP,
+            next_header: IpProtocol::Udp,
+            payload_len: 12,
+            hop_limit: 64,
+        });
+        assert!(!s.accepts(&ip_repr, &udp_repr));
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_write(#[case] medium: Medium) {
+        let mut s = socket_writing(medium);
+        assert_eq!(s.send_slice(&[0xaa; 600]), Ok(600));
+        s.close();
+        assert_eq!(s.send_slice(&[0xaa; 1]), Err(SendError::InvalidState));
+
+        recv!(s, [(TFTP_SERVER_PORT, WRQ)]);
+        send!(s, SERVER_TID, ack(0));
+        assert_eq!(s.state(), State::Transferring);
+        recv!(
+            s,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 1,
+                    data: &[0xaa; 512]
+                }
+            )]
+        );
+
+        // A duplicate ACK doesn't trigger anything.
+        send!(s, SERVER_TID, ack(0));
+        recv!(s, []);
+
+        send!(s, SERVER_TID, ack(1));
+        recv!(
+            s,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 2,
+                    data: &[0xaa; 88]
+                }
+            )]
+        );
+
+        // The last block is retransmitted until acknowledged.
+        recv!(
+            s,
+            time 1_000,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 2,
+                    data: &[0xaa; 88]
+                }
+            )]
+        );
+        send!(s, time 1_100, SERVER_TID, ack(2));
+        assert_eq!(s.state(), State::Finished);
+        recv!(s, time 10_000, []);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_write_wrapped_block(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        s.tx_buffer = SocketBuffer::new(vec![0; 600]);
+        s.socket
+            .write_request(&mut s.cx, SERVER_IP.into(), "firmware.bin")
+            .unwrap();
+        recv!(s, [(TFTP_SERVER_PORT, WRQ)]);
+        send!(s, SERVER_TID, ack(0));
+
+        assert_eq!(s.send_slice(&[0xaa; 512]), Ok(512));
+        recv!(
+            s,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 1,
+                    data: &[0xaa; 512]
+                }
+            )]
+        );
+        send!(s, SERVER_TID, ack(1));
+
+        // The second block wraps around the end of the transmit buffer.
+        assert_eq!(s.send_slice(&[0xbb; 512]), Ok(512));
+        recv!(
+            s,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 2,
+                    data: &[0xbb; 512]
+                }
+            )]
+        );
+        send!(s, SERVER_TID, ack(2));
+        s.close();
+        recv!(
+            s,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 3,
+                    data: &[]
+                }
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_write_exact_block(#[case] medium: Medium) {
+        let mut s = socket_writing(medium);
+        recv!(s, [(TFTP_SERVER_PORT, WRQ)]);
+        send!(s, SERVER_TID, ack(0));
+
+        // A partial block waits for more data.
+        assert_eq!(s.send_slice(&[0xaa; 300]), Ok(300));
+        recv!(s, []);
+        assert_eq!(s.send_slice(&[0xaa; 212]), Ok(212));
+        recv!(
+            s,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 1,
+                    data: &[0xaa; 512]
+                }
+            )]
+        );
+        send!(s, SERVER_TID, ack(1));
+
+        // A file that is a multiple of the block size ends with an empty block.
+        s.close();
+        recv!(
+            s,
+            [(
+                SERVER_TID,
+                TftpRepr::Data {
+                    block: 2,
+                    data: &[]
+                }
+            )]
+        );
+        send!(s, SERVER_TID, ack(2));
+        assert_eq!(s.state(), State::Finished);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_request_errors(#[case] medium: Medium) {
+        let (iface, _, _) = setup(medium);
+        let mut cx = iface.inner;
+        let mut s = Socket::new(
+            SocketBuffer::new(vec![0; 2048]),
+            SocketBuffer::new(vec![0; 2048]),
+        );
+        assert_eq!(
+            s.read_request(&mut cx, SERVER_IP.into(), ""),
+            Err(RequestError::InvalidName)
+        );
+        assert_eq!(
+            s.read_request(
+                &mut cx,
+                SERVER_IP.into(),
+                core::str::from_utf8(&[b'a'; 200]).unwrap()
+            ),
+            Err(RequestError::NameTooLong)
+        );
+        assert_eq!(s.read_request(&mut cx, SERVER_IP.into(), "a"), Ok(()));
+        assert_eq!(
+            s.write_request(&mut cx, SERVER_IP.into(), "a"),
+            Err(RequestError::InvalidState)
+        );
+
+        let mut s = Socket::new(
+            SocketBuffer::new(vec![0; 100]),
+            SocketBuffer::new(vec![0; 100]),
+        );
+        assert_eq!(
+            s.read_request(&mut cx, SERVER_IP.into(), "a"),
+            Err(RequestError::BufferTooSmall)
+        );
+    }
+}
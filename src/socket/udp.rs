@@ -4,11 +4,15 @@ use core::task::Waker;
 
 use crate::iface::Context;
 use crate::phy::PacketMeta;
-use crate::socket::PollAt;
 #[cfg(feature = "async")]
 use crate::socket::WakerRegistration;
+use crate::socket::{FlowLabel, PollAt};
 use crate::storage::Empty;
-use crate::wire::{IpEndpoint, IpListenEndpoint, IpProtocol, IpRepr, UdpRepr};
+#[cfg(feature = "proto-ntp")]
+use crate::time::{Duration, Instant};
+use crate::wire::{IpEndpoint, IpListenEndpoint, IpProtocol, IpRepr, UdpRepr, UDP_HEADER_LEN};
+#[cfg(feature = "proto-ntp")]
+use crate::wire::{NtpLeapIndicator, NtpMode, NtpPacket, NtpRepr, NtpTimestamp};
 
 /// Metadata for a sent or received UDP packet.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -69,6 +73,8 @@ impl std::error::Error for BindError {}
 pub enum SendError {
     Unaddressable,
     BufferFull,
+    /// The datagram doesn't fit in the MTU, and the socket is set to not fragment it.
+    TooLarge,
 }
 
 impl core::fmt::Display for SendError {
@@ -76,6 +82,7 @@ impl core::fmt::Display for SendError {
         match self {
             SendError::Unaddressable => write!(f, "unaddressable"),
             SendError::BufferFull => write!(f, "buffer full"),
+            SendError::TooLarge => write!(f, "too large"),
         }
     }
 }
@@ -103,6 +110,31 @@ impl core::fmt::Display for RecvError {
 #[cfg(feature = "std")]
 impl std::error::Error for RecvError {}
 
+/// A clock offset and round-trip delay measured with an NTP server.
+///
+/// Returned by [`Socket::recv_ntp_response`].
+#[cfg(feature = "proto-ntp")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NtpSample {
+    /// The endpoint the response was received from.
+    pub server: IpEndpoint,
+    /// The stratum of the server.
+    pub stratum: u8,
+    /// The offset of the server clock relative to the local clock, in microseconds.
+    pub offset_micros: i64,
+    /// The round-trip delay to the server, excluding its processing time.
+    pub delay: Duration,
+}
+
+#[cfg(feature = "proto-ntp")]
+impl NtpSample {
+    /// Translate a local `Instant` to the server's clock.
+    pub fn correct(&self, local: Instant) -> Instant {
+        Instant::from_micros(local.total_micros() + self.offset_micros)
+    }
+}
+
 /// A User Datagram Protocol socket.
 ///
 /// A UDP socket is bound to a specific endpoint, and owns transmit and receive
@@ -114,6 +146,19 @@ pub struct Socket<'a> {
     tx_buffer: PacketBuffer<'a>,
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+    /// Whether outgoing packets must not be fragmented.
+    dont_frag: bool,
+    /// The IP MTU of the interface the socket sends packets on, once known.
+    ip_mtu: Option<usize>,
+    /// How the Flow Label of outgoing IPv6 packets is chosen.
+    flow_label: FlowLabel,
+    /// Free payload space in the transmit buffer needed for the socket to be ready to send.
+    send_lowat: usize,
+    /// Payload octets in the receive buffer needed for the socket to be ready to receive.
+    recv_lowat: usize,
+    /// The transmit timestamp of the last SNTP request that is still awaiting a response.
+    #[cfg(feature = "proto-ntp")]
+    ntp_request: Option<NtpTimestamp>,
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
     #[cfg(feature = "async")]
@@ -128,6 +173,13 @@ impl<'a> Socket<'a> {
             rx_buffer,
             tx_buffer,
             hop_limit: None,
+            dont_frag: false,
+            ip_mtu: None,
+            flow_label: FlowLabel::Zero,
+            send_lowat: 1,
+            recv_lowat: 1,
+            #[cfg(feature = "proto-ntp")]
+            ntp_request: None,
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
             #[cfg(feature = "async")]
@@ -176,6 +228,24 @@ impl<'a> Socket<'a> {
         self.endpoint
     }
 
+    /// Return the local endpoint, or None if the socket is not bound.
+    ///
+    /// The address of a socket bound to a port on all addresses is reported as the
+    /// unspecified address. See also the [endpoint](#method.endpoint) method.
+    #[inline]
+    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
+        if !self.is_open() {
+            return None;
+        }
+        Some(self.endpoint.or_unspecified())
+    }
+
+    /// Return the remote endpoint. UDP sockets are never connected, so this is always None.
+    #[inline]
+    pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
+        None
+    }
+
     /// Return the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
     /// See also the [set_hop_limit](#method.set_hop_limit) method
@@ -203,6 +273,92 @@ impl<'a> Socket<'a> {
         self.hop_limit = hop_limit
     }
 
+    /// Return whether outgoing packets must not be fragmented.
+    ///
+    /// See also the [set_dont_fragment](#method.set_dont_fragment) method.
+    pub fn dont_fragment(&self) -> bool {
+        self.dont_frag
+    }
+
+    /// Set whether outgoing packets must not be fragmented.
+    ///
+    /// When set, [send](#method.send) rejects datagrams that don't fit in the MTU of the
+    /// interface of `cx` with [SendError::TooLarge], instead of letting them be fragmented.
+    /// Those enqueued before the option was set are dropped when the interface is polled,
+    /// which is reported as [DropReason::TooLarge] if interface events are enabled. This is
+    /// useful for protocols that must not be fragmented.
+    ///
+    /// Either way, IPv4 packets that are not fragmented are sent with the DF (don't
+    /// fragment) flag, so that routers don't fragment them either. By default, datagrams
+    /// may be fragmented.
+    ///
+    /// [DropReason::TooLarge]: crate::iface::DropReason::TooLarge
+    pub fn set_dont_fragment(&mut self, cx: &Context, dont_frag: bool) {
+        self.dont_frag = dont_frag;
+        self.ip_mtu = Some(cx.ip_mtu());
+    }
+
+    /// Return how the Flow Label of outgoing IPv6 packets is chosen.
+    ///
+    /// See also the [set_flow_label](#method.set_flow_label) method.
+    pub fn flow_label(&self) -> FlowLabel {
+        self.flow_label
+    }
+
+    /// Set how the Flow Label of outgoing IPv6 packets is chosen.
+    ///
+    /// With [FlowLabel::Auto], the label is derived from the endpoints of each
+    /// datagram, so datagrams sent to the same remote endpoint share a label.
+    ///
+    /// By default, the Flow Label is zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a [fixed](FlowLabel::Fixed) Flow Label doesn't fit in
+    /// 20 bits.
+    pub fn set_flow_label(&mut self, flow_label: FlowLabel) {
+        flow_label.check();
+        self.flow_label = flow_label
+    }
+
+    /// Return the send low-water mark.
+    ///
+    /// See also the [set_send_lowat](#method.set_send_lowat) method.
+    pub fn send_lowat(&self) -> usize {
+        self.send_lowat
+    }
+
+    /// Set the send low-water mark.
+    ///
+    /// The socket is only reported as ready to send, by [can_send](#method.can_send) and by
+    /// waking the send waker, once at least this many octets of payload space are free in
+    /// the transmit buffer. Marks larger than the payload buffer are treated as its size,
+    /// and a mark of 0 is treated as 1.
+    ///
+    /// By default, the send low-water mark is 1 octet.
+    pub fn set_send_lowat(&mut self, octets: usize) {
+        self.send_lowat = octets.max(1)
+    }
+
+    /// Return the receive low-water mark.
+    ///
+    /// See also the [set_recv_lowat](#method.set_recv_lowat) method.
+    pub fn recv_lowat(&self) -> usize {
+        self.recv_lowat
+    }
+
+    /// Set the receive low-water mark.
+    ///
+    /// The socket is only reported as ready to receive, by [can_recv](#method.can_recv) and
+    /// by waking the receive waker, once the datagrams in the receive buffer add up to at
+    /// least this many octets of payload. Marks larger than the payload buffer are treated
+    /// as its size, and a mark of 0 is treated as 1.
+    ///
+    /// By default, the receive low-water mark is 1 octet.
+    pub fn set_recv_lowat(&mut self, octets: usize) {
+        self.recv_lowat = octets.max(1)
+    }
+
     /// Bind the socket to the given endpoint.
     ///
     /// This function returns `Err(Error::Illegal)` if the socket was open
@@ -251,16 +407,28 @@ impl<'a> Socket<'a> {
         self.endpoint.port != 0
     }
 
-    /// Check whether the transmit buffer is full.
+    /// Check whether the transmit buffer is not full, and has at least
+    /// [send_lowat](#method.set_send_lowat) octets of payload space free.
     #[inline]
     pub fn can_send(&self) -> bool {
-        !self.tx_buffer.is_full()
+        !self.tx_buffer.is_full() && self.send_lowat_reached()
     }
 
-    /// Check whether the receive buffer is not empty.
+    /// Check whether the receive buffer is not empty, and holds at least
+    /// [recv_lowat](#method.set_recv_lowat) octets of payload.
     #[inline]
     pub fn can_recv(&self) -> bool {
-        !self.rx_buffer.is_empty()
+        !self.rx_buffer.is_empty() && self.recv_lowat_reached()
+    }
+
+    fn send_lowat_reached(&self) -> bool {
+        self.tx_buffer.payload_window() >= self.send_lowat.min(self.tx_buffer.payload_capacity())
+    }
+
+    fn recv_lowat_reached(&self) -> bool {
+        // Empty datagrams still have to be received with the default mark.
+        let len = self.rx_buffer.payload_bytes_count().max(1);
+        len >= self.recv_lowat.min(self.rx_buffer.payload_capacity())
     }
 
     /// Return the maximum number packets the socket can receive.
@@ -294,6 +462,12 @@ impl<'a> Socket<'a> {
     /// `Err(Error::Unaddressable)` if local or remote port, or remote address are unspecified,
     /// and `Err(Error::Truncated)` if there is not enough transmit buffer capacity
     /// to ever send this packet.
+    ///
+    /// IPv4 datagrams that don't fit in the MTU of the interface are fragmented if the
+    /// `proto-ipv4-fragmentation` feature is enabled, as long as they fit in the
+    /// fragmentation buffer (see `FRAGMENTATION_BUFFER_SIZE`); otherwise they are dropped.
+    /// If the socket is set to [not fragment](#method.set_dont_fragment) datagrams, this
+    /// function returns `Err(SendError::TooLarge)` for those that don't fit instead.
     pub fn send(
         &mut self,
         size: usize,
@@ -309,6 +483,13 @@ impl<'a> Socket<'a> {
         if meta.endpoint.port == 0 {
             return Err(SendError::Unaddressable);
         }
+        if self.dont_frag {
+            if let Some(ip_mtu) = self.ip_mtu {
+                if meta.endpoint.addr.version().header_len() + UDP_HEADER_LEN + size > ip_mtu {
+                    return Err(SendError::TooLarge);
+                }
+            }
+        }
 
         let payload_buf = self
             .tx_buffer
@@ -452,6 +633,85 @@ impl<'a> Socket<'a> {
         Ok((length, endpoint))
     }
 
+    /// Enqueue an SNTP client request to the given NTP server.
+    ///
+    /// `now` is used as the transmit timestamp and must be measured from the Unix epoch,
+    /// like [Instant::now]; with any other local epoch the computed offset will
+    /// include the difference between the two epochs. The server echoes the timestamp
+    /// back, and only the response to the last request sent is accepted.
+    ///
+    /// See also [recv_ntp_response](#method.recv_ntp_response).
+    #[cfg(feature = "proto-ntp")]
+    pub fn send_ntp_request(
+        &mut self,
+        server: impl Into<UdpMetadata>,
+        now: Instant,
+    ) -> Result<(), SendError> {
+        let repr = NtpRepr::client_request(NtpTimestamp::from_instant(now));
+        let buf = self.send(repr.buffer_len(), server)?;
+        repr.emit(&mut NtpPacket::new_unchecked(buf));
+        self.ntp_request = Some(repr.transmit_timestamp);
+        Ok(())
+    }
+
+    /// Dequeue an NTP server response, and compute the clock offset and round-trip delay
+    /// from its timestamps and the local reception time `now`.
+    ///
+    /// Datagrams that are not valid server responses to the last request made with
+    /// [send_ntp_request](#method.send_ntp_request), that carry a "kiss-o'-death"
+    /// (stratum 0), or that come from an unsynchronized server are dropped, as are
+    /// duplicate responses. The socket should therefore be dedicated to NTP.
+    ///
+    /// This function returns `Err(RecvError::Exhausted)` if no response is left in
+    /// the receive buffer.
+    #[cfg(feature = "proto-ntp")]
+    pub fn recv_ntp_response(&mut self, now: Instant) -> Result<NtpSample, RecvError> {
+        loop {
+            let (payload, meta) = self.recv()?;
+            let repr = match NtpPacket::new_checked(payload).and_then(|p| NtpRepr::parse(&p)) {
+                Ok(repr) => repr,
+                Err(_) => {
+                    net_debug!("udp:{}: dropping malformed NTP packet", meta.endpoint);
+                    continue;
+                }
+            };
+            if repr.mode != NtpMode::Server
+                || repr.leap_indicator == NtpLeapIndicator::Unsynchronized
+                || repr.stratum == 0
+                || repr.originate_timestamp == NtpTimestamp::ZERO
+                || repr.transmit_timestamp == NtpTimestamp::ZERO
+            {
+                net_debug!("udp:{}: dropping unusable NTP response", meta.endpoint);
+                continue;
+            }
+            // RFC 4330 section 5: the originate timestamp must match the transmit
+            // timestamp of the request, which rejects stale, duplicate and forged replies.
+            if self.ntp_request != Some(repr.originate_timestamp) {
+                net_debug!("udp:{}: dropping unexpected NTP response", meta.endpoint);
+                continue;
+            }
+            self.ntp_request = None;
+
+            // RFC 5905 section 8: T1 is our transmit time echoed back as the originate
+            // timestamp, T2 and T3 are the server receive and transmit times, and T4 is
+            // the time the response arrived.
+            let t1 = repr.originate_timestamp;
+            let t2 = repr.receive_timestamp;
+            let t3 = repr.transmit_timestamp;
+            let t4 = NtpTimestamp::from_instant(now);
+
+            let offset_micros = (t2.micros_since(t1) + t3.micros_since(t4)) / 2;
+            let delay = t4.micros_since(t1) - t3.micros_since(t2);
+
+            return Ok(NtpSample {
+                server: meta.endpoint,
+                stratum: repr.stratum,
+                offset_micros,
+                delay: Duration::from_micros(delay.max(0) as u64),
+            });
+        }
+    }
+
     pub(crate) fn accepts(&self, cx: &mut Context, ip_repr: &IpRepr, repr: &UdpRepr) -> bool {
         if self.endpoint.port != repr.dst_port {
             return false;
@@ -506,7 +766,9 @@ impl<'a> Socket<'a> {
         }
 
         #[cfg(feature = "async")]
-        self.rx_waker.wake();
+        if self.recv_lowat_reached() {
+            self.rx_waker.wake();
+        }
     }
 
     pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
@@ -515,6 +777,7 @@ impl<'a> Socket<'a> {
     {
         let endpoint = self.endpoint;
         let hop_limit = self.hop_limit.unwrap_or(64);
+        self.ip_mtu = Some(cx.ip_mtu());
 
         let res = self.tx_buffer.dequeue_with(|packet_meta, payload_buf| {
             let src_addr = match endpoint.addr {
@@ -558,13 +821,15 @@ impl<'a> Socket<'a> {
             Ok(Err(e)) => Err(e),
             Ok(Ok(())) => {
                 #[cfg(feature = "async")]
-                self.tx_waker.wake();
+                if self.send_lowat_reached() {
+                    self.tx_waker.wake();
+                }
                 Ok(())
             }
         }
     }
 
-    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+    pub(crate) fn poll_at(&self, _cx: &Context) -> PollAt {
         if self.tx_buffer.is_empty() {
             PollAt::Ingress
         } else {
@@ -678,6 +943,23 @@ mod test {
         assert_eq!(socket.bind(0), Err(BindError::Unaddressable));
     }
 
+    #[test]
+    fn test_endpoints() {
+        let mut s = socket(buffer(0), buffer(0));
+        assert_eq!(s.local_endpoint(), None);
+        assert_eq!(s.bind(LOCAL_PORT), Ok(()));
+        assert_eq!(
+            s.local_endpoint(),
+            Some(IpEndpoint::new(IpvXAddress::UNSPECIFIED.into(), LOCAL_PORT))
+        );
+        assert_eq!(s.remote_endpoint(), None);
+
+        let mut s = socket(buffer(0), buffer(0));
+        assert_eq!(s.bind(LOCAL_END), Ok(()));
+        assert_eq!(s.local_endpoint(), Some(LOCAL_END));
+        assert_eq!(s.remote_endpoint(), None);
+    }
+
     #[test]
     fn test_bind_twice() {
         let mut socket = socket(buffer(0), buffer(0));
@@ -685,6 +967,36 @@ mod test {
         assert_eq!(socket.bind(2), Err(BindError::InvalidState));
     }
 
+    #[test]
+    #[cfg(feature = "medium-ip")]
+    fn test_recv_lowat() {
+        let (mut iface, _, _) = setup(Medium::Ip);
+        let cx = iface.context();
+
+        let mut socket = socket(buffer(2), buffer(0));
+        socket.set_recv_lowat(8);
+        assert_eq!(socket.bind(LOCAL_PORT), Ok(()));
+
+        socket.process(
+            cx,
+            PacketMeta::default(),
+            &REMOTE_IP_REPR,
+            &REMOTE_UDP_REPR,
+            PAYLOAD,
+        );
+        assert!(!socket.can_recv());
+        socket.process(
+            cx,
+            PacketMeta::default(),
+            &REMOTE_IP_REPR,
+            &REMOTE_UDP_REPR,
+            PAYLOAD,
+        );
+        assert!(socket.can_recv());
+        assert_eq!(socket.recv(), Ok((&b"abcdef"[..], REMOTE_END.into())));
+        assert!(!socket.can_recv());
+    }
+
     #[test]
     #[should_panic(expected = "the time-to-live value of a packet must not be zero")]
     fn test_set_hop_limit_zero() {
@@ -692,6 +1004,13 @@ mod test {
         s.set_hop_limit(Some(0));
     }
 
+    #[test]
+    #[should_panic(expected = "flow label 0x100000 doesn't fit in 20 bits")]
+    fn test_set_flow_label_too_large() {
+        let mut s = socket(buffer(0), buffer(1));
+        s.set_flow_label(FlowLabel::Fixed(0x100000));
+    }
+
     #[test]
     fn test_send_unaddressable() {
         let mut socket = socket(buffer(0), buffer(1));
@@ -1027,4 +1346,180 @@ mod test {
         socket.close();
         assert!(!socket.is_open());
     }
+
+    #[cfg(feature = "proto-ntp")]
+    fn ntp_buffer(packets: usize) -> PacketBuffer<'static> {
+        PacketBuffer::new(
+            (0..packets)
+                .map(|_| PacketMetadata::EMPTY)
+                .collect::<Vec<_>>(),
+            vec![0; crate::wire::NTP_HEADER_LEN * packets],
+        )
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    #[cfg(feature = "proto-ntp")]
+    fn test_send_ntp_request(#[case] medium: Medium) {
+        let (mut iface, _, _) = setup(medium);
+        let cx = iface.context();
+        let mut socket = socket(buffer(0), ntp_buffer(1));
+        assert_eq!(socket.bind(LOCAL_END), Ok(()));
+
+        let server = IpEndpoint::new(REMOTE_ADDR.into(), crate::wire::NTP_SERVER_PORT);
+        let now = Instant::from_millis(1_672_531_200_500i64);
+        assert_eq!(socket.send_ntp_request(server, now), Ok(()));
+
+        assert_eq!(
+            socket.dispatch(cx, |_, _, (_, udp_repr, payload)| {
+                assert_eq!(udp_repr.src_port, LOCAL_PORT);
+                assert_eq!(udp_repr.dst_port, crate::wire::NTP_SERVER_PORT);
+                assert_eq!(
+                    payload,
+                    &[
+                        0x23, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xe7, 0x5b, 0x4b, 0x80,
+                        0x80, 0x00, 0x00, 0x00,
+                    ][..]
+                );
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    #[cfg(feature = "proto-ntp")]
+    fn test_recv_ntp_response(#[case] medium: Medium) {
+        use crate::wire::{NtpLeapIndicator, NtpPacket, NTP_SERVER_PORT};
+
+        let (mut iface, _, _) = setup(medium);
+        let cx = iface.context();
+        let mut socket = socket(ntp_buffer(2), ntp_buffer(1));
+        assert_eq!(socket.bind(LOCAL_PORT), Ok(()));
+
+        // The server clock is 2.5 s ahead, each direction takes 10 ms and the
+        // server spends 1 ms before replying.
+        let t1 = Instant::from_millis(1_672_531_200_500i64);
+        let t2 = t1 + Duration::from_millis(2_510);
+        let t3 = t2 + Duration::from_millis(1);
+        let t4 = t1 + Duration::from_millis(21);
+
+        let server = IpEndpoint::new(REMOTE_ADDR.into(), NTP_SERVER_PORT);
+        assert_eq!(socket.send_ntp_request(server, t1), Ok(()));
+
+        let reply = NtpRepr {
+            leap_indicator: NtpLeapIndicator::NoWarning,
+            version: 4,
+            mode: NtpMode::Server,
+            stratum: 2,
+            poll: 6,
+            precision: -23,
+            root_delay: 0,
+            root_dispersion: 0,
+            reference_id: [192, 168, 1, 1],
+            reference_timestamp: NtpTimestamp::from_instant(t2),
+            originate_timestamp: NtpTimestamp::from_instant(t1),
+            receive_timestamp: NtpTimestamp::from_instant(t2),
+            transmit_timestamp: NtpTimestamp::from_instant(t3),
+        };
+        let mut bytes = [0; crate::wire::NTP_HEADER_LEN];
+        reply.emit(&mut NtpPacket::new_unchecked(&mut bytes));
+
+        let udp_repr = UdpRepr {
+            src_port: NTP_SERVER_PORT,
+            dst_port: LOCAL_PORT,
+        };
+        // A truncated datagram is skipped over.
+        socket.process(
+            cx,
+            PacketMeta::default(),
+            &REMOTE_IP_REPR,
+            &udp_repr,
+            &bytes[..20],
+        );
+        socket.process(
+            cx,
+            PacketMeta::default(),
+            &REMOTE_IP_REPR,
+            &udp_repr,
+            &bytes,
+        );
+
+        let sample = socket.recv_ntp_response(t4).unwrap();
+        assert_eq!(sample.server, server);
+        assert_eq!(sample.stratum, 2);
+        assert_eq!(sample.offset_micros, 2_500_000);
+        assert_eq!(sample.delay, Duration::from_millis(20));
+        assert_eq!(sample.correct(t4), t4 + Duration::from_millis(2_500));
+
+        assert_eq!(socket.recv_ntp_response(t4), Err(RecvError::Exhausted));
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ip)]
+    #[cfg(feature = "medium-ip")]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    #[cfg(feature = "proto-ntp")]
+    fn test_recv_ntp_response_mismatch(#[case] medium: Medium) {
+        use crate::wire::{NtpLeapIndicator, NtpPacket, NTP_SERVER_PORT};
+
+        let (mut iface, _, _) = setup(medium);
+        let cx = iface.context();
+        let mut socket = socket(ntp_buffer(4), ntp_buffer(1));
+        assert_eq!(socket.bind(LOCAL_PORT), Ok(()));
+
+        let t1 = Instant::from_millis(1_672_531_200_500i64);
+        let server = IpEndpoint::new(REMOTE_ADDR.into(), NTP_SERVER_PORT);
+        assert_eq!(socket.send_ntp_request(server, t1), Ok(()));
+
+        let udp_repr = UdpRepr {
+            src_port: NTP_SERVER_PORT,
+            dst_port: LOCAL_PORT,
+        };
+        let mut reply = |leap_indicator, originate: Instant| {
+            let repr = NtpRepr {
+                leap_indicator,
+                version: 4,
+                mode: NtpMode::Server,
+                stratum: 2,
+                poll: 6,
+                precision: -23,
+                root_delay: 0,
+                root_dispersion: 0,
+                reference_id: [192, 168, 1, 1],
+                reference_timestamp: NtpTimestamp::from_instant(t1),
+                originate_timestamp: NtpTimestamp::from_instant(originate),
+                receive_timestamp: NtpTimestamp::from_instant(t1),
+                transmit_timestamp: NtpTimestamp::from_instant(t1),
+            };
+            let mut bytes = [0; crate::wire::NTP_HEADER_LEN];
+            repr.emit(&mut NtpPacket::new_unchecked(&mut bytes));
+            socket.process(
+                cx,
+                PacketMeta::default(),
+                &REMOTE_IP_REPR,
+                &udp_repr,
+                &bytes,
+            );
+        };
+
+        // A reply to another request, a reply from an unsynchronized server, and
+        // a duplicate of the accepted reply are all dropped.
+        reply(NtpLeapIndicator::NoWarning, t1 - Duration::from_secs(1));
+        reply(NtpLeapIndicator::Unsynchronized, t1);
+        reply(NtpLeapIndicator::NoWarning, t1);
+        reply(NtpLeapIndicator::NoWarning, t1);
+
+        assert_eq!(socket.recv_ntp_response(t1).unwrap().server, server);
+        assert_eq!(socket.recv_ntp_response(t1), Err(RecvError::Exhausted));
+    }
 }
@@ -158,6 +158,15 @@ impl Assembler {
         }
     }
 
+    /// Return the total number of octets in all contiguous data ranges.
+    pub fn data_len(&self) -> usize {
+        self.contigs
+            .iter()
+            .take_while(|contig| contig.has_data())
+            .map(|contig| contig.data_size)
+            .sum()
+    }
+
     fn back(&self) -> Contig {
         self.contigs[self.contigs.len() - 1]
     }
@@ -502,6 +511,15 @@ mod test {
         assert_eq!(assr_before, assr);
     }
 
+    #[test]
+    fn test_data_len() {
+        let assr = contigs![];
+        assert_eq!(assr.data_len(), 0);
+
+        let assr = contigs![(4, 8), (2, 3), (10, 1)];
+        assert_eq!(assr.data_len(), 12);
+    }
+
     #[test]
     fn test_empty_remove_front() {
         let mut assr = contigs![];
@@ -239,6 +239,27 @@ impl<'a, H> PacketBuffer<'a, H> {
         self.payload_ring.capacity()
     }
 
+    /// Return the total number of payload bytes of the packets in the buffer.
+    pub fn payload_bytes_count(&self) -> usize {
+        let len = self.metadata_ring.len();
+        let first = self.metadata_ring.get_allocated(0, len);
+        let second = self
+            .metadata_ring
+            .get_allocated(first.len(), len - first.len());
+        first
+            .iter()
+            .chain(second)
+            .filter(|metadata| !metadata.is_padding())
+            .map(|metadata| metadata.size)
+            .sum()
+    }
+
+    /// Return the number of payload bytes that are free. A packet of this size may still
+    /// not fit if the free space wraps around the end of the payload ring buffer.
+    pub fn payload_window(&self) -> usize {
+        self.payload_ring.window()
+    }
+
     /// Reset the packet buffer and clear any staged.
     #[allow(unused)]
     pub(crate) fn reset(&mut self) {
@@ -255,6 +276,21 @@ mod test {
         PacketBuffer::new(vec![PacketMetadata::EMPTY; 4], vec![0u8; 16])
     }
 
+    #[test]
+    fn test_payload_bytes_count() {
+        let mut buffer = buffer();
+        assert_eq!(buffer.payload_bytes_count(), 0);
+        buffer.enqueue(6, ()).unwrap();
+        buffer.enqueue(8, ()).unwrap();
+        buffer.dequeue().unwrap();
+        assert_eq!(buffer.payload_bytes_count(), 8);
+        assert_eq!(buffer.payload_window(), 8);
+        // This packet doesn't fit at the end of the ring, so it's preceded by padding.
+        buffer.enqueue(4, ()).unwrap();
+        assert_eq!(buffer.payload_bytes_count(), 12);
+        assert_eq!(buffer.payload_window(), 2);
+    }
+
     #[test]
     fn test_simple() {
         let mut buffer = buffer();
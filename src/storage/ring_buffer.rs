@@ -399,6 +399,13 @@ impl<'a, T: 'a> RingBuffer<'a, T> {
         self.length -= count;
         self.read_at = self.get_idx(count);
     }
+
+    /// Move the allocated elements to the start of the storage, so that
+    /// [get_allocated](#method.get_allocated) returns all of them at once.
+    pub fn make_contiguous(&mut self) {
+        self.storage.rotate_left(self.read_at);
+        self.read_at = 0;
+    }
 }
 
 impl<'a, T: 'a> From<ManagedSlice<'a, T>> for RingBuffer<'a, T> {
@@ -407,6 +414,26 @@ impl<'a, T: 'a> From<ManagedSlice<'a, T>> for RingBuffer<'a, T> {
     }
 }
 
+/// Create a fixed-capacity ring buffer backed by an array, without requiring
+/// the `alloc` feature.
+///
+/// ```rust
+/// # #[cfg(feature = "socket-tcp")]
+/// # {
+/// use smoltcp::socket::tcp;
+///
+/// let mut rx_storage = [0; 1024];
+/// let mut tx_storage = [0; 1024];
+/// let socket = tcp::Socket::new(&mut rx_storage, &mut tx_storage);
+/// assert_eq!(socket.recv_capacity(), 1024);
+/// # }
+/// ```
+impl<'a, T: 'a, const N: usize> From<&'a mut [T; N]> for RingBuffer<'a, T> {
+    fn from(array: &'a mut [T; N]) -> RingBuffer<'a, T> {
+        RingBuffer::new(&mut array[..])
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -770,6 +797,20 @@ mod test {
         assert_eq!(&data[..], b"mno\x00\x00\x00");
     }
 
+    #[test]
+    fn test_buffer_make_contiguous() {
+        let mut ring = RingBuffer::new(vec![b'.'; 12]);
+        ring.enqueue_many(12).copy_from_slice(b"abcdefghijkl");
+        ring.dequeue_many(8).copy_from_slice(b"ABCDEFGH");
+        ring.enqueue_many(3).copy_from_slice(b"mno");
+        assert_eq!(ring.get_allocated(0, 7), b"ijkl");
+
+        ring.make_contiguous();
+        assert_eq!(ring.get_allocated(0, 7), b"ijklmno");
+        assert_eq!(ring.enqueue_slice(b"pqrstu"), 5);
+        assert_eq!(ring.get_allocated(0, 12), b"ijklmnopqrst");
+    }
+
     #[test]
     fn test_buffer_with_no_capacity() {
         let mut no_capacity: RingBuffer<u8> = RingBuffer::new(vec![]);
@@ -800,4 +841,50 @@ mod test {
         let large = ring.enqueue_many(8);
         assert_eq!(large.len(), 8);
     }
+
+    #[test]
+    fn test_array_buffer_full() {
+        let mut storage = [0u8; 4];
+        let mut ring = RingBuffer::from(&mut storage);
+        assert_eq!(ring.capacity(), 4);
+
+        assert_eq!(ring.enqueue_slice(b"abcdef"), 4);
+        assert!(ring.is_full());
+        assert_eq!(ring.window(), 0);
+        assert_eq!(ring.enqueue_one(), Err(Full));
+        assert_eq!(ring.enqueue_slice(b"g"), 0);
+
+        let mut data = [0u8; 4];
+        assert_eq!(ring.dequeue_slice(&mut data), 4);
+        assert_eq!(&data, b"abcd");
+        assert!(ring.is_empty());
+        assert_eq!(ring.dequeue_one(), Err(Empty));
+    }
+
+    #[test]
+    fn test_array_buffer_wraparound() {
+        let mut storage = [b'.'; 6];
+        let mut ring = RingBuffer::from(&mut storage);
+
+        assert_eq!(ring.enqueue_slice(b"abcd"), 4);
+        let mut data = [0u8; 3];
+        assert_eq!(ring.dequeue_slice(&mut data), 3);
+        assert_eq!(&data, b"abc");
+
+        // The free space is split between the end and the start of the storage.
+        assert_eq!(ring.window(), 5);
+        assert_eq!(ring.contiguous_window(), 2);
+        assert_eq!(ring.enqueue_many(5), b"..");
+        assert_eq!(ring.enqueue_slice(b"ef"), 2);
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring.window(), 1);
+
+        let mut data = [0u8; 4];
+        assert_eq!(ring.dequeue_slice(&mut data), 4);
+        assert_eq!(&data, b"d..e");
+        assert_eq!(ring.dequeue_many(4), b"f");
+        assert!(ring.is_empty());
+        drop(ring);
+        assert_eq!(&storage, b"efcd..");
+    }
 }
@@ -5,11 +5,15 @@ absolute and relative time.
 
  - [Instant] is used to represent absolute time.
  - [Duration] is used to represent relative time.
+ - [Clock] is a source of the current time, which an interface can hold, see
+   [Config::clock].
 
 [Instant]: struct.Instant.html
 [Duration]: struct.Duration.html
+[Config::clock]: crate::iface::Config::clock
 */
 
+use core::cell::Cell;
 use core::{fmt, ops};
 
 /// A representation of an absolute time value.
@@ -347,6 +351,62 @@ impl From<Duration> for ::core::time::Duration {
     }
 }
 
+/// A source of the current time.
+///
+/// Implement this trait to poll an interface without reading the time at every call
+/// site, see [Config::clock](crate::iface::Config::clock).
+pub trait Clock {
+    /// Return the current time.
+    fn now(&self) -> Instant;
+}
+
+/// A clock that reads the time of the operating system, see [Instant::now].
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdClock;
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// This makes the passing of time deterministic, e.g. in tests: timers fire only after
+/// the clock has been [advanced](#method.advance) past them, and jumps in time can be
+/// simulated with [set](#method.set).
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Cell<Instant>,
+}
+
+impl ManualClock {
+    /// Create a clock that shows the time `now`.
+    pub const fn new(now: Instant) -> ManualClock {
+        ManualClock {
+            now: Cell::new(now),
+        }
+    }
+
+    /// Set the time shown by the clock, which may be earlier than the current one.
+    pub fn set(&self, now: Instant) {
+        self.now.set(now)
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -457,4 +517,14 @@ mod test {
         std_duration = duration.into();
         assert_eq!(std_duration, ::core::time::Duration::from_millis(4934));
     }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(Instant::from_millis(1000));
+        assert_eq!(clock.now(), Instant::from_millis(1000));
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(clock.now(), Instant::from_millis(1250));
+        clock.set(Instant::from_millis(500));
+        assert_eq!(clock.now(), Instant::from_millis(500));
+    }
 }
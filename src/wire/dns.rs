@@ -237,6 +237,14 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
         NetworkEndian::write_u16(field, (old & !mask) | val);
     }
 
+    pub fn set_rcode(&mut self, val: Rcode) {
+        let field = &mut self.buffer.as_mut()[field::FLAGS];
+        let mask = 0x000f;
+        let val: u8 = val.into();
+        let old = NetworkEndian::read_u16(field);
+        NetworkEndian::write_u16(field, (old & !mask) | (val as u16 & mask));
+    }
+
     pub fn set_question_count(&mut self, val: u16) {
         let field = &mut self.buffer.as_mut()[field::QDCOUNT];
         NetworkEndian::write_u16(field, val)
@@ -353,6 +361,33 @@ impl<'a> RecordData<'a> {
             x => Ok(RecordData::Other(x, data)),
         }
     }
+
+    /// Return the type of the record.
+    pub fn type_(&self) -> Type {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            RecordData::A(_) => Type::A,
+            #[cfg(feature = "proto-ipv6")]
+            RecordData::Aaaa(_) => Type::Aaaa,
+            RecordData::Cname(_) => Type::Cname,
+            RecordData::Other(type_, _) => *type_,
+        }
+    }
+
+    /// Return the length of the record data.
+    pub fn buffer_len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            RecordData::A(addr) => addr.as_bytes(),
+            #[cfg(feature = "proto-ipv6")]
+            RecordData::Aaaa(addr) => addr.as_bytes(),
+            RecordData::Cname(data) | RecordData::Other(_, data) => data,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -396,6 +431,25 @@ impl<'a> Record<'a> {
             },
         ))
     }
+
+    /// Return the length of a record that will be emitted from this high-level representation.
+    pub fn buffer_len(&self) -> usize {
+        self.name.len() + 10 + self.data.buffer_len()
+    }
+
+    /// Emit a high-level representation into a buffer.
+    ///
+    /// The name is emitted as is, so it may be a pointer to a name earlier in the packet.
+    pub fn emit(&self, buffer: &mut [u8]) {
+        buffer[..self.name.len()].copy_from_slice(self.name);
+        let rest = &mut buffer[self.name.len()..];
+        let data = self.data.as_bytes();
+        NetworkEndian::write_u16(&mut rest[0..2], self.data.type_().into());
+        NetworkEndian::write_u16(&mut rest[2..4], CLASS_IN);
+        NetworkEndian::write_u32(&mut rest[4..8], self.ttl);
+        NetworkEndian::write_u16(&mut rest[8..10], data.len() as u16);
+        rest[10..10 + data.len()].copy_from_slice(data);
+    }
 }
 
 /// High-level DNS packet representation.
@@ -790,4 +844,23 @@ mod test {
         ];
         assert_eq!(&buf, want);
     }
+
+    #[test]
+    fn test_emit_record() {
+        let record = Record {
+            name: &[0xc0, 0x0c],
+            ttl: 202,
+            data: RecordData::A(Ipv4Address::new(0xac, 0xd9, 0xa8, 0xae)),
+        };
+
+        let mut buf = vec![0; record.buffer_len()];
+        record.emit(&mut buf);
+
+        let want = &[
+            0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xca, 0x00, 0x04, 0xac, 0xd9,
+            0xa8, 0xae,
+        ];
+        assert_eq!(&buf, want);
+        assert_eq!(Record::parse(&buf), Ok((&[][..], record)));
+    }
 }
@@ -4,7 +4,7 @@ use core::{cmp, fmt};
 use super::{Error, Result};
 use crate::phy::ChecksumCapabilities;
 use crate::wire::ip::checksum;
-use crate::wire::{Ipv4Packet, Ipv4Repr};
+use crate::wire::{Ipv4Address, Ipv4Packet, Ipv4Repr};
 
 enum_with_unknown! {
     /// Internet protocol control message type.
@@ -128,6 +128,18 @@ enum_with_unknown! {
     }
 }
 
+impl fmt::Display for Redirect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Redirect::Net => write!(f, "redirect for network"),
+            Redirect::Host => write!(f, "redirect for host"),
+            Redirect::NetToS => write!(f, "redirect for ToS and network"),
+            Redirect::HostToS => write!(f, "redirect for ToS and host"),
+            Redirect::Unknown(id) => write!(f, "{id}"),
+        }
+    }
+}
+
 enum_with_unknown! {
     /// Internet protocol control message subtype for type "Time Exceeded".
     pub enum TimeExceeded(u8) {
@@ -176,6 +188,8 @@ mod field {
 
     pub const UNUSED: Field = 4..8;
 
+    pub const GATEWAY: Field = 4..8;
+
     pub const ECHO_IDENT: Field = 4..6;
     pub const ECHO_SEQNO: Field = 6..8;
 
@@ -259,6 +273,16 @@ impl<T: AsRef<[u8]>> Packet<T> {
         NetworkEndian::read_u16(&data[field::ECHO_SEQNO])
     }
 
+    /// Return the gateway address field (for redirect packets).
+    ///
+    /// # Panics
+    /// This function may panic if this packet is not a redirect packet.
+    #[inline]
+    pub fn gateway(&self) -> Ipv4Address {
+        let data = self.buffer.as_ref();
+        Ipv4Address::from_bytes(&data[field::GATEWAY])
+    }
+
     /// Return the header length.
     /// The result depends on the value of the message type field.
     pub fn header_len(&self) -> usize {
@@ -335,6 +359,16 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
         NetworkEndian::write_u16(&mut data[field::ECHO_SEQNO], value)
     }
 
+    /// Set the gateway address field (for redirect packets).
+    ///
+    /// # Panics
+    /// This function may panic if this packet is not a redirect packet.
+    #[inline]
+    pub fn set_gateway(&mut self, value: Ipv4Address) {
+        let data = self.buffer.as_mut();
+        data[field::GATEWAY].copy_from_slice(value.as_bytes())
+    }
+
     /// Compute and fill in the header checksum.
     pub fn fill_checksum(&mut self) {
         self.set_checksum(0);
@@ -387,6 +421,12 @@ pub enum Repr<'a> {
         header: Ipv4Repr,
         data: &'a [u8],
     },
+    Redirect {
+        reason: Redirect,
+        gateway: Ipv4Address,
+        header: Ipv4Repr,
+        data: &'a [u8],
+    },
 }
 
 impl<'a> Repr<'a> {
@@ -465,6 +505,28 @@ impl<'a> Repr<'a> {
                 })
             }
 
+            (Message::Redirect, code) => {
+                let ip_packet = Ipv4Packet::new_checked(packet.data())?;
+
+                let payload = &packet.data()[ip_packet.header_len() as usize..];
+                if payload.len() < 8 {
+                    return Err(Error);
+                }
+
+                Ok(Repr::Redirect {
+                    reason: Redirect::from(code),
+                    gateway: packet.gateway(),
+                    header: Ipv4Repr {
+                        src_addr: ip_packet.src_addr(),
+                        dst_addr: ip_packet.dst_addr(),
+                        next_header: ip_packet.next_header(),
+                        payload_len: payload.len(),
+                        hop_limit: ip_packet.hop_limit(),
+                    },
+                    data: payload,
+                })
+            }
+
             _ => Err(Error),
         }
     }
@@ -476,7 +538,8 @@ impl<'a> Repr<'a> {
                 field::ECHO_SEQNO.end + data.len()
             }
             &Repr::DstUnreachable { header, data, .. }
-            | &Repr::TimeExceeded { header, data, .. } => {
+            | &Repr::TimeExceeded { header, data, .. }
+            | &Repr::Redirect { header, data, .. } => {
                 field::UNUSED.end + header.buffer_len() + data.len()
             }
         }
@@ -543,6 +606,22 @@ impl<'a> Repr<'a> {
                 let payload = &mut ip_packet.into_inner()[header.buffer_len()..];
                 payload.copy_from_slice(data)
             }
+
+            Repr::Redirect {
+                reason,
+                gateway,
+                header,
+                data,
+            } => {
+                packet.set_msg_type(Message::Redirect);
+                packet.set_msg_code(reason.into());
+                packet.set_gateway(gateway);
+
+                let mut ip_packet = Ipv4Packet::new_unchecked(packet.data_mut());
+                header.emit(&mut ip_packet, checksum_caps);
+                let payload = &mut ip_packet.into_inner()[header.buffer_len()..];
+                payload.copy_from_slice(data)
+            }
         }
 
         if checksum_caps.icmpv4.tx() {
@@ -569,6 +648,9 @@ impl<'a, T: AsRef<[u8]> + ?Sized> fmt::Display for Packet<&'a T> {
                     Message::TimeExceeded => {
                         write!(f, " code={:?}", TimeExceeded::from(self.msg_code()))
                     }
+                    Message::Redirect => {
+                        write!(f, " code={:?}", Redirect::from(self.msg_code()))
+                    }
                     _ => write!(f, " code={}", self.msg_code()),
                 }
             }
@@ -607,6 +689,11 @@ impl<'a> fmt::Display for Repr<'a> {
             Repr::TimeExceeded { reason, .. } => {
                 write!(f, "ICMPv4 time exceeded ({reason})")
             }
+            Repr::Redirect {
+                reason, gateway, ..
+            } => {
+                write!(f, "ICMPv4 {reason} via {gateway}")
+            }
         }
     }
 }
@@ -626,7 +713,7 @@ impl<T: AsRef<[u8]>> PrettyPrint for Packet<T> {
         write!(f, "{indent}{packet}")?;
 
         match packet.msg_type() {
-            Message::DstUnreachable | Message::TimeExceeded => {
+            Message::DstUnreachable | Message::TimeExceeded | Message::Redirect => {
                 indent.increase(f)?;
                 super::Ipv4Packet::<&[u8]>::pretty_print(&packet.data(), f, indent)
             }
@@ -701,4 +788,32 @@ mod test {
         assert_eq!(Packet::new_checked(&bytes[..4]), Err(Error));
         assert!(Packet::new_checked(&bytes[..]).is_ok());
     }
+
+    #[test]
+    fn test_redirect_roundtrip() {
+        let repr = Repr::Redirect {
+            reason: Redirect::Host,
+            gateway: Ipv4Address::new(192, 168, 1, 253),
+            header: Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 1),
+                dst_addr: Ipv4Address::new(10, 0, 0, 1),
+                next_header: crate::wire::IpProtocol::Udp,
+                payload_len: 8,
+                hop_limit: 64,
+            },
+            data: &[0xaa; 8],
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        repr.emit(&mut packet, &ChecksumCapabilities::default());
+        assert_eq!(packet.msg_type(), Message::Redirect);
+        assert_eq!(packet.msg_code(), 1);
+        assert_eq!(packet.gateway(), Ipv4Address::new(192, 168, 1, 253));
+
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(
+            Repr::parse(&packet, &ChecksumCapabilities::default()),
+            Ok(repr)
+        );
+    }
 }
@@ -32,6 +32,18 @@ impl Version {
             _ => Err(Error),
         }
     }
+
+    /// Return the length of the header of an IP packet of this version, without options
+    /// or extension headers.
+    #[cfg(any(feature = "socket-udp", feature = "socket-raw"))]
+    pub(crate) const fn header_len(&self) -> usize {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            Version::Ipv4 => crate::wire::IPV4_HEADER_LEN,
+            #[cfg(feature = "proto-ipv6")]
+            Version::Ipv6 => crate::wire::IPV6_HEADER_LEN,
+        }
+    }
 }
 
 impl fmt::Display for Version {
@@ -464,6 +476,18 @@ impl ListenEndpoint {
     pub const fn is_specified(&self) -> bool {
         self.addr.is_some() && self.port != 0
     }
+
+    /// Return the endpoint, with the unspecified address in place of a missing one.
+    ///
+    /// The unspecified address is `0.0.0.0` if IPv4 is enabled, and `::` otherwise.
+    #[cfg(any(feature = "socket-udp", feature = "socket-icmp"))]
+    pub(crate) fn or_unspecified(&self) -> Endpoint {
+        #[cfg(feature = "proto-ipv4")]
+        let unspecified = Address::Ipv4(Ipv4Address::UNSPECIFIED);
+        #[cfg(not(feature = "proto-ipv4"))]
+        let unspecified = Address::Ipv6(Ipv6Address::UNSPECIFIED);
+        Endpoint::new(self.addr.unwrap_or(unspecified), self.port)
+    }
 }
 
 #[cfg(all(feature = "std", feature = "proto-ipv4", feature = "proto-ipv6"))]
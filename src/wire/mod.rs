@@ -122,11 +122,15 @@ mod ndisc;
     any(feature = "medium-ethernet", feature = "medium-ieee802154")
 ))]
 mod ndiscoption;
+#[cfg(feature = "proto-ntp")]
+mod ntp;
 #[cfg(feature = "proto-rpl")]
 mod rpl;
 #[cfg(all(feature = "proto-sixlowpan", feature = "medium-ieee802154"))]
 mod sixlowpan;
 mod tcp;
+#[cfg(feature = "proto-tftp")]
+mod tftp;
 mod udp;
 
 #[cfg(feature = "proto-ipsec-ah")]
@@ -279,6 +283,21 @@ pub use self::dns::{
     Type as DnsQueryType,
 };
 
+#[cfg(feature = "proto-ntp")]
+pub use self::ntp::{
+    LeapIndicator as NtpLeapIndicator, Mode as NtpMode, Packet as NtpPacket, Repr as NtpRepr,
+    Timestamp as NtpTimestamp, HEADER_LEN as NTP_HEADER_LEN, SERVER_PORT as NTP_SERVER_PORT,
+    VERSION as NTP_VERSION,
+};
+
+#[cfg(feature = "proto-tftp")]
+pub use self::tftp::{
+    ErrorCode as TftpErrorCode, Mode as TftpMode, Opcode as TftpOpcode, Packet as TftpPacket,
+    Repr as TftpRepr, DEFAULT_BLOCK_SIZE as TFTP_DEFAULT_BLOCK_SIZE,
+    MAX_BLOCK_SIZE as TFTP_MAX_BLOCK_SIZE, MIN_BLOCK_SIZE as TFTP_MIN_BLOCK_SIZE,
+    SERVER_PORT as TFTP_SERVER_PORT,
+};
+
 #[cfg(feature = "proto-ipsec-ah")]
 pub use self::ipsec_ah::{Packet as IpSecAuthHeaderPacket, Repr as IpSecAuthHeaderRepr};
 
@@ -284,7 +284,7 @@ impl<'a> Repr<'a> {
             Message::Redirect => Ok(Repr::Redirect {
                 target_addr: packet.target_addr(),
                 dest_addr: packet.dest_addr(),
-                lladdr: src_ll_addr,
+                lladdr: target_ll_addr,
                 redirected_hdr,
             }),
             _ => Err(Error),
@@ -544,4 +544,33 @@ mod test {
         );
         assert_eq!(&*packet.into_inner(), &ROUTER_ADVERT_BYTES[..]);
     }
+
+    #[test]
+    fn test_redirect_repr_roundtrip() {
+        let repr = Icmpv6Repr::Ndisc(Repr::Redirect {
+            target_addr: MOCK_IP_ADDR_2,
+            dest_addr: Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            lladdr: Some(EthernetAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]).into()),
+            redirected_hdr: None,
+        });
+        let mut bytes = vec![0x2a; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        repr.emit(
+            &MOCK_IP_ADDR_1,
+            &MOCK_IP_ADDR_2,
+            &mut packet,
+            &ChecksumCapabilities::default(),
+        );
+        // The link-layer address is carried in a Target Link-Layer Address option.
+        assert_eq!(bytes[field::DEST_ADDR.end], 0x02);
+        assert_eq!(
+            Icmpv6Repr::parse(
+                &MOCK_IP_ADDR_1,
+                &MOCK_IP_ADDR_2,
+                &Packet::new_checked(&bytes[..]).unwrap(),
+                &ChecksumCapabilities::default()
+            ),
+            Ok(repr)
+        );
+    }
 }
@@ -0,0 +1,561 @@
+// See https://tools.ietf.org/html/rfc5905 for the NTPv4 specification and
+// https://tools.ietf.org/html/rfc4330 for the simplified client (SNTP).
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use super::{Error, Result};
+use crate::time::Instant;
+
+pub const SERVER_PORT: u16 = 123;
+
+/// The version number emitted in NTP packets.
+pub const VERSION: u8 = 4;
+
+/// Seconds between the NTP epoch (1900-01-01) and the Unix epoch (1970-01-01).
+const UNIX_EPOCH_OFFSET: i64 = 2_208_988_800;
+
+enum_with_unknown! {
+    /// Leap second warning
+    pub enum LeapIndicator(u8) {
+        NoWarning      = 0,
+        LastMinute61   = 1,
+        LastMinute59   = 2,
+        Unsynchronized = 3,
+    }
+}
+
+enum_with_unknown! {
+    /// Association mode
+    pub enum Mode(u8) {
+        Reserved         = 0,
+        SymmetricActive  = 1,
+        SymmetricPassive = 2,
+        Client           = 3,
+        Server           = 4,
+        Broadcast        = 5,
+        Control          = 6,
+        Private          = 7,
+    }
+}
+
+/// A 64-bit NTP timestamp.
+///
+/// The `secs` field counts seconds since the NTP epoch (1900-01-01 00:00 UTC) modulo
+/// 2<sup>32</sup>, and `frac` is the fractional part of the second in units of
+/// 2<sup>-32</sup> s. The era is not transmitted, so conversions back to an
+/// [Instant] need a nearby reference time to disambiguate it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timestamp {
+    pub secs: u32,
+    pub frac: u32,
+}
+
+impl Timestamp {
+    /// The all-zeroes timestamp, used to mean "unknown" or "not set".
+    pub const ZERO: Timestamp = Timestamp { secs: 0, frac: 0 };
+
+    /// Create a timestamp from an `Instant` measured from the Unix epoch, such as the
+    /// one returned by [Instant::now].
+    pub fn from_instant(instant: Instant) -> Timestamp {
+        let micros = instant.total_micros();
+        let secs = micros.div_euclid(1_000_000);
+        let rem = micros.rem_euclid(1_000_000) as u64;
+        let frac = (rem << 32) / 1_000_000;
+        Timestamp {
+            secs: secs.wrapping_add(UNIX_EPOCH_OFFSET) as u32,
+            frac: frac as u32,
+        }
+    }
+
+    /// Convert the timestamp to an `Instant` measured from the Unix epoch.
+    ///
+    /// Of all the 136-year NTP eras, the one that places the result closest to
+    /// `reference` is chosen.
+    pub fn to_instant(self, reference: Instant) -> Instant {
+        let diff = self.micros_since(Timestamp::from_instant(reference));
+        Instant::from_micros(reference.total_micros() + diff)
+    }
+
+    /// Return the signed number of microseconds from `earlier` to `self`.
+    ///
+    /// The difference is computed modulo 2<sup>32</sup> seconds, so the result is correct
+    /// across an era rollover as long as the two timestamps are less than 68 years apart.
+    /// The fraction is rounded to the nearest microsecond, which makes conversions of
+    /// timestamps created by [from_instant](#method.from_instant) exact.
+    pub fn micros_since(self, earlier: Timestamp) -> i64 {
+        let diff = u64::from(self).wrapping_sub(u64::from(earlier)) as i64;
+        let secs = diff >> 32;
+        let frac = (diff & 0xffff_ffff) as u64;
+        secs * 1_000_000 + ((frac * 1_000_000 + (1 << 31)) >> 32) as i64
+    }
+}
+
+impl From<u64> for Timestamp {
+    fn from(value: u64) -> Timestamp {
+        Timestamp {
+            secs: (value >> 32) as u32,
+            frac: value as u32,
+        }
+    }
+}
+
+impl From<Timestamp> for u64 {
+    fn from(value: Timestamp) -> u64 {
+        (u64::from(value.secs) << 32) | u64::from(value.frac)
+    }
+}
+
+/// A read/write wrapper around a Network Time Protocol packet buffer.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Packet<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+pub(crate) mod field {
+    #![allow(non_snake_case)]
+    #![allow(unused)]
+
+    use crate::wire::field::*;
+
+    pub const LI_VN_MODE: usize = 0;
+    pub const STRATUM: usize = 1;
+    pub const POLL: usize = 2;
+    pub const PRECISION: usize = 3;
+    pub const ROOT_DELAY: Field = 4..8;
+    pub const ROOT_DISPERSION: Field = 8..12;
+    pub const REFERENCE_ID: Field = 12..16;
+    pub const REFERENCE_TS: Field = 16..24;
+    pub const ORIGINATE_TS: Field = 24..32;
+    pub const RECEIVE_TS: Field = 32..40;
+    pub const TRANSMIT_TS: Field = 40..48;
+}
+
+/// The length of an NTP packet without extension fields or a MAC.
+pub const HEADER_LEN: usize = field::TRANSMIT_TS.end;
+
+impl<T: AsRef<[u8]>> Packet<T> {
+    /// Imbue a raw octet buffer with NTP packet structure.
+    pub const fn new_unchecked(buffer: T) -> Packet<T> {
+        Packet { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Packet<T>> {
+        let packet = Self::new_unchecked(buffer);
+        packet.check_len()?;
+        Ok(packet)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    ///
+    /// Extension fields and the message authentication code that may follow
+    /// the header are not checked.
+    pub fn check_len(&self) -> Result<()> {
+        if self.buffer.as_ref().len() < HEADER_LEN {
+            Err(Error)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Consume the packet, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the leap indicator field.
+    pub fn leap_indicator(&self) -> LeapIndicator {
+        LeapIndicator::from(self.buffer.as_ref()[field::LI_VN_MODE] >> 6)
+    }
+
+    /// Return the version number field.
+    pub fn version(&self) -> u8 {
+        (self.buffer.as_ref()[field::LI_VN_MODE] >> 3) & 0x07
+    }
+
+    /// Return the mode field.
+    pub fn mode(&self) -> Mode {
+        Mode::from(self.buffer.as_ref()[field::LI_VN_MODE] & 0x07)
+    }
+
+    /// Return the stratum field.
+    pub fn stratum(&self) -> u8 {
+        self.buffer.as_ref()[field::STRATUM]
+    }
+
+    /// Return the poll interval field, as a log2 of seconds.
+    pub fn poll(&self) -> i8 {
+        self.buffer.as_ref()[field::POLL] as i8
+    }
+
+    /// Return the precision field, as a log2 of seconds.
+    pub fn precision(&self) -> i8 {
+        self.buffer.as_ref()[field::PRECISION] as i8
+    }
+
+    /// Return the root delay field, in NTP short format (16.16 fixed point seconds).
+    pub fn root_delay(&self) -> u32 {
+        NetworkEndian::read_u32(&self.buffer.as_ref()[field::ROOT_DELAY])
+    }
+
+    /// Return the root dispersion field, in NTP short format (16.16 fixed point seconds).
+    pub fn root_dispersion(&self) -> u32 {
+        NetworkEndian::read_u32(&self.buffer.as_ref()[field::ROOT_DISPERSION])
+    }
+
+    /// Return the reference identifier field.
+    pub fn reference_id(&self) -> [u8; 4] {
+        let mut id = [0; 4];
+        id.copy_from_slice(&self.buffer.as_ref()[field::REFERENCE_ID]);
+        id
+    }
+
+    /// Return the reference timestamp field.
+    pub fn reference_timestamp(&self) -> Timestamp {
+        Timestamp::from(NetworkEndian::read_u64(
+            &self.buffer.as_ref()[field::REFERENCE_TS],
+        ))
+    }
+
+    /// Return the originate timestamp field.
+    pub fn originate_timestamp(&self) -> Timestamp {
+        Timestamp::from(NetworkEndian::read_u64(
+            &self.buffer.as_ref()[field::ORIGINATE_TS],
+        ))
+    }
+
+    /// Return the receive timestamp field.
+    pub fn receive_timestamp(&self) -> Timestamp {
+        Timestamp::from(NetworkEndian::read_u64(
+            &self.buffer.as_ref()[field::RECEIVE_TS],
+        ))
+    }
+
+    /// Return the transmit timestamp field.
+    pub fn transmit_timestamp(&self) -> Timestamp {
+        Timestamp::from(NetworkEndian::read_u64(
+            &self.buffer.as_ref()[field::TRANSMIT_TS],
+        ))
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+    /// Set the leap indicator field.
+    pub fn set_leap_indicator(&mut self, value: LeapIndicator) {
+        let data = self.buffer.as_mut();
+        data[field::LI_VN_MODE] = (data[field::LI_VN_MODE] & 0x3f) | (u8::from(value) << 6);
+    }
+
+    /// Set the version number field.
+    pub fn set_version(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::LI_VN_MODE] = (data[field::LI_VN_MODE] & 0xc7) | ((value & 0x07) << 3);
+    }
+
+    /// Set the mode field.
+    pub fn set_mode(&mut self, value: Mode) {
+        let data = self.buffer.as_mut();
+        data[field::LI_VN_MODE] = (data[field::LI_VN_MODE] & 0xf8) | (u8::from(value) & 0x07);
+    }
+
+    /// Set the stratum field.
+    pub fn set_stratum(&mut self, value: u8) {
+        self.buffer.as_mut()[field::STRATUM] = value;
+    }
+
+    /// Set the poll interval field.
+    pub fn set_poll(&mut self, value: i8) {
+        self.buffer.as_mut()[field::POLL] = value as u8;
+    }
+
+    /// Set the precision field.
+    pub fn set_precision(&mut self, value: i8) {
+        self.buffer.as_mut()[field::PRECISION] = value as u8;
+    }
+
+    /// Set the root delay field.
+    pub fn set_root_delay(&mut self, value: u32) {
+        NetworkEndian::write_u32(&mut self.buffer.as_mut()[field::ROOT_DELAY], value);
+    }
+
+    /// Set the root dispersion field.
+    pub fn set_root_dispersion(&mut self, value: u32) {
+        NetworkEndian::write_u32(&mut self.buffer.as_mut()[field::ROOT_DISPERSION], value);
+    }
+
+    /// Set the reference identifier field.
+    pub fn set_reference_id(&mut self, value: [u8; 4]) {
+        self.buffer.as_mut()[field::REFERENCE_ID].copy_from_slice(&value);
+    }
+
+    /// Set the reference timestamp field.
+    pub fn set_reference_timestamp(&mut self, value: Timestamp) {
+        NetworkEndian::write_u64(&mut self.buffer.as_mut()[field::REFERENCE_TS], value.into());
+    }
+
+    /// Set the originate timestamp field.
+    pub fn set_originate_timestamp(&mut self, value: Timestamp) {
+        NetworkEndian::write_u64(&mut self.buffer.as_mut()[field::ORIGINATE_TS], value.into());
+    }
+
+    /// Set the receive timestamp field.
+    pub fn set_receive_timestamp(&mut self, value: Timestamp) {
+        NetworkEndian::write_u64(&mut self.buffer.as_mut()[field::RECEIVE_TS], value.into());
+    }
+
+    /// Set the transmit timestamp field.
+    pub fn set_transmit_timestamp(&mut self, value: Timestamp) {
+        NetworkEndian::write_u64(&mut self.buffer.as_mut()[field::TRANSMIT_TS], value.into());
+    }
+}
+
+/// A high-level representation of a Network Time Protocol packet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Repr {
+    pub leap_indicator: LeapIndicator,
+    pub version: u8,
+    pub mode: Mode,
+    pub stratum: u8,
+    pub poll: i8,
+    pub precision: i8,
+    pub root_delay: u32,
+    pub root_dispersion: u32,
+    pub reference_id: [u8; 4],
+    pub reference_timestamp: Timestamp,
+    pub originate_timestamp: Timestamp,
+    pub receive_timestamp: Timestamp,
+    pub transmit_timestamp: Timestamp,
+}
+
+impl Repr {
+    /// Create a client mode request carrying the given transmit timestamp.
+    ///
+    /// All other fields are zero, as recommended for SNTP clients.
+    pub const fn client_request(transmit_timestamp: Timestamp) -> Repr {
+        Repr {
+            leap_indicator: LeapIndicator::NoWarning,
+            version: VERSION,
+            mode: Mode::Client,
+            stratum: 0,
+            poll: 0,
+            precision: 0,
+            root_delay: 0,
+            root_dispersion: 0,
+            reference_id: [0; 4],
+            reference_timestamp: Timestamp::ZERO,
+            originate_timestamp: Timestamp::ZERO,
+            receive_timestamp: Timestamp::ZERO,
+            transmit_timestamp,
+        }
+    }
+
+    /// Parse a Network Time Protocol packet and return a high-level representation.
+    pub fn parse<T: AsRef<[u8]> + ?Sized>(packet: &Packet<&T>) -> Result<Repr> {
+        packet.check_len()?;
+
+        let version = packet.version();
+        if !(1..=VERSION).contains(&version) {
+            return Err(Error);
+        }
+
+        Ok(Repr {
+            leap_indicator: packet.leap_indicator(),
+            version,
+            mode: packet.mode(),
+            stratum: packet.stratum(),
+            poll: packet.poll(),
+            precision: packet.precision(),
+            root_delay: packet.root_delay(),
+            root_dispersion: packet.root_dispersion(),
+            reference_id: packet.reference_id(),
+            reference_timestamp: packet.reference_timestamp(),
+            originate_timestamp: packet.originate_timestamp(),
+            receive_timestamp: packet.receive_timestamp(),
+            transmit_timestamp: packet.transmit_timestamp(),
+        })
+    }
+
+    /// Return the length of a packet that will be emitted from this high-level representation.
+    pub const fn buffer_len(&self) -> usize {
+        HEADER_LEN
+    }
+
+    /// Emit a high-level representation into a Network Time Protocol packet.
+    pub fn emit<T: AsRef<[u8]> + AsMut<[u8]> + ?Sized>(&self, packet: &mut Packet<&mut T>) {
+        packet.set_leap_indicator(self.leap_indicator);
+        packet.set_version(self.version);
+        packet.set_mode(self.mode);
+        packet.set_stratum(self.stratum);
+        packet.set_poll(self.poll);
+        packet.set_precision(self.precision);
+        packet.set_root_delay(self.root_delay);
+        packet.set_root_dispersion(self.root_dispersion);
+        packet.set_reference_id(self.reference_id);
+        packet.set_reference_timestamp(self.reference_timestamp);
+        packet.set_originate_timestamp(self.originate_timestamp);
+        packet.set_receive_timestamp(self.receive_timestamp);
+        packet.set_transmit_timestamp(self.transmit_timestamp);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A stratum 2 server reply (LI 0, VN 4, mode 4).
+    static SERVER_REPLY_BYTES: [u8; 48] = [
+        0x24, 0x02, 0x06, 0xe9, 0x00, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00, 0x2c, 0xc0, 0xa8, 0x01,
+        0x01, 0xea, 0xb3, 0x9d, 0x40, 0x1c, 0x28, 0xf5, 0xc2, 0xea, 0xb3, 0x9e, 0x10, 0x80, 0x00,
+        0x00, 0x00, 0xea, 0xb3, 0x9e, 0x10, 0x80, 0x83, 0x12, 0x6e, 0xea, 0xb3, 0x9e, 0x10, 0x80,
+        0x9b, 0xa5, 0xe3,
+    ];
+
+    fn server_reply_repr() -> Repr {
+        Repr {
+            leap_indicator: LeapIndicator::NoWarning,
+            version: 4,
+            mode: Mode::Server,
+            stratum: 2,
+            poll: 6,
+            precision: -23,
+            root_delay: 0x1a,
+            root_dispersion: 0x2c,
+            reference_id: [192, 168, 1, 1],
+            reference_timestamp: Timestamp {
+                secs: 0xeab39d40,
+                frac: 0x1c28f5c2,
+            },
+            originate_timestamp: Timestamp {
+                secs: 0xeab39e10,
+                frac: 0x80000000,
+            },
+            receive_timestamp: Timestamp {
+                secs: 0xeab39e10,
+                frac: 0x8083126e,
+            },
+            transmit_timestamp: Timestamp {
+                secs: 0xeab39e10,
+                frac: 0x809ba5e3,
+            },
+        }
+    }
+
+    #[test]
+    fn test_deconstruct() {
+        let packet = Packet::new_checked(&SERVER_REPLY_BYTES[..]).unwrap();
+        assert_eq!(packet.leap_indicator(), LeapIndicator::NoWarning);
+        assert_eq!(packet.version(), 4);
+        assert_eq!(packet.mode(), Mode::Server);
+        assert_eq!(packet.stratum(), 2);
+        assert_eq!(packet.poll(), 6);
+        assert_eq!(packet.precision(), -23);
+        assert_eq!(packet.reference_id(), [192, 168, 1, 1]);
+        assert_eq!(
+            packet.originate_timestamp(),
+            Timestamp {
+                secs: 0xeab39e10,
+                frac: 0x80000000
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let packet = Packet::new_checked(&SERVER_REPLY_BYTES[..]).unwrap();
+        assert_eq!(Repr::parse(&packet), Ok(server_reply_repr()));
+    }
+
+    #[test]
+    fn test_emit() {
+        let repr = server_reply_repr();
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        repr.emit(&mut Packet::new_unchecked(&mut bytes));
+        assert_eq!(bytes, &SERVER_REPLY_BYTES[..]);
+    }
+
+    #[test]
+    fn test_emit_client_request() {
+        let repr = Repr::client_request(Timestamp {
+            secs: 0xeab39e10,
+            frac: 0x80000000,
+        });
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        repr.emit(&mut Packet::new_unchecked(&mut bytes));
+
+        let mut expected = [0u8; 48];
+        expected[0] = 0x23;
+        expected[40..48].copy_from_slice(&[0xea, 0xb3, 0x9e, 0x10, 0x80, 0x00, 0x00, 0x00]);
+        assert_eq!(bytes, &expected[..]);
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        assert_eq!(Packet::new_checked(&SERVER_REPLY_BYTES[..47]), Err(Error));
+    }
+
+    #[test]
+    fn test_parse_bad_version() {
+        let mut bytes = SERVER_REPLY_BYTES;
+        bytes[0] = 0x3c; // VN 7
+        let packet = Packet::new_unchecked(&bytes[..]);
+        assert_eq!(Repr::parse(&packet), Err(Error));
+    }
+
+    #[test]
+    fn test_timestamp_epoch() {
+        // The Unix epoch is 2208988800 seconds into NTP era 0.
+        let ts = Timestamp::from_instant(Instant::from_secs(0));
+        assert_eq!(
+            ts,
+            Timestamp {
+                secs: 2_208_988_800,
+                frac: 0
+            }
+        );
+
+        // 2023-01-01 00:00:00.5 UTC
+        let ts = Timestamp::from_instant(Instant::from_millis(1_672_531_200_500i64));
+        assert_eq!(
+            ts,
+            Timestamp {
+                secs: 0xe75b_4b80,
+                frac: 0x8000_0000
+            }
+        );
+    }
+
+    #[test]
+    fn test_timestamp_roundtrip() {
+        let reference = Instant::from_secs(1_700_000_000);
+        for micros in [0i64, 1, 999_999, 123_456_789, -1, -1_000_001] {
+            let instant = Instant::from_micros(reference.total_micros() + micros);
+            let ts = Timestamp::from_instant(instant);
+            assert_eq!(ts.to_instant(reference), instant);
+        }
+    }
+
+    #[test]
+    fn test_timestamp_era_rollover() {
+        // Era 1 starts on 2036-02-07 06:28:16 UTC.
+        let era1 = Instant::from_secs((1i64 << 32) - UNIX_EPOCH_OFFSET);
+        let before = Instant::from_micros(era1.total_micros() - 1_500_000);
+        let after = Instant::from_micros(era1.total_micros() + 2_250_000);
+
+        let ts_before = Timestamp::from_instant(before);
+        let ts_after = Timestamp::from_instant(after);
+        assert_eq!(ts_before.secs, u32::MAX - 1);
+        assert_eq!(ts_after.secs, 2);
+        assert_eq!(ts_after.micros_since(ts_before), 3_750_000);
+        assert_eq!(ts_before.micros_since(ts_after), -3_750_000);
+
+        assert_eq!(ts_after.to_instant(before), after);
+        assert_eq!(ts_before.to_instant(after), before);
+    }
+}
@@ -0,0 +1,583 @@
+// See https://tools.ietf.org/html/rfc1350 for the TFTP specification,
+// https://tools.ietf.org/html/rfc2347 for option negotiation and
+// https://tools.ietf.org/html/rfc2348 for the block size option.
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use super::{Error, Result};
+
+pub const SERVER_PORT: u16 = 69;
+
+/// The block size used unless a different one is negotiated.
+pub const DEFAULT_BLOCK_SIZE: u16 = 512;
+/// The smallest block size that may be negotiated.
+pub const MIN_BLOCK_SIZE: u16 = 8;
+/// The largest block size that may be negotiated.
+pub const MAX_BLOCK_SIZE: u16 = 65464;
+
+const OPTION_BLOCK_SIZE: &[u8] = b"blksize";
+
+enum_with_unknown! {
+    /// TFTP opcodes
+    pub enum Opcode(u16) {
+        ReadRequest  = 1,
+        WriteRequest = 2,
+        Data         = 3,
+        Ack          = 4,
+        Error        = 5,
+        OptionAck    = 6,
+    }
+}
+
+enum_with_unknown! {
+    /// TFTP error codes
+    pub enum ErrorCode(u16) {
+        Undefined         = 0,
+        FileNotFound      = 1,
+        AccessViolation   = 2,
+        DiskFull          = 3,
+        IllegalOperation  = 4,
+        UnknownTransferId = 5,
+        FileExists        = 6,
+        NoSuchUser        = 7,
+        OptionNegotiation = 8,
+    }
+}
+
+impl core::fmt::Display for Opcode {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            Opcode::ReadRequest => write!(f, "RRQ"),
+            Opcode::WriteRequest => write!(f, "WRQ"),
+            Opcode::Data => write!(f, "DATA"),
+            Opcode::Ack => write!(f, "ACK"),
+            Opcode::Error => write!(f, "ERROR"),
+            Opcode::OptionAck => write!(f, "OACK"),
+            Opcode::Unknown(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// TFTP transfer modes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Mode {
+    Netascii,
+    Octet,
+}
+
+impl Mode {
+    fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            Mode::Netascii => b"netascii",
+            Mode::Octet => b"octet",
+        }
+    }
+
+    fn parse(name: &[u8]) -> Result<Mode> {
+        if name.eq_ignore_ascii_case(b"netascii") {
+            Ok(Mode::Netascii)
+        } else if name.eq_ignore_ascii_case(b"octet") {
+            Ok(Mode::Octet)
+        } else {
+            Err(Error)
+        }
+    }
+}
+
+mod field {
+    use crate::wire::field::*;
+
+    pub const OPCODE: Field = 0..2;
+    pub const BLOCK: Field = 2..4;
+    pub const ERROR_CODE: Field = 2..4;
+    pub const DATA: Rest = 4..;
+}
+
+/// A read/write wrapper around a TFTP packet buffer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Packet<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+impl<T: AsRef<[u8]>> Packet<T> {
+    /// Imbue a raw octet buffer with TFTP packet structure.
+    pub const fn new_unchecked(buffer: T) -> Packet<T> {
+        Packet { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Packet<T>> {
+        let packet = Self::new_unchecked(buffer);
+        packet.check_len()?;
+        Ok(packet)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    ///
+    /// The result of this check is invalidated by calling [set_opcode].
+    ///
+    /// [set_opcode]: #method.set_opcode
+    pub fn check_len(&self) -> Result<()> {
+        let len = self.buffer.as_ref().len();
+        if len < field::OPCODE.end {
+            return Err(Error);
+        }
+        match self.opcode() {
+            Opcode::Data | Opcode::Ack | Opcode::Error if len < field::DATA.start => Err(Error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Consume the packet, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the opcode field.
+    pub fn opcode(&self) -> Opcode {
+        let data = self.buffer.as_ref();
+        Opcode::from(NetworkEndian::read_u16(&data[field::OPCODE]))
+    }
+
+    /// Return the block number field of a DATA or ACK packet.
+    pub fn block_number(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::BLOCK])
+    }
+
+    /// Return the error code field of an ERROR packet.
+    pub fn error_code(&self) -> ErrorCode {
+        let data = self.buffer.as_ref();
+        ErrorCode::from(NetworkEndian::read_u16(&data[field::ERROR_CODE]))
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Packet<&'a T> {
+    /// Return a pointer to the data of a DATA packet, or the message of an ERROR packet.
+    pub fn data(&self) -> &'a [u8] {
+        &self.buffer.as_ref()[field::DATA]
+    }
+
+    /// Return a pointer to everything following the opcode.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.buffer.as_ref()[field::OPCODE.end..]
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+    /// Set the opcode field.
+    pub fn set_opcode(&mut self, value: Opcode) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::OPCODE], value.into())
+    }
+
+    /// Set the block number field of a DATA or ACK packet.
+    pub fn set_block_number(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::BLOCK], value)
+    }
+
+    /// Set the error code field of an ERROR packet.
+    pub fn set_error_code(&mut self, value: ErrorCode) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::ERROR_CODE], value.into())
+    }
+
+    /// Return a mutable pointer to the data of a DATA packet, or the message of an ERROR packet.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer.as_mut()[field::DATA]
+    }
+
+    /// Return a mutable pointer to everything following the opcode.
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer.as_mut()[field::OPCODE.end..]
+    }
+}
+
+/// A high-level representation of a Trivial File Transfer Protocol packet.
+///
+/// Of the options defined by [RFC 2347], only `blksize` ([RFC 2348]) is understood.
+/// Unknown options are skipped when parsing.
+///
+/// [RFC 2347]: https://tools.ietf.org/html/rfc2347
+/// [RFC 2348]: https://tools.ietf.org/html/rfc2348
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Repr<'a> {
+    ReadRequest {
+        filename: &'a [u8],
+        mode: Mode,
+        block_size: Option<u16>,
+    },
+    WriteRequest {
+        filename: &'a [u8],
+        mode: Mode,
+        block_size: Option<u16>,
+    },
+    Data {
+        block: u16,
+        data: &'a [u8],
+    },
+    Ack {
+        block: u16,
+    },
+    Error {
+        code: ErrorCode,
+        message: &'a [u8],
+    },
+    OptionAck {
+        block_size: Option<u16>,
+    },
+}
+
+impl<'a> Repr<'a> {
+    /// Parse a TFTP packet and return a high-level representation.
+    pub fn parse<T>(packet: &Packet<&'a T>) -> Result<Repr<'a>>
+    where
+        T: AsRef<[u8]> + ?Sized,
+    {
+        match packet.opcode() {
+            opcode @ (Opcode::ReadRequest | Opcode::WriteRequest) => {
+                let (filename, rest) = split_string(packet.payload())?;
+                let (mode, rest) = split_string(rest)?;
+                if filename.is_empty() {
+                    return Err(Error);
+                }
+                let mode = Mode::parse(mode)?;
+                let block_size = parse_options(rest)?;
+                Ok(if opcode == Opcode::ReadRequest {
+                    Repr::ReadRequest {
+                        filename,
+                        mode,
+                        block_size,
+                    }
+                } else {
+                    Repr::WriteRequest {
+                        filename,
+                        mode,
+                        block_size,
+                    }
+                })
+            }
+            Opcode::Data => Ok(Repr::Data {
+                block: packet.block_number(),
+                data: packet.data(),
+            }),
+            Opcode::Ack => Ok(Repr::Ack {
+                block: packet.block_number(),
+            }),
+            Opcode::Error => {
+                let (message, _) = split_string(packet.data())?;
+                Ok(Repr::Error {
+                    code: packet.error_code(),
+                    message,
+                })
+            }
+            Opcode::OptionAck => Ok(Repr::OptionAck {
+                block_size: parse_options(packet.payload())?,
+            }),
+            Opcode::Unknown(_) => Err(Error),
+        }
+    }
+
+    /// Return the length of a packet that will be emitted from this high-level representation.
+    pub fn buffer_len(&self) -> usize {
+        field::OPCODE.end
+            + match *self {
+                Repr::ReadRequest {
+                    filename,
+                    mode,
+                    block_size,
+                }
+                | Repr::WriteRequest {
+                    filename,
+                    mode,
+                    block_size,
+                } => filename.len() + 1 + mode.as_bytes().len() + 1 + options_len(block_size),
+                Repr::Data { data, .. } => 2 + data.len(),
+                Repr::Ack { .. } => 2,
+                Repr::Error { message, .. } => 2 + message.len() + 1,
+                Repr::OptionAck { block_size } => options_len(block_size),
+            }
+    }
+
+    /// Emit a high-level representation into a TFTP packet.
+    pub fn emit<T>(&self, packet: &mut Packet<&mut T>)
+    where
+        T: AsRef<[u8]> + AsMut<[u8]> + ?Sized,
+    {
+        match *self {
+            Repr::ReadRequest {
+                filename,
+                mode,
+                block_size,
+            }
+            | Repr::WriteRequest {
+                filename,
+                mode,
+                block_size,
+            } => {
+                packet.set_opcode(match self {
+                    Repr::ReadRequest { .. } => Opcode::ReadRequest,
+                    _ => Opcode::WriteRequest,
+                });
+                let payload = packet.payload_mut();
+                let payload = emit_string(payload, filename);
+                let payload = emit_string(payload, mode.as_bytes());
+                emit_options(payload, block_size);
+            }
+            Repr::Data { block, data } => {
+                packet.set_opcode(Opcode::Data);
+                packet.set_block_number(block);
+                packet.data_mut()[..data.len()].copy_from_slice(data);
+            }
+            Repr::Ack { block } => {
+                packet.set_opcode(Opcode::Ack);
+                packet.set_block_number(block);
+            }
+            Repr::Error { code, message } => {
+                packet.set_opcode(Opcode::Error);
+                packet.set_error_code(code);
+                emit_string(packet.data_mut(), message);
+            }
+            Repr::OptionAck { block_size } => {
+                packet.set_opcode(Opcode::OptionAck);
+                emit_options(packet.payload_mut(), block_size);
+            }
+        }
+    }
+}
+
+/// Split a NUL-terminated string off the front of `buffer`.
+fn split_string(buffer: &[u8]) -> Result<(&[u8], &[u8])> {
+    let end = buffer.iter().position(|&b| b == 0).ok_or(Error)?;
+    Ok((&buffer[..end], &buffer[end + 1..]))
+}
+
+fn emit_string<'b>(buffer: &'b mut [u8], value: &[u8]) -> &'b mut [u8] {
+    buffer[..value.len()].copy_from_slice(value);
+    buffer[value.len()] = 0;
+    &mut buffer[value.len() + 1..]
+}
+
+/// Parse a list of options, returning the value of the block size option if present.
+fn parse_options(mut buffer: &[u8]) -> Result<Option<u16>> {
+    let mut block_size = None;
+    while !buffer.is_empty() {
+        let (name, rest) = split_string(buffer)?;
+        let (value, rest) = split_string(rest)?;
+        buffer = rest;
+
+        if name.eq_ignore_ascii_case(OPTION_BLOCK_SIZE) {
+            let size = parse_decimal(value)?;
+            if !(MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&size) {
+                return Err(Error);
+            }
+            block_size = Some(size);
+        }
+    }
+    Ok(block_size)
+}
+
+fn options_len(block_size: Option<u16>) -> usize {
+    match block_size {
+        Some(size) => OPTION_BLOCK_SIZE.len() + 1 + decimal_len(size) + 1,
+        None => 0,
+    }
+}
+
+fn emit_options(buffer: &mut [u8], block_size: Option<u16>) {
+    if let Some(size) = block_size {
+        let buffer = emit_string(buffer, OPTION_BLOCK_SIZE);
+        let len = decimal_len(size);
+        let mut value = size;
+        for digit in buffer[..len].iter_mut().rev() {
+            *digit = b'0' + (value % 10) as u8;
+            value /= 10;
+        }
+        buffer[len] = 0;
+    }
+}
+
+fn parse_decimal(digits: &[u8]) -> Result<u16> {
+    if digits.is_empty() {
+        return Err(Error);
+    }
+    digits.iter().try_fold(0u16, |acc, &digit| {
+        if !digit.is_ascii_digit() {
+            return Err(Error);
+        }
+        acc.checked_mul(10)
+            .and_then(|acc| acc.checked_add((digit - b'0') as u16))
+            .ok_or(Error)
+    })
+}
+
+fn decimal_len(mut value: u16) -> usize {
+    let mut len = 1;
+    while value >= 10 {
+        value /= 10;
+        len += 1;
+    }
+    len
+}
+
+impl<T: AsRef<[u8]> + ?Sized> core::fmt::Display for Packet<&T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match Repr::parse(self) {
+            Ok(repr) => write!(f, "{repr}"),
+            Err(err) => write!(f, "TFTP ({err})"),
+        }
+    }
+}
+
+impl core::fmt::Display for Repr<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            Repr::ReadRequest { block_size, .. } => {
+                write!(f, "TFTP RRQ blksize={block_size:?}")
+            }
+            Repr::WriteRequest { block_size, .. } => {
+                write!(f, "TFTP WRQ blksize={block_size:?}")
+            }
+            Repr::Data { block, data } => write!(f, "TFTP DATA block={block} len={}", data.len()),
+            Repr::Ack { block } => write!(f, "TFTP ACK block={block}"),
+            Repr::Error { code, .. } => write!(f, "TFTP ERROR code={code:?}"),
+            Repr::OptionAck { block_size } => write!(f, "TFTP OACK blksize={block_size:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::vec;
+
+    static RRQ_BYTES: [u8; 30] = [
+        0x00, 0x01, b'f', b'i', b'r', b'm', b'w', b'a', b'r', b'e', 0x00, b'o', b'c', b't', b'e',
+        b't', 0x00, b'b', b'l', b'k', b's', b'i', b'z', b'e', 0x00, b'1', b'4', b'0', b'0', 0x00,
+    ];
+
+    static DATA_BYTES: [u8; 8] = [0x00, 0x03, 0x00, 0x2a, 0xaa, 0xbb, 0xcc, 0xdd];
+
+    static ERROR_BYTES: [u8; 9] = [0x00, 0x05, 0x00, 0x01, b'g', b'o', b'n', b'e', 0x00];
+
+    static OACK_BYTES: [u8; 14] = [
+        0x00, 0x06, b'B', b'L', b'K', b'S', b'I', b'Z', b'E', 0x00, b'5', b'1', b'2', 0x00,
+    ];
+
+    #[test]
+    fn test_deconstruct_data() {
+        let packet = Packet::new_checked(&DATA_BYTES[..]).unwrap();
+        assert_eq!(packet.opcode(), Opcode::Data);
+        assert_eq!(packet.block_number(), 42);
+        assert_eq!(packet.data(), &[0xaa, 0xbb, 0xcc, 0xdd]);
+    }
+
+    #[test]
+    fn test_check_len() {
+        assert_eq!(Packet::new_checked(&[0x00][..]), Err(Error));
+        assert_eq!(Packet::new_checked(&[0x00, 0x04, 0x00][..]), Err(Error));
+        assert!(Packet::new_checked(&[0x00, 0x04, 0x00, 0x01][..]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_read_request() {
+        let packet = Packet::new_checked(&RRQ_BYTES[..]).unwrap();
+        assert_eq!(
+            Repr::parse(&packet).unwrap(),
+            Repr::ReadRequest {
+                filename: b"firmware",
+                mode: Mode::Octet,
+                block_size: Some(1400),
+            }
+        );
+    }
+
+    #[test]
+    fn test_emit_read_request() {
+        let repr = Repr::ReadRequest {
+            filename: b"firmware",
+            mode: Mode::Octet,
+            block_size: Some(1400),
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        repr.emit(&mut Packet::new_unchecked(&mut bytes));
+        assert_eq!(bytes, &RRQ_BYTES[..]);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let packet = Packet::new_checked(&ERROR_BYTES[..]).unwrap();
+        assert_eq!(
+            Repr::parse(&packet).unwrap(),
+            Repr::Error {
+                code: ErrorCode::FileNotFound,
+                message: b"gone",
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_option_ack() {
+        let packet = Packet::new_checked(&OACK_BYTES[..]).unwrap();
+        assert_eq!(
+            Repr::parse(&packet).unwrap(),
+            Repr::OptionAck {
+                block_size: Some(512)
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_option() {
+        let bytes = [0x00, 0x06, b't', b's', b'i', b'z', b'e', 0x00, b'0', 0x00];
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(
+            Repr::parse(&packet).unwrap(),
+            Repr::OptionAck { block_size: None }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_block_size() {
+        let bytes = [
+            0x00, 0x06, b'b', b'l', b'k', b's', b'i', b'z', b'e', 0x00, b'4', 0x00,
+        ];
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(Repr::parse(&packet), Err(Error));
+    }
+
+    #[test]
+    fn test_emit_roundtrip() {
+        for repr in [
+            Repr::WriteRequest {
+                filename: b"log.txt",
+                mode: Mode::Netascii,
+                block_size: None,
+            },
+            Repr::Data {
+                block: 7,
+                data: &[1, 2, 3],
+            },
+            Repr::Ack { block: 65535 },
+            Repr::Error {
+                code: ErrorCode::DiskFull,
+                message: b"full",
+            },
+            Repr::OptionAck {
+                block_size: Some(8),
+            },
+        ] {
+            let mut bytes = vec![0xa5; repr.buffer_len()];
+            repr.emit(&mut Packet::new_unchecked(&mut bytes));
+            let packet = Packet::new_checked(&bytes[..]).unwrap();
+            assert_eq!(Repr::parse(&packet).unwrap(), repr);
+        }
+    }
+}
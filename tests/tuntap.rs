@@ -0,0 +1,28 @@
+//! Tests for `TunTapInterface` that need an actual TAP device.
+//!
+//! Creating the device and changing its MTU require superuser privileges (or the
+//! `CAP_NET_ADMIN` capability), so these tests are ignored by default. Run them with
+//! `cargo test --test tuntap -- --ignored`.
+#![cfg(all(
+    target_os = "linux",
+    feature = "phy-tuntap_interface",
+    feature = "medium-ethernet"
+))]
+
+use smoltcp::phy::{Device, Medium, TunTapInterface};
+
+#[test]
+#[ignore = "requires CAP_NET_ADMIN"]
+fn test_tap_mtu() {
+    let mut device = TunTapInterface::with_mtu("smoltcp-mtu0", Medium::Ethernet, 9014).unwrap();
+    assert_eq!(device.mtu().unwrap(), 9014);
+    assert_eq!(device.capabilities().max_transmission_unit, 9014);
+
+    device.set_mtu(1294).unwrap();
+    assert_eq!(device.mtu().unwrap(), 1294);
+    assert_eq!(device.capabilities().max_transmission_unit, 1294);
+
+    // The MTU must leave room for the Ethernet header.
+    assert!(device.set_mtu(10).is_err());
+    assert_eq!(device.capabilities().max_transmission_unit, 1294);
+}
@@ -0,0 +1,88 @@
+//! Tests for `TunTapInterface` on macOS, where it uses a utun interface.
+//!
+//! Creating a utun interface and configuring its addresses require superuser privileges,
+//! so these tests are ignored by default. Run them with
+//! `sudo -E cargo test --test utun -- --ignored`.
+#![cfg(all(
+    target_os = "macos",
+    feature = "phy-tuntap_interface",
+    feature = "medium-ip",
+    feature = "proto-ipv4"
+))]
+
+use std::os::unix::io::AsRawFd;
+use std::process::Command;
+
+use smoltcp::phy::{wait, ChecksumCapabilities, Device, Medium, RxToken, TunTapInterface, TxToken};
+use smoltcp::time::{Duration, Instant};
+use smoltcp::wire::{Icmpv4Packet, Icmpv4Repr, IpProtocol, Ipv4Address, Ipv4Packet, Ipv4Repr};
+
+#[test]
+#[ignore = "requires superuser privileges"]
+fn test_utun_ping() {
+    const NAME: &str = "utun99";
+    let local_addr = Ipv4Address::new(10, 200, 0, 1);
+    let remote_addr = Ipv4Address::new(10, 200, 0, 2);
+
+    let mut device = TunTapInterface::new(NAME, Medium::Ip).unwrap();
+    assert_eq!(device.capabilities().medium, Medium::Ip);
+    let status = Command::new("ifconfig")
+        .args([NAME, "inet", "10.200.0.1", "10.200.0.2", "up"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Ask the OS, which owns 10.200.0.1, to answer a ping from the other end of the link.
+    let checksum_caps = ChecksumCapabilities::default();
+    let icmp_repr = Icmpv4Repr::EchoRequest {
+        ident: 0x1234,
+        seq_no: 1,
+        data: b"smoltcp",
+    };
+    let ip_repr = Ipv4Repr {
+        src_addr: remote_addr,
+        dst_addr: local_addr,
+        next_header: IpProtocol::Icmp,
+        payload_len: icmp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+    let tx_token = device.transmit(Instant::now()).unwrap();
+    tx_token.consume(ip_repr.buffer_len() + icmp_repr.buffer_len(), |buffer| {
+        let mut packet = Ipv4Packet::new_unchecked(buffer);
+        ip_repr.emit(&mut packet, &checksum_caps);
+        icmp_repr.emit(
+            &mut Icmpv4Packet::new_unchecked(packet.payload_mut()),
+            &checksum_caps,
+        );
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        assert!(Instant::now() < deadline, "no echo reply received");
+        wait(device.as_raw_fd(), Some(Duration::from_millis(100))).unwrap();
+        let (rx_token, _) = match device.receive(Instant::now()) {
+            Some(tokens) => tokens,
+            None => continue,
+        };
+        let reply = rx_token.consume(|buffer| {
+            let packet = Ipv4Packet::new_checked(&*buffer).unwrap();
+            let ip_repr = Ipv4Repr::parse(&packet, &checksum_caps).unwrap();
+            if ip_repr.next_header != IpProtocol::Icmp || ip_repr.dst_addr != remote_addr {
+                return None;
+            }
+            let icmp_packet = Icmpv4Packet::new_checked(packet.payload()).unwrap();
+            match Icmpv4Repr::parse(&icmp_packet, &checksum_caps).unwrap() {
+                Icmpv4Repr::EchoReply {
+                    ident,
+                    seq_no,
+                    data,
+                } => Some((ident, seq_no, data.to_vec())),
+                _ => None,
+            }
+        });
+        if let Some(reply) = reply {
+            assert_eq!(reply, (0x1234, 1, b"smoltcp".to_vec()));
+            break;
+        }
+    }
+}